@@ -10,8 +10,8 @@ use indradb::util::{
     generate_uuid_v1,
 };
 use indradb::{
-    AllEdgeQuery, AllVertexQuery, CountQueryExt, Edge, Error, Identifier, Json, QueryExt, SpecificEdgeQuery,
-    SpecificVertexQuery, Vertex,
+    AllEdgeQuery, AllVertexQuery, CountQueryExt, Edge, Error, Identifier, IndexScope, Json, QueryExt,
+    SpecificEdgeQuery, SpecificVertexQuery, UniqueConstraint, Vertex,
 };
 use indradb_proto as proto;
 use uuid::Uuid;
@@ -39,6 +39,34 @@ pub async fn main() -> Result<(), Box<dyn StdError>> {
 
     let required_property_name_arg = Arg::with_name("name").help("the property name").required(true);
 
+    let index_scope_args = [
+        Arg::with_name("vertex_type")
+            .help("only index vertices of this type")
+            .long("vertex-type")
+            .value_name("type")
+            .takes_value(true)
+            .conflicts_with("edge_type"),
+        Arg::with_name("edge_type")
+            .help("only index edges of this type")
+            .long("edge-type")
+            .value_name("type")
+            .takes_value(true),
+    ];
+
+    let composite_names_arg = Arg::with_name("names")
+        .help("the property names, in index order")
+        .required(true)
+        .multiple(true);
+
+    let unique_constraint_args = [
+        Arg::with_name("vertex_type")
+            .help("the vertex type the constraint applies to")
+            .required(true),
+        Arg::with_name("name")
+            .help("the property name whose values must be unique")
+            .required(true),
+    ];
+
     let property_value_arg = Arg::with_name("value")
         .help("the property value as JSON")
         .required(true);
@@ -119,6 +147,11 @@ pub async fn main() -> Result<(), Box<dyn StdError>> {
                         .arg(&optional_property_name_arg),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("query")
+                .about("gets values by a text query, e.g. 'V(type=user).out(follows).count()'")
+                .arg(Arg::with_name("text").help("the text of the query").required(true)),
+        )
         .subcommand(
             SubCommand::with_name("delete")
                 .setting(AppSettings::SubcommandRequiredElseHelp)
@@ -145,6 +178,78 @@ pub async fn main() -> Result<(), Box<dyn StdError>> {
                         .arg(&required_property_name_arg),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("index")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("indexes a property")
+                        .arg(&required_property_name_arg)
+                        .args(&index_scope_args),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("removes a property index")
+                        .arg(&required_property_name_arg)
+                        .args(&index_scope_args),
+                )
+                .subcommand(
+                    SubCommand::with_name("add-composite")
+                        .about("adds a composite index over vertex properties")
+                        .arg(&composite_names_arg),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove-composite")
+                        .about("removes a composite index")
+                        .arg(&composite_names_arg),
+                )
+                .subcommand(
+                    SubCommand::with_name("add-fulltext")
+                        .about("adds a full-text index on a vertex property")
+                        .arg(&required_property_name_arg),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove-fulltext")
+                        .about("removes a full-text index")
+                        .arg(&required_property_name_arg),
+                )
+                .subcommand(
+                    SubCommand::with_name("add-vector")
+                        .about("adds a vector index on a vertex property")
+                        .arg(&required_property_name_arg),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove-vector")
+                        .about("removes a vector index")
+                        .arg(&required_property_name_arg),
+                )
+                .subcommand(
+                    SubCommand::with_name("add-geo")
+                        .about("adds a geospatial index on a vertex property")
+                        .arg(&required_property_name_arg),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove-geo")
+                        .about("removes a geospatial index")
+                        .arg(&required_property_name_arg),
+                )
+                .subcommand(SubCommand::with_name("list").about("lists indexed properties")),
+        )
+        .subcommand(
+            SubCommand::with_name("constraint")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("adds a unique constraint on a vertex property")
+                        .args(&unique_constraint_args),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("removes a unique constraint")
+                        .args(&unique_constraint_args),
+                )
+                .subcommand(SubCommand::with_name("list").about("lists unique constraints")),
+        )
         .get_matches();
 
     run(matches).await
@@ -234,6 +339,11 @@ async fn run(matches: clap::ArgMatches<'_>) -> Result<(), Box<dyn StdError>> {
             let output = client.get(q).await?;
             println!("{:?}", extract_edge_properties(output));
         }
+    } else if let Some(matches) = matches.subcommand_matches("query") {
+        let output = client.get_by_text(matches.value_of("text").unwrap()).await?;
+        for value in output {
+            println!("{value:?}");
+        }
     } else if let Some(matches) = matches.subcommand_matches("delete") {
         if let Some(matches) = matches.subcommand_matches("vertex") {
             let q = build_vertex_query(matches)?;
@@ -252,11 +362,140 @@ async fn run(matches: clap::ArgMatches<'_>) -> Result<(), Box<dyn StdError>> {
                 .name(property_name);
             client.delete(q).await?;
         }
+    } else if let Some(matches) = matches.subcommand_matches("index") {
+        if let Some(matches) = matches.subcommand_matches("add") {
+            let property_name = Identifier::new(matches.value_of("name").unwrap())?;
+            client
+                .index_property(property_name, build_index_scope(matches)?)
+                .await?;
+        } else if let Some(matches) = matches.subcommand_matches("remove") {
+            let property_name = Identifier::new(matches.value_of("name").unwrap())?;
+            client
+                .unindex_property(property_name, build_index_scope(matches)?)
+                .await?;
+        } else if matches.subcommand_matches("list").is_some() {
+            let mut indexed_properties: Vec<(Identifier, String)> = client
+                .indexed_properties()
+                .await?
+                .into_iter()
+                .flat_map(|(name, scopes)| {
+                    scopes.into_iter().map(move |scope| {
+                        let scope = match scope {
+                            IndexScope::All => "all".to_string(),
+                            IndexScope::VertexType(t) => format!("vertex-type={}", t.as_str()),
+                            IndexScope::EdgeType(t) => format!("edge-type={}", t.as_str()),
+                        };
+                        (name, scope)
+                    })
+                })
+                .collect();
+            indexed_properties.sort();
+            for (property_name, scope) in indexed_properties {
+                println!("{}\t{}", property_name.as_str(), scope);
+            }
+
+            let mut composite_indexes: Vec<String> = client
+                .composite_indexes()
+                .await?
+                .into_iter()
+                .map(|names| names.iter().map(|name| name.as_str()).collect::<Vec<&str>>().join(","))
+                .collect();
+            composite_indexes.sort();
+            for names in composite_indexes {
+                println!("{names}\tcomposite");
+            }
+
+            let mut fulltext_indexed_properties: Vec<Identifier> =
+                client.fulltext_indexed_properties().await?.into_iter().collect();
+            fulltext_indexed_properties.sort();
+            for property_name in fulltext_indexed_properties {
+                println!("{}\tfulltext", property_name.as_str());
+            }
+
+            let mut vector_indexed_properties: Vec<Identifier> =
+                client.vector_indexed_properties().await?.into_iter().collect();
+            vector_indexed_properties.sort();
+            for property_name in vector_indexed_properties {
+                println!("{}\tvector", property_name.as_str());
+            }
+
+            let mut geo_indexed_properties: Vec<Identifier> =
+                client.geo_indexed_properties().await?.into_iter().collect();
+            geo_indexed_properties.sort();
+            for property_name in geo_indexed_properties {
+                println!("{}\tgeo", property_name.as_str());
+            }
+        } else if let Some(matches) = matches.subcommand_matches("add-composite") {
+            client.index_composite(build_composite_names(matches)?).await?;
+        } else if let Some(matches) = matches.subcommand_matches("remove-composite") {
+            client.unindex_composite(build_composite_names(matches)?).await?;
+        } else if let Some(matches) = matches.subcommand_matches("add-fulltext") {
+            let property_name = Identifier::new(matches.value_of("name").unwrap())?;
+            client.index_property_fulltext(property_name).await?;
+        } else if let Some(matches) = matches.subcommand_matches("remove-fulltext") {
+            let property_name = Identifier::new(matches.value_of("name").unwrap())?;
+            client.unindex_property_fulltext(property_name).await?;
+        } else if let Some(matches) = matches.subcommand_matches("add-vector") {
+            let property_name = Identifier::new(matches.value_of("name").unwrap())?;
+            client.index_property_vector(property_name).await?;
+        } else if let Some(matches) = matches.subcommand_matches("remove-vector") {
+            let property_name = Identifier::new(matches.value_of("name").unwrap())?;
+            client.unindex_property_vector(property_name).await?;
+        } else if let Some(matches) = matches.subcommand_matches("add-geo") {
+            let property_name = Identifier::new(matches.value_of("name").unwrap())?;
+            client.index_property_geo(property_name).await?;
+        } else if let Some(matches) = matches.subcommand_matches("remove-geo") {
+            let property_name = Identifier::new(matches.value_of("name").unwrap())?;
+            client.unindex_property_geo(property_name).await?;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("constraint") {
+        if let Some(matches) = matches.subcommand_matches("add") {
+            client.add_unique_constraint(build_unique_constraint(matches)?).await?;
+        } else if let Some(matches) = matches.subcommand_matches("remove") {
+            client
+                .remove_unique_constraint(build_unique_constraint(matches)?)
+                .await?;
+        } else if matches.subcommand_matches("list").is_some() {
+            let mut unique_constraints: Vec<(Identifier, Identifier)> = client
+                .unique_constraints()
+                .await?
+                .into_iter()
+                .map(|constraint| (constraint.t, constraint.name))
+                .collect();
+            unique_constraints.sort();
+            for (t, name) in unique_constraints {
+                println!("{}\t{}", t.as_str(), name.as_str());
+            }
+        }
     }
 
     Ok(())
 }
 
+fn build_index_scope(matches: &clap::ArgMatches) -> Result<IndexScope, Box<dyn StdError>> {
+    if let Some(t) = matches.value_of("vertex_type") {
+        Ok(IndexScope::VertexType(Identifier::new(t)?))
+    } else if let Some(t) = matches.value_of("edge_type") {
+        Ok(IndexScope::EdgeType(Identifier::new(t)?))
+    } else {
+        Ok(IndexScope::All)
+    }
+}
+
+fn build_composite_names(matches: &clap::ArgMatches) -> Result<Vec<Identifier>, Box<dyn StdError>> {
+    let mut names = Vec::new();
+    for name in matches.values_of("names").unwrap() {
+        names.push(Identifier::new(name)?);
+    }
+    Ok(names)
+}
+
+fn build_unique_constraint(matches: &clap::ArgMatches) -> Result<UniqueConstraint, Box<dyn StdError>> {
+    let t = Identifier::new(matches.value_of("vertex_type").unwrap())?;
+    let name = Identifier::new(matches.value_of("name").unwrap())?;
+    Ok(UniqueConstraint::new(t, name))
+}
+
 fn build_vertex_query(matches: &clap::ArgMatches) -> Result<SpecificVertexQuery, Box<dyn StdError>> {
     let vertex_id = Uuid::parse_str(matches.value_of("uuid").unwrap())?;
     Ok(SpecificVertexQuery::single(vertex_id))
@@ -241,7 +241,7 @@ pub struct PipeQuery {
     pub inner: Box<Query>,
     pub direction: EdgeDirection,
     pub limit: u32,
-    pub t: Option<Identifier>,
+    pub t: Vec<Identifier>,
 }
 
 impl Into<indradb::PipeQuery> for PipeQuery {
@@ -250,7 +250,8 @@ impl Into<indradb::PipeQuery> for PipeQuery {
             inner: Box::new((*self.inner).into()),
             direction: self.direction.into(),
             limit: self.limit,
-            t: self.t.map(|t| t.into()),
+            t: self.t.into_iter().map(|t| t.into()).collect(),
+            after: None,
         }
     }
 }
@@ -569,8 +570,8 @@ fuzz_target!(|ops: Vec<Op>| {
                 cmp!(v1, v2);
             }
             Op::IndexProperty(t) => {
-                let v1 = d1.index_property(t.clone().into());
-                let v2 = d2.index_property(t.into());
+                let v1 = d1.index_property(t.clone().into(), indradb::IndexScope::All);
+                let v2 = d2.index_property(t.into(), indradb::IndexScope::All);
                 cmp!(v1, v2);
             }
         }
@@ -1,16 +1,41 @@
-use crate::errors::{Error, Result};
+use crate::errors::{Error, Result, ValidationError};
 use crate::models::{
-    BulkInsertItem, Edge, EdgeDirection, EdgeProperties, Identifier, Json, NamedProperty, Query, QueryOutputValue,
-    Vertex, VertexProperties,
+    BoundingBox, BulkInsertItem, Cursor, Degree, DistanceMetric, Edge, EdgeDirection, EdgeProperties, FilterStrategy,
+    GeoPoint, Group, GroupKey, Identifier, IndexScope, Json, NamedProperty, OrderByQuery, Path, PlanStep, Query,
+    QueryOutputValue, QueryPlan, ShortestPathQuery, SortDirection, SpecificVertexQuery, Stats, StringPattern,
+    TraversalQuery, UniqueConstraint, Vertex, VertexProperties, VertexWithPropertyValueQuery,
 };
-use std::collections::HashSet;
+use crate::planner;
+use crate::util;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::iter;
+use std::mem;
+use std::ops::Bound;
+use std::rc::Rc;
 use std::vec::Vec;
 use uuid::Uuid;
 
+// How many times `Database::get_or_create_vertex` is attempted while it
+// conflicts with concurrent calls.
+const GET_OR_CREATE_VERTEX_ATTEMPTS: usize = 5;
+
 /// A dynamic iterator over results, which are commonly employed as
 /// transaction return types.
 pub type DynIter<'a, T> = Box<dyn Iterator<Item = Result<T>> + 'a>;
 
+/// A vertex's ID, along with the vector read from its value for a property
+/// with a vector index.
+pub type VertexVector = (Uuid, Vec<f64>);
+
+/// A vertex's ID, along with the point read from its value for a property
+/// with a geospatial index.
+pub type VertexPoint = (Uuid, GeoPoint);
+
 /// Specifies a datastore transaction, which contains nearly all of the
 /// datastore implementation-specific logic.
 ///
@@ -45,6 +70,79 @@ pub trait Transaction<'a> {
     /// * `name` - The property name.
     /// * `value` - The property value.
     fn vertex_ids_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Uuid>>>;
+    /// Get all vertices with a property value within a range. Only values
+    /// of the same JSON type as the bounds are included. If both bounds are
+    /// unbounded, all vertices with the property are included. By default,
+    /// this returns `None`, for datastores that don't support range lookups.
+    ///
+    /// # Arguments
+    /// * `name` - The property name.
+    /// * `lower` - The lower bound of the property value.
+    /// * `upper` - The upper bound of the property value.
+    fn vertex_ids_with_property_range(
+        &'a self,
+        _name: Identifier,
+        _lower: Bound<&Json>,
+        _upper: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        Ok(None)
+    }
+
+    /// Get all vertices in a composite index whose values for the leading
+    /// properties of the index equal the given values. By default, this
+    /// returns `None`, for datastores that don't support composite indexes.
+    ///
+    /// # Arguments
+    /// * `names` - The property names of the composite index, in order.
+    /// * `values` - The values of a prefix of the index's properties. Must
+    ///   not be longer than `names`.
+    fn vertex_ids_with_composite_values(
+        &'a self,
+        _names: &[Identifier],
+        _values: &[Json],
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        Ok(None)
+    }
+    /// Gets the IDs of vertices whose string value for a property contains
+    /// all of the given terms, as split by `util::tokenize`. Returns `None`
+    /// if the property doesn't have a full-text index, which by default is
+    /// always the case.
+    ///
+    /// # Arguments
+    /// * `name` - The property name.
+    /// * `terms` - The terms to match. Must not be empty.
+    fn vertex_ids_with_property_terms(
+        &'a self,
+        _name: Identifier,
+        _terms: &[String],
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        Ok(None)
+    }
+    /// Gets the IDs of vertices with a vector index entry for a property,
+    /// along with their vectors, as read by `util::json_to_vector`. Returns
+    /// `None` if the property doesn't have a vector index, which by default
+    /// is always the case.
+    ///
+    /// # Arguments
+    /// * `name` - The property name.
+    fn vertex_property_vectors(&'a self, _name: Identifier) -> Result<Option<DynIter<'a, VertexVector>>> {
+        Ok(None)
+    }
+    /// Gets the IDs of vertices with a geospatial index entry for a property
+    /// that's within a bounding box, along with their points, as read by
+    /// `GeoPoint::from_json`. Returns `None` if the property doesn't have a
+    /// geospatial index, which by default is always the case.
+    ///
+    /// # Arguments
+    /// * `name` - The property name.
+    /// * `bbox` - The bounding box. This never crosses the antimeridian.
+    fn vertex_points_within(
+        &'a self,
+        _name: Identifier,
+        _bbox: &BoundingBox,
+    ) -> Result<Option<DynIter<'a, VertexPoint>>> {
+        Ok(None)
+    }
 
     /// Gets the number of edges.
     fn edge_count(&self) -> u64;
@@ -67,6 +165,31 @@ pub trait Transaction<'a> {
     /// # Arguments
     /// * `edges` - The edges to get.
     fn specific_edges(&'a self, edges: Vec<Edge>) -> Result<DynIter<'a, Edge>>;
+    /// Counts the edges of a vertex in one direction. The default
+    /// implementation scans them with `range_edges` or
+    /// `range_reversed_edges`; datastores should override it if they can
+    /// count them without reading each one.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the vertex.
+    /// * `direction` - Whether to count outbound or inbound edges.
+    /// * `t` - Only count edges of this type.
+    fn vertex_degree(&'a self, id: Uuid, direction: EdgeDirection, t: Option<Identifier>) -> Result<u64> {
+        let lower_bound = Edge::new(id, t.unwrap_or_default(), Uuid::default());
+        let iter = match direction {
+            EdgeDirection::Outbound => self.range_edges(lower_bound)?,
+            EdgeDirection::Inbound => self.range_reversed_edges(lower_bound)?,
+        };
+        let mut degree = 0;
+        for edge in iter {
+            let edge = edge?;
+            if edge.outbound_id != id || t.is_some_and(|t| edge.t != t) {
+                break;
+            }
+            degree += 1;
+        }
+        Ok(degree)
+    }
     /// Get all edges with a given property.
     ///
     /// # Arguments
@@ -78,6 +201,23 @@ pub trait Transaction<'a> {
     /// * `name` - The property name.
     /// * `value` - The property value.
     fn edges_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Edge>>>;
+    /// Get all edges with a property value within a range. Only values of
+    /// the same JSON type as the bounds are included. If both bounds are
+    /// unbounded, all edges with the property are included. By default, this
+    /// returns `None`, for datastores that don't support range lookups.
+    ///
+    /// # Arguments
+    /// * `name` - The property name.
+    /// * `lower` - The lower bound of the property value.
+    /// * `upper` - The upper bound of the property value.
+    fn edges_with_property_range(
+        &'a self,
+        _name: Identifier,
+        _lower: Bound<&Json>,
+        _upper: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Edge>>> {
+        Ok(None)
+    }
 
     /// Gets the value of a vertex property if it exists, or `None` otherwise.
     ///
@@ -103,6 +243,49 @@ pub trait Transaction<'a> {
     /// * `edge` - The edge.
     fn all_edge_properties_for_edge(&'a self, edge: &Edge) -> Result<DynIter<'a, (Identifier, Json)>>;
 
+    /// Estimates the number of vertices, which is used to plan queries.
+    /// Returns `None` if there's no cheap way to estimate it, which is the
+    /// default.
+    fn estimated_vertex_count(&self) -> Option<u64> {
+        None
+    }
+
+    /// Estimates the number of edges, which is used to plan queries.
+    /// Returns `None` if there's no cheap way to estimate it, which is the
+    /// default.
+    fn estimated_edge_count(&self) -> Option<u64> {
+        None
+    }
+
+    /// Estimates the number of vertices with an indexed property value,
+    /// which is used to plan queries. Returns `None` if there's no cheap way
+    /// to estimate it, which is the default.
+    ///
+    /// # Arguments
+    /// * `name` - The property name.
+    /// * `value` - The property value.
+    fn estimated_vertex_property_value_count(&self, _name: Identifier, _value: &Json) -> Option<u64> {
+        None
+    }
+
+    /// Estimates the number of edges with an indexed property value, which
+    /// is used to plan queries. Returns `None` if there's no cheap way to
+    /// estimate it, which is the default.
+    ///
+    /// # Arguments
+    /// * `name` - The property name.
+    /// * `value` - The property value.
+    fn estimated_edge_property_value_count(&self, _name: Identifier, _value: &Json) -> Option<u64> {
+        None
+    }
+
+    /// Gets counts of the vertices and edges of each type, and of the
+    /// vertices and edges with each indexed property value. By default, this
+    /// errors out, but this can be overridden in datastores that keep counts.
+    fn stats(&self) -> Result<Stats> {
+        Err(Error::Unsupported)
+    }
+
     /// Deletes the given vertices.
     ///
     /// # Arguments
@@ -130,6 +313,34 @@ pub trait Transaction<'a> {
         Err(Error::Unsupported)
     }
 
+    /// Commits the changes made in this transaction. By default, this is a
+    /// no-op, which is suitable for datastores that apply changes
+    /// immediately.
+    fn commit(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Discards the changes made in this transaction. By default, this
+    /// errors out, but this can be overridden in datastores that support
+    /// rolling back.
+    fn rollback(&mut self) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Locks a vertex property value for vertices of a type, so that the
+    /// transaction conflicts with any other that concurrently locks the
+    /// same one. This is what makes `get_or_create_vertex` atomic. By
+    /// default, this is a no-op, which is suitable for datastores that run
+    /// transactions one at a time.
+    ///
+    /// # Arguments
+    /// * `t`: The vertex type.
+    /// * `name`: The property name.
+    /// * `value`: The property value.
+    fn lock_vertex_property_value(&mut self, _t: Identifier, _name: Identifier, _value: &Json) -> Result<()> {
+        Ok(())
+    }
+
     /// Creates a new vertex. Returns whether the vertex was successfully
     /// created - if this is false, it's because a vertex with the same UUID
     /// already exists.
@@ -173,11 +384,175 @@ pub trait Transaction<'a> {
     }
 
     /// Enables indexing on a specified property. When indexing is enabled on a
-    /// property, it's possible to query on its presence and values.
+    /// property, it's possible to query on its presence and values. A
+    /// property can be indexed under several scopes, in which case the index
+    /// covers the vertices and edges of each of them.
     ///
     /// # Arguments
     /// * `name`: The name of the property to index.
-    fn index_property(&mut self, name: Identifier) -> Result<()>;
+    /// * `scope`: Which vertices and edges the index covers.
+    fn index_property(&mut self, name: Identifier, scope: IndexScope) -> Result<()>;
+
+    /// Disables indexing on a specified property under a given scope. The
+    /// index is removed once no scopes remain. This is a no-op if the
+    /// property isn't indexed under the scope. By default, this errors out,
+    /// but this can be overridden in datastores that support unindexing.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    /// * `scope`: The scope to unindex.
+    fn unindex_property(&mut self, _name: Identifier, _scope: IndexScope) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Gets the names of all indexed properties, along with the scopes each
+    /// is indexed under. By default, this errors out, but this can be
+    /// overridden in datastores that support indexing.
+    fn indexed_properties(&self) -> Result<HashMap<Identifier, HashSet<IndexScope>>> {
+        Err(Error::Unsupported)
+    }
+
+    /// Enables a composite index over an ordered list of vertex property
+    /// names. Vertices that have every one of the properties are indexed by
+    /// their values, which makes it possible to query on equality of any
+    /// prefix of the properties. This is a no-op if the index already
+    /// exists. By default, this errors out, but this can be overridden in
+    /// datastores that support composite indexes.
+    ///
+    /// # Arguments
+    /// * `names`: The property names, in index order.
+    fn index_composite(&mut self, _names: Vec<Identifier>) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Disables a composite index. This is a no-op if the index doesn't
+    /// exist. By default, this errors out.
+    ///
+    /// # Arguments
+    /// * `names`: The property names of the index, in index order.
+    fn unindex_composite(&mut self, _names: Vec<Identifier>) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Gets the property names of all composite indexes. By default, this is
+    /// empty, for datastores that don't support composite indexes.
+    fn composite_indexes(&self) -> Result<HashSet<Vec<Identifier>>> {
+        Ok(HashSet::new())
+    }
+
+    /// Enables a full-text index on a vertex property, which makes it
+    /// possible to query for vertices whose string values contain terms.
+    /// Values that aren't strings aren't indexed. This is a no-op if the
+    /// index already exists. By default, this errors out, but this can be
+    /// overridden in datastores that support full-text indexes.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    fn index_property_fulltext(&mut self, _name: Identifier) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Disables a full-text index. This is a no-op if the index doesn't
+    /// exist. By default, this errors out.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    fn unindex_property_fulltext(&mut self, _name: Identifier) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Gets the names of all properties with a full-text index. By default,
+    /// this is empty, for datastores that don't support full-text indexes.
+    fn fulltext_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        Ok(HashSet::new())
+    }
+
+    /// Enables a vector index on a vertex property, which makes it possible
+    /// to query for the vertices with the nearest values to a vector. Values
+    /// that aren't non-empty arrays of numbers aren't indexed. This is a
+    /// no-op if the index already exists. By default, this errors out, but
+    /// this can be overridden in datastores that support vector indexes.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    fn index_property_vector(&mut self, _name: Identifier) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Disables a vector index. This is a no-op if the index doesn't exist.
+    /// By default, this errors out.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    fn unindex_property_vector(&mut self, _name: Identifier) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Gets the names of all properties with a vector index. By default, this
+    /// is empty, for datastores that don't support vector indexes.
+    fn vector_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        Ok(HashSet::new())
+    }
+
+    /// Enables a geospatial index on a vertex property, which makes it
+    /// possible to query for the vertices with values within an area.
+    /// Values that aren't objects with numeric `lat` and `lon` fields aren't
+    /// indexed. This is a no-op if the index already exists. By default, this
+    /// errors out, but this can be overridden in datastores that support
+    /// geospatial indexes.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    fn index_property_geo(&mut self, _name: Identifier) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Disables a geospatial index. This is a no-op if the index doesn't
+    /// exist. By default, this errors out.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    fn unindex_property_geo(&mut self, _name: Identifier) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Gets the names of all properties with a geospatial index. By default,
+    /// this is empty, for datastores that don't support geospatial indexes.
+    fn geo_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        Ok(HashSet::new())
+    }
+
+    /// Adds a constraint that no two vertices of a type have the same value
+    /// for a property. Once added, setting a property to a value that
+    /// another vertex of the type already has fails with
+    /// `Error::ConstraintViolation`. This is a no-op if the constraint
+    /// already exists. By default, this errors out, but this can be overridden
+    /// in datastores that support unique constraints.
+    ///
+    /// # Arguments
+    /// * `constraint`: The constraint to add.
+    ///
+    /// # Errors
+    /// Returns `Error::ConstraintViolation` if existing vertices already
+    /// violate the constraint.
+    fn add_unique_constraint(&mut self, _constraint: UniqueConstraint) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Removes a unique constraint. This is a no-op if the constraint
+    /// doesn't exist. By default, this errors out.
+    ///
+    /// # Arguments
+    /// * `constraint`: The constraint to remove.
+    fn remove_unique_constraint(&mut self, _constraint: UniqueConstraint) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Gets all unique constraints. By default, this is empty, for datastores
+    /// that don't support unique constraints.
+    fn unique_constraints(&self) -> Result<HashSet<UniqueConstraint>> {
+        Ok(HashSet::new())
+    }
 
     /// Sets vertex properties.
     ///
@@ -185,6 +560,11 @@ pub trait Transaction<'a> {
     /// * `vertices`: The vertices to set the properties on.
     /// * `name`: The property name.
     /// * `value`: The property value.
+    ///
+    /// # Errors
+    /// Returns `Error::ConstraintViolation` if the property is unique for
+    /// the type of one of the vertices, and another vertex of the type
+    /// already has the value.
     fn set_vertex_properties(&mut self, vertices: Vec<Uuid>, name: Identifier, value: &Json) -> Result<()>;
     /// Sets edge properties.
     ///
@@ -200,10 +580,24 @@ pub trait Transaction<'a> {
 pub trait Datastore {
     /// The datastore transaction type.
     type Transaction<'a>: Transaction<'a>
+    where
+        Self: 'a;
+    /// The datastore read-only transaction type. Datastores that don't
+    /// distinguish between reads and writes can use the same type as
+    /// `Transaction`.
+    type ReadTransaction<'a>: Transaction<'a>
     where
         Self: 'a;
     /// Creates a new transaction.
+    ///
+    /// Changes made in the transaction are only kept once `commit` is
+    /// called, so a transaction that's dropped without committing is rolled
+    /// back. Use `Database::transaction` to commit or roll back based on the
+    /// result of a closure.
     fn transaction(&self) -> Self::Transaction<'_>;
+    /// Creates a new read-only transaction. Datastores may run these
+    /// concurrently with each other.
+    fn read_transaction(&self) -> Self::ReadTransaction<'_>;
 }
 
 /// The IndraDB database.
@@ -230,10 +624,44 @@ impl<D: Datastore> Database<D> {
     /// Syncs persisted content. Depending on the datastore implementation,
     /// this has different meanings - including potentially being a no-op.
     pub fn sync(&self) -> Result<()> {
-        let txn = self.datastore.transaction();
+        let txn = self.datastore.read_transaction();
         txn.sync()
     }
 
+    /// Runs a closure within a single transaction. If the closure succeeds,
+    /// all of the changes it made are committed atomically; otherwise they
+    /// are rolled back and the closure's error is returned.
+    ///
+    /// # Arguments
+    /// * `f`: The closure to run, which is passed a handle to the
+    ///   transaction.
+    ///
+    /// # Errors
+    /// If rolling back fails, that error is returned instead of the
+    /// closure's. Datastores that don't support rollbacks apply changes
+    /// immediately, so with those the closure's error is returned as-is,
+    /// and changes made before the error are kept. If the transaction
+    /// conflicts with a concurrent one, `Error::Conflict` is returned and
+    /// none of the changes are kept, so the closure can be run again.
+    pub fn transaction<'a, T, F>(&'a self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut DatabaseTransaction<'a, D>) -> Result<T>,
+    {
+        let mut txn = DatabaseTransaction {
+            txn: self.datastore.transaction(),
+        };
+        match f(&mut txn) {
+            Ok(value) => {
+                txn.txn.commit()?;
+                Ok(value)
+            }
+            Err(err) => match txn.txn.rollback() {
+                Ok(()) | Err(Error::Unsupported) => Err(err),
+                Err(rollback_err) => Err(rollback_err),
+            },
+        }
+    }
+
     /// Creates a new vertex. Returns whether the vertex was successfully
     /// created - if this is false, it's because a vertex with the same UUID
     /// already exists.
@@ -241,8 +669,7 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `vertex`: The vertex to create.
     pub fn create_vertex(&self, vertex: &Vertex) -> Result<bool> {
-        let mut txn = self.datastore.transaction();
-        txn.create_vertex(vertex)
+        self.transaction(|txn| txn.create_vertex(vertex))
     }
 
     /// Creates a new vertex with just a type specification. As opposed to
@@ -252,13 +679,7 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `t`: The type of the vertex to create.
     pub fn create_vertex_from_type(&self, t: Identifier) -> Result<Uuid> {
-        let v = Vertex::new(t);
-
-        if !self.create_vertex(&v)? {
-            Err(Error::UuidTaken)
-        } else {
-            Ok(v.id)
-        }
+        self.transaction(|txn| txn.create_vertex_from_type(t))
     }
 
     /// Creates a new edge. Returns whether the edge was successfully
@@ -268,8 +689,39 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `edge`: The edge to create.
     pub fn create_edge(&self, edge: &Edge) -> Result<bool> {
-        let mut txn = self.datastore.transaction();
-        txn.create_edge(edge)
+        self.transaction(|txn| txn.create_edge(edge))
+    }
+
+    /// Atomically gets the vertex of a type with a property value, or
+    /// creates one with the value and some other properties if there isn't
+    /// any. Returns the vertex's UUID, and whether it was created.
+    ///
+    /// # Arguments
+    /// * `t`: The vertex type.
+    /// * `name`: The property name to look the vertex up by.
+    /// * `value`: The property value to look the vertex up by.
+    /// * `properties`: The other properties to set if the vertex is created.
+    ///
+    /// # Errors
+    /// Returns `Error::NotIndexed` if the property isn't indexed for the
+    /// type. With datastores that run transactions concurrently, two calls
+    /// racing to create the same vertex conflict, and all but one of them
+    /// fail to commit. Those are retried, which gets the created vertex, and
+    /// `Error::Conflict` is only returned if a call keeps conflicting.
+    pub fn get_or_create_vertex(
+        &self,
+        t: Identifier,
+        name: Identifier,
+        value: &Json,
+        properties: Vec<NamedProperty>,
+    ) -> Result<(Uuid, bool)> {
+        let mut attempts = 1;
+        loop {
+            match self.transaction(|txn| txn.get_or_create_vertex(t, name, value, properties.clone())) {
+                Err(Error::Conflict) if attempts < GET_OR_CREATE_VERTEX_ATTEMPTS => attempts += 1,
+                result => return result,
+            }
+        }
     }
 
     /// Gets values specified by a query.
@@ -277,13 +729,55 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `q`: The query to run.
     pub fn get<Q: Into<Query>>(&self, q: Q) -> Result<Vec<QueryOutputValue>> {
-        let q = q.into();
-        let txn = self.datastore.transaction();
+        self.get_iter(q, |output| {
+            output.into_iter().map(QueryOutputValueIter::into_value).collect()
+        })
+    }
+
+    /// Gets values specified by a query in its text syntax, e.g.
+    /// `V(type=user).out(follows).count()`. See `Query`'s `FromStr`
+    /// implementation for the syntax.
+    ///
+    /// # Arguments
+    /// * `q`: The text of the query to run.
+    pub fn query_str(&self, q: &str) -> Result<Vec<QueryOutputValue>> {
+        self.get(q.parse::<Query>()?)
+    }
+
+    /// Gets values specified by a query, without collecting them into
+    /// memory. The closure is passed the lazily evaluated output values,
+    /// which read from the datastore as they're iterated over. Included
+    /// values that other parts of the query are piped from are collected
+    /// up-front.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    /// * `f`: The closure to pass the output values to.
+    pub fn get_iter<Q, T, F>(&self, q: Q, f: F) -> Result<T>
+    where
+        Q: Into<Query>,
+        F: for<'b> FnOnce(Vec<QueryOutputValueIter<'b>>) -> Result<T>,
+    {
+        let txn = self.datastore.read_transaction();
+        let q = planner::plan(&txn, q.into())?;
         let mut output = Vec::with_capacity(q.output_len());
         unsafe {
-            query(&txn as *const D::Transaction<'_>, &q, &mut output)?;
+            query_iter(&txn as *const D::ReadTransaction<'_>, &q, &mut output)?;
         }
-        Ok(output)
+        // The output borrows from the transaction, so it's consumed by the
+        // closure before the transaction is dropped
+        f(output)
+    }
+
+    /// Gets the plan a query would be run with, including the estimated
+    /// number of rows output by each step. The query isn't run, so the
+    /// estimates come from the datastore's counts, where it keeps them.
+    ///
+    /// # Arguments
+    /// * `q`: The query to explain.
+    pub fn explain<Q: Into<Query>>(&self, q: Q) -> Result<QueryPlan> {
+        let txn = self.datastore.read_transaction();
+        explain(&txn, q.into())
     }
 
     /// Deletes values specified by a query.
@@ -291,44 +785,7 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `q`: The query to run.
     pub fn delete<Q: Into<Query>>(&self, q: Q) -> Result<()> {
-        let q = q.into();
-        let mut txn = self.datastore.transaction();
-        let mut output = Vec::with_capacity(q.output_len());
-        unsafe {
-            query(&txn as *const D::Transaction<'_>, &q, &mut output)?;
-        }
-        match output.pop().unwrap() {
-            QueryOutputValue::Vertices(vertices) => {
-                txn.delete_vertices(vertices)?;
-            }
-            QueryOutputValue::Edges(edges) => {
-                txn.delete_edges(edges)?;
-            }
-            QueryOutputValue::VertexProperties(vertex_properties) => {
-                txn.delete_vertex_properties(
-                    vertex_properties
-                        .into_iter()
-                        .flat_map(|vps| {
-                            let iter = vps.props.iter().map(move |vp| (vps.vertex.id, vp.name));
-                            iter.collect::<Vec<(Uuid, Identifier)>>()
-                        })
-                        .collect(),
-                )?;
-            }
-            QueryOutputValue::EdgeProperties(edge_properties) => {
-                txn.delete_edge_properties(
-                    edge_properties
-                        .into_iter()
-                        .flat_map(|eps| {
-                            let iter = eps.props.iter().map(move |ep| (eps.edge.clone(), ep.name));
-                            iter.collect::<Vec<(Edge, Identifier)>>()
-                        })
-                        .collect(),
-                )?;
-            }
-            QueryOutputValue::Count(_) => return Err(Error::OperationOnQuery),
-        }
-        Ok(())
+        self.transaction(|txn| txn.delete(q))
     }
 
     /// Sets properties.
@@ -338,23 +795,7 @@ impl<D: Datastore> Database<D> {
     /// * `name`: The property name.
     /// * `value`: The property value.
     pub fn set_properties<Q: Into<Query>>(&self, q: Q, name: Identifier, value: &Json) -> Result<()> {
-        let q = q.into();
-        let mut txn = self.datastore.transaction();
-        let mut output = Vec::with_capacity(q.output_len());
-        unsafe {
-            query(&txn as *const D::Transaction<'_>, &q, &mut output)?;
-        }
-
-        match output.pop().unwrap() {
-            QueryOutputValue::Vertices(vertices) => {
-                txn.set_vertex_properties(vertices.into_iter().map(|v| v.id).collect(), name, value)?;
-            }
-            QueryOutputValue::Edges(edges) => {
-                txn.set_edge_properties(edges, name, value)?;
-            }
-            _ => return Err(Error::OperationOnQuery),
-        }
-        Ok(())
+        self.transaction(|txn| txn.set_properties(q, name, value))
     }
 
     /// Bulk inserts many vertices, edges, and/or properties.
@@ -362,309 +803,1911 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `items`: The items to insert.
     pub fn bulk_insert(&self, items: Vec<BulkInsertItem>) -> Result<()> {
-        let mut txn = self.datastore.transaction();
-        txn.bulk_insert(items)
+        self.transaction(|txn| txn.bulk_insert(items))
     }
 
     /// Enables indexing on a specified property. When indexing is enabled on a
-    /// property, it's possible to query on its presence and values.
+    /// property, it's possible to query on its presence and values. A
+    /// property can be indexed under several scopes, in which case the index
+    /// covers the vertices and edges of each of them.
     ///
     /// # Arguments
     /// * `name`: The name of the property to index.
-    pub fn index_property(&self, name: Identifier) -> Result<()> {
-        let mut txn = self.datastore.transaction();
-        txn.index_property(name)
+    /// * `scope`: Which vertices and edges the index covers.
+    pub fn index_property(&self, name: Identifier, scope: IndexScope) -> Result<()> {
+        self.transaction(|txn| txn.index_property(name, scope))
     }
-}
 
-unsafe fn query<'a, T: Transaction<'a> + 'a>(
-    txn: *const T,
-    q: &Query,
-    output: &mut Vec<QueryOutputValue>,
-) -> Result<()> {
-    let value = match q {
-        Query::AllVertex => {
-            let iter = (*txn).all_vertices()?;
-            QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
-        }
-        Query::RangeVertex(ref q) => {
-            let mut iter: DynIter<Vertex> = if let Some(start_id) = q.start_id {
-                (*txn).range_vertices(start_id)?
-            } else {
-                (*txn).all_vertices()?
-            };
+    /// Disables indexing on a specified property under a given scope. The
+    /// index is removed once no scopes remain. This is a no-op if the
+    /// property isn't indexed under the scope.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    /// * `scope`: The scope to unindex.
+    pub fn unindex_property(&self, name: Identifier, scope: IndexScope) -> Result<()> {
+        self.transaction(|txn| txn.unindex_property(name, scope))
+    }
 
-            if let Some(ref t) = q.t {
-                iter = Box::new(iter.filter(move |r| match r {
-                    Ok(v) => &v.t == t,
-                    Err(_) => true,
-                }));
-            }
+    /// Gets the names of all indexed properties, along with the scopes each
+    /// is indexed under.
+    pub fn indexed_properties(&self) -> Result<HashMap<Identifier, HashSet<IndexScope>>> {
+        let txn = self.datastore.read_transaction();
+        txn.indexed_properties()
+    }
 
-            iter = Box::new(iter.take(q.limit as usize));
-            QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
-        }
-        Query::SpecificVertex(ref q) => {
-            let iter = (*txn).specific_vertices(q.ids.clone())?;
-            QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
-        }
-        Query::Pipe(ref q) => {
-            query(txn, &q.inner, output)?;
-            let piped_values = output.pop().unwrap();
+    /// Gets counts of the vertices and edges of each type, and of the
+    /// vertices and edges with each indexed property value. Datastores keep
+    /// these up to date as data changes, so this doesn't scan the data.
+    pub fn stats(&self) -> Result<Stats> {
+        let txn = self.datastore.read_transaction();
+        txn.stats()
+    }
 
-            let values = match piped_values {
-                QueryOutputValue::Edges(ref piped_edges) => {
-                    let iter: Box<dyn Iterator<Item = Uuid>> = match q.direction {
-                        EdgeDirection::Outbound => Box::new(piped_edges.iter().map(|e| e.outbound_id)),
-                        EdgeDirection::Inbound => Box::new(piped_edges.iter().map(|e| e.inbound_id)),
-                    };
+    /// Enables a composite index over an ordered list of vertex property
+    /// names. Vertices that have every one of the properties are indexed by
+    /// their values, which makes it possible to query on equality of any
+    /// prefix of the properties.
+    ///
+    /// # Arguments
+    /// * `names`: The property names, in index order.
+    ///
+    /// # Errors
+    /// Returns `ValidationError::InvalidValue` if there are no names, or if
+    /// a name is repeated.
+    pub fn index_composite(&self, names: Vec<Identifier>) -> Result<()> {
+        validate_composite_index(&names)?;
+        self.transaction(|txn| txn.index_composite(names))
+    }
 
-                    let mut iter: DynIter<Vertex> = (*txn).specific_vertices(iter.collect())?;
+    /// Disables a composite index.
+    ///
+    /// # Arguments
+    /// * `names`: The property names of the index, in index order.
+    pub fn unindex_composite(&self, names: Vec<Identifier>) -> Result<()> {
+        self.transaction(|txn| txn.unindex_composite(names))
+    }
 
-                    if let Some(ref t) = q.t {
-                        iter = Box::new(iter.filter(move |r| match r {
-                            Ok(v) => &v.t == t,
-                            Err(_) => true,
-                        }));
-                    }
+    /// Gets the property names of all composite indexes.
+    pub fn composite_indexes(&self) -> Result<HashSet<Vec<Identifier>>> {
+        let txn = self.datastore.read_transaction();
+        txn.composite_indexes()
+    }
+
+    /// Enables a full-text index on a vertex property, which makes it
+    /// possible to query for vertices whose string values contain terms
+    /// with `VertexWithPropertyMatchQuery`.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    pub fn index_property_fulltext(&self, name: Identifier) -> Result<()> {
+        self.transaction(|txn| txn.index_property_fulltext(name))
+    }
+
+    /// Disables a full-text index.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    pub fn unindex_property_fulltext(&self, name: Identifier) -> Result<()> {
+        self.transaction(|txn| txn.unindex_property_fulltext(name))
+    }
+
+    /// Gets the names of all properties with a full-text index.
+    pub fn fulltext_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        let txn = self.datastore.read_transaction();
+        txn.fulltext_indexed_properties()
+    }
+
+    /// Enables a vector index on a vertex property, which makes it possible
+    /// to query for the vertices with the nearest values to a vector with
+    /// `NearestVerticesQuery`.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    pub fn index_property_vector(&self, name: Identifier) -> Result<()> {
+        self.transaction(|txn| txn.index_property_vector(name))
+    }
+
+    /// Disables a vector index.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    pub fn unindex_property_vector(&self, name: Identifier) -> Result<()> {
+        self.transaction(|txn| txn.unindex_property_vector(name))
+    }
+
+    /// Gets the names of all properties with a vector index.
+    pub fn vector_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        let txn = self.datastore.read_transaction();
+        txn.vector_indexed_properties()
+    }
+
+    /// Enables a geospatial index on a vertex property, which makes it
+    /// possible to query for the vertices with values within an area with
+    /// `WithinBoundingBoxQuery` and `WithinRadiusQuery`.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    pub fn index_property_geo(&self, name: Identifier) -> Result<()> {
+        self.transaction(|txn| txn.index_property_geo(name))
+    }
+
+    /// Disables a geospatial index.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    pub fn unindex_property_geo(&self, name: Identifier) -> Result<()> {
+        self.transaction(|txn| txn.unindex_property_geo(name))
+    }
+
+    /// Gets the names of all properties with a geospatial index.
+    pub fn geo_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        let txn = self.datastore.read_transaction();
+        txn.geo_indexed_properties()
+    }
+
+    /// Adds a constraint that no two vertices of a type have the same value
+    /// for a property. Once added, setting a property to a value that
+    /// another vertex of the type already has fails with
+    /// `Error::ConstraintViolation`.
+    ///
+    /// # Arguments
+    /// * `constraint`: The constraint to add.
+    ///
+    /// # Errors
+    /// Returns `Error::ConstraintViolation` if existing vertices already
+    /// violate the constraint.
+    pub fn add_unique_constraint(&self, constraint: UniqueConstraint) -> Result<()> {
+        self.transaction(|txn| txn.add_unique_constraint(constraint))
+    }
+
+    /// Removes a unique constraint.
+    ///
+    /// # Arguments
+    /// * `constraint`: The constraint to remove.
+    pub fn remove_unique_constraint(&self, constraint: UniqueConstraint) -> Result<()> {
+        self.transaction(|txn| txn.remove_unique_constraint(constraint))
+    }
+
+    /// Gets all unique constraints.
+    pub fn unique_constraints(&self) -> Result<HashSet<UniqueConstraint>> {
+        let txn = self.datastore.read_transaction();
+        txn.unique_constraints()
+    }
+}
+
+/// A handle to a transaction that's in progress, as passed to the closure
+/// given to `Database::transaction`. Changes made through it only become
+/// visible outside of the transaction once it's committed.
+pub struct DatabaseTransaction<'a, D: Datastore + 'a> {
+    txn: D::Transaction<'a>,
+}
+
+impl<'a, D: Datastore + 'a> DatabaseTransaction<'a, D> {
+    /// Creates a new vertex. Returns whether the vertex was successfully
+    /// created - if this is false, it's because a vertex with the same UUID
+    /// already exists.
+    ///
+    /// # Arguments
+    /// * `vertex`: The vertex to create.
+    pub fn create_vertex(&mut self, vertex: &Vertex) -> Result<bool> {
+        self.txn.create_vertex(vertex)
+    }
+
+    /// Creates a new vertex with just a type specification. Returns the new
+    /// vertex's UUID.
+    ///
+    /// # Arguments
+    /// * `t`: The type of the vertex to create.
+    pub fn create_vertex_from_type(&mut self, t: Identifier) -> Result<Uuid> {
+        let v = Vertex::new(t);
+
+        if !self.create_vertex(&v)? {
+            Err(Error::UuidTaken)
+        } else {
+            Ok(v.id)
+        }
+    }
+
+    /// Creates a new edge. Returns whether the edge was successfully
+    /// created - if this is false, it's because one of the specified vertices
+    /// is missing.
+    ///
+    /// # Arguments
+    /// * `edge`: The edge to create.
+    pub fn create_edge(&mut self, edge: &Edge) -> Result<bool> {
+        self.txn.create_edge(edge)
+    }
+
+    /// Gets the vertex of a type with a property value, or creates one with
+    /// the value and some other properties if there isn't any. Returns the
+    /// vertex's UUID, and whether it was created.
+    ///
+    /// # Arguments
+    /// * `t`: The vertex type.
+    /// * `name`: The property name to look the vertex up by.
+    /// * `value`: The property value to look the vertex up by.
+    /// * `properties`: The other properties to set if the vertex is created.
+    pub fn get_or_create_vertex(
+        &mut self,
+        t: Identifier,
+        name: Identifier,
+        value: &Json,
+        properties: Vec<NamedProperty>,
+    ) -> Result<(Uuid, bool)> {
+        // Locked before the lookup, so that a concurrent creation of the
+        // vertex that the lookup misses is caught as a conflict
+        self.txn.lock_vertex_property_value(t, name, value)?;
+
+        let q = VertexWithPropertyValueQuery::new(name, value.clone()).t(t);
+        if let Some(QueryOutputValue::Vertices(vertices)) = self.get(q)?.pop() {
+            if let Some(vertex) = vertices.into_iter().next() {
+                return Ok((vertex.id, false));
+            }
+        }
+
+        let id = self.create_vertex_from_type(t)?;
+        self.set_properties(SpecificVertexQuery::single(id), name, value)?;
+        for property in properties {
+            self.set_properties(SpecificVertexQuery::single(id), property.name, &property.value)?;
+        }
+        Ok((id, true))
+    }
+
+    /// Gets values specified by a query. This observes any changes already
+    /// made in the transaction.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    pub fn get<Q: Into<Query>>(&self, q: Q) -> Result<Vec<QueryOutputValue>> {
+        let q = q.into();
+        let mut output = Vec::with_capacity(q.output_len());
+        unsafe {
+            query(&self.txn as *const D::Transaction<'_>, &q, &mut output)?;
+        }
+        Ok(output)
+    }
 
-                    iter = Box::new(iter.take(q.limit as usize));
+    /// Deletes values specified by a query.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    pub fn delete<Q: Into<Query>>(&mut self, q: Q) -> Result<()> {
+        let q = q.into();
+        let mut output = Vec::with_capacity(q.output_len());
+        unsafe {
+            query(&self.txn as *const D::Transaction<'_>, &q, &mut output)?;
+        }
+        match output.pop().unwrap() {
+            QueryOutputValue::Vertices(vertices) => {
+                self.txn.delete_vertices(vertices)?;
+            }
+            QueryOutputValue::Edges(edges) => {
+                self.txn.delete_edges(edges)?;
+            }
+            QueryOutputValue::VertexProperties(vertex_properties) => {
+                self.txn.delete_vertex_properties(
+                    vertex_properties
+                        .into_iter()
+                        .flat_map(|vps| {
+                            let iter = vps.props.iter().map(move |vp| (vps.vertex.id, vp.name));
+                            iter.collect::<Vec<(Uuid, Identifier)>>()
+                        })
+                        .collect(),
+                )?;
+            }
+            QueryOutputValue::EdgeProperties(edge_properties) => {
+                self.txn.delete_edge_properties(
+                    edge_properties
+                        .into_iter()
+                        .flat_map(|eps| {
+                            let iter = eps.props.iter().map(move |ep| (eps.edge.clone(), ep.name));
+                            iter.collect::<Vec<(Edge, Identifier)>>()
+                        })
+                        .collect(),
+                )?;
+            }
+            QueryOutputValue::Count(_)
+            | QueryOutputValue::Path(_)
+            | QueryOutputValue::Cursor(_)
+            | QueryOutputValue::Aggregate(_)
+            | QueryOutputValue::Groups(_)
+            | QueryOutputValue::Degrees(_) => return Err(Error::OperationOnQuery),
+        }
+        Ok(())
+    }
+
+    /// Sets properties.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    /// * `name`: The property name.
+    /// * `value`: The property value.
+    pub fn set_properties<Q: Into<Query>>(&mut self, q: Q, name: Identifier, value: &Json) -> Result<()> {
+        let q = q.into();
+        let mut output = Vec::with_capacity(q.output_len());
+        unsafe {
+            query(&self.txn as *const D::Transaction<'_>, &q, &mut output)?;
+        }
+
+        match output.pop().unwrap() {
+            QueryOutputValue::Vertices(vertices) => {
+                self.txn
+                    .set_vertex_properties(vertices.into_iter().map(|v| v.id).collect(), name, value)?;
+            }
+            QueryOutputValue::Edges(edges) => {
+                self.txn.set_edge_properties(edges, name, value)?;
+            }
+            _ => return Err(Error::OperationOnQuery),
+        }
+        Ok(())
+    }
+
+    /// Bulk inserts many vertices, edges, and/or properties.
+    ///
+    /// # Arguments
+    /// * `items`: The items to insert.
+    pub fn bulk_insert(&mut self, items: Vec<BulkInsertItem>) -> Result<()> {
+        self.txn.bulk_insert(items)
+    }
+
+    /// Enables indexing on a specified property. When indexing is enabled on a
+    /// property, it's possible to query on its presence and values. A
+    /// property can be indexed under several scopes, in which case the index
+    /// covers the vertices and edges of each of them.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    /// * `scope`: Which vertices and edges the index covers.
+    pub fn index_property(&mut self, name: Identifier, scope: IndexScope) -> Result<()> {
+        self.txn.index_property(name, scope)
+    }
+
+    /// Disables indexing on a specified property under a given scope. The
+    /// index is removed once no scopes remain. This is a no-op if the
+    /// property isn't indexed under the scope.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    /// * `scope`: The scope to unindex.
+    pub fn unindex_property(&mut self, name: Identifier, scope: IndexScope) -> Result<()> {
+        self.txn.unindex_property(name, scope)
+    }
+
+    /// Gets the names of all indexed properties, along with the scopes each
+    /// is indexed under.
+    pub fn indexed_properties(&self) -> Result<HashMap<Identifier, HashSet<IndexScope>>> {
+        self.txn.indexed_properties()
+    }
+
+    /// Enables a composite index over an ordered list of vertex property
+    /// names. Vertices that have every one of the properties are indexed by
+    /// their values, which makes it possible to query on equality of any
+    /// prefix of the properties.
+    ///
+    /// # Arguments
+    /// * `names`: The property names, in index order.
+    ///
+    /// # Errors
+    /// Returns `ValidationError::InvalidValue` if there are no names, or if
+    /// a name is repeated.
+    pub fn index_composite(&mut self, names: Vec<Identifier>) -> Result<()> {
+        validate_composite_index(&names)?;
+        self.txn.index_composite(names)
+    }
+
+    /// Disables a composite index.
+    ///
+    /// # Arguments
+    /// * `names`: The property names of the index, in index order.
+    pub fn unindex_composite(&mut self, names: Vec<Identifier>) -> Result<()> {
+        self.txn.unindex_composite(names)
+    }
+
+    /// Gets the property names of all composite indexes.
+    pub fn composite_indexes(&self) -> Result<HashSet<Vec<Identifier>>> {
+        self.txn.composite_indexes()
+    }
+
+    /// Enables a full-text index on a vertex property, which makes it
+    /// possible to query for vertices whose string values contain terms
+    /// with `VertexWithPropertyMatchQuery`.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    pub fn index_property_fulltext(&mut self, name: Identifier) -> Result<()> {
+        self.txn.index_property_fulltext(name)
+    }
+
+    /// Disables a full-text index.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    pub fn unindex_property_fulltext(&mut self, name: Identifier) -> Result<()> {
+        self.txn.unindex_property_fulltext(name)
+    }
+
+    /// Gets the names of all properties with a full-text index.
+    pub fn fulltext_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        self.txn.fulltext_indexed_properties()
+    }
+
+    /// Enables a vector index on a vertex property, which makes it possible
+    /// to query for the vertices with the nearest values to a vector with
+    /// `NearestVerticesQuery`.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    pub fn index_property_vector(&mut self, name: Identifier) -> Result<()> {
+        self.txn.index_property_vector(name)
+    }
+
+    /// Disables a vector index.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    pub fn unindex_property_vector(&mut self, name: Identifier) -> Result<()> {
+        self.txn.unindex_property_vector(name)
+    }
+
+    /// Gets the names of all properties with a vector index.
+    pub fn vector_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        self.txn.vector_indexed_properties()
+    }
+
+    /// Enables a geospatial index on a vertex property, which makes it
+    /// possible to query for the vertices with values within an area with
+    /// `WithinBoundingBoxQuery` and `WithinRadiusQuery`.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    pub fn index_property_geo(&mut self, name: Identifier) -> Result<()> {
+        self.txn.index_property_geo(name)
+    }
+
+    /// Disables a geospatial index.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    pub fn unindex_property_geo(&mut self, name: Identifier) -> Result<()> {
+        self.txn.unindex_property_geo(name)
+    }
+
+    /// Gets the names of all properties with a geospatial index.
+    pub fn geo_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        self.txn.geo_indexed_properties()
+    }
+
+    /// Adds a constraint that no two vertices of a type have the same value
+    /// for a property. Once added, setting a property to a value that
+    /// another vertex of the type already has fails with
+    /// `Error::ConstraintViolation`.
+    ///
+    /// # Arguments
+    /// * `constraint`: The constraint to add.
+    ///
+    /// # Errors
+    /// Returns `Error::ConstraintViolation` if existing vertices already
+    /// violate the constraint.
+    pub fn add_unique_constraint(&mut self, constraint: UniqueConstraint) -> Result<()> {
+        self.txn.add_unique_constraint(constraint)
+    }
+
+    /// Removes a unique constraint.
+    ///
+    /// # Arguments
+    /// * `constraint`: The constraint to remove.
+    pub fn remove_unique_constraint(&mut self, constraint: UniqueConstraint) -> Result<()> {
+        self.txn.remove_unique_constraint(constraint)
+    }
+
+    /// Gets all unique constraints.
+    pub fn unique_constraints(&self) -> Result<HashSet<UniqueConstraint>> {
+        self.txn.unique_constraints()
+    }
+}
+
+/// A lazily evaluated value returned from a query, as passed to the closure
+/// given to `Database::get_iter`. Items are only read from the datastore as
+/// the iterators are advanced.
+pub enum QueryOutputValueIter<'a> {
+    /// Vertices.
+    Vertices(DynIter<'a, Vertex>),
+    /// Edges.
+    Edges(DynIter<'a, Edge>),
+    /// A count.
+    Count(u64),
+    /// Vertex properties.
+    VertexProperties(DynIter<'a, VertexProperties>),
+    /// Edge properties.
+    EdgeProperties(DynIter<'a, EdgeProperties>),
+    /// A path, or `None` if there isn't one.
+    Path(Option<Path>),
+    /// A cursor for the next page of an ordered query, or `None` if it was
+    /// the last page.
+    Cursor(Option<Cursor>),
+    /// The result of a sum, min, max or average, or `None` if there were no
+    /// values to aggregate.
+    Aggregate(Option<Json>),
+    /// The number of items in each group.
+    Groups(Vec<Group>),
+    /// The number of edges of each vertex.
+    Degrees(DynIter<'a, Degree>),
+}
+
+impl<'a> QueryOutputValueIter<'a> {
+    /// Evaluates the remainder of the value, collecting it into memory.
+    pub fn into_value(self) -> Result<QueryOutputValue> {
+        Ok(match self {
+            QueryOutputValueIter::Vertices(iter) => QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?),
+            QueryOutputValueIter::Edges(iter) => QueryOutputValue::Edges(iter.collect::<Result<Vec<Edge>>>()?),
+            QueryOutputValueIter::Count(count) => QueryOutputValue::Count(count),
+            QueryOutputValueIter::VertexProperties(iter) => {
+                QueryOutputValue::VertexProperties(iter.collect::<Result<Vec<VertexProperties>>>()?)
+            }
+            QueryOutputValueIter::EdgeProperties(iter) => {
+                QueryOutputValue::EdgeProperties(iter.collect::<Result<Vec<EdgeProperties>>>()?)
+            }
+            QueryOutputValueIter::Path(path) => QueryOutputValue::Path(path),
+            QueryOutputValueIter::Cursor(cursor) => QueryOutputValue::Cursor(cursor),
+            QueryOutputValueIter::Aggregate(value) => QueryOutputValue::Aggregate(value),
+            QueryOutputValueIter::Groups(groups) => QueryOutputValue::Groups(groups),
+            QueryOutputValueIter::Degrees(iter) => QueryOutputValue::Degrees(iter.collect::<Result<Vec<Degree>>>()?),
+        })
+    }
+}
+
+impl<'a> From<QueryOutputValue> for QueryOutputValueIter<'a> {
+    fn from(value: QueryOutputValue) -> Self {
+        match value {
+            QueryOutputValue::Vertices(vertices) => {
+                QueryOutputValueIter::Vertices(Box::new(vertices.into_iter().map(Ok)))
+            }
+            QueryOutputValue::Edges(edges) => QueryOutputValueIter::Edges(Box::new(edges.into_iter().map(Ok))),
+            QueryOutputValue::Count(count) => QueryOutputValueIter::Count(count),
+            QueryOutputValue::VertexProperties(vertex_properties) => {
+                QueryOutputValueIter::VertexProperties(Box::new(vertex_properties.into_iter().map(Ok)))
+            }
+            QueryOutputValue::EdgeProperties(edge_properties) => {
+                QueryOutputValueIter::EdgeProperties(Box::new(edge_properties.into_iter().map(Ok)))
+            }
+            QueryOutputValue::Path(path) => QueryOutputValueIter::Path(path),
+            QueryOutputValue::Cursor(cursor) => QueryOutputValueIter::Cursor(cursor),
+            QueryOutputValue::Aggregate(value) => QueryOutputValueIter::Aggregate(value),
+            QueryOutputValue::Groups(groups) => QueryOutputValueIter::Groups(groups),
+            QueryOutputValue::Degrees(degrees) => QueryOutputValueIter::Degrees(Box::new(degrees.into_iter().map(Ok))),
+        }
+    }
+}
+
+fn validate_composite_index(names: &[Identifier]) -> Result<()> {
+    let unique_names: HashSet<&Identifier> = names.iter().collect();
+    if names.is_empty() || unique_names.len() != names.len() {
+        Err(ValidationError::InvalidValue.into())
+    } else {
+        Ok(())
+    }
+}
+
+fn once_err<'a, T: 'a>(err: Error) -> DynIter<'a, T> {
+    Box::new(iter::once(Err(err)))
+}
+
+unsafe fn query<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &Query,
+    output: &mut Vec<QueryOutputValue>,
+) -> Result<()> {
+    let q = planner::plan(&*txn, q.clone())?;
+    let mut iter_output = Vec::with_capacity(q.output_len());
+    query_iter(txn, &q, &mut iter_output)?;
+    for value in iter_output {
+        output.push(value.into_value()?);
+    }
+    Ok(())
+}
+
+fn explain<'a, T: Transaction<'a>>(txn: &T, q: Query) -> Result<QueryPlan> {
+    let q = planner::plan(txn, q)?;
+
+    let mut subqueries = vec![&q];
+    while let Some(inner) = planner::inner_query(subqueries[subqueries.len() - 1]) {
+        subqueries.push(inner);
+    }
+
+    let steps = subqueries
+        .into_iter()
+        .rev()
+        .map(|subquery| PlanStep {
+            name: planner::query_name(subquery).to_string(),
+            strategy: planner::strategy(txn, subquery),
+            estimated_rows: planner::estimate_rows(txn, subquery),
+        })
+        .collect();
+
+    Ok(QueryPlan { query: q, steps })
+}
+
+// Runs the query that's being piped from. If it's an include query, the
+// piped value is evaluated up-front, so that it can be both exported and
+// piped.
+unsafe fn query_piped<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &Query,
+    output: &mut Vec<QueryOutputValueIter<'a>>,
+) -> Result<QueryOutputValueIter<'a>> {
+    query_iter(txn, q, output)?;
+    let piped_values = output.pop().unwrap();
+
+    if let Query::Include(_) = q {
+        // keep the value exported
+        let piped_values = piped_values.into_value()?;
+        output.push(piped_values.clone().into());
+        Ok(piped_values.into())
+    } else {
+        Ok(piped_values)
+    }
+}
+
+// Finds a composite index whose leading properties are exactly the ones
+// queried on, and orders the queried values to match the index.
+unsafe fn composite_index_values<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    properties: &[(Identifier, Json)],
+) -> Result<(Vec<Identifier>, Vec<Json>)> {
+    let names: HashMap<Identifier, &Json> = properties.iter().map(|(name, value)| (*name, value)).collect();
+    if names.is_empty() || names.len() != properties.len() {
+        return Err(ValidationError::InvalidValue.into());
+    }
+
+    for index in (*txn).composite_indexes()? {
+        if index.len() < names.len() {
+            continue;
+        }
+        // Index names are unique, so this covers every queried name
+        let values: Option<Vec<Json>> = index[..names.len()]
+            .iter()
+            .map(|name| names.get(name).map(|value| (*value).clone()))
+            .collect();
+        if let Some(values) = values {
+            return Ok((index, values));
+        }
+    }
+
+    Err(Error::NotIndexed)
+}
+
+unsafe fn vertices_for_ids<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    ids: impl Iterator<Item = Result<Uuid>> + 'a,
+) -> DynIter<'a, Vertex> {
+    Box::new(ids.flat_map(move |r| match r {
+        Ok(id) => match (*txn).specific_vertices(vec![id]) {
+            Ok(iter) => iter,
+            Err(err) => once_err(err),
+        },
+        Err(err) => once_err(err),
+    }))
+}
+
+// Gets the scopes a property is indexed under, which is empty if the
+// property isn't indexed at all.
+unsafe fn index_scopes<'a, T: Transaction<'a> + 'a>(txn: *const T, name: Identifier) -> Result<HashSet<IndexScope>> {
+    Ok((*txn).indexed_properties()?.remove(&name).unwrap_or_default())
+}
+
+// Checks that a property index can answer a query for vertices of a given
+// type, or for all vertices if no type is given.
+unsafe fn check_vertex_index<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    name: Identifier,
+    t: Option<Identifier>,
+) -> Result<()> {
+    let scopes = index_scopes(txn, name)?;
+    let covered = match t {
+        Some(t) => scopes.iter().any(|scope| scope.covers_vertex(t)),
+        None => scopes.contains(&IndexScope::All),
+    };
+    if covered {
+        Ok(())
+    } else {
+        Err(Error::NotIndexed)
+    }
+}
+
+// Checks that a property index can answer a query for edges of a given
+// type, or for all edges if no type is given.
+unsafe fn check_edge_index<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    name: Identifier,
+    t: Option<Identifier>,
+) -> Result<()> {
+    let scopes = index_scopes(txn, name)?;
+    let covered = match t {
+        Some(t) => scopes.iter().any(|scope| scope.covers_edge(t)),
+        None => scopes.contains(&IndexScope::All),
+    };
+    if covered {
+        Ok(())
+    } else {
+        Err(Error::NotIndexed)
+    }
+}
+
+// Wraps piped vertices that are about to be filtered with a property index,
+// so that any vertex whose type isn't covered by the index produces an
+// error rather than being silently filtered.
+unsafe fn covered_vertices<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    iter: DynIter<'a, Vertex>,
+    name: Identifier,
+) -> Result<DynIter<'a, Vertex>> {
+    let scopes = index_scopes(txn, name)?;
+    if scopes.is_empty() {
+        Err(Error::NotIndexed)
+    } else if scopes.contains(&IndexScope::All) {
+        Ok(iter)
+    } else {
+        Ok(Box::new(iter.map(move |r| match r {
+            Ok(v) if !scopes.iter().any(|scope| scope.covers_vertex(v.t)) => Err(Error::NotIndexed),
+            r => r,
+        })))
+    }
+}
+
+// Wraps piped edges that are about to be filtered with a property index,
+// so that any edge whose type isn't covered by the index produces an error
+// rather than being silently filtered.
+unsafe fn covered_edges<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    iter: DynIter<'a, Edge>,
+    name: Identifier,
+) -> Result<DynIter<'a, Edge>> {
+    let scopes = index_scopes(txn, name)?;
+    if scopes.is_empty() {
+        Err(Error::NotIndexed)
+    } else if scopes.contains(&IndexScope::All) {
+        Ok(iter)
+    } else {
+        Ok(Box::new(iter.map(move |r| match r {
+            Ok(e) if !scopes.iter().any(|scope| scope.covers_edge(e.t)) => Err(Error::NotIndexed),
+            r => r,
+        })))
+    }
+}
+
+// Filters piped vertices by reading each one's property, rather than
+// looking them up in the property's index.
+unsafe fn check_vertex_property<'a, T: Transaction<'a> + 'a, F>(
+    txn: *const T,
+    iter: DynIter<'a, Vertex>,
+    name: Identifier,
+    keep: F,
+) -> DynIter<'a, Vertex>
+where
+    F: Fn(Option<&Json>) -> bool + 'a,
+{
+    Box::new(iter.filter_map(
+        move |r| match r.and_then(|v| Ok(((*txn).vertex_property(&v, name)?, v))) {
+            Ok((value, v)) => keep(value.as_ref()).then_some(Ok(v)),
+            Err(err) => Some(Err(err)),
+        },
+    ))
+}
+
+// Filters piped edges by reading each one's property, rather than looking
+// them up in the property's index.
+unsafe fn check_edge_property<'a, T: Transaction<'a> + 'a, F>(
+    txn: *const T,
+    iter: DynIter<'a, Edge>,
+    name: Identifier,
+    keep: F,
+) -> DynIter<'a, Edge>
+where
+    F: Fn(Option<&Json>) -> bool + 'a,
+{
+    Box::new(iter.filter_map(
+        move |r| match r.and_then(|e| Ok(((*txn).edge_property(&e, name)?, e))) {
+            Ok((value, e)) => keep(value.as_ref()).then_some(Ok(e)),
+            Err(err) => Some(Err(err)),
+        },
+    ))
+}
+
+// Encodes a value such that comparing the encodings orders values of
+// different JSON types consistently, which `Json`'s own ordering doesn't.
+fn sort_key(value: &Json) -> Vec<u8> {
+    util::build(&[util::Component::Json(value)])
+}
+
+// Reads the values of a property for piped vertices or edges, skipping
+// those without it.
+unsafe fn piped_property_values<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    piped: QueryOutputValueIter<'a>,
+    name: Identifier,
+) -> Result<DynIter<'a, Json>> {
+    let values: DynIter<'a, Option<Json>> = match piped {
+        QueryOutputValueIter::Vertices(iter) => Box::new(iter.map(move |r| (*txn).vertex_property(&r?, name))),
+        QueryOutputValueIter::Edges(iter) => Box::new(iter.map(move |r| (*txn).edge_property(&r?, name))),
+        _ => return Err(Error::OperationOnQuery),
+    };
+    Ok(Box::new(values.filter_map(|r| r.transpose())))
+}
+
+// Gets the smallest value, or the largest if `largest` is set, by comparing
+// their sort keys.
+fn extreme_value(values: DynIter<Json>, largest: bool) -> Result<Option<Json>> {
+    let mut extreme: Option<(Vec<u8>, Json)> = None;
+    for value in values {
+        let value = value?;
+        let key = sort_key(&value);
+        let replace = match extreme {
+            Some((ref extreme_key, _)) if largest => key > *extreme_key,
+            Some((ref extreme_key, _)) => key < *extreme_key,
+            None => true,
+        };
+        if replace {
+            extreme = Some((key, value));
+        }
+    }
+    Ok(extreme.map(|(_, value)| value))
+}
+
+// Sums the numeric values, returning the sum and the number of values. The
+// sum is also kept as an integer for as long as every value is an integer
+// and the sum doesn't overflow.
+fn sum_values(values: DynIter<Json>) -> Result<(f64, Option<i64>, u64)> {
+    let mut sum = 0.0;
+    let mut int_sum = Some(0i64);
+    let mut count = 0;
+    for value in values {
+        let value = value?;
+        if let Some(n) = value.as_f64() {
+            sum += n;
+            int_sum = int_sum.and_then(|int_sum| int_sum.checked_add(value.as_i64()?));
+            count += 1;
+        }
+    }
+    Ok((sum, int_sum, count))
+}
+
+// Sorts items by their value for a property, returning the page of items
+// after the query's cursor, along with a cursor for the next page if there
+// are more items. Values are compared by their encoded form, which orders
+// values of different JSON types consistently.
+fn ordered_page<I, T>(
+    items: impl Iterator<Item = Result<(Option<Json>, I, T)>>,
+    q: &OrderByQuery,
+) -> Result<(Option<Cursor>, Vec<T>)>
+where
+    I: Ord + Serialize + DeserializeOwned,
+{
+    let key = |value: &Option<Json>| value.as_ref().map(sort_key);
+    let direction = q.direction;
+    let cmp = move |a: (&Option<Vec<u8>>, &I), b: (&Option<Vec<u8>>, &I)| {
+        let by_value = match (a.0, b.0) {
+            (Some(a_key), Some(b_key)) => match direction {
+                SortDirection::Ascending => a_key.cmp(b_key),
+                SortDirection::Descending => b_key.cmp(a_key),
+            },
+            // Items without the property are last in either direction
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        // Ties are broken by ID, so that pages are deterministic
+        by_value.then_with(|| a.1.cmp(b.1))
+    };
+    let entry_cmp = |a: &(Option<Vec<u8>>, I, T), b: &(Option<Vec<u8>>, I, T)| cmp((&a.0, &a.1), (&b.0, &b.1));
+    let after = q.cursor.as_ref().map(|cursor| cursor.decode::<I>()).transpose()?;
+
+    // One more item than the limit is kept, to tell if there's another page
+    let k = (q.limit as usize).saturating_add(1);
+    let mut page = Vec::new();
+    for item in items {
+        let (value, id, item) = item?;
+        let value_key = key(&value);
+        if let Some((ref after_key, ref after_id)) = after {
+            if cmp((&value_key, &id), (after_key, after_id)) != Ordering::Greater {
+                continue;
+            }
+        }
+        page.push((value_key, id, item));
+        // Like with nearest vertices, items are pruned as they accumulate,
+        // so memory use is bounded by the limit
+        if page.len() >= k.saturating_mul(2) {
+            page.select_nth_unstable_by(k - 1, entry_cmp);
+            page.truncate(k);
+        }
+    }
+    page.sort_unstable_by(entry_cmp);
+
+    let cursor = if page.len() > q.limit as usize {
+        page.truncate(q.limit as usize);
+        match page.last() {
+            Some((value_key, id, _)) => Some(Cursor::encode(value_key, id)?),
+            // With a limit of zero, the next page starts where this one did
+            None => q.cursor.clone(),
+        }
+    } else {
+        None
+    };
+    Ok((cursor, page.into_iter().map(|(_, _, item)| item).collect()))
+}
+
+// Finds the IDs of the `k` nearest vectors to a given one, from nearest to
+// furthest, by measuring the distance to each in turn. Vectors of a
+// different length, and those whose distance is undefined, are skipped.
+fn nearest_vertex_ids(
+    iter: DynIter<VertexVector>,
+    vector: &[f64],
+    k: usize,
+    metric: DistanceMetric,
+) -> Result<Vec<Uuid>> {
+    if k == 0 {
+        return Ok(Vec::new());
+    }
+    // Ties are broken by ID, so that results are deterministic
+    let cmp = |a: &(f64, Uuid), b: &(f64, Uuid)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1));
+    let mut nearest: Vec<(f64, Uuid)> = Vec::new();
+    for item in iter {
+        let (id, other) = item?;
+        if other.len() != vector.len() {
+            continue;
+        }
+        let distance = metric.distance(vector, &other);
+        if distance.is_nan() {
+            continue;
+        }
+        nearest.push((distance, id));
+        // Candidates are pruned to the nearest `k` as they accumulate, so
+        // memory use is bounded by `k` rather than the size of the index
+        if nearest.len() >= k.saturating_mul(2) {
+            nearest.select_nth_unstable_by(k - 1, cmp);
+            nearest.truncate(k);
+        }
+    }
+    nearest.sort_unstable_by(cmp);
+    nearest.truncate(k);
+    Ok(nearest.into_iter().map(|(_, id)| id).collect())
+}
+
+// Finds the IDs of vertices whose geospatial property is within a bounding
+// box, and optionally also within a distance of a point, ordered by ID.
+// Boxes that cross the antimeridian are looked up as one box on either side
+// of it.
+unsafe fn vertex_ids_within<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    name: Identifier,
+    bbox: &BoundingBox,
+    circle: Option<(GeoPoint, f64)>,
+) -> Result<Vec<Uuid>> {
+    bbox.validate()?;
+    let mut ids = BTreeSet::new();
+    for part in bbox.split_at_antimeridian() {
+        let iter = match (*txn).vertex_points_within(name, &part)? {
+            Some(iter) => iter,
+            None => return Err(Error::NotIndexed),
+        };
+        for item in iter {
+            let (id, point) = item?;
+            if circle.is_none_or(|(center, radius)| center.distance(&point) <= radius) {
+                ids.insert(id);
+            }
+        }
+    }
+    Ok(ids.into_iter().collect())
+}
+
+// Validates the center and radius of a circle.
+fn validate_circle(center: &GeoPoint, radius: f64) -> Result<()> {
+    GeoPoint::new(center.lat, center.lon)?;
+    if radius.is_nan() || radius < 0.0 {
+        return Err(ValidationError::InvalidValue.into());
+    }
+    Ok(())
+}
 
-                    QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
+// Filters piped vertices or edges by reading each one's geospatial property
+// and checking whether it's in an area.
+unsafe fn query_piped_within<'a, T: Transaction<'a> + 'a, F>(
+    txn: *const T,
+    inner: &Query,
+    output: &mut Vec<QueryOutputValueIter<'a>>,
+    name: Identifier,
+    within: F,
+) -> Result<QueryOutputValueIter<'a>>
+where
+    F: Fn(&GeoPoint) -> bool + 'a,
+{
+    let matches = move |value: &Option<Json>| value.as_ref().and_then(GeoPoint::from_json).is_some_and(|p| within(&p));
+    match query_piped(txn, inner, output)? {
+        QueryOutputValueIter::Edges(piped_edges) => {
+            let iter = piped_edges.filter_map(move |r| {
+                let edge = match r {
+                    Ok(edge) => edge,
+                    Err(err) => return Some(Err(err)),
+                };
+                match (*txn).edge_property(&edge, name) {
+                    Ok(ref value) if matches(value) => Some(Ok(edge)),
+                    Ok(_) => None,
+                    Err(err) => Some(Err(err)),
+                }
+            });
+            Ok(QueryOutputValueIter::Edges(Box::new(iter)))
+        }
+        QueryOutputValueIter::Vertices(piped_vertices) => {
+            let iter = piped_vertices.filter_map(move |r| {
+                let vertex = match r {
+                    Ok(vertex) => vertex,
+                    Err(err) => return Some(Err(err)),
+                };
+                match (*txn).vertex_property(&vertex, name) {
+                    Ok(ref value) if matches(value) => Some(Ok(vertex)),
+                    Ok(_) => None,
+                    Err(err) => Some(Err(err)),
                 }
-                QueryOutputValue::Vertices(ref piped_vertices) => {
-                    let mut edges = Vec::new();
+            });
+            Ok(QueryOutputValueIter::Vertices(Box::new(iter)))
+        }
+        _ => Err(Error::OperationOnQuery),
+    }
+}
 
-                    for vertex in piped_vertices {
-                        let lower_bound = match &q.t {
-                            Some(t) => Edge::new(vertex.id, *t, Uuid::default()),
-                            None => Edge::new(vertex.id, Identifier::default(), Uuid::default()),
-                        };
+fn vertices_of_type<'a>(iter: DynIter<'a, Vertex>, t: Option<Identifier>) -> DynIter<'a, Vertex> {
+    match t {
+        Some(t) => Box::new(iter.filter(move |r| match r {
+            Ok(v) => v.t == t,
+            Err(_) => true,
+        })),
+        None => iter,
+    }
+}
 
-                        let mut iter = if q.direction == EdgeDirection::Outbound {
-                            (*txn).range_edges(lower_bound)?
-                        } else {
-                            (*txn).range_reversed_edges(lower_bound)?
-                        };
+fn edges_of_type<'a>(iter: DynIter<'a, Edge>, t: Option<Identifier>) -> DynIter<'a, Edge> {
+    match t {
+        Some(t) => Box::new(iter.filter(move |r| match r {
+            Ok(e) => e.t == t,
+            Err(_) => true,
+        })),
+        None => iter,
+    }
+}
 
-                        iter = Box::new(iter.take_while(move |r| match r {
-                            Ok(e) => e.outbound_id == vertex.id,
-                            Err(_) => true,
-                        }));
+// Gets the edges of a vertex in a direction, optionally only those of some
+// types, which are scanned in the order given. Each type's edges are a
+// contiguous range of the index, so they're scanned separately rather than
+// filtered out of all the edges of the vertex.
+unsafe fn edges_for_vertex<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    id: Uuid,
+    direction: EdgeDirection,
+    types: Vec<Identifier>,
+    after: Option<Edge>,
+) -> Result<DynIter<'a, Edge>> {
+    let mut iter = if types.is_empty() {
+        edges_in_range(txn, id, direction, None, after)?
+    } else {
+        Box::new(types.into_iter().flat_map(move |t| {
+            match edges_in_range(txn, id, direction, Some(t), after.clone()) {
+                Ok(iter) => iter,
+                Err(err) => once_err(err),
+            }
+        }))
+    };
 
-                        if let Some(ref t) = q.t {
-                            iter = Box::new(iter.filter(move |r| match r {
-                                Ok(e) => &e.t == t,
-                                Err(_) => true,
-                            }));
-                        }
+    if direction == EdgeDirection::Inbound {
+        iter = Box::new(iter.map(move |r| Ok(r?.reversed())));
+    }
 
-                        if q.direction == EdgeDirection::Inbound {
-                            iter = Box::new(iter.map(move |r| Ok(r?.reversed())));
-                        }
+    Ok(iter)
+}
+
+// Returns whether a query is guaranteed to output vertices in order of
+// their IDs.
+fn outputs_vertices_in_id_order(q: &Query) -> bool {
+    matches!(q, Query::AllVertex | Query::RangeVertex(_))
+}
 
-                        iter = Box::new(iter.take((q.limit as usize) - edges.len()));
+// Scans the edges of a vertex in the index for a direction, optionally only
+// those of a type, stopping at the first edge past them. The edges aren't
+// reversed back for inbound scans.
+unsafe fn edges_in_range<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    id: Uuid,
+    direction: EdgeDirection,
+    t: Option<Identifier>,
+    after: Option<Edge>,
+) -> Result<DynIter<'a, Edge>> {
+    let mut lower_bound = Edge::new(id, t.unwrap_or_default(), Uuid::default());
 
-                        for result in iter {
-                            edges.push(result?);
-                        }
+    // `after` is in the same orientation as the range being scanned, so the
+    // scan can start from it rather than from the first edge of the vertex
+    if let Some(ref after) = after {
+        lower_bound = lower_bound.max(after.clone());
+    }
+
+    let mut iter = if direction == EdgeDirection::Outbound {
+        (*txn).range_edges(lower_bound)?
+    } else {
+        (*txn).range_reversed_edges(lower_bound)?
+    };
+
+    if let Some(after) = after {
+        iter = Box::new(iter.skip_while(move |r| match r {
+            Ok(e) => *e == after,
+            Err(_) => false,
+        }));
+    }
 
-                        if edges.len() >= (q.limit as usize) {
-                            break;
+    iter = Box::new(iter.take_while(move |r| match r {
+        Ok(e) => e.outbound_id == id && t.is_none_or(|t| e.t == t),
+        Err(_) => true,
+    }));
+
+    Ok(iter)
+}
+
+// Breadth-first traversal from a set of starting vertices. Each vertex is
+// yielded at most once, at the shallowest depth it's reached from.
+unsafe fn traverse<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    start: DynIter<'a, Vertex>,
+    q: &TraversalQuery,
+) -> DynIter<'a, Vertex> {
+    let direction = q.direction;
+    let t = q.t;
+    let min_depth = q.min_depth;
+    let max_depth = q.max_depth;
+
+    let mut start = Some(start);
+    let mut visited = HashSet::new();
+    // Vertices at `depth - 1` that still need to be expanded, and the
+    // vertices found at `depth` that will be expanded after them
+    let mut frontier = VecDeque::new();
+    let mut next_frontier = VecDeque::new();
+    let mut edges: Option<DynIter<'a, Edge>> = None;
+    let mut depth = 1;
+
+    let iter = iter::from_fn(move || {
+        if let Some(vertices) = start.as_mut() {
+            for r in vertices.by_ref() {
+                match r {
+                    Ok(v) => {
+                        if visited.insert(v.id) {
+                            if max_depth > 0 {
+                                frontier.push_back(v.id);
+                            }
+                            if min_depth == 0 {
+                                return Some(Ok(v));
+                            }
                         }
                     }
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+            start = None;
+        }
 
-                    QueryOutputValue::Edges(edges)
+        loop {
+            if let Some(iter) = edges.as_mut() {
+                match iter.next() {
+                    Some(Ok(e)) => {
+                        let id = match direction {
+                            EdgeDirection::Outbound => e.inbound_id,
+                            EdgeDirection::Inbound => e.outbound_id,
+                        };
+                        if !visited.insert(id) {
+                            continue;
+                        }
+                        if depth < max_depth {
+                            next_frontier.push_back(id);
+                        }
+                        if depth >= min_depth {
+                            match (*txn).specific_vertices(vec![id]) {
+                                Ok(mut vertices) => {
+                                    if let Some(r) = vertices.next() {
+                                        return Some(r);
+                                    }
+                                }
+                                Err(err) => return Some(Err(err)),
+                            }
+                        }
+                        continue;
+                    }
+                    Some(Err(err)) => return Some(Err(err)),
+                    None => edges = None,
                 }
-                _ => {
-                    return Err(Error::OperationOnQuery);
+            }
+
+            if let Some(id) = frontier.pop_front() {
+                match edges_for_vertex(txn, id, direction, t.into_iter().collect(), None) {
+                    Ok(iter) => edges = Some(iter),
+                    Err(err) => return Some(Err(err)),
                 }
+            } else if !next_frontier.is_empty() {
+                frontier = mem::take(&mut next_frontier);
+                depth += 1;
+            } else {
+                return None;
+            }
+        }
+    });
+
+    Box::new(iter)
+}
+
+// How each vertex visited by a breadth-first search was reached: the edge
+// followed to it (or `None` for where the search started), and its depth.
+type SearchParents = HashMap<Uuid, (Option<Edge>, u32)>;
+
+// Expands a breadth-first search by one level, returning the newly visited
+// vertices.
+unsafe fn expand_search<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    frontier: &[Uuid],
+    direction: EdgeDirection,
+    t: &[Identifier],
+    depth: u32,
+    parents: &mut SearchParents,
+) -> Result<Vec<Uuid>> {
+    let mut next_frontier = Vec::new();
+    for id in frontier {
+        for r in edges_for_vertex(txn, *id, direction, t.to_vec(), None)? {
+            let e = r?;
+            let neighbor_id = match direction {
+                EdgeDirection::Outbound => e.inbound_id,
+                EdgeDirection::Inbound => e.outbound_id,
+            };
+            if let Entry::Vacant(entry) = parents.entry(neighbor_id) {
+                entry.insert((Some(e), depth));
+                next_frontier.push(neighbor_id);
+            }
+        }
+    }
+    Ok(next_frontier)
+}
+
+// Walks the edges back from a vertex visited by a breadth-first search to
+// where the search started, returning them in the order they were walked.
+fn search_edges(parents: &SearchParents, mut id: Uuid) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    while let Some((Some(e), _)) = parents.get(&id) {
+        id = if e.inbound_id == id {
+            e.outbound_id
+        } else {
+            e.inbound_id
+        };
+        edges.push(e.clone());
+    }
+    edges
+}
+
+unsafe fn shortest_path<'a, T: Transaction<'a> + 'a>(txn: *const T, q: &ShortestPathQuery) -> Result<Option<Path>> {
+    let reversed_direction = match q.direction {
+        EdgeDirection::Outbound => EdgeDirection::Inbound,
+        EdgeDirection::Inbound => EdgeDirection::Outbound,
+    };
+
+    let mut forward_parents = SearchParents::from([(q.source, (None, 0))]);
+    let mut forward_frontier = vec![q.source];
+    let mut forward_depth = 0;
+    let mut backward_parents = SearchParents::from([(q.target, (None, 0))]);
+    let mut backward_frontier = vec![q.target];
+    let mut backward_depth = 0;
+
+    // The vertex where the two searches meet. For a unidirectional search,
+    // this is always the target.
+    let mut meeting_id = if q.source == q.target { Some(q.target) } else { None };
+
+    while meeting_id.is_none() && forward_depth + backward_depth < q.max_depth {
+        if !q.bidirectional || forward_frontier.len() <= backward_frontier.len() {
+            forward_depth += 1;
+            forward_frontier = expand_search(
+                txn,
+                &forward_frontier,
+                q.direction,
+                &q.t,
+                forward_depth,
+                &mut forward_parents,
+            )?;
+            meeting_id = forward_frontier
+                .iter()
+                .filter(|id| backward_parents.contains_key(id))
+                .min_by_key(|id| backward_parents[id].1)
+                .copied();
+        } else {
+            backward_depth += 1;
+            backward_frontier = expand_search(
+                txn,
+                &backward_frontier,
+                reversed_direction,
+                &q.t,
+                backward_depth,
+                &mut backward_parents,
+            )?;
+            meeting_id = backward_frontier
+                .iter()
+                .filter(|id| forward_parents.contains_key(id))
+                .min_by_key(|id| forward_parents[id].1)
+                .copied();
+        }
+
+        if forward_frontier.is_empty() || backward_frontier.is_empty() {
+            break;
+        }
+    }
+
+    let meeting_id = match meeting_id {
+        Some(meeting_id) => meeting_id,
+        None => return Ok(None),
+    };
+
+    let mut edges = search_edges(&forward_parents, meeting_id);
+    edges.reverse();
+    edges.extend(search_edges(&backward_parents, meeting_id));
+
+    let mut ids = vec![q.source];
+    for e in &edges {
+        ids.push(match q.direction {
+            EdgeDirection::Outbound => e.inbound_id,
+            EdgeDirection::Inbound => e.outbound_id,
+        });
+    }
+
+    let mut vertices = Vec::with_capacity(ids.len());
+    for id in ids {
+        match (*txn).specific_vertices(vec![id])?.next() {
+            Some(vertex) => vertices.push(vertex?),
+            None => return Ok(None),
+        }
+    }
+
+    Ok(Some(Path::new(vertices, edges)))
+}
+
+unsafe fn vertex_properties<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    vertex: &Vertex,
+    name: Option<Identifier>,
+) -> Result<Vec<NamedProperty>> {
+    let mut props = Vec::new();
+    if let Some(name) = name {
+        if let Some(value) = (*txn).vertex_property(vertex, name)? {
+            props.push(NamedProperty::new(name, value));
+        }
+    } else {
+        for result in (*txn).all_vertex_properties_for_vertex(vertex)? {
+            let (name, value) = result?;
+            props.push(NamedProperty::new(name, value));
+        }
+    }
+    Ok(props)
+}
+
+unsafe fn edge_properties<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    edge: &Edge,
+    name: Option<Identifier>,
+) -> Result<Vec<NamedProperty>> {
+    let mut props = Vec::new();
+    if let Some(name) = name {
+        if let Some(value) = (*txn).edge_property(edge, name)? {
+            props.push(NamedProperty::new(name, value));
+        }
+    } else {
+        for result in (*txn).all_edge_properties_for_edge(edge)? {
+            let (name, value) = result?;
+            props.push(NamedProperty::new(name, value));
+        }
+    }
+    Ok(props)
+}
+
+unsafe fn query_iter<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &Query,
+    output: &mut Vec<QueryOutputValueIter<'a>>,
+) -> Result<()> {
+    let value = match q {
+        Query::AllVertex => QueryOutputValueIter::Vertices((*txn).all_vertices()?),
+        Query::RangeVertex(ref q) => {
+            let mut iter: DynIter<Vertex> = if let Some(start_id) = q.start_id {
+                (*txn).range_vertices(start_id)?
+            } else {
+                (*txn).all_vertices()?
             };
 
-            if let Query::Include(_) = *q.inner {
-                // keep the value exported
-                output.push(piped_values);
+            if let Some(t) = q.t {
+                iter = Box::new(iter.filter(move |r| match r {
+                    Ok(v) => v.t == t,
+                    Err(_) => true,
+                }));
             }
 
-            values
+            QueryOutputValueIter::Vertices(Box::new(iter.take(q.limit as usize)))
         }
-        Query::PipeProperty(ref q) => {
-            query(txn, &q.inner, output)?;
-            let piped_values = output.pop().unwrap();
-
-            let values = match piped_values {
-                QueryOutputValue::Edges(ref piped_edges) => {
-                    let mut edge_properties = Vec::with_capacity(piped_edges.len());
-                    for edge in piped_edges {
-                        let mut props = Vec::new();
-                        if let Some(name) = &q.name {
-                            if let Some(value) = (*txn).edge_property(edge, *name)? {
-                                props.push(NamedProperty::new(*name, value.clone()));
-                            }
-                        } else {
-                            for result in (*txn).all_edge_properties_for_edge(edge)? {
-                                let (name, value) = result?;
-                                props.push(NamedProperty::new(name, value.clone()));
-                            }
-                        }
-                        if !props.is_empty() {
-                            edge_properties.push(EdgeProperties::new(edge.clone(), props));
-                        }
-                    }
+        Query::SpecificVertex(ref q) => QueryOutputValueIter::Vertices((*txn).specific_vertices(q.ids.clone())?),
+        Query::Pipe(ref q) => match query_piped(txn, &q.inner, output)? {
+            QueryOutputValueIter::Edges(piped_edges) => {
+                if q.after.is_some() {
+                    // Only scans of edges can be resumed from an edge
+                    return Err(ValidationError::InvalidValue.into());
+                }
+                let direction = q.direction;
+                let ids = piped_edges.map(move |r| {
+                    let e = r?;
+                    Ok(match direction {
+                        EdgeDirection::Outbound => e.outbound_id,
+                        EdgeDirection::Inbound => e.inbound_id,
+                    })
+                });
 
-                    QueryOutputValue::EdgeProperties(edge_properties)
+                let mut iter = vertices_for_ids(txn, ids);
+
+                if !q.t.is_empty() {
+                    let types: HashSet<Identifier> = q.t.iter().copied().collect();
+                    iter = Box::new(iter.filter(move |r| match r {
+                        Ok(v) => types.contains(&v.t),
+                        Err(_) => true,
+                    }));
                 }
-                QueryOutputValue::Vertices(ref piped_vertices) => {
-                    let mut vertex_properties = Vec::with_capacity(piped_vertices.len());
-                    for vertex in piped_vertices {
-                        let mut props = Vec::new();
-                        if let Some(name) = &q.name {
-                            if let Some(value) = (*txn).vertex_property(vertex, *name)? {
-                                props.push(NamedProperty::new(*name, value.clone()));
-                            }
-                        } else {
-                            for result in (*txn).all_vertex_properties_for_vertex(vertex)? {
-                                let (name, value) = result?;
-                                props.push(NamedProperty::new(name, value.clone()));
+
+                QueryOutputValueIter::Vertices(Box::new(iter.take(q.limit as usize)))
+            }
+            QueryOutputValueIter::Vertices(mut piped_vertices) => {
+                let direction = q.direction;
+                // Each type's edges are scanned in index order, so that the
+                // edges of a vertex are output in order
+                let mut types = q.t.clone();
+                types.sort();
+                types.dedup();
+                // Edges are scanned with the piped vertex as the outbound
+                // ID, so inbound edges are resumed from their reversal
+                let mut after = q.after.as_ref().map(|after| match direction {
+                    EdgeDirection::Outbound => after.clone(),
+                    EdgeDirection::Inbound => after.reversed(),
+                });
+                if let Some(ref after) = after {
+                    // Skip the vertices before the one the edge is for
+                    let start_id = after.outbound_id;
+                    if outputs_vertices_in_id_order(&q.inner) {
+                        // This also works if the vertex has since been
+                        // deleted
+                        piped_vertices = Box::new(piped_vertices.skip_while(move |r| match r {
+                            Ok(vertex) => vertex.id < start_id,
+                            Err(_) => false,
+                        }));
+                    } else {
+                        let found = Rc::new(Cell::new(false));
+                        let found_in_skip = found.clone();
+                        let iter = piped_vertices.skip_while(move |r| match r {
+                            Ok(vertex) if vertex.id != start_id => true,
+                            _ => {
+                                found_in_skip.set(true);
+                                false
                             }
-                        }
-                        if !props.is_empty() {
-                            vertex_properties.push(VertexProperties::new(vertex.clone(), props));
+                        });
+                        // The vertex can't be found if it's since been
+                        // deleted, in which case there's no telling where
+                        // to resume from
+                        let stale =
+                            iter::once_with(move || (!found.get()).then(|| Err(ValidationError::InvalidValue.into())));
+                        piped_vertices = Box::new(iter.chain(stale.flatten()));
+                    }
+                }
+                let iter = piped_vertices.flat_map(move |r| match r {
+                    Ok(vertex) => {
+                        let after = after.take().filter(|after| after.outbound_id == vertex.id);
+                        match edges_for_vertex(txn, vertex.id, direction, types.clone(), after) {
+                            Ok(iter) => iter,
+                            Err(err) => once_err(err),
                         }
                     }
-
-                    QueryOutputValue::VertexProperties(vertex_properties)
+                    Err(err) => once_err(err),
+                });
+                QueryOutputValueIter::Edges(Box::new(iter.take(q.limit as usize)))
+            }
+            _ => {
+                return Err(Error::OperationOnQuery);
+            }
+        },
+        Query::PipeProperty(ref q) => {
+            let name = q.name;
+            match query_piped(txn, &q.inner, output)? {
+                QueryOutputValueIter::Edges(piped_edges) => {
+                    let iter = piped_edges.filter_map(move |r| {
+                        let edge = match r {
+                            Ok(edge) => edge,
+                            Err(err) => return Some(Err(err)),
+                        };
+                        match edge_properties(txn, &edge, name) {
+                            Ok(props) if props.is_empty() => None,
+                            Ok(props) => Some(Ok(EdgeProperties::new(edge, props))),
+                            Err(err) => Some(Err(err)),
+                        }
+                    });
+                    QueryOutputValueIter::EdgeProperties(Box::new(iter))
+                }
+                QueryOutputValueIter::Vertices(piped_vertices) => {
+                    let iter = piped_vertices.filter_map(move |r| {
+                        let vertex = match r {
+                            Ok(vertex) => vertex,
+                            Err(err) => return Some(Err(err)),
+                        };
+                        match vertex_properties(txn, &vertex, name) {
+                            Ok(props) if props.is_empty() => None,
+                            Ok(props) => Some(Ok(VertexProperties::new(vertex, props))),
+                            Err(err) => Some(Err(err)),
+                        }
+                    });
+                    QueryOutputValueIter::VertexProperties(Box::new(iter))
                 }
                 _ => {
                     return Err(Error::OperationOnQuery);
                 }
-            };
-
-            if let Query::Include(_) = *q.inner {
-                // keep the value exported
-                output.push(piped_values);
             }
-
-            values
         }
         Query::VertexWithPropertyPresence(ref q) => {
+            check_vertex_index(txn, q.name, q.t)?;
             if let Some(iter) = (*txn).vertex_ids_with_property(q.name)? {
-                let iter = (*txn).specific_vertices(iter.collect::<Result<Vec<Uuid>>>()?)?;
-                QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
+                QueryOutputValueIter::Vertices(vertices_of_type(vertices_for_ids(txn, iter), q.t))
             } else {
                 return Err(Error::NotIndexed);
             }
         }
         Query::VertexWithPropertyValue(ref q) => {
+            check_vertex_index(txn, q.name, q.t)?;
             if let Some(iter) = (*txn).vertex_ids_with_property_value(q.name, &q.value)? {
-                let iter = (*txn).specific_vertices(iter.collect::<Result<Vec<Uuid>>>()?)?;
-                QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
+                QueryOutputValueIter::Vertices(vertices_of_type(vertices_for_ids(txn, iter), q.t))
+            } else {
+                return Err(Error::NotIndexed);
+            }
+        }
+        Query::VertexWithPropertyValues(ref q) => {
+            let (names, values) = composite_index_values(txn, &q.values)?;
+            if let Some(iter) = (*txn).vertex_ids_with_composite_values(&names, &values)? {
+                QueryOutputValueIter::Vertices(vertices_for_ids(txn, iter))
+            } else {
+                return Err(Error::NotIndexed);
+            }
+        }
+        Query::VertexWithPropertyMatch(ref q) => {
+            let terms: Vec<String> = util::tokenize(&q.text).into_iter().collect();
+            if terms.is_empty() {
+                return Err(ValidationError::InvalidValue.into());
+            }
+            if let Some(iter) = (*txn).vertex_ids_with_property_terms(q.name, &terms)? {
+                QueryOutputValueIter::Vertices(vertices_for_ids(txn, iter))
+            } else {
+                return Err(Error::NotIndexed);
+            }
+        }
+        Query::NearestVertices(ref q) => {
+            if q.vector.is_empty() || q.vector.iter().any(|c| !c.is_finite()) {
+                return Err(ValidationError::InvalidValue.into());
+            }
+            if let Some(iter) = (*txn).vertex_property_vectors(q.name)? {
+                let ids = nearest_vertex_ids(iter, &q.vector, q.k as usize, q.metric)?;
+                QueryOutputValueIter::Vertices(vertices_for_ids(txn, ids.into_iter().map(Ok)))
+            } else {
+                return Err(Error::NotIndexed);
+            }
+        }
+        Query::WithinBoundingBox(ref q) => {
+            let ids = vertex_ids_within(txn, q.name, &q.bbox, None)?;
+            QueryOutputValueIter::Vertices(vertices_for_ids(txn, ids.into_iter().map(Ok)))
+        }
+        Query::WithinRadius(ref q) => {
+            validate_circle(&q.center, q.radius)?;
+            let bbox = BoundingBox::around(q.center, q.radius);
+            let ids = vertex_ids_within(txn, q.name, &bbox, Some((q.center, q.radius)))?;
+            QueryOutputValueIter::Vertices(vertices_for_ids(txn, ids.into_iter().map(Ok)))
+        }
+        Query::VertexWithPropertyRange(ref q) => {
+            check_vertex_index(txn, q.name, q.t)?;
+            if let Some(iter) = (*txn).vertex_ids_with_property_range(q.name, q.lower.as_ref(), q.upper.as_ref())? {
+                QueryOutputValueIter::Vertices(vertices_of_type(vertices_for_ids(txn, iter), q.t))
             } else {
                 return Err(Error::NotIndexed);
             }
         }
         Query::EdgeWithPropertyPresence(ref q) => {
+            check_edge_index(txn, q.name, q.t)?;
             if let Some(iter) = (*txn).edges_with_property(q.name)? {
-                QueryOutputValue::Edges(iter.collect::<Result<Vec<Edge>>>()?)
+                QueryOutputValueIter::Edges(edges_of_type(iter, q.t))
             } else {
                 return Err(Error::NotIndexed);
             }
         }
         Query::EdgeWithPropertyValue(ref q) => {
+            check_edge_index(txn, q.name, q.t)?;
             if let Some(iter) = (*txn).edges_with_property_value(q.name, &q.value)? {
-                QueryOutputValue::Edges(iter.collect::<Result<Vec<Edge>>>()?)
+                QueryOutputValueIter::Edges(edges_of_type(iter, q.t))
+            } else {
+                return Err(Error::NotIndexed);
+            }
+        }
+        Query::EdgeWithPropertyRange(ref q) => {
+            check_edge_index(txn, q.name, q.t)?;
+            if let Some(iter) = (*txn).edges_with_property_range(q.name, q.lower.as_ref(), q.upper.as_ref())? {
+                QueryOutputValueIter::Edges(edges_of_type(iter, q.t))
             } else {
                 return Err(Error::NotIndexed);
             }
         }
         Query::PipeWithPropertyPresence(ref q) => {
-            query(txn, &q.inner, output)?;
-            let piped_values = output.pop().unwrap();
-
-            let values = match piped_values {
-                QueryOutputValue::Edges(ref piped_edges) => {
-                    let edges_with_property = match (*txn).edges_with_property(q.name)? {
-                        Some(iter) => iter.collect::<Result<HashSet<Edge>>>()?,
-                        None => return Err(Error::NotIndexed),
-                    };
-                    let iter = piped_edges.iter().filter(move |e| {
-                        let contains = edges_with_property.contains(e);
-                        (q.exists && contains) || (!q.exists && !contains)
-                    });
-                    QueryOutputValue::Edges(iter.cloned().collect())
+            let exists = q.exists;
+            let strategy = planner::filter_strategy(&*txn, &q.inner, q.name, None);
+            match query_piped(txn, &q.inner, output)? {
+                QueryOutputValueIter::Edges(piped_edges) => {
+                    let piped_edges = covered_edges(txn, piped_edges, q.name)?;
+                    if strategy == FilterStrategy::PropertyChecks {
+                        let iter =
+                            check_edge_property(txn, piped_edges, q.name, move |value| value.is_some() == exists);
+                        QueryOutputValueIter::Edges(iter)
+                    } else {
+                        let edges_with_property = match (*txn).edges_with_property(q.name)? {
+                            Some(iter) => iter.collect::<Result<HashSet<Edge>>>()?,
+                            None => return Err(Error::NotIndexed),
+                        };
+                        let iter = piped_edges.filter(move |r| match r {
+                            Ok(e) => edges_with_property.contains(e) == exists,
+                            Err(_) => true,
+                        });
+                        QueryOutputValueIter::Edges(Box::new(iter))
+                    }
                 }
-                QueryOutputValue::Vertices(ref piped_vertices) => {
-                    let vertices_with_property = match (*txn).vertex_ids_with_property(q.name)? {
-                        Some(iter) => iter.collect::<Result<HashSet<Uuid>>>()?,
-                        None => return Err(Error::NotIndexed),
-                    };
-                    let iter = piped_vertices.iter().filter(move |v| {
-                        let contains = vertices_with_property.contains(&v.id);
-                        (q.exists && contains) || (!q.exists && !contains)
-                    });
-                    QueryOutputValue::Vertices(iter.cloned().collect())
+                QueryOutputValueIter::Vertices(piped_vertices) => {
+                    let piped_vertices = covered_vertices(txn, piped_vertices, q.name)?;
+                    if strategy == FilterStrategy::PropertyChecks {
+                        let iter =
+                            check_vertex_property(txn, piped_vertices, q.name, move |value| value.is_some() == exists);
+                        QueryOutputValueIter::Vertices(iter)
+                    } else {
+                        let vertices_with_property = match (*txn).vertex_ids_with_property(q.name)? {
+                            Some(iter) => iter.collect::<Result<HashSet<Uuid>>>()?,
+                            None => return Err(Error::NotIndexed),
+                        };
+                        let iter = piped_vertices.filter(move |r| match r {
+                            Ok(v) => vertices_with_property.contains(&v.id) == exists,
+                            Err(_) => true,
+                        });
+                        QueryOutputValueIter::Vertices(Box::new(iter))
+                    }
                 }
                 _ => {
                     return Err(Error::OperationOnQuery);
                 }
-            };
-
-            if let Query::Include(_) = *q.inner {
-                // keep the value exported
-                output.push(piped_values);
             }
-
-            values
         }
         Query::PipeWithPropertyValue(ref q) => {
-            query(txn, &q.inner, output)?;
-            let piped_values = output.pop().unwrap();
-
-            let values = match piped_values {
-                QueryOutputValue::Edges(ref piped_edges) => {
-                    let edges = match (*txn).edges_with_property_value(q.name, &q.value)? {
-                        Some(iter) => iter.collect::<Result<HashSet<Edge>>>()?,
+            let equal = q.equal;
+            let strategy = planner::filter_strategy(&*txn, &q.inner, q.name, Some(&q.value));
+            match query_piped(txn, &q.inner, output)? {
+                QueryOutputValueIter::Edges(piped_edges) => {
+                    let piped_edges = covered_edges(txn, piped_edges, q.name)?;
+                    if strategy == FilterStrategy::PropertyChecks {
+                        let expected = q.value.clone();
+                        let iter = check_edge_property(txn, piped_edges, q.name, move |value| {
+                            (value == Some(&expected)) == equal
+                        });
+                        QueryOutputValueIter::Edges(iter)
+                    } else {
+                        let edges = match (*txn).edges_with_property_value(q.name, &q.value)? {
+                            Some(iter) => iter.collect::<Result<HashSet<Edge>>>()?,
+                            None => return Err(Error::NotIndexed),
+                        };
+                        let iter = piped_edges.filter(move |r| match r {
+                            Ok(e) => edges.contains(e) == equal,
+                            Err(_) => true,
+                        });
+                        QueryOutputValueIter::Edges(Box::new(iter))
+                    }
+                }
+                QueryOutputValueIter::Vertices(piped_vertices) => {
+                    let piped_vertices = covered_vertices(txn, piped_vertices, q.name)?;
+                    if strategy == FilterStrategy::PropertyChecks {
+                        let expected = q.value.clone();
+                        let iter = check_vertex_property(txn, piped_vertices, q.name, move |value| {
+                            (value == Some(&expected)) == equal
+                        });
+                        QueryOutputValueIter::Vertices(iter)
+                    } else {
+                        let vertex_ids = match (*txn).vertex_ids_with_property_value(q.name, &q.value)? {
+                            Some(iter) => iter.collect::<Result<HashSet<Uuid>>>()?,
+                            None => return Err(Error::NotIndexed),
+                        };
+                        let iter = piped_vertices.filter(move |r| match r {
+                            Ok(v) => vertex_ids.contains(&v.id) == equal,
+                            Err(_) => true,
+                        });
+                        QueryOutputValueIter::Vertices(Box::new(iter))
+                    }
+                }
+                _ => {
+                    return Err(Error::OperationOnQuery);
+                }
+            }
+        }
+        Query::PipeWithPropertyRange(ref q) => match query_piped(txn, &q.inner, output)? {
+            QueryOutputValueIter::Edges(piped_edges) => {
+                let piped_edges = covered_edges(txn, piped_edges, q.name)?;
+                let edges = match (*txn).edges_with_property_range(q.name, q.lower.as_ref(), q.upper.as_ref())? {
+                    Some(iter) => iter.collect::<Result<HashSet<Edge>>>()?,
+                    None => return Err(Error::NotIndexed),
+                };
+                let iter = piped_edges.filter(move |r| match r {
+                    Ok(e) => edges.contains(e),
+                    Err(_) => true,
+                });
+                QueryOutputValueIter::Edges(Box::new(iter))
+            }
+            QueryOutputValueIter::Vertices(piped_vertices) => {
+                let piped_vertices = covered_vertices(txn, piped_vertices, q.name)?;
+                let vertex_ids =
+                    match (*txn).vertex_ids_with_property_range(q.name, q.lower.as_ref(), q.upper.as_ref())? {
+                        Some(iter) => iter.collect::<Result<HashSet<Uuid>>>()?,
                         None => return Err(Error::NotIndexed),
                     };
-                    let iter = piped_edges.iter().filter(move |e| {
-                        let contains = edges.contains(e);
-                        (q.equal && contains) || (!q.equal && !contains)
+                let iter = piped_vertices.filter(move |r| match r {
+                    Ok(v) => vertex_ids.contains(&v.id),
+                    Err(_) => true,
+                });
+                QueryOutputValueIter::Vertices(Box::new(iter))
+            }
+            _ => {
+                return Err(Error::OperationOnQuery);
+            }
+        },
+        Query::PipeWithPropertyPattern(ref q) => {
+            let name = q.name;
+            let pattern = q.pattern.clone();
+            let regex = pattern.compile()?;
+            let matches = move |value: &Json| match (&pattern, &regex, value.as_str()) {
+                (StringPattern::StartsWith(prefix), _, Some(s)) => s.starts_with(prefix.as_str()),
+                (_, Some(regex), Some(s)) => regex.is_match(s),
+                _ => false,
+            };
+
+            // Prefixes are looked up in the property index, if there is one.
+            // Values it doesn't cover fall back to having their property
+            // checked.
+            let scopes = index_scopes(txn, name)?;
+            let prefix_range = match q.pattern {
+                StringPattern::StartsWith(ref prefix) if !scopes.is_empty() => {
+                    let lower = Json::new(serde_json::Value::String(prefix.clone()));
+                    let upper = util::prefix_upper_bound(prefix).map(|s| Json::new(serde_json::Value::String(s)));
+                    Some((lower, upper))
+                }
+                _ => None,
+            };
+
+            match query_piped(txn, &q.inner, output)? {
+                QueryOutputValueIter::Edges(piped_edges) => {
+                    let edges = match prefix_range {
+                        Some((ref lower, ref upper)) => {
+                            let upper = upper.as_ref().map_or(Bound::Unbounded, Bound::Excluded);
+                            match (*txn).edges_with_property_range(name, Bound::Included(lower), upper)? {
+                                Some(iter) => Some(iter.collect::<Result<HashSet<Edge>>>()?),
+                                None => None,
+                            }
+                        }
+                        None => None,
+                    };
+                    let iter = piped_edges.filter_map(move |r| {
+                        let edge = match r {
+                            Ok(edge) => edge,
+                            Err(err) => return Some(Err(err)),
+                        };
+                        let matched = match edges {
+                            Some(ref edges) if scopes.iter().any(|scope| scope.covers_edge(edge.t)) => {
+                                Ok(edges.contains(&edge))
+                            }
+                            _ => (*txn)
+                                .edge_property(&edge, name)
+                                .map(|value| value.is_some_and(|v| matches(&v))),
+                        };
+                        match matched {
+                            Ok(true) => Some(Ok(edge)),
+                            Ok(false) => None,
+                            Err(err) => Some(Err(err)),
+                        }
                     });
-                    QueryOutputValue::Edges(iter.cloned().collect())
+                    QueryOutputValueIter::Edges(Box::new(iter))
                 }
-                QueryOutputValue::Vertices(ref piped_vertices) => {
-                    let vertex_ids = match (*txn).vertex_ids_with_property_value(q.name, &q.value)? {
-                        Some(iter) => iter.collect::<Result<HashSet<Uuid>>>()?,
-                        None => return Err(Error::NotIndexed),
+                QueryOutputValueIter::Vertices(piped_vertices) => {
+                    let vertex_ids = match prefix_range {
+                        Some((ref lower, ref upper)) => {
+                            let upper = upper.as_ref().map_or(Bound::Unbounded, Bound::Excluded);
+                            match (*txn).vertex_ids_with_property_range(name, Bound::Included(lower), upper)? {
+                                Some(iter) => Some(iter.collect::<Result<HashSet<Uuid>>>()?),
+                                None => None,
+                            }
+                        }
+                        None => None,
                     };
-                    let iter = piped_vertices.iter().filter(move |v| {
-                        let contains = vertex_ids.contains(&v.id);
-                        (q.equal && contains) || (!q.equal && !contains)
+                    let iter = piped_vertices.filter_map(move |r| {
+                        let vertex = match r {
+                            Ok(vertex) => vertex,
+                            Err(err) => return Some(Err(err)),
+                        };
+                        let matched = match vertex_ids {
+                            Some(ref vertex_ids) if scopes.iter().any(|scope| scope.covers_vertex(vertex.t)) => {
+                                Ok(vertex_ids.contains(&vertex.id))
+                            }
+                            _ => (*txn)
+                                .vertex_property(&vertex, name)
+                                .map(|value| value.is_some_and(|v| matches(&v))),
+                        };
+                        match matched {
+                            Ok(true) => Some(Ok(vertex)),
+                            Ok(false) => None,
+                            Err(err) => Some(Err(err)),
+                        }
                     });
-                    QueryOutputValue::Vertices(iter.cloned().collect())
+                    QueryOutputValueIter::Vertices(Box::new(iter))
                 }
                 _ => {
                     return Err(Error::OperationOnQuery);
                 }
-            };
-
-            if let Query::Include(_) = *q.inner {
-                // keep the value exported
-                output.push(piped_values);
             }
-
-            values
         }
-        Query::AllEdge => {
-            let iter = (*txn).all_edges()?;
-            QueryOutputValue::Edges(iter.collect::<Result<Vec<Edge>>>()?)
+        Query::PipeWithinBoundingBox(ref q) => {
+            q.bbox.validate()?;
+            let bbox = q.bbox;
+            query_piped_within(txn, &q.inner, output, q.name, move |point| bbox.contains(point))?
         }
-        Query::SpecificEdge(ref q) => {
-            let iter = (*txn).specific_edges(q.edges.clone())?;
-            QueryOutputValue::Edges(iter.collect::<Result<Vec<Edge>>>()?)
+        Query::PipeWithinRadius(ref q) => {
+            validate_circle(&q.center, q.radius)?;
+            let (center, radius) = (q.center, q.radius);
+            query_piped_within(txn, &q.inner, output, q.name, move |point| {
+                center.distance(point) <= radius
+            })?
+        }
+        Query::Traversal(ref q) => match query_piped(txn, &q.inner, output)? {
+            QueryOutputValueIter::Vertices(piped_vertices) => {
+                let iter = traverse(txn, piped_vertices, q);
+                QueryOutputValueIter::Vertices(Box::new(iter.take(q.limit as usize)))
+            }
+            _ => {
+                return Err(Error::OperationOnQuery);
+            }
+        },
+        Query::ShortestPath(ref q) => QueryOutputValueIter::Path(shortest_path(txn, q)?),
+        Query::OrderBy(ref q) => {
+            let name = q.name;
+            match query_piped(txn, &q.inner, output)? {
+                QueryOutputValueIter::Vertices(piped_vertices) => {
+                    let items = piped_vertices.map(|r| {
+                        let vertex = r?;
+                        let value = (*txn).vertex_property(&vertex, name)?;
+                        Ok((value, vertex.id, vertex))
+                    });
+                    let (cursor, vertices) = ordered_page(items, q)?;
+                    output.push(QueryOutputValueIter::Cursor(cursor));
+                    QueryOutputValueIter::Vertices(Box::new(vertices.into_iter().map(Ok)))
+                }
+                QueryOutputValueIter::Edges(piped_edges) => {
+                    let items = piped_edges.map(|r| {
+                        let edge = r?;
+                        let value = (*txn).edge_property(&edge, name)?;
+                        Ok((value, edge.clone(), edge))
+                    });
+                    let (cursor, edges) = ordered_page(items, q)?;
+                    output.push(QueryOutputValueIter::Cursor(cursor));
+                    QueryOutputValueIter::Edges(Box::new(edges.into_iter().map(Ok)))
+                }
+                _ => {
+                    return Err(Error::OperationOnQuery);
+                }
+            }
         }
+        Query::AllEdge => QueryOutputValueIter::Edges((*txn).all_edges()?),
+        Query::SpecificEdge(ref q) => QueryOutputValueIter::Edges((*txn).specific_edges(q.edges.clone())?),
         Query::Include(ref q) => {
-            query(txn, &q.inner, output)?;
+            query_iter(txn, &q.inner, output)?;
             output.pop().unwrap()
         }
         Query::Count(ref q) => {
@@ -673,26 +2716,86 @@ unsafe fn query<'a, T: Transaction<'a> + 'a>(
                 Query::AllVertex => (*txn).vertex_count(),
                 Query::AllEdge => (*txn).edge_count(),
                 q => {
-                    query(txn, q, output)?;
-                    let piped_values = output.pop().unwrap();
-                    let len = match piped_values {
-                        QueryOutputValue::Vertices(ref v) => v.len(),
-                        QueryOutputValue::Edges(ref e) => e.len(),
-                        QueryOutputValue::VertexProperties(ref p) => p.len(),
-                        QueryOutputValue::EdgeProperties(ref p) => p.len(),
+                    let len = match query_piped(txn, q, output)? {
+                        QueryOutputValueIter::Vertices(iter) => count_iter(iter)?,
+                        QueryOutputValueIter::Edges(iter) => count_iter(iter)?,
+                        QueryOutputValueIter::VertexProperties(iter) => count_iter(iter)?,
+                        QueryOutputValueIter::EdgeProperties(iter) => count_iter(iter)?,
                         _ => return Err(Error::OperationOnQuery),
                     };
-                    if let Query::Include(_) = q {
-                        // keep the value exported
-                        output.push(piped_values);
-                    }
                     len as u64
                 }
             };
-            QueryOutputValue::Count(count)
+            QueryOutputValueIter::Count(count)
+        }
+        Query::Sum(ref q) => {
+            let values = piped_property_values(txn, query_piped(txn, &q.inner, output)?, q.name)?;
+            let sum = match sum_values(values)? {
+                (_, _, 0) => None,
+                (_, Some(int_sum), _) => Some(Json::new(int_sum.into())),
+                (sum, None, _) => Some(Json::new(sum.into())),
+            };
+            QueryOutputValueIter::Aggregate(sum)
+        }
+        Query::Min(ref q) => {
+            let values = piped_property_values(txn, query_piped(txn, &q.inner, output)?, q.name)?;
+            QueryOutputValueIter::Aggregate(extreme_value(values, false)?)
+        }
+        Query::Max(ref q) => {
+            let values = piped_property_values(txn, query_piped(txn, &q.inner, output)?, q.name)?;
+            QueryOutputValueIter::Aggregate(extreme_value(values, true)?)
+        }
+        Query::Avg(ref q) => {
+            let values = piped_property_values(txn, query_piped(txn, &q.inner, output)?, q.name)?;
+            let avg = match sum_values(values)? {
+                (_, _, 0) => None,
+                (sum, _, count) => Some(Json::new((sum / count as f64).into())),
+            };
+            QueryOutputValueIter::Aggregate(avg)
+        }
+        Query::GroupBy(ref q) => {
+            let piped = query_piped(txn, &q.inner, output)?;
+            let keys: DynIter<Json> = match (&q.key, piped) {
+                (GroupKey::Type, QueryOutputValueIter::Vertices(iter)) => {
+                    Box::new(iter.map(|r| Ok(Json::new(r?.t.as_str().into()))))
+                }
+                (GroupKey::Type, QueryOutputValueIter::Edges(iter)) => {
+                    Box::new(iter.map(|r| Ok(Json::new(r?.t.as_str().into()))))
+                }
+                (GroupKey::Type, _) => return Err(Error::OperationOnQuery),
+                (GroupKey::Property(name), piped) => piped_property_values(txn, piped, *name)?,
+            };
+            let mut groups: BTreeMap<Vec<u8>, Group> = BTreeMap::new();
+            for key in keys {
+                let key = key?;
+                groups.entry(sort_key(&key)).or_insert_with(|| Group::new(key, 0)).count += 1;
+            }
+            QueryOutputValueIter::Groups(groups.into_values().collect())
+        }
+        Query::Degree(ref q) => {
+            let t = q.t;
+            let iter = match query_piped(txn, &q.inner, output)? {
+                QueryOutputValueIter::Vertices(iter) => iter.map(move |r| {
+                    let id = r?.id;
+                    let outbound = (*txn).vertex_degree(id, EdgeDirection::Outbound, t)?;
+                    let inbound = (*txn).vertex_degree(id, EdgeDirection::Inbound, t)?;
+                    Ok(Degree::new(id, outbound, inbound))
+                }),
+                _ => return Err(Error::OperationOnQuery),
+            };
+            QueryOutputValueIter::Degrees(Box::new(iter))
         }
     };
 
     output.push(value);
     Ok(())
 }
+
+fn count_iter<T>(iter: DynIter<'_, T>) -> Result<usize> {
+    let mut len = 0;
+    for result in iter {
+        result?;
+        len += 1;
+    }
+    Ok(len)
+}
@@ -7,7 +7,7 @@ use std::result::Result as StdResult;
 use bincode::Error as BincodeError;
 use rmp_serde::encode::Error as RmpEncodeError;
 #[cfg(feature = "rocksdb-datastore")]
-use rocksdb::Error as RocksDbError;
+use rocksdb::{Error as RocksDbError, ErrorKind};
 use serde_json::Error as JsonError;
 
 /// An error triggered by the datastore.
@@ -35,6 +35,14 @@ pub enum Error {
     /// The operation cannot work with the given query, based off it's output
     /// type (e.g. attempting to delete using a query that outputs a count.)
     OperationOnQuery,
+
+    /// A property value would be the same as another vertex's, on a property
+    /// that's unique for the vertex type.
+    ConstraintViolation,
+
+    /// The transaction conflicted with a concurrent transaction that wrote to
+    /// the same data, and was not committed. It can be retried.
+    Conflict,
 }
 
 impl StdError for Error {
@@ -58,6 +66,8 @@ impl fmt::Display for Error {
             Error::Unsupported => write!(f, "functionality not supported"),
             Error::Invalid(ref err) => write!(f, "{err}"),
             Error::OperationOnQuery => write!(f, "the operation cannot work with the given query"),
+            Error::ConstraintViolation => write!(f, "property value violates a unique constraint"),
+            Error::Conflict => write!(f, "transaction conflicted with a concurrent transaction"),
         }
     }
 }
@@ -90,7 +100,12 @@ impl From<RmpEncodeError> for Error {
 #[cfg(feature = "rocksdb-datastore")]
 impl From<RocksDbError> for Error {
     fn from(err: RocksDbError) -> Self {
-        Error::Datastore(Box::new(err))
+        match err.kind() {
+            // Optimistic transactions fail to commit with these when another
+            // transaction wrote to the same keys
+            ErrorKind::Busy | ErrorKind::TryAgain => Error::Conflict,
+            _ => Error::Datastore(Box::new(err)),
+        }
     }
 }
 
@@ -116,6 +131,13 @@ pub enum ValidationError {
     /// a query that gets vertex properties from a query that outputs a
     /// count.)
     InnerQuery,
+    /// The text of a query couldn't be parsed.
+    Syntax {
+        /// The byte offset in the text where parsing failed.
+        position: usize,
+        /// A description of what was expected.
+        message: String,
+    },
 }
 
 impl StdError for ValidationError {}
@@ -127,6 +149,9 @@ impl fmt::Display for ValidationError {
             ValidationError::ValueTooLong => write!(f, "value too long"),
             ValidationError::CannotIncrementUuid => write!(f, "could not increment the UUID"),
             ValidationError::InnerQuery => write!(f, "the given query combination cannot be nested"),
+            ValidationError::Syntax { position, ref message } => {
+                write!(f, "syntax error at position {position}: {message}")
+            }
         }
     }
 }
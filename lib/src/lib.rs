@@ -23,6 +23,8 @@ mod database;
 mod errors;
 mod memory;
 mod models;
+mod parser;
+mod planner;
 pub mod util;
 
 pub use crate::database::*;
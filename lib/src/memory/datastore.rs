@@ -1,13 +1,21 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
+use std::iter;
+use std::ops::Bound;
 use std::path::PathBuf;
 use std::result::Result as StdResult;
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
+use super::rtree::RTree;
 use crate::errors::{Error, Result};
 use crate::util;
-use crate::{Database, Datastore, DynIter, Edge, Identifier, Json, Transaction, Vertex};
+use crate::{
+    BoundingBox, Database, Datastore, DynIter, Edge, EdgeDirection, GeoPoint, Identifier, IndexScope, Json,
+    PropertyValueCount, Stats, Transaction, UniqueConstraint, Vertex, VertexPoint, VertexVector,
+};
 
 use rmp_serde::decode::Error as RmpDecodeError;
 use serde::{Deserialize, Serialize};
@@ -20,8 +28,77 @@ enum IndexedPropertyMember {
     Edge(Edge),
 }
 
+// A value in a property index. Values are ordered by their encoding, as
+// written by `util::Component::Json`, so that a range of them can be read
+// from the index without scanning it. They're persisted as plain JSON.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "Json", into = "Json")]
+struct IndexedValue {
+    encoded: Vec<u8>,
+    value: Json,
+}
+
+impl From<Json> for IndexedValue {
+    fn from(value: Json) -> Self {
+        Self {
+            encoded: encode_value(&value),
+            value,
+        }
+    }
+}
+
+impl From<IndexedValue> for Json {
+    fn from(value: IndexedValue) -> Self {
+        value.value
+    }
+}
+
+impl PartialEq for IndexedValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.encoded == other.encoded
+    }
+}
+
+impl Eq for IndexedValue {}
+
+impl PartialOrd for IndexedValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for IndexedValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.encoded.cmp(&other.encoded)
+    }
+}
+
+impl Borrow<[u8]> for IndexedValue {
+    fn borrow(&self) -> &[u8] {
+        &self.encoded
+    }
+}
+
+fn encode_value(value: &Json) -> Vec<u8> {
+    util::build(&[util::Component::Json(value)])
+}
+
+// Gets the entries of a property index with values in a range.
+fn property_values_in_range<'a>(
+    container: &'a BTreeMap<IndexedValue, HashSet<IndexedPropertyMember>>,
+    range: &'a util::JsonRange,
+) -> impl Iterator<Item = &'a HashSet<IndexedPropertyMember>> + 'a {
+    let end = match range.end {
+        Some(ref end) => Bound::Excluded(end.as_slice()),
+        None => Bound::Unbounded,
+    };
+    container
+        .range::<[u8], _>((Bound::Included(range.start.as_slice()), end))
+        .map(|(_, members)| members)
+}
+
 // All of the data is actually stored in this struct, which is stored
-// internally to the datastore itself. This way, we can wrap a mutex around
+// internally to the datastore itself. This way, we can wrap a lock around
 // the entire datastore, rather than on a per-data structure basis, as the
 // latter approach would risk deadlocking without extreme care.
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -31,49 +108,176 @@ struct InternalMemory {
     reversed_edges: BTreeSet<Edge>,
     vertex_properties: BTreeMap<(Uuid, Identifier), Json>,
     edge_properties: BTreeMap<(Edge, Identifier), Json>,
-    property_values: HashMap<Identifier, HashMap<Json, HashSet<IndexedPropertyMember>>>,
+    property_values: HashMap<Identifier, BTreeMap<IndexedValue, HashSet<IndexedPropertyMember>>>,
+    // Images persisted before index scopes were introduced don't have this,
+    // in which case every indexed property is scoped to everything
+    #[serde(default)]
+    property_index_scopes: HashMap<Identifier, HashSet<IndexScope>>,
+    // Composite indexes, keyed by their property names. Each maps the
+    // encoded values of a vertex's properties, in index order, to the
+    // vertices that have them.
+    #[serde(default)]
+    composite_values: HashMap<Vec<Identifier>, BTreeMap<Vec<u8>, HashSet<Uuid>>>,
+    // Unique constraints. Each maps the encoded values of the property to
+    // the vertex of the type that has it.
+    #[serde(default)]
+    unique_values: HashMap<UniqueConstraint, HashMap<Vec<u8>, Uuid>>,
+    // Full-text indexes, keyed by property name. Each maps the terms in
+    // string values to the vertices whose values contain them.
+    #[serde(default)]
+    fulltext_values: HashMap<Identifier, HashMap<String, HashSet<Uuid>>>,
+    // Vector indexes, keyed by property name. Each maps vertices to the
+    // vectors read from their values.
+    #[serde(default)]
+    vector_values: HashMap<Identifier, BTreeMap<Uuid, Vec<f64>>>,
+    // Geospatial indexes, keyed by property name. Each holds the points
+    // read from vertices' values.
+    #[serde(default)]
+    geo_values: HashMap<Identifier, RTree>,
+    // The number of vertices and edges of each type. These are counted when
+    // an image is read rather than persisted.
+    #[serde(skip)]
+    vertex_type_counts: HashMap<Identifier, u64>,
+    #[serde(skip)]
+    edge_type_counts: HashMap<Identifier, u64>,
 }
 
-pub struct MemoryTransaction<'a> {
-    internal: MutexGuard<'a, InternalMemory>,
-    path: Option<PathBuf>,
+fn increment_count(counts: &mut HashMap<Identifier, u64>, t: Identifier) {
+    *counts.entry(t).or_default() += 1;
 }
 
-impl<'a> Transaction<'a> for MemoryTransaction<'a> {
+fn decrement_count(counts: &mut HashMap<Identifier, u64>, t: Identifier) {
+    if let Some(count) = counts.get_mut(&t) {
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(&t);
+        }
+    }
+}
+
+// Builds the key of a vertex in a composite index, or `None` if the vertex
+// doesn't have every property of the index.
+fn composite_key(
+    vertex_properties: &BTreeMap<(Uuid, Identifier), Json>,
+    names: &[Identifier],
+    id: Uuid,
+) -> Option<Vec<u8>> {
+    let mut components = Vec::with_capacity(names.len());
+    for name in names {
+        components.push(util::Component::Json(vertex_properties.get(&(id, *name))?));
+    }
+    Some(util::build(&components))
+}
+
+impl InternalMemory {
     fn vertex_count(&self) -> u64 {
-        self.internal.vertices.len() as u64
+        self.vertices.len() as u64
     }
 
-    fn all_vertices(&'a self) -> Result<DynIter<'a, Vertex>> {
-        let iter = self
-            .internal
-            .vertices
-            .iter()
-            .map(|(id, t)| Ok(Vertex::with_id(*id, *t)));
+    // Inserts a vertex, returning whether it was inserted, which it isn't if
+    // a vertex with the same ID already exists.
+    fn insert_vertex(&mut self, id: Uuid, t: Identifier) -> bool {
+        if self.vertices.contains_key(&id) {
+            return false;
+        }
+        self.vertices.insert(id, t);
+        increment_count(&mut self.vertex_type_counts, t);
+        true
+    }
+
+    fn remove_vertex(&mut self, id: Uuid) -> Option<Identifier> {
+        let t = self.vertices.remove(&id)?;
+        decrement_count(&mut self.vertex_type_counts, t);
+        Some(t)
+    }
+
+    // Inserts an edge, returning whether it was inserted, which it isn't if
+    // it already exists.
+    fn insert_edge(&mut self, edge: Edge) -> bool {
+        if !self.edges.insert(edge.clone()) {
+            return false;
+        }
+        increment_count(&mut self.edge_type_counts, edge.t);
+        self.reversed_edges.insert(edge.reversed());
+        true
+    }
+
+    fn remove_edge(&mut self, edge: &Edge) -> bool {
+        if !self.edges.remove(edge) {
+            return false;
+        }
+        decrement_count(&mut self.edge_type_counts, edge.t);
+        self.reversed_edges.remove(&edge.reversed());
+        true
+    }
+
+    fn count_types(&mut self) {
+        self.vertex_type_counts.clear();
+        for t in self.vertices.values() {
+            increment_count(&mut self.vertex_type_counts, *t);
+        }
+        self.edge_type_counts.clear();
+        for edge in &self.edges {
+            increment_count(&mut self.edge_type_counts, edge.t);
+        }
+    }
+
+    fn stats(&self) -> Result<Stats> {
+        let mut property_value_counts = Vec::new();
+        for (name, container) in &self.property_values {
+            for (value, members) in container {
+                let mut count = PropertyValueCount {
+                    name: *name,
+                    value: value.value.clone(),
+                    vertex_count: 0,
+                    edge_count: 0,
+                };
+                for member in members {
+                    match member {
+                        IndexedPropertyMember::Vertex(id) if self.vertices.contains_key(id) => count.vertex_count += 1,
+                        IndexedPropertyMember::Edge(edge) if self.edges.contains(edge) => count.edge_count += 1,
+                        _ => {}
+                    }
+                }
+                if count.vertex_count > 0 || count.edge_count > 0 {
+                    property_value_counts.push(count);
+                }
+            }
+        }
+        property_value_counts
+            .sort_by_cached_key(|count| (count.name, util::build(&[util::Component::Json(&count.value)])));
+
+        Ok(Stats {
+            vertex_count: self.vertex_count(),
+            edge_count: self.edge_count(),
+            vertex_type_counts: self.vertex_type_counts.iter().map(|(t, count)| (*t, *count)).collect(),
+            edge_type_counts: self.edge_type_counts.iter().map(|(t, count)| (*t, *count)).collect(),
+            property_value_counts,
+        })
+    }
+
+    fn all_vertices(&self) -> Result<DynIter<'_, Vertex>> {
+        let iter = self.vertices.iter().map(|(id, t)| Ok(Vertex::with_id(*id, *t)));
         Ok(Box::new(iter))
     }
 
-    fn range_vertices(&'a self, offset: Uuid) -> Result<DynIter<'a, Vertex>> {
+    fn range_vertices(&self, offset: Uuid) -> Result<DynIter<'_, Vertex>> {
         let iter = self
-            .internal
             .vertices
             .range(offset..)
             .map(|(id, t)| Ok(Vertex::with_id(*id, *t)));
         Ok(Box::new(iter))
     }
 
-    fn specific_vertices(&'a self, ids: Vec<Uuid>) -> Result<DynIter<'a, Vertex>> {
-        let iter = ids.into_iter().filter_map(move |id| {
-            self.internal
-                .vertices
-                .get(&id)
-                .map(|value| Ok(Vertex::with_id(id, *value)))
-        });
+    fn specific_vertices(&self, ids: Vec<Uuid>) -> Result<DynIter<'_, Vertex>> {
+        let iter = ids
+            .into_iter()
+            .filter_map(move |id| self.vertices.get(&id).map(|value| Ok(Vertex::with_id(id, *value))));
         Ok(Box::new(iter))
     }
 
-    fn vertex_ids_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Uuid>>> {
-        if let Some(container) = self.internal.property_values.get(&name) {
+    fn vertex_ids_with_property(&self, name: Identifier) -> Result<Option<DynIter<'_, Uuid>>> {
+        if let Some(container) = self.property_values.get(&name) {
             let mut vertex_ids = HashSet::<Uuid>::default();
             for sub_container in container.values() {
                 for member in sub_container {
@@ -88,9 +292,9 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         }
     }
 
-    fn vertex_ids_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Uuid>>> {
-        if let Some(container) = self.internal.property_values.get(&name) {
-            if let Some(sub_container) = container.get(value) {
+    fn vertex_ids_with_property_value(&self, name: Identifier, value: &Json) -> Result<Option<DynIter<'_, Uuid>>> {
+        if let Some(container) = self.property_values.get(&name) {
+            if let Some(sub_container) = container.get(encode_value(value).as_slice()) {
                 let iter = Box::new(sub_container.iter().filter_map(move |member| match member {
                     IndexedPropertyMember::Vertex(id) => Some(Ok(*id)),
                     _ => None,
@@ -105,35 +309,130 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         }
     }
 
+    fn vertex_ids_with_property_range(
+        &self,
+        name: Identifier,
+        lower: Bound<&Json>,
+        upper: Bound<&Json>,
+    ) -> Result<Option<DynIter<'_, Uuid>>> {
+        if let Some(container) = self.property_values.get(&name) {
+            let mut vertex_ids = HashSet::<Uuid>::default();
+            if let Some(range) = util::JsonRange::new(lower, upper) {
+                for sub_container in property_values_in_range(container, &range) {
+                    for member in sub_container {
+                        if let IndexedPropertyMember::Vertex(id) = member {
+                            vertex_ids.insert(*id);
+                        }
+                    }
+                }
+            }
+            Ok(Some(Box::new(vertex_ids.into_iter().map(Ok))))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn vertex_ids_with_composite_values(
+        &self,
+        names: &[Identifier],
+        values: &[Json],
+    ) -> Result<Option<DynIter<'_, Uuid>>> {
+        if let Some(container) = self.composite_values.get(names) {
+            // JSON values are encoded without being a prefix of one another,
+            // so the keys matching a prefix of values are contiguous
+            let components: Vec<util::Component> = values.iter().map(util::Component::Json).collect();
+            let prefix = util::build(&components);
+            let iter = container
+                .range(prefix.clone()..)
+                .take_while(move |(key, _)| key.starts_with(&prefix))
+                .flat_map(|(_, ids)| ids.iter().map(|id| Ok(*id)));
+            Ok(Some(Box::new(iter)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn vertex_ids_with_property_terms(&self, name: Identifier, terms: &[String]) -> Result<Option<DynIter<'_, Uuid>>> {
+        if let Some(container) = self.fulltext_values.get(&name) {
+            let mut term_ids: Vec<&HashSet<Uuid>> = Vec::with_capacity(terms.len());
+            for term in terms {
+                match container.get(term) {
+                    Some(ids) => term_ids.push(ids),
+                    None => return Ok(Some(Box::new(iter::empty()))),
+                }
+            }
+            // Start from the rarest term, so that as few vertices as
+            // possible are checked against the rest
+            term_ids.sort_by_key(|ids| ids.len());
+            let vertex_ids: Vec<Uuid> = match term_ids.split_first() {
+                Some((first, rest)) => first
+                    .iter()
+                    .filter(|id| rest.iter().all(|ids| ids.contains(id)))
+                    .copied()
+                    .collect(),
+                None => Vec::new(),
+            };
+            Ok(Some(Box::new(vertex_ids.into_iter().map(Ok))))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn vertex_property_vectors(&self, name: Identifier) -> Result<Option<DynIter<'_, VertexVector>>> {
+        if let Some(container) = self.vector_values.get(&name) {
+            let iter = container.iter().map(|(id, vector)| Ok((*id, vector.clone())));
+            Ok(Some(Box::new(iter)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn vertex_points_within(&self, name: Identifier, bbox: &BoundingBox) -> Result<Option<DynIter<'_, VertexPoint>>> {
+        if let Some(container) = self.geo_values.get(&name) {
+            Ok(Some(Box::new(container.search(bbox).into_iter().map(Ok))))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn edge_count(&self) -> u64 {
-        self.internal.edges.len() as u64
+        self.edges.len() as u64
     }
 
-    fn all_edges(&'a self) -> Result<DynIter<'a, Edge>> {
-        let iter = self.internal.edges.iter().map(|e| Ok(e.clone()));
+    fn all_edges(&self) -> Result<DynIter<'_, Edge>> {
+        let iter = self.edges.iter().map(|e| Ok(e.clone()));
         Ok(Box::new(iter))
     }
 
-    fn range_edges(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
-        let iter = self.internal.edges.range(offset..).map(|e| Ok(e.clone()));
+    fn range_edges(&self, offset: Edge) -> Result<DynIter<'_, Edge>> {
+        let iter = self.edges.range(offset..).map(|e| Ok(e.clone()));
         Ok(Box::new(iter))
     }
 
-    fn range_reversed_edges(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
-        let iter = self.internal.reversed_edges.range(offset..).map(|e| Ok(e.clone()));
+    fn range_reversed_edges(&self, offset: Edge) -> Result<DynIter<'_, Edge>> {
+        let iter = self.reversed_edges.range(offset..).map(|e| Ok(e.clone()));
         Ok(Box::new(iter))
     }
 
-    fn specific_edges(&'a self, edges: Vec<Edge>) -> Result<DynIter<'a, Edge>> {
-        let iter = edges
-            .into_iter()
-            .filter(move |edge| self.internal.edges.contains(edge))
-            .map(Ok);
+    fn vertex_degree(&self, id: Uuid, direction: EdgeDirection, t: Option<Identifier>) -> u64 {
+        let edges = match direction {
+            EdgeDirection::Outbound => &self.edges,
+            EdgeDirection::Inbound => &self.reversed_edges,
+        };
+        let lower_bound = Edge::new(id, t.unwrap_or_default(), Uuid::default());
+        edges
+            .range(lower_bound..)
+            .take_while(|e| e.outbound_id == id && t.is_none_or(|t| e.t == t))
+            .count() as u64
+    }
+
+    fn specific_edges(&self, edges: Vec<Edge>) -> Result<DynIter<'_, Edge>> {
+        let iter = edges.into_iter().filter(move |edge| self.edges.contains(edge)).map(Ok);
         Ok(Box::new(iter))
     }
 
-    fn edges_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Edge>>> {
-        if let Some(container) = self.internal.property_values.get(&name) {
+    fn edges_with_property(&self, name: Identifier) -> Result<Option<DynIter<'_, Edge>>> {
+        if let Some(container) = self.property_values.get(&name) {
             let mut edges = HashSet::<Edge>::default();
             for sub_container in container.values() {
                 for member in sub_container {
@@ -148,11 +447,11 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         }
     }
 
-    fn edges_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Edge>>> {
-        if let Some(container) = self.internal.property_values.get(&name) {
-            if let Some(sub_container) = container.get(value) {
+    fn edges_with_property_value(&self, name: Identifier, value: &Json) -> Result<Option<DynIter<'_, Edge>>> {
+        if let Some(container) = self.property_values.get(&name) {
+            if let Some(sub_container) = container.get(encode_value(value).as_slice()) {
                 let iter = Box::new(sub_container.iter().filter_map(move |member| match member {
-                    IndexedPropertyMember::Edge(edge) if self.internal.edges.contains(edge) => Some(edge),
+                    IndexedPropertyMember::Edge(edge) if self.edges.contains(edge) => Some(edge),
                     _ => None,
                 }));
                 Ok(Some(Box::new(iter.map(|e| Ok(e.clone())))))
@@ -165,36 +464,61 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         }
     }
 
+    fn edges_with_property_range(
+        &self,
+        name: Identifier,
+        lower: Bound<&Json>,
+        upper: Bound<&Json>,
+    ) -> Result<Option<DynIter<'_, Edge>>> {
+        if let Some(container) = self.property_values.get(&name) {
+            let mut edges = HashSet::<Edge>::default();
+            if let Some(range) = util::JsonRange::new(lower, upper) {
+                for sub_container in property_values_in_range(container, &range) {
+                    for member in sub_container {
+                        if let IndexedPropertyMember::Edge(edge) = member {
+                            if self.edges.contains(edge) {
+                                edges.insert(edge.clone());
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(Some(Box::new(edges.into_iter().map(Ok))))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn vertex_property(&self, vertex: &Vertex, name: Identifier) -> Result<Option<Json>> {
-        if let Some(value) = self.internal.vertex_properties.get(&(vertex.id, name)) {
+        if let Some(value) = self.vertex_properties.get(&(vertex.id, name)) {
             Ok(Some(value.clone()))
         } else {
             Ok(None)
         }
     }
 
-    fn all_vertex_properties_for_vertex(&'a self, vertex: &Vertex) -> Result<DynIter<'a, (Identifier, Json)>> {
+    fn all_vertex_properties_for_vertex(&self, vertex: &Vertex) -> Result<DynIter<'_, (Identifier, Json)>> {
         let mut vertex_properties = Vec::new();
         let from = &(vertex.id, Identifier::default());
         let to = &(util::next_uuid(vertex.id).unwrap(), Identifier::default());
-        for ((_prop_vertex_id, prop_name), prop_value) in self.internal.vertex_properties.range(from..to) {
+        for ((_prop_vertex_id, prop_name), prop_value) in self.vertex_properties.range(from..to) {
             vertex_properties.push((*prop_name, prop_value.clone()));
         }
         Ok(Box::new(vertex_properties.into_iter().map(Ok)))
     }
 
     fn edge_property(&self, edge: &Edge, name: Identifier) -> Result<Option<Json>> {
-        if let Some(value) = self.internal.edge_properties.get(&(edge.clone(), name)) {
+        if let Some(value) = self.edge_properties.get(&(edge.clone(), name)) {
             Ok(Some(value.clone()))
         } else {
             Ok(None)
         }
     }
 
-    fn all_edge_properties_for_edge(&'a self, edge: &Edge) -> Result<DynIter<'a, (Identifier, Json)>> {
+    fn all_edge_properties_for_edge(&self, edge: &Edge) -> Result<DynIter<'_, (Identifier, Json)>> {
         let mut edge_properties = Vec::new();
         let from = &(edge.clone(), Identifier::default());
-        for ((prop_edge, prop_name), prop_value) in self.internal.edge_properties.range(from..) {
+        for ((prop_edge, prop_name), prop_value) in self.edge_properties.range(from..) {
             if prop_edge != edge {
                 break;
             }
@@ -203,10 +527,669 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         Ok(Box::new(edge_properties.into_iter().map(Ok)))
     }
 
+    // The number of vertices and edges with an indexed property value, which
+    // is an upper bound of either alone
+    fn property_value_count(&self, name: Identifier, value: &Json) -> Option<u64> {
+        let container = self.property_values.get(&name)?;
+        Some(
+            container
+                .get(encode_value(value).as_slice())
+                .map_or(0, |members| members.len() as u64),
+        )
+    }
+
+    fn indexed_properties(&self) -> Result<HashMap<Identifier, HashSet<IndexScope>>> {
+        Ok(self.property_index_scopes.clone())
+    }
+
+    fn is_vertex_indexed(&self, name: Identifier, id: Uuid) -> bool {
+        match (self.property_index_scopes.get(&name), self.vertices.get(&id)) {
+            (Some(scopes), Some(t)) => scopes.iter().any(|scope| scope.covers_vertex(*t)),
+            _ => false,
+        }
+    }
+
+    fn is_edge_indexed(&self, name: Identifier, edge: &Edge) -> bool {
+        match self.property_index_scopes.get(&name) {
+            Some(scopes) => scopes.iter().any(|scope| scope.covers_edge(edge.t)),
+            None => false,
+        }
+    }
+
+    // Rebuilds the index for a property from scratch, based on the scopes
+    // it's currently indexed under.
+    fn rebuild_property_index(&mut self, name: Identifier) {
+        let scopes = match self.property_index_scopes.get(&name) {
+            Some(scopes) if !scopes.is_empty() => scopes,
+            _ => {
+                self.property_index_scopes.remove(&name);
+                self.property_values.remove(&name);
+                return;
+            }
+        };
+
+        let mut property_container: BTreeMap<IndexedValue, HashSet<IndexedPropertyMember>> = BTreeMap::new();
+        for (id, t) in self.vertices.iter() {
+            if !scopes.iter().any(|scope| scope.covers_vertex(*t)) {
+                continue;
+            }
+            if let Some(value) = self.vertex_properties.get(&(*id, name)) {
+                property_container
+                    .entry(value.clone().into())
+                    .or_default()
+                    .insert(IndexedPropertyMember::Vertex(*id));
+            }
+        }
+        for edge in self.edges.iter() {
+            if !scopes.iter().any(|scope| scope.covers_edge(edge.t)) {
+                continue;
+            }
+            if let Some(value) = self.edge_properties.get(&(edge.clone(), name)) {
+                property_container
+                    .entry(value.clone().into())
+                    .or_default()
+                    .insert(IndexedPropertyMember::Edge(edge.clone()));
+            }
+        }
+
+        self.property_values.insert(name, property_container);
+    }
+
+    fn composite_indexes(&self) -> Result<HashSet<Vec<Identifier>>> {
+        Ok(self.composite_values.keys().cloned().collect())
+    }
+
+    // Adds a vertex to the composite indexes that include a property. This
+    // should be called after the property is set.
+    fn index_composite_values(&mut self, id: Uuid, name: Identifier) {
+        for (names, container) in self.composite_values.iter_mut() {
+            if names.contains(&name) {
+                if let Some(key) = composite_key(&self.vertex_properties, names, id) {
+                    container.entry(key).or_default().insert(id);
+                }
+            }
+        }
+    }
+
+    // Removes a vertex from the composite indexes that include a property.
+    // This should be called before the property is changed, since the
+    // vertex's entries are keyed by its current values.
+    fn unindex_composite_values(&mut self, id: Uuid, name: Identifier) {
+        for (names, container) in self.composite_values.iter_mut() {
+            if names.contains(&name) {
+                if let Some(key) = composite_key(&self.vertex_properties, names, id) {
+                    if let Some(ids) = container.get_mut(&key) {
+                        ids.remove(&id);
+                        if ids.is_empty() {
+                            container.remove(&key);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn unique_constraints(&self) -> Result<HashSet<UniqueConstraint>> {
+        Ok(self.unique_values.keys().copied().collect())
+    }
+
+    // Checks that setting a property on a vertex wouldn't give it the same
+    // value as another vertex of its type, if the property is unique for
+    // the type.
+    fn check_unique_value(&self, id: Uuid, name: Identifier, value: &Json) -> Result<()> {
+        if let Some(t) = self.vertices.get(&id) {
+            if let Some(owners) = self.unique_values.get(&UniqueConstraint::new(*t, name)) {
+                let key = util::build(&[util::Component::Json(value)]);
+                if owners.get(&key).is_some_and(|owner| *owner != id) {
+                    return Err(Error::ConstraintViolation);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Records a vertex's value for a property, if the property is unique for
+    // the vertex's type. This should be called after the property is set.
+    fn index_unique_value(&mut self, id: Uuid, name: Identifier) {
+        if let (Some(t), Some(value)) = (self.vertices.get(&id), self.vertex_properties.get(&(id, name))) {
+            if let Some(owners) = self.unique_values.get_mut(&UniqueConstraint::new(*t, name)) {
+                owners.insert(util::build(&[util::Component::Json(value)]), id);
+            }
+        }
+    }
+
+    // Removes a vertex's recorded value for a property. This should be
+    // called before the property is changed.
+    fn unindex_unique_value(&mut self, id: Uuid, name: Identifier) {
+        if let Some(value) = self.vertex_properties.get(&(id, name)) {
+            let key = util::build(&[util::Component::Json(value)]);
+            for (constraint, owners) in self.unique_values.iter_mut() {
+                if constraint.name == name && owners.get(&key) == Some(&id) {
+                    owners.remove(&key);
+                }
+            }
+        }
+    }
+
+    // Gets the values of a property for vertices of a type, failing if any
+    // two are the same.
+    fn build_unique_values(&self, constraint: UniqueConstraint) -> Result<HashMap<Vec<u8>, Uuid>> {
+        let mut owners = HashMap::new();
+        for (id, t) in self.vertices.iter() {
+            if *t != constraint.t {
+                continue;
+            }
+            if let Some(value) = self.vertex_properties.get(&(*id, constraint.name)) {
+                if owners
+                    .insert(util::build(&[util::Component::Json(value)]), *id)
+                    .is_some()
+                {
+                    return Err(Error::ConstraintViolation);
+                }
+            }
+        }
+        Ok(owners)
+    }
+
+    fn fulltext_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        Ok(self.fulltext_values.keys().copied().collect())
+    }
+
+    // Adds a vertex's value for a property to the property's full-text
+    // index, if it has one. This should be called after the property is
+    // set.
+    fn index_fulltext_value(&mut self, id: Uuid, name: Identifier) {
+        if let (Some(container), Some(value)) = (
+            self.fulltext_values.get_mut(&name),
+            self.vertex_properties.get(&(id, name)),
+        ) {
+            if let Some(text) = value.as_str() {
+                for term in util::tokenize(text) {
+                    container.entry(term).or_default().insert(id);
+                }
+            }
+        }
+    }
+
+    // Removes a vertex's value for a property from the property's full-text
+    // index. This should be called before the property is changed.
+    fn unindex_fulltext_value(&mut self, id: Uuid, name: Identifier) {
+        if let (Some(container), Some(value)) = (
+            self.fulltext_values.get_mut(&name),
+            self.vertex_properties.get(&(id, name)),
+        ) {
+            if let Some(text) = value.as_str() {
+                for term in util::tokenize(text) {
+                    if let Some(ids) = container.get_mut(&term) {
+                        ids.remove(&id);
+                        if ids.is_empty() {
+                            container.remove(&term);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Builds a full-text index from scratch.
+    fn rebuild_fulltext_index(&mut self, name: Identifier) {
+        let mut container: HashMap<String, HashSet<Uuid>> = HashMap::new();
+        for id in self.vertices.keys() {
+            if let Some(text) = self
+                .vertex_properties
+                .get(&(*id, name))
+                .and_then(|value| value.as_str())
+            {
+                for term in util::tokenize(text) {
+                    container.entry(term).or_default().insert(*id);
+                }
+            }
+        }
+        self.fulltext_values.insert(name, container);
+    }
+
+    fn vector_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        Ok(self.vector_values.keys().copied().collect())
+    }
+
+    // Adds a vertex's value for a property to the property's vector index,
+    // if it has one. This should be called after the property is set.
+    fn index_vector_value(&mut self, id: Uuid, name: Identifier) {
+        if let (Some(container), Some(value)) = (
+            self.vector_values.get_mut(&name),
+            self.vertex_properties.get(&(id, name)),
+        ) {
+            if let Some(vector) = util::json_to_vector(value) {
+                container.insert(id, vector);
+            }
+        }
+    }
+
+    // Removes a vertex's value for a property from the property's vector
+    // index.
+    fn unindex_vector_value(&mut self, id: Uuid, name: Identifier) {
+        if let Some(container) = self.vector_values.get_mut(&name) {
+            container.remove(&id);
+        }
+    }
+
+    // Builds a vector index from scratch.
+    fn rebuild_vector_index(&mut self, name: Identifier) {
+        let mut container: BTreeMap<Uuid, Vec<f64>> = BTreeMap::new();
+        for id in self.vertices.keys() {
+            if let Some(vector) = self.vertex_properties.get(&(*id, name)).and_then(util::json_to_vector) {
+                container.insert(*id, vector);
+            }
+        }
+        self.vector_values.insert(name, container);
+    }
+
+    fn geo_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        Ok(self.geo_values.keys().copied().collect())
+    }
+
+    // Adds a vertex's value for a property to the property's geospatial
+    // index, if it has one. This should be called after the property is set.
+    fn index_geo_value(&mut self, id: Uuid, name: Identifier) {
+        if let (Some(container), Some(value)) =
+            (self.geo_values.get_mut(&name), self.vertex_properties.get(&(id, name)))
+        {
+            if let Some(point) = GeoPoint::from_json(value) {
+                container.insert(id, point);
+            }
+        }
+    }
+
+    // Removes a vertex's value for a property from the property's geospatial
+    // index. This should be called before the property is changed, since
+    // the value is needed to find the point.
+    fn unindex_geo_value(&mut self, id: Uuid, name: Identifier) {
+        if let (Some(container), Some(value)) =
+            (self.geo_values.get_mut(&name), self.vertex_properties.get(&(id, name)))
+        {
+            if let Some(point) = GeoPoint::from_json(value) {
+                container.remove(id, &point);
+            }
+        }
+    }
+
+    // Builds a geospatial index from scratch.
+    fn rebuild_geo_index(&mut self, name: Identifier) {
+        let mut container = RTree::default();
+        for id in self.vertices.keys() {
+            if let Some(point) = self.vertex_properties.get(&(*id, name)).and_then(GeoPoint::from_json) {
+                container.insert(*id, point);
+            }
+        }
+        self.geo_values.insert(name, container);
+    }
+
+    // Builds a composite index from scratch.
+    fn rebuild_composite_index(&mut self, names: Vec<Identifier>) {
+        let mut container: BTreeMap<Vec<u8>, HashSet<Uuid>> = BTreeMap::new();
+        for id in self.vertices.keys() {
+            if let Some(key) = composite_key(&self.vertex_properties, &names, *id) {
+                container.entry(key).or_default().insert(*id);
+            }
+        }
+        self.composite_values.insert(names, container);
+    }
+
+    fn sync(&self, path: Option<&PathBuf>) -> Result<()> {
+        if let Some(persist_path) = path {
+            let temp_path = NamedTempFile::new().map_err(|err| Error::Datastore(Box::new(err)))?;
+            {
+                let mut buf = BufWriter::new(temp_path.as_file());
+                rmp_serde::encode::write(&mut buf, self)?;
+            }
+            temp_path
+                .persist(persist_path)
+                .map_err(|err| Error::Datastore(Box::new(err)))?;
+        }
+        Ok(())
+    }
+}
+
+// A change made by a transaction, recorded so that it can be undone if the
+// transaction is rolled back. Property changes hold the value the property
+// had before the change, if any, and removed unique constraints hold the
+// values they had.
+enum UndoEntry {
+    CreatedVertex(Uuid),
+    DeletedVertex(Uuid, Identifier),
+    CreatedEdge(Edge),
+    DeletedEdge(Edge),
+    VertexProperty(Uuid, Identifier, Option<Json>),
+    EdgeProperty(Edge, Identifier, Option<Json>),
+    IndexedProperty(Identifier, IndexScope),
+    UnindexedProperty(Identifier, IndexScope),
+    IndexedComposite(Vec<Identifier>),
+    UnindexedComposite(Vec<Identifier>),
+    AddedUniqueConstraint(UniqueConstraint),
+    RemovedUniqueConstraint(UniqueConstraint, HashMap<Vec<u8>, Uuid>),
+    IndexedFulltext(Identifier),
+    UnindexedFulltext(Identifier),
+    IndexedVector(Identifier),
+    UnindexedVector(Identifier),
+    IndexedGeo(Identifier),
+    UnindexedGeo(Identifier),
+}
+
+pub struct MemoryTransaction<'a> {
+    internal: RwLockWriteGuard<'a, InternalMemory>,
+    path: Option<PathBuf>,
+    undo_log: Vec<UndoEntry>,
+}
+
+impl<'a> MemoryTransaction<'a> {
+    fn undo(&mut self, entry: UndoEntry) {
+        match entry {
+            UndoEntry::CreatedVertex(id) => {
+                self.internal.remove_vertex(id);
+            }
+            UndoEntry::DeletedVertex(id, t) => {
+                self.internal.insert_vertex(id, t);
+            }
+            UndoEntry::CreatedEdge(edge) => {
+                self.internal.remove_edge(&edge);
+            }
+            UndoEntry::DeletedEdge(edge) => {
+                self.internal.insert_edge(edge);
+            }
+            UndoEntry::VertexProperty(id, name, old_value) => {
+                let internal = &mut *self.internal;
+                internal.unindex_composite_values(id, name);
+                internal.unindex_unique_value(id, name);
+                internal.unindex_fulltext_value(id, name);
+                internal.unindex_vector_value(id, name);
+                internal.unindex_geo_value(id, name);
+                if let Some(value) = internal.vertex_properties.remove(&(id, name)) {
+                    if let Some(property_container) = internal.property_values.get_mut(&name) {
+                        if let Some(members) = property_container.get_mut(encode_value(&value).as_slice()) {
+                            members.remove(&IndexedPropertyMember::Vertex(id));
+                        }
+                    }
+                }
+                if let Some(value) = old_value {
+                    if internal.is_vertex_indexed(name, id) {
+                        if let Some(property_container) = internal.property_values.get_mut(&name) {
+                            property_container
+                                .entry(value.clone().into())
+                                .or_default()
+                                .insert(IndexedPropertyMember::Vertex(id));
+                        }
+                    }
+                    internal.vertex_properties.insert((id, name), value);
+                }
+                internal.index_composite_values(id, name);
+                internal.index_unique_value(id, name);
+                internal.index_fulltext_value(id, name);
+                internal.index_vector_value(id, name);
+                internal.index_geo_value(id, name);
+            }
+            UndoEntry::EdgeProperty(edge, name, old_value) => {
+                let internal = &mut *self.internal;
+                if let Some(value) = internal.edge_properties.remove(&(edge.clone(), name)) {
+                    if let Some(property_container) = internal.property_values.get_mut(&name) {
+                        if let Some(members) = property_container.get_mut(encode_value(&value).as_slice()) {
+                            members.remove(&IndexedPropertyMember::Edge(edge.clone()));
+                        }
+                    }
+                }
+                if let Some(value) = old_value {
+                    if internal.is_edge_indexed(name, &edge) {
+                        if let Some(property_container) = internal.property_values.get_mut(&name) {
+                            property_container
+                                .entry(value.clone().into())
+                                .or_default()
+                                .insert(IndexedPropertyMember::Edge(edge.clone()));
+                        }
+                    }
+                    internal.edge_properties.insert((edge, name), value);
+                }
+            }
+            UndoEntry::IndexedProperty(name, scope) => {
+                if let Some(scopes) = self.internal.property_index_scopes.get_mut(&name) {
+                    scopes.remove(&scope);
+                }
+                self.internal.rebuild_property_index(name);
+            }
+            UndoEntry::UnindexedProperty(name, scope) => {
+                self.internal
+                    .property_index_scopes
+                    .entry(name)
+                    .or_default()
+                    .insert(scope);
+                self.internal.rebuild_property_index(name);
+            }
+            UndoEntry::IndexedComposite(names) => {
+                self.internal.composite_values.remove(&names);
+            }
+            UndoEntry::UnindexedComposite(names) => {
+                self.internal.rebuild_composite_index(names);
+            }
+            UndoEntry::IndexedFulltext(name) => {
+                self.internal.fulltext_values.remove(&name);
+            }
+            UndoEntry::UnindexedFulltext(name) => {
+                self.internal.rebuild_fulltext_index(name);
+            }
+            UndoEntry::IndexedVector(name) => {
+                self.internal.vector_values.remove(&name);
+            }
+            UndoEntry::UnindexedVector(name) => {
+                self.internal.rebuild_vector_index(name);
+            }
+            UndoEntry::IndexedGeo(name) => {
+                self.internal.geo_values.remove(&name);
+            }
+            UndoEntry::UnindexedGeo(name) => {
+                self.internal.rebuild_geo_index(name);
+            }
+            UndoEntry::AddedUniqueConstraint(constraint) => {
+                self.internal.unique_values.remove(&constraint);
+            }
+            UndoEntry::RemovedUniqueConstraint(constraint, owners) => {
+                self.internal.unique_values.insert(constraint, owners);
+            }
+        }
+    }
+
+    fn undo_all(&mut self) {
+        while let Some(entry) = self.undo_log.pop() {
+            self.undo(entry);
+        }
+    }
+}
+
+impl<'a> Drop for MemoryTransaction<'a> {
+    fn drop(&mut self) {
+        // Changes that weren't committed are rolled back
+        self.undo_all();
+    }
+}
+
+// Implements the `Transaction` methods that only read, for both kinds of
+// transaction, whose guards deref to `InternalMemory`.
+macro_rules! delegate_reads {
+    () => {
+        fn vertex_count(&self) -> u64 {
+            self.internal.vertex_count()
+        }
+
+        fn all_vertices(&'a self) -> Result<DynIter<'a, Vertex>> {
+            self.internal.all_vertices()
+        }
+
+        fn range_vertices(&'a self, offset: Uuid) -> Result<DynIter<'a, Vertex>> {
+            self.internal.range_vertices(offset)
+        }
+
+        fn specific_vertices(&'a self, ids: Vec<Uuid>) -> Result<DynIter<'a, Vertex>> {
+            self.internal.specific_vertices(ids)
+        }
+
+        fn vertex_ids_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Uuid>>> {
+            self.internal.vertex_ids_with_property(name)
+        }
+
+        fn vertex_ids_with_property_value(
+            &'a self,
+            name: Identifier,
+            value: &Json,
+        ) -> Result<Option<DynIter<'a, Uuid>>> {
+            self.internal.vertex_ids_with_property_value(name, value)
+        }
+
+        fn vertex_ids_with_property_range(
+            &'a self,
+            name: Identifier,
+            lower: Bound<&Json>,
+            upper: Bound<&Json>,
+        ) -> Result<Option<DynIter<'a, Uuid>>> {
+            self.internal.vertex_ids_with_property_range(name, lower, upper)
+        }
+
+        fn vertex_ids_with_composite_values(
+            &'a self,
+            names: &[Identifier],
+            values: &[Json],
+        ) -> Result<Option<DynIter<'a, Uuid>>> {
+            self.internal.vertex_ids_with_composite_values(names, values)
+        }
+
+        fn vertex_ids_with_property_terms(
+            &'a self,
+            name: Identifier,
+            terms: &[String],
+        ) -> Result<Option<DynIter<'a, Uuid>>> {
+            self.internal.vertex_ids_with_property_terms(name, terms)
+        }
+
+        fn vertex_property_vectors(&'a self, name: Identifier) -> Result<Option<DynIter<'a, VertexVector>>> {
+            self.internal.vertex_property_vectors(name)
+        }
+
+        fn vertex_points_within(
+            &'a self,
+            name: Identifier,
+            bbox: &BoundingBox,
+        ) -> Result<Option<DynIter<'a, VertexPoint>>> {
+            self.internal.vertex_points_within(name, bbox)
+        }
+
+        fn edge_count(&self) -> u64 {
+            self.internal.edge_count()
+        }
+
+        fn all_edges(&'a self) -> Result<DynIter<'a, Edge>> {
+            self.internal.all_edges()
+        }
+
+        fn range_edges(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
+            self.internal.range_edges(offset)
+        }
+
+        fn range_reversed_edges(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
+            self.internal.range_reversed_edges(offset)
+        }
+
+        fn vertex_degree(&'a self, id: Uuid, direction: EdgeDirection, t: Option<Identifier>) -> Result<u64> {
+            Ok(self.internal.vertex_degree(id, direction, t))
+        }
+
+        fn specific_edges(&'a self, edges: Vec<Edge>) -> Result<DynIter<'a, Edge>> {
+            self.internal.specific_edges(edges)
+        }
+
+        fn edges_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Edge>>> {
+            self.internal.edges_with_property(name)
+        }
+
+        fn edges_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Edge>>> {
+            self.internal.edges_with_property_value(name, value)
+        }
+
+        fn edges_with_property_range(
+            &'a self,
+            name: Identifier,
+            lower: Bound<&Json>,
+            upper: Bound<&Json>,
+        ) -> Result<Option<DynIter<'a, Edge>>> {
+            self.internal.edges_with_property_range(name, lower, upper)
+        }
+
+        fn vertex_property(&self, vertex: &Vertex, name: Identifier) -> Result<Option<Json>> {
+            self.internal.vertex_property(vertex, name)
+        }
+
+        fn all_vertex_properties_for_vertex(&'a self, vertex: &Vertex) -> Result<DynIter<'a, (Identifier, Json)>> {
+            self.internal.all_vertex_properties_for_vertex(vertex)
+        }
+
+        fn edge_property(&self, edge: &Edge, name: Identifier) -> Result<Option<Json>> {
+            self.internal.edge_property(edge, name)
+        }
+
+        fn all_edge_properties_for_edge(&'a self, edge: &Edge) -> Result<DynIter<'a, (Identifier, Json)>> {
+            self.internal.all_edge_properties_for_edge(edge)
+        }
+
+        fn estimated_vertex_count(&self) -> Option<u64> {
+            Some(self.internal.vertices.len() as u64)
+        }
+
+        fn estimated_edge_count(&self) -> Option<u64> {
+            Some(self.internal.edges.len() as u64)
+        }
+
+        fn estimated_vertex_property_value_count(&self, name: Identifier, value: &Json) -> Option<u64> {
+            self.internal.property_value_count(name, value)
+        }
+
+        fn estimated_edge_property_value_count(&self, name: Identifier, value: &Json) -> Option<u64> {
+            self.internal.property_value_count(name, value)
+        }
+
+        fn stats(&self) -> Result<Stats> {
+            self.internal.stats()
+        }
+
+        fn sync(&self) -> Result<()> {
+            self.internal.sync(self.path.as_ref())
+        }
+
+        fn indexed_properties(&self) -> Result<HashMap<Identifier, HashSet<IndexScope>>> {
+            self.internal.indexed_properties()
+        }
+
+        fn composite_indexes(&self) -> Result<HashSet<Vec<Identifier>>> {
+            self.internal.composite_indexes()
+        }
+
+        fn fulltext_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+            self.internal.fulltext_indexed_properties()
+        }
+
+        fn vector_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+            self.internal.vector_indexed_properties()
+        }
+
+        fn geo_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+            self.internal.geo_indexed_properties()
+        }
+
+        fn unique_constraints(&self) -> Result<HashSet<UniqueConstraint>> {
+            self.internal.unique_constraints()
+        }
+    };
+}
+
+impl<'a> Transaction<'a> for MemoryTransaction<'a> {
+    delegate_reads!();
+
     fn delete_vertices(&mut self, vertices: Vec<Vertex>) -> Result<()> {
         for vertex in vertices {
-            self.internal.vertices.remove(&vertex.id);
-
             let mut deletable_vertex_properties: Vec<(Uuid, Identifier)> = Vec::new();
             for (property_key, _) in self
                 .internal
@@ -223,6 +1206,12 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
             }
             self.delete_vertex_properties(deletable_vertex_properties)?;
 
+            // The vertex is removed after its properties, so that rolling
+            // back restores it before them
+            if let Some(t) = self.internal.remove_vertex(vertex.id) {
+                self.undo_log.push(UndoEntry::DeletedVertex(vertex.id, t));
+            }
+
             let mut deletable_edges: Vec<Edge> = Vec::new();
             for edge in self.internal.edges.iter() {
                 if edge.outbound_id == vertex.id || edge.inbound_id == vertex.id {
@@ -236,8 +1225,9 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
 
     fn delete_edges(&mut self, edges: Vec<Edge>) -> Result<()> {
         for edge in edges {
-            self.internal.edges.remove(&edge);
-            self.internal.reversed_edges.remove(&edge.reversed());
+            if self.internal.remove_edge(&edge) {
+                self.undo_log.push(UndoEntry::DeletedEdge(edge.clone()));
+            }
 
             let mut deletable_edge_properties: Vec<(Edge, Identifier)> = Vec::new();
             for (property_key, _) in self
@@ -260,13 +1250,24 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
 
     fn delete_vertex_properties(&mut self, props: Vec<(Uuid, Identifier)>) -> Result<()> {
         for prop in props {
+            let (property_vertex_id, property_name) = prop;
+            self.internal
+                .unindex_composite_values(property_vertex_id, property_name);
+            self.internal.unindex_unique_value(property_vertex_id, property_name);
+            self.internal.unindex_fulltext_value(property_vertex_id, property_name);
+            self.internal.unindex_vector_value(property_vertex_id, property_name);
+            self.internal.unindex_geo_value(property_vertex_id, property_name);
             if let Some(property_value) = self.internal.vertex_properties.remove(&prop) {
-                let (property_vertex_id, property_name) = prop;
+                self.undo_log.push(UndoEntry::VertexProperty(
+                    property_vertex_id,
+                    property_name,
+                    Some(property_value.clone()),
+                ));
                 if let Some(property_container) = self.internal.property_values.get_mut(&property_name) {
-                    debug_assert!(property_container
-                        .get_mut(&property_value)
-                        .unwrap()
-                        .remove(&IndexedPropertyMember::Vertex(property_vertex_id)));
+                    // Vertices outside of the index's scopes won't be in it
+                    if let Some(members) = property_container.get_mut(encode_value(&property_value).as_slice()) {
+                        members.remove(&IndexedPropertyMember::Vertex(property_vertex_id));
+                    }
                 }
             }
         }
@@ -277,38 +1278,37 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         for prop in props {
             if let Some(property_value) = self.internal.edge_properties.remove(&prop) {
                 let (property_edge, property_name) = prop;
+                self.undo_log.push(UndoEntry::EdgeProperty(
+                    property_edge.clone(),
+                    property_name,
+                    Some(property_value.clone()),
+                ));
                 if let Some(property_container) = self.internal.property_values.get_mut(&property_name) {
-                    debug_assert!(property_container
-                        .get_mut(&property_value)
-                        .unwrap()
-                        .remove(&IndexedPropertyMember::Edge(property_edge)));
+                    // Edges outside of the index's scopes won't be in it
+                    if let Some(members) = property_container.get_mut(encode_value(&property_value).as_slice()) {
+                        members.remove(&IndexedPropertyMember::Edge(property_edge));
+                    }
                 }
             }
         }
         Ok(())
     }
 
-    fn sync(&self) -> Result<()> {
-        if let Some(ref persist_path) = self.path {
-            let temp_path = NamedTempFile::new().map_err(|err| Error::Datastore(Box::new(err)))?;
-            {
-                let mut buf = BufWriter::new(temp_path.as_file());
-                rmp_serde::encode::write(&mut buf, &*self.internal)?;
-            }
-            temp_path
-                .persist(persist_path)
-                .map_err(|err| Error::Datastore(Box::new(err)))?;
-        }
+    fn commit(&mut self) -> Result<()> {
+        self.undo_log.clear();
         Ok(())
     }
 
-    fn create_vertex(&mut self, vertex: &Vertex) -> Result<bool> {
-        let mut inserted = false;
+    fn rollback(&mut self) -> Result<()> {
+        self.undo_all();
+        Ok(())
+    }
 
-        self.internal.vertices.entry(vertex.id).or_insert_with(|| {
-            inserted = true;
-            vertex.t
-        });
+    fn create_vertex(&mut self, vertex: &Vertex) -> Result<bool> {
+        let inserted = self.internal.insert_vertex(vertex.id, vertex.t);
+        if inserted {
+            self.undo_log.push(UndoEntry::CreatedVertex(vertex.id));
+        }
 
         Ok(inserted)
     }
@@ -320,38 +1320,110 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
             return Ok(false);
         }
 
-        self.internal.edges.insert(edge.clone());
-        self.internal.reversed_edges.insert(edge.reversed());
+        if self.internal.insert_edge(edge.clone()) {
+            self.undo_log.push(UndoEntry::CreatedEdge(edge.clone()));
+        }
         Ok(true)
     }
 
-    fn index_property(&mut self, name: Identifier) -> Result<()> {
-        let mut property_container: HashMap<Json, HashSet<IndexedPropertyMember>> = HashMap::new();
-        for id in self.internal.vertices.keys() {
-            if let Some(value) = self.internal.vertex_properties.get(&(*id, name)) {
-                property_container
-                    .entry(value.clone())
-                    .or_default()
-                    .insert(IndexedPropertyMember::Vertex(*id));
-            }
+    fn index_property(&mut self, name: Identifier, scope: IndexScope) -> Result<()> {
+        if self
+            .internal
+            .property_index_scopes
+            .entry(name)
+            .or_default()
+            .insert(scope)
+        {
+            self.undo_log.push(UndoEntry::IndexedProperty(name, scope));
+            self.internal.rebuild_property_index(name);
         }
-        for edge in self.internal.edges.iter() {
-            if let Some(value) = self.internal.edge_properties.get(&(edge.clone(), name)) {
-                property_container
-                    .entry(value.clone())
-                    .or_default()
-                    .insert(IndexedPropertyMember::Edge(edge.clone()));
+        Ok(())
+    }
+
+    fn unindex_property(&mut self, name: Identifier, scope: IndexScope) -> Result<()> {
+        if let Some(scopes) = self.internal.property_index_scopes.get_mut(&name) {
+            if scopes.remove(&scope) {
+                self.undo_log.push(UndoEntry::UnindexedProperty(name, scope));
+                self.internal.rebuild_property_index(name);
             }
         }
+        Ok(())
+    }
 
-        let existing_property_container = self.internal.property_values.entry(name).or_default();
-        for (value, members) in property_container.into_iter() {
-            let existing_members = existing_property_container.entry(value).or_default();
-            for member in members {
-                existing_members.insert(member);
-            }
+    fn index_composite(&mut self, names: Vec<Identifier>) -> Result<()> {
+        if !self.internal.composite_values.contains_key(&names) {
+            self.internal.rebuild_composite_index(names.clone());
+            self.undo_log.push(UndoEntry::IndexedComposite(names));
+        }
+        Ok(())
+    }
+
+    fn unindex_composite(&mut self, names: Vec<Identifier>) -> Result<()> {
+        if self.internal.composite_values.remove(&names).is_some() {
+            self.undo_log.push(UndoEntry::UnindexedComposite(names));
+        }
+        Ok(())
+    }
+
+    fn index_property_fulltext(&mut self, name: Identifier) -> Result<()> {
+        if !self.internal.fulltext_values.contains_key(&name) {
+            self.internal.rebuild_fulltext_index(name);
+            self.undo_log.push(UndoEntry::IndexedFulltext(name));
+        }
+        Ok(())
+    }
+
+    fn unindex_property_fulltext(&mut self, name: Identifier) -> Result<()> {
+        if self.internal.fulltext_values.remove(&name).is_some() {
+            self.undo_log.push(UndoEntry::UnindexedFulltext(name));
+        }
+        Ok(())
+    }
+
+    fn index_property_vector(&mut self, name: Identifier) -> Result<()> {
+        if !self.internal.vector_values.contains_key(&name) {
+            self.internal.rebuild_vector_index(name);
+            self.undo_log.push(UndoEntry::IndexedVector(name));
+        }
+        Ok(())
+    }
+
+    fn unindex_property_vector(&mut self, name: Identifier) -> Result<()> {
+        if self.internal.vector_values.remove(&name).is_some() {
+            self.undo_log.push(UndoEntry::UnindexedVector(name));
         }
+        Ok(())
+    }
 
+    fn index_property_geo(&mut self, name: Identifier) -> Result<()> {
+        if !self.internal.geo_values.contains_key(&name) {
+            self.internal.rebuild_geo_index(name);
+            self.undo_log.push(UndoEntry::IndexedGeo(name));
+        }
+        Ok(())
+    }
+
+    fn unindex_property_geo(&mut self, name: Identifier) -> Result<()> {
+        if self.internal.geo_values.remove(&name).is_some() {
+            self.undo_log.push(UndoEntry::UnindexedGeo(name));
+        }
+        Ok(())
+    }
+
+    fn add_unique_constraint(&mut self, constraint: UniqueConstraint) -> Result<()> {
+        if !self.internal.unique_values.contains_key(&constraint) {
+            let owners = self.internal.build_unique_values(constraint)?;
+            self.internal.unique_values.insert(constraint, owners);
+            self.undo_log.push(UndoEntry::AddedUniqueConstraint(constraint));
+        }
+        Ok(())
+    }
+
+    fn remove_unique_constraint(&mut self, constraint: UniqueConstraint) -> Result<()> {
+        if let Some(owners) = self.internal.unique_values.remove(&constraint) {
+            self.undo_log
+                .push(UndoEntry::RemovedUniqueConstraint(constraint, owners));
+        }
         Ok(())
     }
 
@@ -363,14 +1435,29 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         self.delete_vertex_properties(deletable_vertex_properties)?;
 
         for vertex_id in &vertex_ids {
+            // Vertices set earlier in the loop are accounted for, so this
+            // also catches setting the same value on several vertices
+            self.internal.check_unique_value(*vertex_id, name, value)?;
             self.internal
                 .vertex_properties
                 .insert((*vertex_id, name), value.clone());
+            self.undo_log.push(UndoEntry::VertexProperty(*vertex_id, name, None));
+            self.internal.index_composite_values(*vertex_id, name);
+            self.internal.index_unique_value(*vertex_id, name);
+            self.internal.index_fulltext_value(*vertex_id, name);
+            self.internal.index_vector_value(*vertex_id, name);
+            self.internal.index_geo_value(*vertex_id, name);
         }
 
+        let indexed_ids: Vec<Uuid> = vertex_ids
+            .into_iter()
+            .filter(|id| self.internal.is_vertex_indexed(name, *id))
+            .collect();
         if let Some(property_container) = self.internal.property_values.get_mut(&name) {
-            let property_container = property_container.entry(value.clone()).or_insert_with(HashSet::new);
-            for vertex_id in vertex_ids.into_iter() {
+            let property_container = property_container
+                .entry(value.clone().into())
+                .or_insert_with(HashSet::new);
+            for vertex_id in indexed_ids.into_iter() {
                 property_container.insert(IndexedPropertyMember::Vertex(vertex_id));
             }
         }
@@ -389,11 +1476,18 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
             self.internal
                 .edge_properties
                 .insert((edge.clone(), name), value.clone());
+            self.undo_log.push(UndoEntry::EdgeProperty(edge.clone(), name, None));
         }
 
+        let indexed_edges: Vec<Edge> = edges
+            .into_iter()
+            .filter(|edge| self.internal.is_edge_indexed(name, edge))
+            .collect();
         if let Some(property_container) = self.internal.property_values.get_mut(&name) {
-            let property_container = property_container.entry(value.clone()).or_insert_with(HashSet::new);
-            for edge in edges.into_iter() {
+            let property_container = property_container
+                .entry(value.clone().into())
+                .or_insert_with(HashSet::new);
+            for edge in indexed_edges.into_iter() {
                 property_container.insert(IndexedPropertyMember::Edge(edge));
             }
         }
@@ -402,10 +1496,46 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
     }
 }
 
+// Implements the `Transaction` methods that write, for read-only
+// transactions, by returning `Error::Unsupported`.
+macro_rules! unsupported_writes {
+    ($(fn $name:ident(&mut self $(, $arg:ident: $t:ty)*) -> $ret:ty;)*) => {
+        $(
+            fn $name(&mut self $(, $arg: $t)*) -> $ret {
+                Err(Error::Unsupported)
+            }
+        )*
+    };
+}
+
+/// A read-only transaction on the in-memory datastore. Any number of these
+/// can run concurrently, but they block, and are blocked by, transactions
+/// that write. Methods that write return `Error::Unsupported`.
+pub struct MemoryReadTransaction<'a> {
+    internal: RwLockReadGuard<'a, InternalMemory>,
+    path: Option<PathBuf>,
+}
+
+impl<'a> Transaction<'a> for MemoryReadTransaction<'a> {
+    delegate_reads!();
+
+    unsupported_writes! {
+        fn delete_vertices(&mut self, _vertices: Vec<Vertex>) -> Result<()>;
+        fn delete_edges(&mut self, _edges: Vec<Edge>) -> Result<()>;
+        fn delete_vertex_properties(&mut self, _props: Vec<(Uuid, Identifier)>) -> Result<()>;
+        fn delete_edge_properties(&mut self, _props: Vec<(Edge, Identifier)>) -> Result<()>;
+        fn create_vertex(&mut self, _vertex: &Vertex) -> Result<bool>;
+        fn create_edge(&mut self, _edge: &Edge) -> Result<bool>;
+        fn index_property(&mut self, _name: Identifier, _scope: IndexScope) -> Result<()>;
+        fn set_vertex_properties(&mut self, _vertex_ids: Vec<Uuid>, _name: Identifier, _value: &Json) -> Result<()>;
+        fn set_edge_properties(&mut self, _edges: Vec<Edge>, _name: Identifier, _value: &Json) -> Result<()>;
+    }
+}
+
 /// An in-memory datastore.
 #[derive(Debug, Clone)]
 pub struct MemoryDatastore {
-    internal: Arc<Mutex<InternalMemory>>,
+    internal: Arc<RwLock<InternalMemory>>,
     path: Option<PathBuf>,
 }
 
@@ -413,7 +1543,7 @@ impl MemoryDatastore {
     /// Creates a new in-memory database with no persistence.
     pub fn new_db() -> Database<MemoryDatastore> {
         Database::new(MemoryDatastore {
-            internal: Arc::new(Mutex::new(InternalMemory::default())),
+            internal: Arc::new(RwLock::new(InternalMemory::default())),
             path: None,
         })
     }
@@ -427,9 +1557,16 @@ impl MemoryDatastore {
         let path = path.into();
         let f = File::open(&path).map_err(RmpDecodeError::InvalidDataRead)?;
         let buf = BufReader::new(f);
-        let internal: InternalMemory = rmp_serde::from_read(buf)?;
+        let mut internal: InternalMemory = rmp_serde::from_read(buf)?;
+        for name in internal.property_values.keys() {
+            internal
+                .property_index_scopes
+                .entry(*name)
+                .or_insert_with(|| HashSet::from([IndexScope::All]));
+        }
+        internal.count_types();
         Ok(Database::new(MemoryDatastore {
-            internal: Arc::new(Mutex::new(internal)),
+            internal: Arc::new(RwLock::new(internal)),
             path: Some(path),
         }))
     }
@@ -442,7 +1579,7 @@ impl MemoryDatastore {
     /// * `path`: The path to the persisted image.
     pub fn create_msgpack_db<P: Into<PathBuf>>(path: P) -> Database<MemoryDatastore> {
         Database::new(MemoryDatastore {
-            internal: Arc::new(Mutex::new(InternalMemory::default())),
+            internal: Arc::new(RwLock::new(InternalMemory::default())),
             path: Some(path.into()),
         })
     }
@@ -450,9 +1587,17 @@ impl MemoryDatastore {
 
 impl Datastore for MemoryDatastore {
     type Transaction<'a> = MemoryTransaction<'a>;
+    type ReadTransaction<'a> = MemoryReadTransaction<'a>;
     fn transaction(&'_ self) -> Self::Transaction<'_> {
         MemoryTransaction {
-            internal: self.internal.lock().unwrap(),
+            internal: self.internal.write().unwrap(),
+            path: self.path.clone(),
+            undo_log: Vec::new(),
+        }
+    }
+    fn read_transaction(&'_ self) -> Self::ReadTransaction<'_> {
+        MemoryReadTransaction {
+            internal: self.internal.read().unwrap(),
             path: self.path.clone(),
         }
     }
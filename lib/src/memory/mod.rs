@@ -4,6 +4,7 @@
 //! requested.
 
 mod datastore;
+mod rtree;
 
 pub use datastore::MemoryDatastore;
 
@@ -15,12 +16,16 @@ full_bench_impl!(MemoryDatastore::new_db());
 mod tests {
     use super::MemoryDatastore;
     use crate::util::{extract_count, extract_vertices};
-    use crate::{ijson, AllVertexQuery, CountQueryExt, Database, Identifier, SpecificVertexQuery};
+    use crate::{
+        ijson, AllVertexQuery, CountQueryExt, Database, Datastore, Error, Identifier, IndexScope, SpecificVertexQuery,
+        Transaction, Vertex, VertexWithPropertyRangeQuery,
+    };
 
     use tempfile::NamedTempFile;
     use uuid::Uuid;
 
     full_test_impl!(MemoryDatastore::new_db());
+    rollback_test_impl!(MemoryDatastore::new_db());
 
     fn create_vertex_with_property(db: &Database<MemoryDatastore>) -> Uuid {
         let id = db.create_vertex_from_type(Identifier::default()).unwrap();
@@ -46,4 +51,45 @@ mod tests {
         let db = MemoryDatastore::read_msgpack_db(path.path()).unwrap();
         expect_vertex(&db, id);
     }
+
+    #[test]
+    fn should_serialize_msgpack_property_index() {
+        let path = NamedTempFile::new().unwrap();
+        let db = MemoryDatastore::create_msgpack_db(path.path());
+        let name = Identifier::new("age").unwrap();
+        db.index_property(name, IndexScope::All).unwrap();
+        let mut ids = Vec::new();
+        for age in [5, 10, 20] {
+            let id = db.create_vertex_from_type(Identifier::default()).unwrap();
+            db.set_properties(SpecificVertexQuery::single(id), name, &ijson!(age))
+                .unwrap();
+            ids.push(id);
+        }
+        db.sync().unwrap();
+
+        // Index values are persisted as JSON, and put back in order when read
+        let db = MemoryDatastore::read_msgpack_db(path.path()).unwrap();
+        let q = VertexWithPropertyRangeQuery::new(name).gt(ijson!(5)).lt(ijson!(20));
+        let vertices = extract_vertices(db.get(q).unwrap()).unwrap();
+        assert_eq!(vertices.into_iter().map(|v| v.id).collect::<Vec<_>>(), vec![ids[1]]);
+    }
+
+    #[test]
+    fn should_allow_concurrent_read_transactions() {
+        let db = MemoryDatastore::new_db();
+        let id = create_vertex_with_property(&db);
+        let txn_1 = db.datastore.read_transaction();
+        let txn_2 = db.datastore.read_transaction();
+        assert_eq!(txn_1.vertex_count(), 1);
+        assert_eq!(txn_2.vertex_count(), 1);
+        expect_vertex(&db, id);
+    }
+
+    #[test]
+    fn should_not_write_in_read_transaction() {
+        let db = MemoryDatastore::new_db();
+        let mut txn = db.datastore.read_transaction();
+        let result = txn.create_vertex(&Vertex::new(Identifier::default()));
+        assert!(matches!(result, Err(Error::Unsupported)));
+    }
 }
@@ -0,0 +1,267 @@
+use std::mem;
+
+use crate::{BoundingBox, GeoPoint, VertexPoint};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// The maximum number of entries in a node before it's split.
+const MAX_ENTRIES: usize = 16;
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Node {
+    Leaf(Vec<VertexPoint>),
+    Internal(Vec<(BoundingBox, Node)>),
+}
+
+impl Node {
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf(points) => points.len(),
+            Node::Internal(children) => children.len(),
+        }
+    }
+
+    // Gets the smallest box containing everything in the node, which must
+    // not be empty.
+    fn bounds(&self) -> BoundingBox {
+        let mut boxes: Box<dyn Iterator<Item = BoundingBox>> = match self {
+            Node::Leaf(points) => Box::new(points.iter().map(|(_, point)| point_bounds(point))),
+            Node::Internal(children) => Box::new(children.iter().map(|(bounds, _)| *bounds)),
+        };
+        let first = boxes.next().unwrap();
+        boxes.fold(first, |acc, b| union(&acc, &b))
+    }
+
+    // Inserts a point, returning a new sibling node if this node had to be
+    // split.
+    fn insert(&mut self, id: Uuid, point: GeoPoint) -> Option<Node> {
+        match self {
+            Node::Leaf(points) => points.push((id, point)),
+            Node::Internal(children) => {
+                let target = point_bounds(&point);
+                // Descend into the child whose box grows the least, breaking
+                // ties by the smaller box
+                let (i, _) = children
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (bounds, _))| {
+                        let grown = area(&union(bounds, &target));
+                        (i, (grown - area(bounds), grown))
+                    })
+                    .min_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)))
+                    .unwrap();
+                let child = &mut children[i];
+                let sibling = child.1.insert(id, point);
+                child.0 = child.1.bounds();
+                if let Some(sibling) = sibling {
+                    children.push((sibling.bounds(), sibling));
+                }
+            }
+        }
+
+        if self.len() > MAX_ENTRIES {
+            Some(self.split())
+        } else {
+            None
+        }
+    }
+
+    // Splits the node in two along the longer side of its box, keeping the
+    // lower half and returning the upper half.
+    fn split(&mut self) -> Node {
+        let bounds = self.bounds();
+        let by_lat = bounds.max_lat - bounds.min_lat > bounds.max_lon - bounds.min_lon;
+        let center = move |b: &BoundingBox| {
+            if by_lat {
+                b.min_lat + b.max_lat
+            } else {
+                b.min_lon + b.max_lon
+            }
+        };
+        match self {
+            Node::Leaf(points) => {
+                points.sort_by(|a, b| center(&point_bounds(&a.1)).total_cmp(&center(&point_bounds(&b.1))));
+                Node::Leaf(points.split_off(points.len() / 2))
+            }
+            Node::Internal(children) => {
+                children.sort_by(|a, b| center(&a.0).total_cmp(&center(&b.0)));
+                Node::Internal(children.split_off(children.len() / 2))
+            }
+        }
+    }
+
+    // Removes a point, returning whether it was found.
+    fn remove(&mut self, id: Uuid, point: &GeoPoint) -> bool {
+        match self {
+            Node::Leaf(points) => match points.iter().position(|(other_id, _)| *other_id == id) {
+                Some(i) => {
+                    points.swap_remove(i);
+                    true
+                }
+                None => false,
+            },
+            Node::Internal(children) => {
+                for i in 0..children.len() {
+                    let (bounds, child) = &mut children[i];
+                    if contains(bounds, point) && child.remove(id, point) {
+                        // Empty children are dropped rather than merged into
+                        // their siblings, which keeps every leaf at the same
+                        // depth
+                        if child.len() == 0 {
+                            children.swap_remove(i);
+                        } else {
+                            *bounds = child.bounds();
+                        }
+                        return true;
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    fn search(&self, bbox: &BoundingBox, results: &mut Vec<VertexPoint>) {
+        match self {
+            Node::Leaf(points) => {
+                results.extend(points.iter().filter(|(_, point)| contains(bbox, point)).copied());
+            }
+            Node::Internal(children) => {
+                for (bounds, child) in children {
+                    if intersects(bounds, bbox) {
+                        child.search(bbox, results);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An R-tree of the points in a geospatial index. Boxes in the tree never
+/// cross the antimeridian.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RTree {
+    root: Node,
+}
+
+impl Default for RTree {
+    fn default() -> Self {
+        Self {
+            root: Node::Leaf(Vec::new()),
+        }
+    }
+}
+
+impl RTree {
+    /// Adds a vertex's point to the tree.
+    pub(crate) fn insert(&mut self, id: Uuid, point: GeoPoint) {
+        if let Some(sibling) = self.root.insert(id, point) {
+            let root = mem::replace(&mut self.root, Node::Leaf(Vec::new()));
+            self.root = Node::Internal(vec![(root.bounds(), root), (sibling.bounds(), sibling)]);
+        }
+    }
+
+    /// Removes a vertex's point from the tree.
+    pub(crate) fn remove(&mut self, id: Uuid, point: &GeoPoint) {
+        self.root.remove(id, point);
+        // Collapse the root while it only has one child
+        loop {
+            match self.root {
+                Node::Internal(ref mut children) if children.len() <= 1 => {
+                    self.root = match children.pop() {
+                        Some((_, child)) => child,
+                        None => Node::Leaf(Vec::new()),
+                    };
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Gets the vertices whose points are within a bounding box, which must
+    /// not cross the antimeridian.
+    pub(crate) fn search(&self, bbox: &BoundingBox) -> Vec<VertexPoint> {
+        let mut results = Vec::new();
+        self.root.search(bbox, &mut results);
+        results
+    }
+}
+
+fn point_bounds(point: &GeoPoint) -> BoundingBox {
+    BoundingBox {
+        min_lat: point.lat,
+        min_lon: point.lon,
+        max_lat: point.lat,
+        max_lon: point.lon,
+    }
+}
+
+fn union(first: &BoundingBox, second: &BoundingBox) -> BoundingBox {
+    BoundingBox {
+        min_lat: first.min_lat.min(second.min_lat),
+        min_lon: first.min_lon.min(second.min_lon),
+        max_lat: first.max_lat.max(second.max_lat),
+        max_lon: first.max_lon.max(second.max_lon),
+    }
+}
+
+fn area(bbox: &BoundingBox) -> f64 {
+    (bbox.max_lat - bbox.min_lat) * (bbox.max_lon - bbox.min_lon)
+}
+
+fn contains(bbox: &BoundingBox, point: &GeoPoint) -> bool {
+    point.lat >= bbox.min_lat && point.lat <= bbox.max_lat && point.lon >= bbox.min_lon && point.lon <= bbox.max_lon
+}
+
+fn intersects(first: &BoundingBox, second: &BoundingBox) -> bool {
+    first.min_lat <= second.max_lat
+        && first.max_lat >= second.min_lat
+        && first.min_lon <= second.max_lon
+        && first.max_lon >= second.min_lon
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RTree;
+    use crate::util::generate_uuid_v1;
+    use crate::{BoundingBox, GeoPoint};
+
+    #[test]
+    fn should_search_rtree() {
+        let mut tree = RTree::default();
+        let mut points = Vec::new();
+        for lat in -20..20 {
+            for lon in -20..20 {
+                let point = GeoPoint::new(lat as f64, lon as f64).unwrap();
+                let id = generate_uuid_v1();
+                tree.insert(id, point);
+                points.push((id, point));
+            }
+        }
+
+        // Remove every other point
+        for (id, point) in points.iter().step_by(2) {
+            tree.remove(*id, point);
+        }
+
+        let bbox = BoundingBox::new(GeoPoint::new(-5.5, 0.0).unwrap(), GeoPoint::new(10.0, 3.5).unwrap()).unwrap();
+        let mut found = tree.search(&bbox);
+        found.sort_by_key(|(id, _)| *id);
+        let mut expected: Vec<_> = points
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter(|(_, point)| bbox.contains(point))
+            .copied()
+            .collect();
+        expected.sort_by_key(|(id, _)| *id);
+        assert_eq!(found.len(), 32);
+        assert_eq!(found, expected);
+
+        // Removing the rest empties the tree
+        for (id, point) in points.iter().skip(1).step_by(2) {
+            tree.remove(*id, point);
+        }
+        assert!(tree.search(&bbox).is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt::Write;
+
+use crate::errors;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// An opaque position in the results of an ordered query, which is returned
+/// alongside a page of results to get the page after it.
+#[derive(Eq, PartialEq, Clone, Debug, Hash)]
+pub struct Cursor(String);
+
+impl Cursor {
+    /// Gets the cursor as a string, e.g. to send to a client.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // Encodes the position of an item, from the sort key of its property
+    // value and its ID.
+    pub(crate) fn encode<I: Serialize>(key: &Option<Vec<u8>>, id: &I) -> errors::Result<Self> {
+        let bytes = rmp_serde::to_vec(&(key, id))?;
+        let mut s = String::with_capacity(bytes.len() * 2);
+        for b in bytes {
+            write!(s, "{b:02x}").unwrap();
+        }
+        Ok(Self(s))
+    }
+
+    // Decodes the position of an item, failing if the cursor wasn't
+    // produced by `encode` with the same type of ID.
+    pub(crate) fn decode<I: DeserializeOwned>(&self) -> errors::ValidationResult<(Option<Vec<u8>>, I)> {
+        if !self.0.len().is_multiple_of(2) || !self.0.is_ascii() {
+            return Err(errors::ValidationError::InvalidValue);
+        }
+        let bytes = (0..self.0.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&self.0[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| errors::ValidationError::InvalidValue)?;
+        rmp_serde::from_slice(&bytes).map_err(|_| errors::ValidationError::InvalidValue)
+    }
+}
+
+impl From<String> for Cursor {
+    fn from(s: String) -> Self {
+        Self(s)
+    }
+}
+
+impl From<Cursor> for String {
+    fn from(cursor: Cursor) -> Self {
+        cursor.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cursor;
+    use crate::{Edge, Identifier};
+    use uuid::Uuid;
+
+    #[test]
+    fn should_round_trip_cursor() {
+        let edge = Edge::new(Uuid::default(), Identifier::new("follows").unwrap(), Uuid::default());
+        let cursor = Cursor::encode(&Some(vec![0, 1, 255]), &edge).unwrap();
+        let (key, decoded): (Option<Vec<u8>>, Edge) = cursor.decode().unwrap();
+        assert_eq!(key, Some(vec![0, 1, 255]));
+        assert_eq!(decoded, edge);
+
+        // Cursors for one type of item can't be used for another
+        assert!(cursor.decode::<Uuid>().is_err());
+        assert!(Cursor::from("zz".to_string()).decode::<Uuid>().is_err());
+        assert!(Cursor::from("é".to_string()).decode::<Uuid>().is_err());
+    }
+}
@@ -0,0 +1,26 @@
+use uuid::Uuid;
+
+/// The number of edges of a vertex, as output by a `DegreeQuery`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Degree {
+    /// The ID of the vertex.
+    pub id: Uuid,
+
+    /// The number of outbound edges of the vertex.
+    pub outbound: u64,
+
+    /// The number of inbound edges of the vertex.
+    pub inbound: u64,
+}
+
+impl Degree {
+    /// Creates a new degree.
+    ///
+    /// # Arguments
+    /// * `id`: The ID of the vertex.
+    /// * `outbound`: The number of outbound edges of the vertex.
+    /// * `inbound`: The number of inbound edges of the vertex.
+    pub fn new(id: Uuid, outbound: u64, inbound: u64) -> Self {
+        Self { id, outbound, inbound }
+    }
+}
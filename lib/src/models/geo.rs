@@ -0,0 +1,224 @@
+use crate::{errors, Json};
+
+use serde::{Deserialize, Serialize};
+
+// The mean radius of the earth, in meters.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// A point on the earth, in degrees.
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct GeoPoint {
+    /// The latitude, between -90 and 90.
+    pub lat: f64,
+    /// The longitude, between -180 and 180.
+    pub lon: f64,
+}
+
+// Points are validated to not have NaN coordinates
+impl Eq for GeoPoint {}
+
+impl GeoPoint {
+    /// Creates a new point.
+    ///
+    /// # Arguments
+    /// * `lat`: The latitude, between -90 and 90.
+    /// * `lon`: The longitude, between -180 and 180.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if either coordinate is out of range.
+    pub fn new(lat: f64, lon: f64) -> errors::ValidationResult<Self> {
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(errors::ValidationError::InvalidValue);
+        }
+        Ok(Self { lat, lon })
+    }
+
+    /// Reads a point out of a JSON value, which is indexable by a geospatial
+    /// index if it's an object with numeric `lat` and `lon` fields that are
+    /// in range.
+    ///
+    /// # Arguments
+    /// * `value`: The JSON value.
+    pub fn from_json(value: &Json) -> Option<Self> {
+        let lat = value.get("lat")?.as_f64()?;
+        let lon = value.get("lon")?.as_f64()?;
+        Self::new(lat, lon).ok()
+    }
+
+    /// Gets the great-circle distance to another point, in meters.
+    ///
+    /// # Arguments
+    /// * `other`: The other point.
+    pub fn distance(&self, other: &GeoPoint) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.lon - self.lon).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+    }
+}
+
+/// An area bounded by lines of latitude and longitude, including its edges.
+/// If `min_lon` is greater than `max_lon`, the box crosses the antimeridian.
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct BoundingBox {
+    /// The southern edge.
+    pub min_lat: f64,
+    /// The western edge.
+    pub min_lon: f64,
+    /// The northern edge.
+    pub max_lat: f64,
+    /// The eastern edge.
+    pub max_lon: f64,
+}
+
+// Boxes are validated to not have NaN coordinates
+impl Eq for BoundingBox {}
+
+impl BoundingBox {
+    /// Creates a new bounding box from its south-west and north-east
+    /// corners.
+    ///
+    /// # Arguments
+    /// * `south_west`: The south-west corner.
+    /// * `north_east`: The north-east corner.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the south-west corner is north of the
+    /// north-east corner.
+    pub fn new(south_west: GeoPoint, north_east: GeoPoint) -> errors::ValidationResult<Self> {
+        let bbox = Self {
+            min_lat: south_west.lat,
+            min_lon: south_west.lon,
+            max_lat: north_east.lat,
+            max_lon: north_east.lon,
+        };
+        bbox.validate()?;
+        Ok(bbox)
+    }
+
+    /// Gets the smallest bounding box that contains every point within a
+    /// distance of a center point.
+    ///
+    /// # Arguments
+    /// * `center`: The center point.
+    /// * `radius`: The distance from the center, in meters.
+    pub fn around(center: GeoPoint, radius: f64) -> Self {
+        let d_lat = (radius / EARTH_RADIUS).to_degrees();
+        let min_lat = center.lat - d_lat;
+        let max_lat = center.lat + d_lat;
+        if min_lat <= -90.0 || max_lat >= 90.0 || radius / EARTH_RADIUS >= std::f64::consts::FRAC_PI_2 {
+            // The circle covers a pole, and so every longitude
+            return Self {
+                min_lat: min_lat.max(-90.0),
+                min_lon: -180.0,
+                max_lat: max_lat.min(90.0),
+                max_lon: 180.0,
+            };
+        }
+
+        // The circle is widest, in longitude, at the latitude where it
+        // meets its tangent meridians
+        let d_lon = ((radius / EARTH_RADIUS).sin() / center.lat.to_radians().cos())
+            .min(1.0)
+            .asin()
+            .to_degrees();
+        let wrap = |lon: f64| {
+            if lon < -180.0 {
+                lon + 360.0
+            } else if lon > 180.0 {
+                lon - 360.0
+            } else {
+                lon
+            }
+        };
+        Self {
+            min_lat,
+            min_lon: wrap(center.lon - d_lon),
+            max_lat,
+            max_lon: wrap(center.lon + d_lon),
+        }
+    }
+
+    /// Returns whether a point is in the box.
+    ///
+    /// # Arguments
+    /// * `point`: The point.
+    pub fn contains(&self, point: &GeoPoint) -> bool {
+        if point.lat < self.min_lat || point.lat > self.max_lat {
+            false
+        } else if self.min_lon <= self.max_lon {
+            point.lon >= self.min_lon && point.lon <= self.max_lon
+        } else {
+            point.lon >= self.min_lon || point.lon <= self.max_lon
+        }
+    }
+
+    // Checks that the box's coordinates are in range, and that its southern
+    // edge isn't north of its northern edge.
+    pub(crate) fn validate(&self) -> errors::ValidationResult<()> {
+        GeoPoint::new(self.min_lat, self.min_lon)?;
+        GeoPoint::new(self.max_lat, self.max_lon)?;
+        if self.min_lat > self.max_lat {
+            return Err(errors::ValidationError::InvalidValue);
+        }
+        Ok(())
+    }
+
+    // Splits a box that crosses the antimeridian into one on either side of
+    // it, so that each part's `min_lon` is no greater than its `max_lon`.
+    pub(crate) fn split_at_antimeridian(&self) -> Vec<BoundingBox> {
+        if self.min_lon <= self.max_lon {
+            vec![*self]
+        } else {
+            vec![
+                Self {
+                    max_lon: 180.0,
+                    ..*self
+                },
+                Self {
+                    min_lon: -180.0,
+                    ..*self
+                },
+            ]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundingBox, GeoPoint};
+    use crate::ijson;
+
+    #[test]
+    fn should_read_point_from_json() {
+        assert_eq!(
+            GeoPoint::from_json(&ijson!({"lat": 45.5, "lon": -122})),
+            Some(GeoPoint { lat: 45.5, lon: -122.0 })
+        );
+        assert_eq!(GeoPoint::from_json(&ijson!({"lat": 91, "lon": 0})), None);
+        assert_eq!(GeoPoint::from_json(&ijson!({"lat": "45.5", "lon": 0})), None);
+        assert_eq!(GeoPoint::from_json(&ijson!([45.5, 0])), None);
+    }
+
+    #[test]
+    fn should_measure_distance() {
+        let portland = GeoPoint::new(45.5152, -122.6784).unwrap();
+        let seattle = GeoPoint::new(47.6062, -122.3321).unwrap();
+        assert_eq!((portland.distance(&seattle) / 1000.0).round(), 234.0);
+        assert_eq!(portland.distance(&portland), 0.0);
+    }
+
+    #[test]
+    fn should_get_box_around_point() {
+        let center = GeoPoint::new(0.0, 179.99).unwrap();
+        let bbox = BoundingBox::around(center, 10_000.0);
+        assert!(bbox.min_lon > bbox.max_lon);
+        assert!(bbox.contains(&GeoPoint::new(0.0, -179.99).unwrap()));
+        assert!(!bbox.contains(&GeoPoint::new(0.0, 179.8).unwrap()));
+        assert_eq!(bbox.split_at_antimeridian().len(), 2);
+
+        let bbox = BoundingBox::around(GeoPoint::new(89.99, 0.0).unwrap(), 10_000.0);
+        assert_eq!((bbox.min_lon, bbox.max_lon, bbox.max_lat), (-180.0, 180.0, 90.0));
+    }
+}
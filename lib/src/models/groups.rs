@@ -0,0 +1,22 @@
+use crate::Json;
+
+/// The number of items in a group, as output by a `GroupByQuery`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Group {
+    /// The key shared by the items in the group.
+    pub key: Json,
+
+    /// The number of items in the group.
+    pub count: u64,
+}
+
+impl Group {
+    /// Creates a new group.
+    ///
+    /// # Arguments
+    /// * `key`: The key shared by the items in the group.
+    /// * `count`: The number of items in the group.
+    pub fn new(key: Json, count: u64) -> Self {
+        Self { key, count }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::Identifier;
+
+use serde::{Deserialize, Serialize};
+
+/// Which vertices and edges a property index covers.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Hash, Serialize, Deserialize)]
+pub enum IndexScope {
+    /// Every vertex and edge with the property.
+    All,
+    /// Only vertices of the given type.
+    VertexType(Identifier),
+    /// Only edges of the given type.
+    EdgeType(Identifier),
+}
+
+impl IndexScope {
+    /// Returns whether vertices of a given type are covered by the scope.
+    ///
+    /// # Arguments
+    /// * `t`: The vertex type.
+    pub fn covers_vertex(&self, t: Identifier) -> bool {
+        match self {
+            IndexScope::All => true,
+            IndexScope::VertexType(scope_t) => *scope_t == t,
+            IndexScope::EdgeType(_) => false,
+        }
+    }
+
+    /// Returns whether edges of a given type are covered by the scope.
+    ///
+    /// # Arguments
+    /// * `t`: The edge type.
+    pub fn covers_edge(&self, t: Identifier) -> bool {
+        match self {
+            IndexScope::All => true,
+            IndexScope::VertexType(_) => false,
+            IndexScope::EdgeType(scope_t) => *scope_t == t,
+        }
+    }
+}
+
+/// A constraint that no two vertices of a type have the same value for a
+/// property.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Hash, Serialize, Deserialize)]
+pub struct UniqueConstraint {
+    /// The vertex type the constraint applies to.
+    pub t: Identifier,
+    /// The name of the property whose values must be unique.
+    pub name: Identifier,
+}
+
+impl UniqueConstraint {
+    /// Creates a new unique constraint.
+    ///
+    /// # Arguments
+    /// * `t`: The vertex type the constraint applies to.
+    /// * `name`: The name of the property whose values must be unique.
+    pub fn new(t: Identifier, name: Identifier) -> Self {
+        Self { t, name }
+    }
+}
@@ -1,15 +1,31 @@
 mod bulk_insert;
+mod cursors;
+mod degrees;
 mod edges;
+mod geo;
+mod groups;
 mod identifiers;
+mod indexes;
 mod json;
+mod paths;
+mod plans;
 mod properties;
 mod queries;
+mod stats;
 mod vertices;
 
 pub use self::bulk_insert::BulkInsertItem;
+pub use self::cursors::Cursor;
+pub use self::degrees::Degree;
 pub use self::edges::Edge;
+pub use self::geo::{BoundingBox, GeoPoint};
+pub use self::groups::Group;
 pub use self::identifiers::Identifier;
+pub use self::indexes::{IndexScope, UniqueConstraint};
 pub use self::json::Json;
+pub use self::paths::Path;
+pub use self::plans::{FilterStrategy, PlanStep, QueryPlan};
 pub use self::properties::{EdgeProperties, EdgeProperty, NamedProperty, VertexProperties, VertexProperty};
 pub use self::queries::*;
+pub use self::stats::{PropertyValueCount, Stats};
 pub use self::vertices::Vertex;
@@ -0,0 +1,23 @@
+use crate::{Edge, Vertex};
+
+/// A path through the graph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    /// The vertices along the path, in order from the source to the target.
+    pub vertices: Vec<Vertex>,
+
+    /// The edges along the path, in order from the source to the target.
+    /// There is always one fewer edge than there are vertices.
+    pub edges: Vec<Edge>,
+}
+
+impl Path {
+    /// Creates a new path.
+    ///
+    /// # Arguments
+    /// * `vertices`: The vertices along the path.
+    /// * `edges`: The edges along the path.
+    pub fn new(vertices: Vec<Vertex>, edges: Vec<Edge>) -> Self {
+        Self { vertices, edges }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::Query;
+
+/// How piped vertices or edges are filtered by a property.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+pub enum FilterStrategy {
+    /// Reads everything with the property (or property value) from its
+    /// index, and keeps the piped items that are among them.
+    IndexLookup,
+    /// Reads the property of each piped item.
+    PropertyChecks,
+}
+
+/// A step of a query plan, which runs one of the nested queries that make
+/// up a query.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct PlanStep {
+    /// The kind of query run by the step, e.g. `PipeWithPropertyValue`.
+    pub name: String,
+
+    /// How the step filters piped items, if it's a property filter that
+    /// can be run more than one way.
+    pub strategy: Option<FilterStrategy>,
+
+    /// The number of rows the step was estimated to output, or `None` if it
+    /// couldn't be estimated.
+    pub estimated_rows: Option<u64>,
+}
+
+/// The plan a query would be run with, as returned by `Database::explain`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct QueryPlan {
+    /// The query after planning, which may have its filters reordered or
+    /// rewritten as index lookups.
+    pub query: Query,
+
+    /// The steps of the plan, from the first to run to the last.
+    pub steps: Vec<PlanStep>,
+}
@@ -1,7 +1,9 @@
+use std::ops::Bound;
 use std::str::FromStr;
 
-use crate::{errors, Edge, Identifier, Json};
+use crate::{errors, util, BoundingBox, Cursor, Edge, GeoPoint, Identifier, Json};
 
+use regex::Regex;
 use uuid::Uuid;
 
 macro_rules! into_query {
@@ -24,6 +26,67 @@ macro_rules! nestable_query {
     };
 }
 
+macro_rules! range_query {
+    ($name:ident) => {
+        impl $name {
+            /// Only include property values greater than a given value.
+            ///
+            /// # Arguments
+            /// * `value`: The exclusive lower bound.
+            pub fn gt(self, value: Json) -> Self {
+                Self {
+                    lower: Bound::Excluded(value),
+                    ..self
+                }
+            }
+
+            /// Only include property values greater than or equal to a given
+            /// value.
+            ///
+            /// # Arguments
+            /// * `value`: The inclusive lower bound.
+            pub fn gte(self, value: Json) -> Self {
+                Self {
+                    lower: Bound::Included(value),
+                    ..self
+                }
+            }
+
+            /// Only include property values less than a given value.
+            ///
+            /// # Arguments
+            /// * `value`: The exclusive upper bound.
+            pub fn lt(self, value: Json) -> Self {
+                Self {
+                    upper: Bound::Excluded(value),
+                    ..self
+                }
+            }
+
+            /// Only include property values less than or equal to a given
+            /// value.
+            ///
+            /// # Arguments
+            /// * `value`: The inclusive upper bound.
+            pub fn lte(self, value: Json) -> Self {
+                Self {
+                    upper: Bound::Included(value),
+                    ..self
+                }
+            }
+
+            /// Only include property values between two values, inclusive.
+            ///
+            /// # Arguments
+            /// * `lower`: The inclusive lower bound.
+            /// * `upper`: The inclusive upper bound.
+            pub fn between(self, lower: Json, upper: Json) -> Self {
+                self.gte(lower).lte(upper)
+            }
+        }
+    };
+}
+
 /// Specifies what kind of items should be piped from one type of query to
 /// another.
 ///
@@ -61,6 +124,36 @@ impl From<EdgeDirection> for String {
     }
 }
 
+/// Specifies which order to sort query results in.
+#[derive(Eq, PartialEq, Clone, Debug, Hash, Copy)]
+pub enum SortDirection {
+    /// Smallest values first.
+    Ascending,
+    /// Largest values first.
+    Descending,
+}
+
+impl FromStr for SortDirection {
+    type Err = errors::ValidationError;
+
+    fn from_str(s: &str) -> Result<SortDirection, Self::Err> {
+        match s {
+            "ascending" => Ok(SortDirection::Ascending),
+            "descending" => Ok(SortDirection::Descending),
+            _ => Err(errors::ValidationError::InvalidValue),
+        }
+    }
+}
+
+impl From<SortDirection> for String {
+    fn from(d: SortDirection) -> Self {
+        match d {
+            SortDirection::Ascending => "ascending".to_string(),
+            SortDirection::Descending => "descending".to_string(),
+        }
+    }
+}
+
 /// A query to get a set of values from the database.
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum Query {
@@ -74,6 +167,20 @@ pub enum Query {
     VertexWithPropertyPresence(VertexWithPropertyPresenceQuery),
     /// Gets vertices with a property equal to a given value.
     VertexWithPropertyValue(VertexWithPropertyValueQuery),
+    /// Gets vertices with a property value within a range.
+    VertexWithPropertyRange(VertexWithPropertyRangeQuery),
+    /// Gets vertices with several properties equal to given values.
+    VertexWithPropertyValues(VertexWithPropertyValuesQuery),
+    /// Gets vertices with a string property containing all of the given
+    /// terms.
+    VertexWithPropertyMatch(VertexWithPropertyMatchQuery),
+    /// Gets the vertices whose vector property is nearest to a given vector.
+    NearestVertices(NearestVerticesQuery),
+    /// Gets vertices with a geospatial property within a bounding box.
+    WithinBoundingBox(WithinBoundingBoxQuery),
+    /// Gets vertices with a geospatial property within a distance of a
+    /// point.
+    WithinRadius(WithinRadiusQuery),
 
     /// Gets all edges.
     AllEdge,
@@ -83,6 +190,8 @@ pub enum Query {
     EdgeWithPropertyPresence(EdgeWithPropertyPresenceQuery),
     /// Gets edges with a property equal to a given value.
     EdgeWithPropertyValue(EdgeWithPropertyValueQuery),
+    /// Gets edges with a property value within a range.
+    EdgeWithPropertyRange(EdgeWithPropertyRangeQuery),
 
     /// Gets the vertices associated with edges, or edges associated with
     /// vertices.
@@ -93,11 +202,43 @@ pub enum Query {
     PipeWithPropertyPresence(PipeWithPropertyPresenceQuery),
     /// Gets vertices or edges with a property equal to a given value.
     PipeWithPropertyValue(PipeWithPropertyValueQuery),
+    /// Gets vertices or edges with a property value within a range.
+    PipeWithPropertyRange(PipeWithPropertyRangeQuery),
+    /// Gets vertices or edges with a string property matching a pattern.
+    PipeWithPropertyPattern(PipeWithPropertyPatternQuery),
+    /// Gets vertices or edges with a geospatial property within a bounding
+    /// box.
+    PipeWithinBoundingBox(PipeWithinBoundingBoxQuery),
+    /// Gets vertices or edges with a geospatial property within a distance
+    /// of a point.
+    PipeWithinRadius(PipeWithinRadiusQuery),
+    /// Gets the vertices reachable from a set of vertices within a range of
+    /// hops.
+    Traversal(TraversalQuery),
+    /// Gets the shortest path between two vertices.
+    ShortestPath(ShortestPathQuery),
+    /// Sorts vertices or edges by a property, a page at a time.
+    OrderBy(OrderByQuery),
 
     /// Includes the results of a query in output.
     Include(IncludeQuery),
     /// Counts the number of items returned from a query.
     Count(CountQuery),
+    /// Sums a numeric property of the items returned from a query.
+    Sum(SumQuery),
+    /// Gets the smallest value of a property of the items returned from a
+    /// query.
+    Min(MinQuery),
+    /// Gets the largest value of a property of the items returned from a
+    /// query.
+    Max(MaxQuery),
+    /// Averages a numeric property of the items returned from a query.
+    Avg(AvgQuery),
+    /// Counts the items returned from a query by their type or the value of
+    /// a property.
+    GroupBy(GroupByQuery),
+    /// Counts the edges of each vertex returned from a query.
+    Degree(DegreeQuery),
 }
 
 impl Query {
@@ -111,15 +252,35 @@ impl Query {
             | Query::SpecificVertex(_)
             | Query::VertexWithPropertyPresence(_)
             | Query::VertexWithPropertyValue(_)
+            | Query::VertexWithPropertyRange(_)
+            | Query::VertexWithPropertyValues(_)
+            | Query::VertexWithPropertyMatch(_)
+            | Query::NearestVertices(_)
+            | Query::WithinBoundingBox(_)
+            | Query::WithinRadius(_)
             | Query::AllEdge
             | Query::SpecificEdge(_)
             | Query::EdgeWithPropertyPresence(_)
             | Query::EdgeWithPropertyValue(_)
-            | Query::Count(_) => 1,
+            | Query::EdgeWithPropertyRange(_)
+            | Query::ShortestPath(_)
+            | Query::Count(_)
+            | Query::Sum(_)
+            | Query::Min(_)
+            | Query::Max(_)
+            | Query::Avg(_)
+            | Query::GroupBy(_)
+            | Query::Degree(_) => 1,
             Query::Pipe(q) => q.inner.output_len(),
             Query::PipeProperty(q) => q.inner.output_len(),
             Query::PipeWithPropertyPresence(q) => q.inner.output_len(),
             Query::PipeWithPropertyValue(q) => q.inner.output_len(),
+            Query::PipeWithPropertyRange(q) => q.inner.output_len(),
+            Query::PipeWithPropertyPattern(q) => q.inner.output_len(),
+            Query::PipeWithinBoundingBox(q) => q.inner.output_len(),
+            Query::PipeWithinRadius(q) => q.inner.output_len(),
+            Query::Traversal(q) => q.inner.output_len(),
+            Query::OrderBy(q) => 1 + q.inner.output_len(),
             Query::Include(q) => 1 + q.inner.output_len(),
         }
     }
@@ -133,13 +294,28 @@ impl Query {
             | Query::RangeVertex(_)
             | Query::SpecificVertex(_)
             | Query::VertexWithPropertyPresence(_)
-            | Query::VertexWithPropertyValue(_) => Ok(QueryOutputValue::Vertices(Vec::default())),
+            | Query::VertexWithPropertyValue(_)
+            | Query::VertexWithPropertyRange(_)
+            | Query::VertexWithPropertyValues(_)
+            | Query::VertexWithPropertyMatch(_)
+            | Query::NearestVertices(_)
+            | Query::WithinBoundingBox(_)
+            | Query::WithinRadius(_) => Ok(QueryOutputValue::Vertices(Vec::default())),
             Query::AllEdge
             | Query::SpecificEdge(_)
             | Query::EdgeWithPropertyPresence(_)
-            | Query::EdgeWithPropertyValue(_) => Ok(QueryOutputValue::Edges(Vec::default())),
+            | Query::EdgeWithPropertyValue(_)
+            | Query::EdgeWithPropertyRange(_) => Ok(QueryOutputValue::Edges(Vec::default())),
             Query::Count(_) => Ok(QueryOutputValue::Count(0)),
-            Query::Pipe(q) => q.inner.output_type(),
+            Query::Sum(_) | Query::Min(_) | Query::Max(_) | Query::Avg(_) => Ok(QueryOutputValue::Aggregate(None)),
+            Query::GroupBy(_) => Ok(QueryOutputValue::Groups(Vec::default())),
+            Query::Degree(_) => Ok(QueryOutputValue::Degrees(Vec::default())),
+            Query::ShortestPath(_) => Ok(QueryOutputValue::Path(None)),
+            Query::Pipe(q) => match q.inner.output_type()? {
+                QueryOutputValue::Vertices(_) => Ok(QueryOutputValue::Edges(Vec::default())),
+                QueryOutputValue::Edges(_) => Ok(QueryOutputValue::Vertices(Vec::default())),
+                _ => Err(errors::ValidationError::InnerQuery),
+            },
             Query::PipeProperty(q) => match q.inner.output_type()? {
                 QueryOutputValue::Vertices(_) => Ok(QueryOutputValue::VertexProperties(Vec::default())),
                 QueryOutputValue::Edges(_) => Ok(QueryOutputValue::EdgeProperties(Vec::default())),
@@ -147,6 +323,12 @@ impl Query {
             },
             Query::PipeWithPropertyPresence(q) => q.inner.output_type(),
             Query::PipeWithPropertyValue(q) => q.inner.output_type(),
+            Query::PipeWithPropertyRange(q) => q.inner.output_type(),
+            Query::PipeWithPropertyPattern(q) => q.inner.output_type(),
+            Query::PipeWithinBoundingBox(q) => q.inner.output_type(),
+            Query::PipeWithinRadius(q) => q.inner.output_type(),
+            Query::Traversal(_) => Ok(QueryOutputValue::Vertices(Vec::default())),
+            Query::OrderBy(q) => q.inner.output_type(),
             Query::Include(q) => q.inner.output_type(),
         }
     }
@@ -206,6 +388,107 @@ pub trait QueryExt: Into<Query> {
         PipeWithPropertyValueQuery::new(Box::new(self.into()), name, value, false)
     }
 
+    /// Gets values with a property value within a range. The range is
+    /// unbounded until bounds are set on the returned query.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    fn with_property_range<T: Into<Identifier>>(self, name: T) -> errors::ValidationResult<PipeWithPropertyRangeQuery> {
+        PipeWithPropertyRangeQuery::new(Box::new(self.into()), name)
+    }
+
+    /// Gets values with a string property starting with a prefix. This uses
+    /// the property's index if it has one.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    /// * `prefix`: The prefix.
+    fn with_property_starting_with<T: Into<Identifier>, S: Into<String>>(
+        self,
+        name: T,
+        prefix: S,
+    ) -> errors::ValidationResult<PipeWithPropertyPatternQuery> {
+        PipeWithPropertyPatternQuery::new(Box::new(self.into()), name, StringPattern::StartsWith(prefix.into()))
+    }
+
+    /// Gets values with a string property matching a glob.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    /// * `glob`: The glob, where `*` matches any run of characters and `?`
+    ///   matches any single character.
+    fn with_property_matching_glob<T: Into<Identifier>, S: Into<String>>(
+        self,
+        name: T,
+        glob: S,
+    ) -> errors::ValidationResult<PipeWithPropertyPatternQuery> {
+        PipeWithPropertyPatternQuery::new(Box::new(self.into()), name, StringPattern::Glob(glob.into()))
+    }
+
+    /// Gets values with a string property matching a regex.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    /// * `regex`: The regex, which matches if it's found anywhere in the
+    ///   value.
+    fn with_property_matching_regex<T: Into<Identifier>, S: Into<String>>(
+        self,
+        name: T,
+        regex: S,
+    ) -> errors::ValidationResult<PipeWithPropertyPatternQuery> {
+        PipeWithPropertyPatternQuery::new(Box::new(self.into()), name, StringPattern::Regex(regex.into()))
+    }
+
+    /// Gets values with a geospatial property within a bounding box.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    /// * `bbox`: The bounding box.
+    fn within_bounding_box<T: Into<Identifier>>(
+        self,
+        name: T,
+        bbox: BoundingBox,
+    ) -> errors::ValidationResult<PipeWithinBoundingBoxQuery> {
+        PipeWithinBoundingBoxQuery::new(Box::new(self.into()), name, bbox)
+    }
+
+    /// Gets values with a geospatial property within a distance of a point.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    /// * `center`: The point to measure distance from.
+    /// * `radius`: The maximum distance, in meters.
+    fn within_radius<T: Into<Identifier>>(
+        self,
+        name: T,
+        center: GeoPoint,
+        radius: f64,
+    ) -> errors::ValidationResult<PipeWithinRadiusQuery> {
+        PipeWithinRadiusQuery::new(Box::new(self.into()), name, center, radius)
+    }
+
+    /// Gets the vertices reachable from this query's vertices by following
+    /// edges in a given direction.
+    ///
+    /// # Arguments
+    /// * `direction`: Which direction to follow edges in.
+    fn traverse(self, direction: EdgeDirection) -> errors::ValidationResult<TraversalQuery> {
+        TraversalQuery::new(Box::new(self.into()), direction)
+    }
+
+    /// Sorts the query results by a property.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to sort by.
+    /// * `direction`: Which order to sort in.
+    fn order_by<T: Into<Identifier>>(
+        self,
+        name: T,
+        direction: SortDirection,
+    ) -> errors::ValidationResult<OrderByQuery> {
+        OrderByQuery::new(Box::new(self.into()), name, direction)
+    }
+
     /// Gets the properties associated with the query results.
     fn properties(self) -> errors::ValidationResult<PipePropertyQuery> {
         PipePropertyQuery::new(Box::new(self.into()))
@@ -215,6 +498,52 @@ pub trait QueryExt: Into<Query> {
     fn include(self) -> IncludeQuery {
         IncludeQuery::new(Box::new(self.into()))
     }
+
+    /// Sums a numeric property of the query results.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    fn sum<T: Into<Identifier>>(self, name: T) -> errors::ValidationResult<SumQuery> {
+        SumQuery::new(Box::new(self.into()), name)
+    }
+
+    /// Gets the smallest value of a property of the query results.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    fn min<T: Into<Identifier>>(self, name: T) -> errors::ValidationResult<MinQuery> {
+        MinQuery::new(Box::new(self.into()), name)
+    }
+
+    /// Gets the largest value of a property of the query results.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    fn max<T: Into<Identifier>>(self, name: T) -> errors::ValidationResult<MaxQuery> {
+        MaxQuery::new(Box::new(self.into()), name)
+    }
+
+    /// Averages a numeric property of the query results.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    fn avg<T: Into<Identifier>>(self, name: T) -> errors::ValidationResult<AvgQuery> {
+        AvgQuery::new(Box::new(self.into()), name)
+    }
+
+    /// Counts the query results by their type or the value of a property.
+    ///
+    /// # Arguments
+    /// * `key`: What to group the results by.
+    fn group_by(self, key: GroupKey) -> errors::ValidationResult<GroupByQuery> {
+        GroupByQuery::new(Box::new(self.into()), key)
+    }
+
+    /// Counts the outbound and inbound edges of each of the query's
+    /// vertices.
+    fn degree(self) -> errors::ValidationResult<DegreeQuery> {
+        DegreeQuery::new(Box::new(self.into()))
+    }
 }
 
 pub trait CountQueryExt: Into<Query> {
@@ -340,6 +669,9 @@ impl SpecificVertexQuery {
 pub struct VertexWithPropertyPresenceQuery {
     /// The name of the property.
     pub name: Identifier,
+    /// Only include vertices of this type, which allows an index scoped to
+    /// the type to be used.
+    pub t: Option<Identifier>,
 }
 
 nestable_query!(VertexWithPropertyPresenceQuery, VertexWithPropertyPresence);
@@ -350,7 +682,18 @@ impl VertexWithPropertyPresenceQuery {
     /// # Arguments
     /// * `name`: The property name.
     pub fn new<T: Into<Identifier>>(name: T) -> Self {
-        Self { name: name.into() }
+        Self {
+            name: name.into(),
+            t: None,
+        }
+    }
+
+    /// Only include vertices of a given type.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
     }
 }
 
@@ -361,6 +704,9 @@ pub struct VertexWithPropertyValueQuery {
     pub name: Identifier,
     /// The value of the property.
     pub value: Json,
+    /// Only include vertices of this type, which allows an index scoped to
+    /// the type to be used.
+    pub t: Option<Identifier>,
 }
 
 nestable_query!(VertexWithPropertyValueQuery, VertexWithPropertyValue);
@@ -375,114 +721,510 @@ impl VertexWithPropertyValueQuery {
         Self {
             name: name.into(),
             value,
+            t: None,
         }
     }
+
+    /// Only include vertices of a given type.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
+    }
 }
 
-/// Gets all edges.
+/// Gets vertices with a property value within a range.
+///
+/// # Examples
+/// ```
+/// use indradb::{ijson, Identifier, VertexWithPropertyRangeQuery};
+/// // A query to return vertices with an `age` property greater than 30.
+/// let q = VertexWithPropertyRangeQuery::new(Identifier::new("age").unwrap()).gt(ijson!(30));
+/// ```
 #[derive(Eq, PartialEq, Clone, Debug)]
-pub struct AllEdgeQuery;
+pub struct VertexWithPropertyRangeQuery {
+    /// The name of the property.
+    pub name: Identifier,
+    /// The lower bound of the property value.
+    pub lower: Bound<Json>,
+    /// The upper bound of the property value.
+    pub upper: Bound<Json>,
+    /// Only include vertices of this type, which allows an index scoped to
+    /// the type to be used.
+    pub t: Option<Identifier>,
+}
 
-impl QueryExt for AllEdgeQuery {}
-impl CountQueryExt for AllEdgeQuery {}
+nestable_query!(VertexWithPropertyRangeQuery, VertexWithPropertyRange);
+range_query!(VertexWithPropertyRangeQuery);
 
-// we don't want to impl From since the reverse operation isn't allowed
-#[allow(clippy::from_over_into)]
-impl Into<Query> for AllEdgeQuery {
-    fn into(self) -> Query {
-        Query::AllEdge
+impl VertexWithPropertyRangeQuery {
+    /// Creates a new vertex with property range query, which is unbounded
+    /// until bounds are set.
+    ///
+    /// # Arguments
+    /// * `name`: The property name.
+    pub fn new<T: Into<Identifier>>(name: T) -> Self {
+        Self {
+            name: name.into(),
+            lower: Bound::Unbounded,
+            upper: Bound::Unbounded,
+            t: None,
+        }
+    }
+
+    /// Only include vertices of a given type.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
     }
 }
 
-/// Gets a specific set of edges.
+/// Gets vertices with several properties equal to given values. This
+/// requires a composite index whose leading properties are exactly the
+/// queried ones, in any order.
+///
+/// # Examples
+/// ```
+/// use indradb::{ijson, Identifier, VertexWithPropertyValuesQuery};
+/// // A query to return vertices in Portland, Oregon.
+/// let q = VertexWithPropertyValuesQuery::new(Identifier::new("state").unwrap(), ijson!("OR"))
+///     .and(Identifier::new("city").unwrap(), ijson!("Portland"));
+/// ```
 #[derive(Eq, PartialEq, Clone, Debug)]
-pub struct SpecificEdgeQuery {
-    /// The edges to get.
-    pub edges: Vec<Edge>,
+pub struct VertexWithPropertyValuesQuery {
+    /// The names of the properties, along with their values.
+    pub values: Vec<(Identifier, Json)>,
 }
 
-nestable_query!(SpecificEdgeQuery, SpecificEdge);
+nestable_query!(VertexWithPropertyValuesQuery, VertexWithPropertyValues);
 
-impl SpecificEdgeQuery {
-    /// Creates a new edge query for getting a list of specific edges.
+impl VertexWithPropertyValuesQuery {
+    /// Creates a new vertex with property values query.
     ///
-    /// Arguments
-    /// * `edges`: The edges to get.
-    pub fn new(edges: Vec<Edge>) -> Self {
-        Self { edges }
+    /// # Arguments
+    /// * `name`: The name of the first property.
+    /// * `value`: The value of the first property.
+    pub fn new<T: Into<Identifier>>(name: T, value: Json) -> Self {
+        Self {
+            values: vec![(name.into(), value)],
+        }
     }
 
-    /// Creates a new edge query for getting a single edge.
+    /// Adds another property to match on.
     ///
-    /// Arguments
-    /// * `edge`: The edge to get.
-    pub fn single(edge: Edge) -> Self {
-        Self { edges: vec![edge] }
+    /// # Arguments
+    /// * `name`: The property name.
+    /// * `value`: The property value.
+    pub fn and<T: Into<Identifier>>(mut self, name: T, value: Json) -> Self {
+        self.values.push((name.into(), value));
+        self
     }
 }
 
-/// Gets edges with or without a given property.
+/// Gets vertices with a string property containing all of the terms in some
+/// text. This requires a full-text index on the property. The text is split
+/// into terms the same way indexed values are; see `util::tokenize`.
+///
+/// # Examples
+/// ```
+/// use indradb::{Identifier, VertexWithPropertyMatchQuery};
+/// // A query to return vertices whose name contains "ada" and "lovelace".
+/// let q = VertexWithPropertyMatchQuery::new(Identifier::new("name").unwrap(), "Ada Lovelace");
+/// ```
 #[derive(Eq, PartialEq, Clone, Debug)]
-pub struct EdgeWithPropertyPresenceQuery {
+pub struct VertexWithPropertyMatchQuery {
     /// The name of the property.
     pub name: Identifier,
+    /// The text whose terms must all be in the property value.
+    pub text: String,
 }
 
-nestable_query!(EdgeWithPropertyPresenceQuery, EdgeWithPropertyPresence);
+nestable_query!(VertexWithPropertyMatchQuery, VertexWithPropertyMatch);
 
-impl EdgeWithPropertyPresenceQuery {
-    /// Creates a new edge with property presence query.
+impl VertexWithPropertyMatchQuery {
+    /// Creates a new vertex with property match query.
     ///
     /// # Arguments
     /// * `name`: The property name.
-    pub fn new<T: Into<Identifier>>(name: T) -> Self {
-        Self { name: name.into() }
+    /// * `text`: The text whose terms must all be in the property value.
+    pub fn new<T: Into<Identifier>, S: Into<String>>(name: T, text: S) -> Self {
+        Self {
+            name: name.into(),
+            text: text.into(),
+        }
     }
 }
 
-/// Gets edges with a property equal to a given value.
-#[derive(Eq, PartialEq, Clone, Debug)]
-pub struct EdgeWithPropertyValueQuery {
+/// How the distance between two vectors is measured.
+#[derive(Eq, PartialEq, Clone, Debug, Hash, Copy)]
+pub enum DistanceMetric {
+    /// The straight-line distance between the vectors.
+    Euclidean,
+    /// One minus the cosine of the angle between the vectors, which ignores
+    /// their magnitudes.
+    Cosine,
+    /// The negated dot product of the vectors, so that vectors with a
+    /// larger dot product are nearer.
+    DotProduct,
+}
+
+impl DistanceMetric {
+    /// Measures the distance between two vectors of the same length. This
+    /// is NaN for the cosine distance if either vector has a magnitude of
+    /// zero.
+    ///
+    /// # Arguments
+    /// * `first`: The first vector.
+    /// * `second`: The second vector.
+    pub fn distance(&self, first: &[f64], second: &[f64]) -> f64 {
+        let dot = || first.iter().zip(second).map(|(a, b)| a * b).sum::<f64>();
+        match self {
+            DistanceMetric::Euclidean => first
+                .iter()
+                .zip(second)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f64>()
+                .sqrt(),
+            DistanceMetric::Cosine => {
+                let first_norm = first.iter().map(|a| a * a).sum::<f64>().sqrt();
+                let second_norm = second.iter().map(|b| b * b).sum::<f64>().sqrt();
+                1.0 - dot() / (first_norm * second_norm)
+            }
+            DistanceMetric::DotProduct => -dot(),
+        }
+    }
+}
+
+impl FromStr for DistanceMetric {
+    type Err = errors::ValidationError;
+
+    fn from_str(s: &str) -> Result<DistanceMetric, Self::Err> {
+        match s {
+            "euclidean" => Ok(DistanceMetric::Euclidean),
+            "cosine" => Ok(DistanceMetric::Cosine),
+            "dot_product" => Ok(DistanceMetric::DotProduct),
+            _ => Err(errors::ValidationError::InvalidValue),
+        }
+    }
+}
+
+/// Gets the `k` vertices whose vector property is nearest to a given
+/// vector, ordered from nearest to furthest. This requires a vector index on
+/// the property. Vertices whose value isn't an array of numbers of the same
+/// length as the vector are skipped.
+///
+/// The search is exact, comparing the vector against every indexed value.
+///
+/// # Examples
+/// ```
+/// use indradb::{DistanceMetric, Identifier, NearestVerticesQuery};
+/// // A query to return the 10 vertices with the most similar embeddings.
+/// let q = NearestVerticesQuery::new(
+///     Identifier::new("embedding").unwrap(),
+///     vec![0.1, 0.2, 0.3],
+///     10,
+///     DistanceMetric::Cosine,
+/// );
+/// ```
+#[derive(PartialEq, Clone, Debug)]
+pub struct NearestVerticesQuery {
     /// The name of the property.
     pub name: Identifier,
-    /// The value of the property.
-    pub value: Json,
+    /// The vector to find the nearest values to.
+    pub vector: Vec<f64>,
+    /// The maximum number of vertices to return.
+    pub k: u32,
+    /// How distance is measured.
+    pub metric: DistanceMetric,
 }
 
-nestable_query!(EdgeWithPropertyValueQuery, EdgeWithPropertyValue);
+// Like `Json`, vectors are compared by value, so a query with NaN components
+// isn't equal to itself
+impl Eq for NearestVerticesQuery {}
 
-impl EdgeWithPropertyValueQuery {
-    /// Creates a new edge with property value query.
+nestable_query!(NearestVerticesQuery, NearestVertices);
+
+impl NearestVerticesQuery {
+    /// Creates a new nearest vertices query.
     ///
     /// # Arguments
     /// * `name`: The property name.
-    /// * `value`: The property value.
-    pub fn new<T: Into<Identifier>>(name: T, value: Json) -> Self {
+    /// * `vector`: The vector to find the nearest values to.
+    /// * `k`: The maximum number of vertices to return.
+    /// * `metric`: How distance is measured.
+    pub fn new<T: Into<Identifier>>(name: T, vector: Vec<f64>, k: u32, metric: DistanceMetric) -> Self {
         Self {
             name: name.into(),
-            value,
+            vector,
+            k,
+            metric,
         }
     }
 }
 
-/// Gets the vertices associated with edges, or edges associated with
-/// vertices.
+/// Gets the vertices whose geospatial property is within a bounding box.
+/// This requires a geospatial index on the property.
 ///
-/// Generally, you shouldn't need to construct this directly, but rather call
-/// `.outbound()` or `.inbound()`.
+/// # Examples
+/// ```
+/// use indradb::{BoundingBox, GeoPoint, Identifier, WithinBoundingBoxQuery};
+/// // A query for the vertices located in Portland, Oregon.
+/// let bbox = BoundingBox::new(
+///     GeoPoint::new(45.43, -122.84).unwrap(),
+///     GeoPoint::new(45.65, -122.47).unwrap(),
+/// )
+/// .unwrap();
+/// let q = WithinBoundingBoxQuery::new(Identifier::new("location").unwrap(), bbox);
+/// ```
 #[derive(Eq, PartialEq, Clone, Debug)]
-pub struct PipeQuery {
-    /// The edge query to build off of.
-    pub inner: Box<Query>,
-
-    /// Whether to get outbound or inbound values.
-    pub direction: EdgeDirection,
-
-    /// Limits the number of values to get.
-    pub limit: u32,
-
-    /// Filters the type of values returned.
-    pub t: Option<Identifier>,
+pub struct WithinBoundingBoxQuery {
+    /// The name of the property.
+    pub name: Identifier,
+    /// The bounding box.
+    pub bbox: BoundingBox,
+}
+
+nestable_query!(WithinBoundingBoxQuery, WithinBoundingBox);
+
+impl WithinBoundingBoxQuery {
+    /// Creates a new within bounding box query.
+    ///
+    /// # Arguments
+    /// * `name`: The property name.
+    /// * `bbox`: The bounding box.
+    pub fn new<T: Into<Identifier>>(name: T, bbox: BoundingBox) -> Self {
+        Self {
+            name: name.into(),
+            bbox,
+        }
+    }
+}
+
+/// Gets the vertices whose geospatial property is within a distance of a
+/// point, as measured along the surface of the earth. This requires a
+/// geospatial index on the property.
+///
+/// # Examples
+/// ```
+/// use indradb::{GeoPoint, Identifier, WithinRadiusQuery};
+/// // A query for the vertices within 5km of a point.
+/// let q = WithinRadiusQuery::new(
+///     Identifier::new("location").unwrap(),
+///     GeoPoint::new(45.52, -122.68).unwrap(),
+///     5000.0,
+/// );
+/// ```
+#[derive(PartialEq, Clone, Debug)]
+pub struct WithinRadiusQuery {
+    /// The name of the property.
+    pub name: Identifier,
+    /// The point to measure distance from.
+    pub center: GeoPoint,
+    /// The maximum distance, in meters.
+    pub radius: f64,
+}
+
+// Like `Json`, the radius is compared by value, so a query with a NaN radius
+// isn't equal to itself
+impl Eq for WithinRadiusQuery {}
+
+nestable_query!(WithinRadiusQuery, WithinRadius);
+
+impl WithinRadiusQuery {
+    /// Creates a new within radius query.
+    ///
+    /// # Arguments
+    /// * `name`: The property name.
+    /// * `center`: The point to measure distance from.
+    /// * `radius`: The maximum distance, in meters.
+    pub fn new<T: Into<Identifier>>(name: T, center: GeoPoint, radius: f64) -> Self {
+        Self {
+            name: name.into(),
+            center,
+            radius,
+        }
+    }
+}
+
+/// Gets all edges.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct AllEdgeQuery;
+
+impl QueryExt for AllEdgeQuery {}
+impl CountQueryExt for AllEdgeQuery {}
+
+// we don't want to impl From since the reverse operation isn't allowed
+#[allow(clippy::from_over_into)]
+impl Into<Query> for AllEdgeQuery {
+    fn into(self) -> Query {
+        Query::AllEdge
+    }
+}
+
+/// Gets a specific set of edges.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct SpecificEdgeQuery {
+    /// The edges to get.
+    pub edges: Vec<Edge>,
+}
+
+nestable_query!(SpecificEdgeQuery, SpecificEdge);
+
+impl SpecificEdgeQuery {
+    /// Creates a new edge query for getting a list of specific edges.
+    ///
+    /// Arguments
+    /// * `edges`: The edges to get.
+    pub fn new(edges: Vec<Edge>) -> Self {
+        Self { edges }
+    }
+
+    /// Creates a new edge query for getting a single edge.
+    ///
+    /// Arguments
+    /// * `edge`: The edge to get.
+    pub fn single(edge: Edge) -> Self {
+        Self { edges: vec![edge] }
+    }
+}
+
+/// Gets edges with or without a given property.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct EdgeWithPropertyPresenceQuery {
+    /// The name of the property.
+    pub name: Identifier,
+    /// Only include edges of this type, which allows an index scoped to
+    /// the type to be used.
+    pub t: Option<Identifier>,
+}
+
+nestable_query!(EdgeWithPropertyPresenceQuery, EdgeWithPropertyPresence);
+
+impl EdgeWithPropertyPresenceQuery {
+    /// Creates a new edge with property presence query.
+    ///
+    /// # Arguments
+    /// * `name`: The property name.
+    pub fn new<T: Into<Identifier>>(name: T) -> Self {
+        Self {
+            name: name.into(),
+            t: None,
+        }
+    }
+
+    /// Only include edges of a given type.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
+    }
+}
+
+/// Gets edges with a property equal to a given value.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct EdgeWithPropertyValueQuery {
+    /// The name of the property.
+    pub name: Identifier,
+    /// The value of the property.
+    pub value: Json,
+    /// Only include edges of this type, which allows an index scoped to
+    /// the type to be used.
+    pub t: Option<Identifier>,
+}
+
+nestable_query!(EdgeWithPropertyValueQuery, EdgeWithPropertyValue);
+
+impl EdgeWithPropertyValueQuery {
+    /// Creates a new edge with property value query.
+    ///
+    /// # Arguments
+    /// * `name`: The property name.
+    /// * `value`: The property value.
+    pub fn new<T: Into<Identifier>>(name: T, value: Json) -> Self {
+        Self {
+            name: name.into(),
+            value,
+            t: None,
+        }
+    }
+
+    /// Only include edges of a given type.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
+    }
+}
+
+/// Gets edges with a property value within a range.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct EdgeWithPropertyRangeQuery {
+    /// The name of the property.
+    pub name: Identifier,
+    /// The lower bound of the property value.
+    pub lower: Bound<Json>,
+    /// The upper bound of the property value.
+    pub upper: Bound<Json>,
+    /// Only include edges of this type, which allows an index scoped to
+    /// the type to be used.
+    pub t: Option<Identifier>,
+}
+
+nestable_query!(EdgeWithPropertyRangeQuery, EdgeWithPropertyRange);
+range_query!(EdgeWithPropertyRangeQuery);
+
+impl EdgeWithPropertyRangeQuery {
+    /// Creates a new edge with property range query, which is unbounded
+    /// until bounds are set.
+    ///
+    /// # Arguments
+    /// * `name`: The property name.
+    pub fn new<T: Into<Identifier>>(name: T) -> Self {
+        Self {
+            name: name.into(),
+            lower: Bound::Unbounded,
+            upper: Bound::Unbounded,
+            t: None,
+        }
+    }
+
+    /// Only include edges of a given type.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
+    }
+}
+
+/// Gets the vertices associated with edges, or edges associated with
+/// vertices.
+///
+/// Generally, you shouldn't need to construct this directly, but rather call
+/// `.outbound()` or `.inbound()`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct PipeQuery {
+    /// The edge query to build off of.
+    pub inner: Box<Query>,
+
+    /// Whether to get outbound or inbound values.
+    pub direction: EdgeDirection,
+
+    /// Limits the number of values to get.
+    pub limit: u32,
+
+    /// Filters the types of values returned. If empty, values of all types
+    /// are returned.
+    pub t: Vec<Identifier>,
+
+    /// When piping from vertices to edges, only get the edges after this
+    /// one, which resumes a previous scan from the last edge it returned.
+    pub after: Option<Edge>,
 }
 
 nestable_query!(PipeQuery, Pipe);
@@ -503,7 +1245,8 @@ impl PipeQuery {
             inner,
             direction,
             limit: u32::MAX,
-            t: None,
+            t: Vec::default(),
+            after: None,
         })
     }
 
@@ -517,10 +1260,12 @@ impl PipeQuery {
             direction: self.direction,
             limit,
             t: self.t,
+            after: self.after,
         }
     }
 
-    /// Filter the type of values returned.
+    /// Filter the type of values returned. This replaces any types that
+    /// were previously set.
     ///
     /// # Arguments
     /// * `t`: Sets the type filter.
@@ -529,7 +1274,45 @@ impl PipeQuery {
             inner: self.inner,
             direction: self.direction,
             limit: self.limit,
-            t: Some(t),
+            t: vec![t],
+            after: self.after,
+        }
+    }
+
+    /// Adds a type to the type filter, so that values of any of several
+    /// types are returned.
+    ///
+    /// # Arguments
+    /// * `t`: The type to add to the type filter.
+    pub fn add_t(self, t: Identifier) -> Self {
+        let mut types = self.t;
+        types.push(t);
+        Self {
+            inner: self.inner,
+            direction: self.direction,
+            limit: self.limit,
+            t: types,
+            after: self.after,
+        }
+    }
+
+    /// Only get the edges after a given one, when piping from vertices to
+    /// edges. Edges are returned grouped by the piped vertex they're for,
+    /// and then in order, so passing the last edge from a previous page
+    /// resumes where it left off. If the edge's piped vertex has since been
+    /// deleted, vertices piped in order of their IDs, as by `AllVertexQuery`
+    /// and `RangeVertexQuery`, resume from the next vertex. Otherwise, the
+    /// query fails with `ValidationError::InvalidValue`.
+    ///
+    /// # Arguments
+    /// * `after`: The last edge that was returned.
+    pub fn after(self, after: Edge) -> Self {
+        Self {
+            inner: self.inner,
+            direction: self.direction,
+            limit: self.limit,
+            t: self.t,
+            after: Some(after),
         }
     }
 }
@@ -646,60 +1429,574 @@ impl PipeWithPropertyValueQuery {
     }
 }
 
-/// Includes the results of a query in output.
-///
-/// The outermost part of a query will always be explicitly included. This
-/// allows you to also output an intermediate result.
+/// Gets vertices or edges with a property value within a range.
 ///
-/// # Examples
-/// ```
-/// use indradb::{AllVertexQuery, QueryExt};
-/// // A query to return all edges in the database, which are implicitly
-/// // included as the outermost results.
-/// let q = AllVertexQuery.outbound();
-/// // A query to return all vertices and all edges in the database, because
-/// // vertices are explicitly included as intermediate results.
-/// let q = AllVertexQuery.include().outbound();
-/// ```
+/// Only values of the same type as the bounds are included, since values of
+/// different types aren't comparable.
 #[derive(Eq, PartialEq, Clone, Debug)]
-pub struct IncludeQuery {
-    /// The query to export.
+pub struct PipeWithPropertyRangeQuery {
+    /// The query to filter.
     pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+    /// The lower bound of the property value.
+    pub lower: Bound<Json>,
+    /// The upper bound of the property value.
+    pub upper: Bound<Json>,
 }
 
-nestable_query!(IncludeQuery, Include);
+nestable_query!(PipeWithPropertyRangeQuery, PipeWithPropertyRange);
+range_query!(PipeWithPropertyRangeQuery);
 
-impl IncludeQuery {
-    /// Marks a query as exported.
+impl PipeWithPropertyRangeQuery {
+    /// Constructs a new pipe with property range query, which is unbounded
+    /// until bounds are set.
     ///
-    /// Arguments
-    /// * `inner`: The query to export.
-    pub fn new(inner: Box<Query>) -> Self {
-        Self { inner }
+    /// # Arguments
+    /// * `inner`: The inner query.
+    /// * `name`: The property name to filter.
+    pub fn new<T: Into<Identifier>>(inner: Box<Query>, name: T) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        Ok(Self {
+            inner,
+            name: name.into(),
+            lower: Bound::Unbounded,
+            upper: Bound::Unbounded,
+        })
     }
 }
 
-/// Counts the number of items returned from a query.
+/// A pattern that string property values are matched against.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum StringPattern {
+    /// Matches strings starting with a prefix.
+    StartsWith(String),
+    /// Matches strings against a glob, where `*` matches any run of
+    /// characters and `?` matches any single character.
+    Glob(String),
+    /// Matches strings that a regex is found in.
+    Regex(String),
+}
+
+impl StringPattern {
+    // Compiles globs and regexes. Prefixes are checked directly, so there's
+    // nothing to compile for them.
+    pub(crate) fn compile(&self) -> errors::ValidationResult<Option<Regex>> {
+        let pattern = match self {
+            StringPattern::StartsWith(_) => return Ok(None),
+            StringPattern::Glob(glob) => util::glob_to_regex(glob),
+            StringPattern::Regex(regex) => regex.clone(),
+        };
+        match Regex::new(&pattern) {
+            Ok(regex) => Ok(Some(regex)),
+            Err(_) => Err(errors::ValidationError::InvalidValue),
+        }
+    }
+}
+
+/// Gets vertices or edges with a string property matching a pattern.
+///
+/// Values that aren't strings never match. Prefix patterns are answered with
+/// the property's index where it covers the piped values, which makes them
+/// suitable for autocomplete; other patterns check each piped value's
+/// property.
 ///
 /// # Examples
 /// ```
-/// use indradb::{AllVertexQuery, CountQueryExt};
-/// // A query to return the total number of vertices in the database.
-/// let q = AllVertexQuery.count();
+/// use indradb::{AllVertexQuery, Identifier, QueryExt};
+/// // A query for the vertices with names starting with "ada".
+/// let q = AllVertexQuery.with_property_starting_with(Identifier::new("name").unwrap(), "ada");
 /// ```
 #[derive(Eq, PartialEq, Clone, Debug)]
-pub struct CountQuery {
-    /// The query to export.
+pub struct PipeWithPropertyPatternQuery {
+    /// The query to filter.
     pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+    /// The pattern the property value must match.
+    pub pattern: StringPattern,
 }
 
-into_query!(CountQuery, Count);
+nestable_query!(PipeWithPropertyPatternQuery, PipeWithPropertyPattern);
 
-impl CountQuery {
-    /// Marks a query as exported.
+impl PipeWithPropertyPatternQuery {
+    /// Constructs a new pipe with property pattern query.
     ///
-    /// Arguments
-    /// * `inner`: The query to export.
+    /// # Arguments
+    /// * `inner`: The inner query.
+    /// * `name`: The property name to filter.
+    /// * `pattern`: The pattern the property value must match.
+    ///
+    /// # Errors
+    /// Returns `ValidationError::InvalidValue` if the pattern is a regex that
+    /// doesn't compile.
+    pub fn new<T: Into<Identifier>>(
+        inner: Box<Query>,
+        name: T,
+        pattern: StringPattern,
+    ) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        pattern.compile()?;
+        Ok(Self {
+            inner,
+            name: name.into(),
+            pattern,
+        })
+    }
+}
+
+/// Gets vertices or edges with a geospatial property within a bounding box.
+///
+/// Each piped value's property is checked, so no index is needed. Values
+/// that aren't objects with numeric `lat` and `lon` fields never match.
+///
+/// # Examples
+/// ```
+/// use indradb::{AllVertexQuery, BoundingBox, GeoPoint, Identifier, QueryExt};
+/// // A query for the vertices located in Portland, Oregon.
+/// let bbox = BoundingBox::new(
+///     GeoPoint::new(45.43, -122.84).unwrap(),
+///     GeoPoint::new(45.65, -122.47).unwrap(),
+/// )
+/// .unwrap();
+/// let q = AllVertexQuery.within_bounding_box(Identifier::new("location").unwrap(), bbox);
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct PipeWithinBoundingBoxQuery {
+    /// The query to filter.
+    pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+    /// The bounding box.
+    pub bbox: BoundingBox,
+}
+
+nestable_query!(PipeWithinBoundingBoxQuery, PipeWithinBoundingBox);
+
+impl PipeWithinBoundingBoxQuery {
+    /// Constructs a new pipe within bounding box query.
+    ///
+    /// # Arguments
+    /// * `inner`: The inner query.
+    /// * `name`: The property name to filter.
+    /// * `bbox`: The bounding box.
+    pub fn new<T: Into<Identifier>>(inner: Box<Query>, name: T, bbox: BoundingBox) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        Ok(Self {
+            inner,
+            name: name.into(),
+            bbox,
+        })
+    }
+}
+
+/// Gets vertices or edges with a geospatial property within a distance of a
+/// point.
+///
+/// Each piped value's property is checked, so no index is needed. Values
+/// that aren't objects with numeric `lat` and `lon` fields never match.
+///
+/// # Examples
+/// ```
+/// use indradb::{GeoPoint, Identifier, QueryExt, SpecificVertexQuery};
+/// use uuid::Uuid;
+/// // A query for the venues within 5km of a point that a user follows.
+/// let q = SpecificVertexQuery::single(Uuid::default())
+///     .outbound()
+///     .unwrap()
+///     .inbound()
+///     .unwrap()
+///     .within_radius(
+///         Identifier::new("location").unwrap(),
+///         GeoPoint::new(45.52, -122.68).unwrap(),
+///         5000.0,
+///     );
+/// ```
+#[derive(PartialEq, Clone, Debug)]
+pub struct PipeWithinRadiusQuery {
+    /// The query to filter.
+    pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+    /// The point to measure distance from.
+    pub center: GeoPoint,
+    /// The maximum distance, in meters.
+    pub radius: f64,
+}
+
+// Like `Json`, the radius is compared by value, so a query with a NaN radius
+// isn't equal to itself
+impl Eq for PipeWithinRadiusQuery {}
+
+nestable_query!(PipeWithinRadiusQuery, PipeWithinRadius);
+
+impl PipeWithinRadiusQuery {
+    /// Constructs a new pipe within radius query.
+    ///
+    /// # Arguments
+    /// * `inner`: The inner query.
+    /// * `name`: The property name to filter.
+    /// * `center`: The point to measure distance from.
+    /// * `radius`: The maximum distance, in meters.
+    pub fn new<T: Into<Identifier>>(
+        inner: Box<Query>,
+        name: T,
+        center: GeoPoint,
+        radius: f64,
+    ) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        Ok(Self {
+            inner,
+            name: name.into(),
+            center,
+            radius,
+        })
+    }
+}
+
+/// Gets the vertices reachable from a set of vertices within a range of
+/// hops.
+///
+/// The traversal is breadth-first, and each vertex is visited at most once,
+/// at the shallowest depth it can be reached from. Vertices at depths
+/// between `min_depth` and `max_depth` (inclusive) are returned, where the
+/// starting vertices are at depth 0.
+///
+/// # Examples
+/// ```
+/// use indradb::{EdgeDirection, QueryExt, SpecificVertexQuery};
+/// use uuid::Uuid;
+/// // A query to return the vertices between one and three outbound hops
+/// // away from a vertex.
+/// let q = SpecificVertexQuery::single(Uuid::default())
+///     .traverse(EdgeDirection::Outbound)
+///     .unwrap()
+///     .min_depth(1)
+///     .max_depth(3);
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct TraversalQuery {
+    /// The vertex query to start the traversal from.
+    pub inner: Box<Query>,
+
+    /// Whether to follow outbound or inbound edges.
+    pub direction: EdgeDirection,
+
+    /// The minimum depth of vertices to return.
+    pub min_depth: u32,
+
+    /// The maximum depth of vertices to return.
+    pub max_depth: u32,
+
+    /// Only follow edges of this type.
+    pub t: Option<Identifier>,
+
+    /// Limits the number of vertices to get.
+    pub limit: u32,
+}
+
+nestable_query!(TraversalQuery, Traversal);
+
+impl TraversalQuery {
+    /// Constructs a new traversal query, which gets the vertices one hop
+    /// away by default.
+    ///
+    /// # Arguments
+    /// * `inner`: The vertex query to start the traversal from.
+    /// * `direction`: Which direction to follow edges in.
+    pub fn new(inner: Box<Query>, direction: EdgeDirection) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+
+        Ok(Self {
+            inner,
+            direction,
+            min_depth: 1,
+            max_depth: 1,
+            t: None,
+            limit: u32::MAX,
+        })
+    }
+
+    /// Sets the minimum depth.
+    ///
+    /// # Arguments
+    /// * `min_depth`: The minimum depth of vertices to return.
+    pub fn min_depth(self, min_depth: u32) -> Self {
+        Self { min_depth, ..self }
+    }
+
+    /// Sets the maximum depth.
+    ///
+    /// # Arguments
+    /// * `max_depth`: The maximum depth of vertices to return.
+    pub fn max_depth(self, max_depth: u32) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    /// Only follow edges of a given type.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the edge type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
+    }
+
+    /// Sets the limit.
+    ///
+    /// # Arguments
+    /// * `limit`: Limits the number of returned results.
+    pub fn limit(self, limit: u32) -> Self {
+        Self { limit, ..self }
+    }
+}
+
+/// Gets the shortest path between two vertices.
+///
+/// The path is found via a breadth-first search over the edges of each
+/// vertex, which can optionally run from both ends at once. This generally
+/// visits far fewer vertices when the graph fans out quickly.
+///
+/// # Examples
+/// ```
+/// use indradb::ShortestPathQuery;
+/// use uuid::Uuid;
+/// // A query to return a path of at most five outbound hops between two
+/// // vertices.
+/// let q = ShortestPathQuery::new(Uuid::default(), Uuid::default())
+///     .max_depth(5)
+///     .bidirectional(true);
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ShortestPathQuery {
+    /// The ID of the vertex to start from.
+    pub source: Uuid,
+
+    /// The ID of the vertex to find a path to.
+    pub target: Uuid,
+
+    /// Whether to follow outbound or inbound edges from the source.
+    pub direction: EdgeDirection,
+
+    /// Only follow edges of these types. If empty, edges of any type are
+    /// followed.
+    pub t: Vec<Identifier>,
+
+    /// The maximum number of edges in the path.
+    pub max_depth: u32,
+
+    /// Whether to search from both the source and the target.
+    pub bidirectional: bool,
+}
+
+into_query!(ShortestPathQuery, ShortestPath);
+
+impl ShortestPathQuery {
+    /// Constructs a new shortest path query, which follows outbound edges
+    /// of any type by default.
+    ///
+    /// # Arguments
+    /// * `source`: The ID of the vertex to start from.
+    /// * `target`: The ID of the vertex to find a path to.
+    pub fn new(source: Uuid, target: Uuid) -> Self {
+        Self {
+            source,
+            target,
+            direction: EdgeDirection::Outbound,
+            t: Vec::default(),
+            max_depth: u32::MAX,
+            bidirectional: false,
+        }
+    }
+
+    /// Sets the direction.
+    ///
+    /// # Arguments
+    /// * `direction`: Which direction to follow edges in.
+    pub fn direction(self, direction: EdgeDirection) -> Self {
+        Self { direction, ..self }
+    }
+
+    /// Only follow edges of the given types.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the edge type filter.
+    pub fn t(self, t: Vec<Identifier>) -> Self {
+        Self { t, ..self }
+    }
+
+    /// Sets the maximum depth.
+    ///
+    /// # Arguments
+    /// * `max_depth`: The maximum number of edges in the path.
+    pub fn max_depth(self, max_depth: u32) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    /// Sets whether to search from both ends.
+    ///
+    /// # Arguments
+    /// * `bidirectional`: Whether to search from both the source and the
+    ///   target.
+    pub fn bidirectional(self, bidirectional: bool) -> Self {
+        Self { bidirectional, ..self }
+    }
+}
+
+/// Sorts vertices or edges by a property, a page at a time.
+///
+/// Items are sorted by the value of the property, with items that don't
+/// have it last regardless of the direction, and ties broken by ID. Along
+/// with the sorted items, the query outputs a cursor if there are more items
+/// after the page, which can be passed to `after` to get the next page. All
+/// of the inner query's results are read to sort them, so it's best to
+/// filter them down first.
+///
+/// # Examples
+/// ```
+/// use indradb::{Identifier, QueryExt, SortDirection, VertexWithPropertyPresenceQuery};
+/// // A query to return the 20 users with the highest scores.
+/// let q = VertexWithPropertyPresenceQuery::new(Identifier::new("user").unwrap())
+///     .order_by(Identifier::new("score").unwrap(), SortDirection::Descending)
+///     .unwrap()
+///     .limit(20);
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct OrderByQuery {
+    /// The query to sort.
+    pub inner: Box<Query>,
+
+    /// The name of the property to sort by.
+    pub name: Identifier,
+
+    /// Which order to sort in.
+    pub direction: SortDirection,
+
+    /// Limits the number of values to get.
+    pub limit: u32,
+
+    /// Only get the values after this position, as returned with a previous
+    /// page.
+    pub cursor: Option<Cursor>,
+}
+
+nestable_query!(OrderByQuery, OrderBy);
+
+impl OrderByQuery {
+    /// Constructs a new order by query, which gets the first page of
+    /// results by default.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to sort.
+    /// * `name`: The name of the property to sort by.
+    /// * `direction`: Which order to sort in.
+    pub fn new<T: Into<Identifier>>(
+        inner: Box<Query>,
+        name: T,
+        direction: SortDirection,
+    ) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+
+        Ok(Self {
+            inner,
+            name: name.into(),
+            direction,
+            limit: u32::MAX,
+            cursor: None,
+        })
+    }
+
+    /// Sets the limit.
+    ///
+    /// # Arguments
+    /// * `limit`: Limits the number of returned results.
+    pub fn limit(self, limit: u32) -> Self {
+        Self { limit, ..self }
+    }
+
+    /// Gets the page after a cursor.
+    ///
+    /// # Arguments
+    /// * `cursor`: The cursor returned with the previous page.
+    pub fn after(self, cursor: Cursor) -> Self {
+        Self {
+            cursor: Some(cursor),
+            ..self
+        }
+    }
+}
+
+/// Includes the results of a query in output.
+///
+/// The outermost part of a query will always be explicitly included. This
+/// allows you to also output an intermediate result.
+///
+/// # Examples
+/// ```
+/// use indradb::{AllVertexQuery, QueryExt};
+/// // A query to return all edges in the database, which are implicitly
+/// // included as the outermost results.
+/// let q = AllVertexQuery.outbound();
+/// // A query to return all vertices and all edges in the database, because
+/// // vertices are explicitly included as intermediate results.
+/// let q = AllVertexQuery.include().outbound();
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct IncludeQuery {
+    /// The query to export.
+    pub inner: Box<Query>,
+}
+
+nestable_query!(IncludeQuery, Include);
+
+impl IncludeQuery {
+    /// Marks a query as exported.
+    ///
+    /// Arguments
+    /// * `inner`: The query to export.
+    pub fn new(inner: Box<Query>) -> Self {
+        Self { inner }
+    }
+}
+
+/// Counts the number of items returned from a query.
+///
+/// # Examples
+/// ```
+/// use indradb::{AllVertexQuery, CountQueryExt};
+/// // A query to return the total number of vertices in the database.
+/// let q = AllVertexQuery.count();
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct CountQuery {
+    /// The query to export.
+    pub inner: Box<Query>,
+}
+
+into_query!(CountQuery, Count);
+
+impl CountQuery {
+    /// Marks a query as exported.
+    ///
+    /// Arguments
+    /// * `inner`: The query to export.
     pub fn new(inner: Box<Query>) -> errors::ValidationResult<Self> {
         match inner.output_type()? {
             QueryOutputValue::Vertices(_)
@@ -712,6 +2009,236 @@ impl CountQuery {
     }
 }
 
+// Checks that an aggregate is over vertices or edges, which have the
+// properties being aggregated.
+fn validate_aggregate_inner(inner: &Query) -> errors::ValidationResult<()> {
+    match inner.output_type()? {
+        QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => Ok(()),
+        _ => Err(errors::ValidationError::InnerQuery),
+    }
+}
+
+/// Sums a numeric property of the vertices or edges returned from a query.
+///
+/// Values that aren't numbers, and items without the property, are skipped.
+/// The sum is output as an integer if every value is an integer, otherwise
+/// as a float, and is `None` if there are no values.
+///
+/// # Examples
+/// ```
+/// use indradb::{Identifier, QueryExt, VertexWithPropertyPresenceQuery};
+/// // A query to return the total of all order amounts.
+/// let q = VertexWithPropertyPresenceQuery::new(Identifier::new("amount").unwrap())
+///     .sum(Identifier::new("amount").unwrap());
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct SumQuery {
+    /// The query to aggregate.
+    pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+}
+
+into_query!(SumQuery, Sum);
+
+impl SumQuery {
+    /// Constructs a new sum query.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to aggregate.
+    /// * `name`: The name of the property.
+    pub fn new<T: Into<Identifier>>(inner: Box<Query>, name: T) -> errors::ValidationResult<Self> {
+        validate_aggregate_inner(&inner)?;
+        Ok(Self {
+            inner,
+            name: name.into(),
+        })
+    }
+}
+
+/// Gets the smallest value of a property of the vertices or edges returned
+/// from a query, or nothing if none of them have it.
+///
+/// Values are compared in the same order as `OrderByQuery` sorts them in,
+/// so values of different JSON types can be compared, with null being the
+/// smallest, followed by booleans, numbers, strings, arrays and objects.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct MinQuery {
+    /// The query to aggregate.
+    pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+}
+
+into_query!(MinQuery, Min);
+
+impl MinQuery {
+    /// Constructs a new min query.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to aggregate.
+    /// * `name`: The name of the property.
+    pub fn new<T: Into<Identifier>>(inner: Box<Query>, name: T) -> errors::ValidationResult<Self> {
+        validate_aggregate_inner(&inner)?;
+        Ok(Self {
+            inner,
+            name: name.into(),
+        })
+    }
+}
+
+/// Gets the largest value of a property of the vertices or edges returned
+/// from a query, or nothing if none of them have it.
+///
+/// Values are compared in the same way as for `MinQuery`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct MaxQuery {
+    /// The query to aggregate.
+    pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+}
+
+into_query!(MaxQuery, Max);
+
+impl MaxQuery {
+    /// Constructs a new max query.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to aggregate.
+    /// * `name`: The name of the property.
+    pub fn new<T: Into<Identifier>>(inner: Box<Query>, name: T) -> errors::ValidationResult<Self> {
+        validate_aggregate_inner(&inner)?;
+        Ok(Self {
+            inner,
+            name: name.into(),
+        })
+    }
+}
+
+/// Averages a numeric property of the vertices or edges returned from a
+/// query.
+///
+/// Values that aren't numbers, and items without the property, are skipped.
+/// The average is output as a float, or nothing if there are no values.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct AvgQuery {
+    /// The query to aggregate.
+    pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+}
+
+into_query!(AvgQuery, Avg);
+
+impl AvgQuery {
+    /// Constructs a new average query.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to aggregate.
+    /// * `name`: The name of the property.
+    pub fn new<T: Into<Identifier>>(inner: Box<Query>, name: T) -> errors::ValidationResult<Self> {
+        validate_aggregate_inner(&inner)?;
+        Ok(Self {
+            inner,
+            name: name.into(),
+        })
+    }
+}
+
+/// What to group the results of a `GroupByQuery` by.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum GroupKey {
+    /// The vertex or edge type, as a JSON string.
+    Type,
+    /// The value of a property. Items without the property aren't counted.
+    Property(Identifier),
+}
+
+/// Counts the vertices or edges returned from a query by their type or the
+/// value of a property.
+///
+/// Groups are output in the same order as `OrderByQuery` sorts their keys
+/// in.
+///
+/// # Examples
+/// ```
+/// use indradb::{AllVertexQuery, GroupKey, QueryExt};
+/// // A query to return the number of vertices of each type.
+/// let q = AllVertexQuery.group_by(GroupKey::Type);
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct GroupByQuery {
+    /// The query to aggregate.
+    pub inner: Box<Query>,
+    /// What to group the results by.
+    pub key: GroupKey,
+}
+
+into_query!(GroupByQuery, GroupBy);
+
+impl GroupByQuery {
+    /// Constructs a new group by query.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to aggregate.
+    /// * `key`: What to group the results by.
+    pub fn new(inner: Box<Query>, key: GroupKey) -> errors::ValidationResult<Self> {
+        validate_aggregate_inner(&inner)?;
+        Ok(Self { inner, key })
+    }
+}
+
+/// Counts the outbound and inbound edges of each vertex returned from a
+/// query, optionally only those of a type.
+///
+/// This is cheaper than piping to the edges and counting them, since the
+/// edges are counted in the datastore's edge index without being read.
+/// Degrees are output in the same order as the vertices.
+///
+/// # Examples
+/// ```
+/// use indradb::{Identifier, QueryExt, RangeVertexQuery};
+/// // A query to return how many followers each user has.
+/// let q = RangeVertexQuery::new()
+///     .t(Identifier::new("user").unwrap())
+///     .degree()
+///     .unwrap()
+///     .t(Identifier::new("follows").unwrap());
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct DegreeQuery {
+    /// The vertex query to count the edges of.
+    pub inner: Box<Query>,
+    /// Only count edges of this type.
+    pub t: Option<Identifier>,
+}
+
+into_query!(DegreeQuery, Degree);
+
+impl DegreeQuery {
+    /// Constructs a new degree query, which counts edges of all types.
+    ///
+    /// # Arguments
+    /// * `inner`: The vertex query to count the edges of.
+    pub fn new(inner: Box<Query>) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+
+        Ok(Self { inner, t: None })
+    }
+
+    /// Only count edges of a given type.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
+    }
+}
+
 /// Value(s) returned from a query.
 #[derive(Clone, Debug, PartialEq)]
 pub enum QueryOutputValue {
@@ -725,13 +2252,28 @@ pub enum QueryOutputValue {
     VertexProperties(Vec<crate::VertexProperties>),
     /// Edge properties.
     EdgeProperties(Vec<crate::EdgeProperties>),
+    /// A path, or `None` if there isn't one.
+    Path(Option<crate::Path>),
+    /// A cursor for the next page of an ordered query, or `None` if it was
+    /// the last page.
+    Cursor(Option<crate::Cursor>),
+    /// The result of a sum, min, max or average, or `None` if there were no
+    /// values to aggregate.
+    Aggregate(Option<Json>),
+    /// The number of items in each group.
+    Groups(Vec<crate::Group>),
+    /// The number of edges of each vertex.
+    Degrees(Vec<crate::Degree>),
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        ijson, AllVertexQuery, CountQuery, CountQueryExt, EdgeDirection, Identifier, PipePropertyQuery, PipeQuery,
-        PipeWithPropertyPresenceQuery, PipeWithPropertyValueQuery, Query, ValidationError,
+        ijson, AllEdgeQuery, AllVertexQuery, CountQuery, CountQueryExt, DegreeQuery, DistanceMetric, EdgeDirection,
+        GeoPoint, GroupByQuery, GroupKey, Identifier, OrderByQuery, PipePropertyQuery, PipeQuery,
+        PipeWithPropertyPatternQuery, PipeWithPropertyPresenceQuery, PipeWithPropertyRangeQuery,
+        PipeWithPropertyValueQuery, PipeWithinRadiusQuery, Query, QueryExt, QueryOutputValue, SortDirection,
+        StringPattern, SumQuery, TraversalQuery, ValidationError,
     };
     use std::str::FromStr;
 
@@ -757,6 +2299,18 @@ mod tests {
         assert_eq!(s, "inbound".to_string());
     }
 
+    #[test]
+    fn should_convert_sort_direction() {
+        assert_eq!(SortDirection::from_str("ascending").unwrap(), SortDirection::Ascending);
+        assert_eq!(
+            SortDirection::from_str("descending").unwrap(),
+            SortDirection::Descending
+        );
+        assert!(SortDirection::from_str("foo").is_err());
+        let s: String = SortDirection::Descending.into();
+        assert_eq!(s, "descending".to_string());
+    }
+
     #[test]
     fn should_fail_for_nested_count_queries() {
         let q: Query = AllVertexQuery.count().unwrap().into();
@@ -774,5 +2328,75 @@ mod tests {
             ijson!("bar"),
             true,
         ));
+        expect_inner_query_err(PipeWithPropertyRangeQuery::new(
+            Box::new(q.clone()),
+            Identifier::new("foo").unwrap(),
+        ));
+        expect_inner_query_err(PipeWithPropertyPatternQuery::new(
+            Box::new(q.clone()),
+            Identifier::new("foo").unwrap(),
+            StringPattern::StartsWith("bar".to_string()),
+        ));
+        expect_inner_query_err(PipeWithinRadiusQuery::new(
+            Box::new(q.clone()),
+            Identifier::new("foo").unwrap(),
+            GeoPoint::new(0.0, 0.0).unwrap(),
+            1.0,
+        ));
+        expect_inner_query_err(OrderByQuery::new(
+            Box::new(q.clone()),
+            Identifier::new("foo").unwrap(),
+            SortDirection::Ascending,
+        ));
+        expect_inner_query_err(SumQuery::new(Box::new(q.clone()), Identifier::new("foo").unwrap()));
+        expect_inner_query_err(GroupByQuery::new(Box::new(q.clone()), GroupKey::Type));
+        expect_inner_query_err(DegreeQuery::new(Box::new(q.clone())));
+        expect_inner_query_err(TraversalQuery::new(Box::new(q), EdgeDirection::Outbound));
+    }
+
+    #[test]
+    fn should_get_pipe_output_type() {
+        let q: Query = AllVertexQuery.outbound().unwrap().into();
+        assert_eq!(q.output_type().unwrap(), QueryOutputValue::Edges(Vec::default()));
+        let q: Query = AllEdgeQuery.inbound().unwrap().properties().unwrap().into();
+        assert_eq!(
+            q.output_type().unwrap(),
+            QueryOutputValue::VertexProperties(Vec::default())
+        );
+    }
+
+    #[test]
+    fn should_fail_for_edge_traversal_queries() {
+        let q: Query = AllEdgeQuery.into();
+        expect_inner_query_err(TraversalQuery::new(Box::new(q), EdgeDirection::Outbound));
+    }
+
+    #[test]
+    fn should_fail_for_edge_degree_queries() {
+        expect_inner_query_err(AllEdgeQuery.degree());
+    }
+
+    #[test]
+    fn should_measure_distance() {
+        assert_eq!(DistanceMetric::Euclidean.distance(&[0.0, 3.0], &[4.0, 0.0]), 5.0);
+        assert_eq!(DistanceMetric::Cosine.distance(&[1.0, 0.0], &[0.0, 2.0]), 1.0);
+        assert_eq!(DistanceMetric::Cosine.distance(&[1.0, 1.0], &[2.0, 2.0]).round(), 0.0);
+        assert!(DistanceMetric::Cosine.distance(&[0.0, 0.0], &[1.0, 1.0]).is_nan());
+        assert_eq!(DistanceMetric::DotProduct.distance(&[1.0, 2.0], &[3.0, 4.0]), -11.0);
+    }
+
+    #[test]
+    fn should_convert_str_to_distance_metric() {
+        assert_eq!(DistanceMetric::from_str("cosine").unwrap(), DistanceMetric::Cosine);
+        assert!(DistanceMetric::from_str("manhattan").is_err());
+    }
+
+    #[test]
+    fn should_fail_for_invalid_regex() {
+        let result = AllVertexQuery.with_property_matching_regex(Identifier::new("foo").unwrap(), "(");
+        match result {
+            Err(ValidationError::InvalidValue) => (),
+            _ => panic!("unexpected result: {:?}", result),
+        }
     }
 }
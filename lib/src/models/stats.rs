@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use crate::{Identifier, Json};
+
+/// The number of vertices and edges with an indexed property value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyValueCount {
+    /// The property name.
+    pub name: Identifier,
+
+    /// The property value.
+    pub value: Json,
+
+    /// The number of indexed vertices with the value.
+    pub vertex_count: u64,
+
+    /// The number of indexed edges with the value.
+    pub edge_count: u64,
+}
+
+/// Counts of what's in a datastore, as returned by `Database::stats`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// The number of vertices.
+    pub vertex_count: u64,
+
+    /// The number of edges.
+    pub edge_count: u64,
+
+    /// The number of vertices of each type.
+    pub vertex_type_counts: BTreeMap<Identifier, u64>,
+
+    /// The number of edges of each type.
+    pub edge_type_counts: BTreeMap<Identifier, u64>,
+
+    /// The number of vertices and edges with each indexed property value,
+    /// ordered by property name and then by value. Values that nothing has
+    /// are left out.
+    pub property_value_counts: Vec<PropertyValueCount>,
+}
@@ -0,0 +1,501 @@
+use std::str::FromStr;
+
+use crate::errors::{ValidationError, ValidationResult};
+use crate::{
+    AllEdgeQuery, AllVertexQuery, AvgQuery, CountQuery, DegreeQuery, EdgeDirection, GroupByQuery, GroupKey, Identifier,
+    IncludeQuery, Json, MaxQuery, MinQuery, OrderByQuery, PipePropertyQuery, PipeQuery, PipeWithPropertyPresenceQuery,
+    PipeWithPropertyValueQuery, Query, QueryOutputValue, RangeVertexQuery, SortDirection, SpecificVertexQuery,
+    SumQuery, VertexWithPropertyValueQuery,
+};
+
+use uuid::Uuid;
+
+/// Parses a query from its text syntax, which chains steps off of a
+/// source, e.g. `V(type=user).out(follows).has(active=true).props(name)`.
+///
+/// Sources:
+/// * `V()`: all vertices.
+/// * `V(id, ...)`: vertices with the given UUIDs.
+/// * `V(type=t)`: vertices of a type.
+/// * `V(name=value)`: vertices with an indexed property value, optionally
+///   combined with a type, e.g. `V(type=user, email="a@example.com")`.
+/// * `E()`: all edges.
+///
+/// Steps:
+/// * `outE(t...)`, `inE(t...)`: the outbound or inbound edges of vertices,
+///   optionally only those of some types, e.g. `outE(follows, likes)`.
+/// * `outV()`, `inV()`: the outbound or inbound vertices of edges.
+/// * `out(t...)`, `in(t...)`: the vertices at the other end of outbound or
+///   inbound edges, i.e. `outE(t).inV()` and `inE(t).outV()`.
+/// * `has(name)`, `hasNot(name)`: filters by whether a property is set.
+/// * `has(name=value)`, `hasNot(name=value)`: filters by whether an
+///   indexed property equals a value.
+/// * `limit(n)`: limits the results of `V()`, a pipe or `order()`.
+/// * `order(name, asc|desc)`: sorts by a property, ascending by default.
+/// * `include()`: includes the results so far in the output.
+/// * `props(name?)`: gets all properties, or just one.
+/// * `count()`, `sum(name)`, `min(name)`, `max(name)`, `avg(name)`:
+///   aggregates the results.
+/// * `groupCount(name?)`: counts the results by type, or by a property.
+/// * `degree(t?)`: counts the outbound and inbound edges of each vertex,
+///   optionally only those of a type.
+///
+/// Values are JSON, except that a bare word other than `true`, `false` or
+/// `null` is a string, so `has(status=active)` and `has(status="active")`
+/// are the same.
+impl FromStr for Query {
+    type Err = ValidationError;
+
+    fn from_str(s: &str) -> Result<Query, Self::Err> {
+        let mut parser = Parser { text: s, pos: 0 };
+        let mut q = parser.source()?;
+        while parser.eat('.') {
+            q = parser.step(q)?;
+        }
+        if parser.peek().is_some() {
+            return parser.error("expected '.' or the end of the query");
+        }
+        Ok(q)
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T, S: Into<String>>(&self, message: S) -> ValidationResult<T> {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at<T, S: Into<String>>(&self, position: usize, message: S) -> ValidationResult<T> {
+        Err(ValidationError::Syntax {
+            position,
+            message: message.into(),
+        })
+    }
+
+    // Skips any whitespace, and returns the position of the next token.
+    fn position(&mut self) -> usize {
+        self.peek();
+        self.pos
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    // Skips any whitespace, and returns the next character.
+    fn peek(&mut self) -> Option<char> {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+        self.rest().chars().next()
+    }
+
+    // Consumes the next character if it's `c`.
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> ValidationResult<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            self.error(format!("expected '{c}'"))
+        }
+    }
+
+    // Consumes a bare word, which is made of the same characters as an
+    // identifier.
+    fn word(&mut self) -> ValidationResult<&'a str> {
+        let start = self.position();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c == '-' || c == '_' || c.is_alphanumeric()))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return self.error("expected a word");
+        }
+        self.pos = start + len;
+        Ok(&rest[..len])
+    }
+
+    fn name(&mut self) -> ValidationResult<Identifier> {
+        let start = self.position();
+        let word = self.word()?;
+        Identifier::new(word).or_else(|_| self.error_at(start, "invalid name"))
+    }
+
+    fn integer(&mut self) -> ValidationResult<u32> {
+        let start = self.position();
+        let word = self.word()?;
+        word.parse().or_else(|_| self.error_at(start, "expected a number"))
+    }
+
+    fn value(&mut self) -> ValidationResult<Json> {
+        let start = self.position();
+        match self.peek() {
+            Some('"' | '[' | '{') => {
+                // These are self-delimiting, so the JSON parser can tell
+                // where they end
+                let mut values = serde_json::Deserializer::from_str(self.rest()).into_iter::<serde_json::Value>();
+                match values.next() {
+                    Some(Ok(value)) => {
+                        self.pos += values.byte_offset();
+                        Ok(Json::new(value))
+                    }
+                    _ => self.error_at(start, "invalid JSON value"),
+                }
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let rest = self.rest();
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+                    .unwrap_or(rest.len());
+                self.pos += len;
+                match serde_json::from_str(&rest[..len]) {
+                    Ok(value) => Ok(Json::new(value)),
+                    Err(_) => self.error_at(start, "invalid number"),
+                }
+            }
+            _ => {
+                let value = match self.word()? {
+                    "true" => serde_json::Value::Bool(true),
+                    "false" => serde_json::Value::Bool(false),
+                    "null" => serde_json::Value::Null,
+                    word => serde_json::Value::String(word.to_string()),
+                };
+                Ok(Json::new(value))
+            }
+        }
+    }
+
+    fn source(&mut self) -> ValidationResult<Query> {
+        let start = self.position();
+        let q = match self.word()? {
+            "V" => {
+                self.expect('(')?;
+                self.vertices()?
+            }
+            "E" => {
+                self.expect('(')?;
+                AllEdgeQuery.into()
+            }
+            _ => return self.error_at(start, "expected 'V' or 'E'"),
+        };
+        self.expect(')')?;
+        Ok(q)
+    }
+
+    // Parses the arguments of `V(...)`.
+    fn vertices(&mut self) -> ValidationResult<Query> {
+        if self.peek() == Some(')') {
+            return Ok(AllVertexQuery.into());
+        }
+
+        let mut ids = Vec::new();
+        let mut t = None;
+        let mut property = None;
+        loop {
+            let start = self.position();
+            let word = self.word()?;
+            if self.eat('=') {
+                if word == "type" && t.is_none() {
+                    t = Some(self.name()?);
+                } else if word != "type" && property.is_none() {
+                    let name = Identifier::new(word).or_else(|_| self.error_at(start, "invalid name"))?;
+                    property = Some((name, self.value()?));
+                } else {
+                    return self.error_at(start, "only one type and one property value can be filtered on");
+                }
+            } else {
+                ids.push(Uuid::parse_str(word).or_else(|_| self.error_at(start, "expected a vertex ID or a filter"))?);
+            }
+            if !self.eat(',') {
+                break;
+            }
+        }
+
+        if !ids.is_empty() {
+            if t.is_some() || property.is_some() {
+                return self.error("vertex IDs can't be combined with filters");
+            }
+            return Ok(SpecificVertexQuery::new(ids).into());
+        }
+        Ok(match (t, property) {
+            (Some(t), Some((name, value))) => VertexWithPropertyValueQuery::new(name, value).t(t).into(),
+            (None, Some((name, value))) => VertexWithPropertyValueQuery::new(name, value).into(),
+            (Some(t), None) => RangeVertexQuery::new().t(t).into(),
+            (None, None) => unreachable!(),
+        })
+    }
+
+    fn step(&mut self, q: Query) -> ValidationResult<Query> {
+        let start = self.position();
+        let step = self.word()?;
+        self.expect('(')?;
+        let q = match step {
+            "outE" => self.edges(start, q, EdgeDirection::Outbound)?,
+            "inE" => self.edges(start, q, EdgeDirection::Inbound)?,
+            "outV" => self.edge_vertices(start, q, EdgeDirection::Outbound)?,
+            "inV" => self.edge_vertices(start, q, EdgeDirection::Inbound)?,
+            "out" => {
+                let edges = self.edges(start, q, EdgeDirection::Outbound)?;
+                PipeQuery::new(Box::new(edges), EdgeDirection::Inbound)?.into()
+            }
+            "in" => {
+                let edges = self.edges(start, q, EdgeDirection::Inbound)?;
+                PipeQuery::new(Box::new(edges), EdgeDirection::Outbound)?.into()
+            }
+            "has" | "hasNot" => {
+                let name = self.name()?;
+                let matches = step == "has";
+                if self.eat('=') {
+                    PipeWithPropertyValueQuery::new(Box::new(q), name, self.value()?, matches)?.into()
+                } else {
+                    PipeWithPropertyPresenceQuery::new(Box::new(q), name, matches)?.into()
+                }
+            }
+            "limit" => {
+                let limit = self.integer()?;
+                match q {
+                    Query::AllVertex => RangeVertexQuery::new().limit(limit).into(),
+                    Query::RangeVertex(q) => q.limit(limit).into(),
+                    Query::Pipe(q) => q.limit(limit).into(),
+                    Query::OrderBy(q) => q.limit(limit).into(),
+                    _ => return self.error_at(start, "limit() must follow V(), a pipe or order()"),
+                }
+            }
+            "order" => {
+                let name = self.name()?;
+                let direction = if self.eat(',') {
+                    let direction_start = self.position();
+                    match self.word()? {
+                        "asc" => SortDirection::Ascending,
+                        "desc" => SortDirection::Descending,
+                        _ => return self.error_at(direction_start, "expected 'asc' or 'desc'"),
+                    }
+                } else {
+                    SortDirection::Ascending
+                };
+                OrderByQuery::new(Box::new(q), name, direction)?.into()
+            }
+            "include" => IncludeQuery::new(Box::new(q)).into(),
+            "props" => {
+                let q = PipePropertyQuery::new(Box::new(q))?;
+                if self.peek() == Some(')') {
+                    q.into()
+                } else {
+                    q.name(self.name()?).into()
+                }
+            }
+            "count" => CountQuery::new(Box::new(q))?.into(),
+            "sum" => SumQuery::new(Box::new(q), self.name()?)?.into(),
+            "min" => MinQuery::new(Box::new(q), self.name()?)?.into(),
+            "max" => MaxQuery::new(Box::new(q), self.name()?)?.into(),
+            "avg" => AvgQuery::new(Box::new(q), self.name()?)?.into(),
+            "groupCount" => {
+                let key = if self.peek() == Some(')') {
+                    GroupKey::Type
+                } else {
+                    GroupKey::Property(self.name()?)
+                };
+                GroupByQuery::new(Box::new(q), key)?.into()
+            }
+            "degree" => {
+                let q = DegreeQuery::new(Box::new(q))?;
+                if self.peek() == Some(')') {
+                    q.into()
+                } else {
+                    q.t(self.name()?).into()
+                }
+            }
+            _ => return self.error_at(start, format!("unknown step '{step}'")),
+        };
+        self.expect(')')?;
+        Ok(q)
+    }
+
+    // Pipes from vertices to their edges, with optional type arguments.
+    fn edges(&mut self, start: usize, q: Query, direction: EdgeDirection) -> ValidationResult<Query> {
+        if !matches!(q.output_type()?, QueryOutputValue::Vertices(_)) {
+            return self.error_at(start, "expected the step to follow vertices");
+        }
+        let mut q = PipeQuery::new(Box::new(q), direction)?;
+        if self.peek() != Some(')') {
+            q = q.t(self.name()?);
+            while self.eat(',') {
+                q = q.add_t(self.name()?);
+            }
+        }
+        Ok(q.into())
+    }
+
+    // Pipes from edges to the vertices at one of their ends.
+    fn edge_vertices(&mut self, start: usize, q: Query, direction: EdgeDirection) -> ValidationResult<Query> {
+        if !matches!(q.output_type()?, QueryOutputValue::Edges(_)) {
+            return self.error_at(start, "expected the step to follow edges");
+        }
+        Ok(PipeQuery::new(Box::new(q), direction)?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ijson, AllEdgeQuery, AllVertexQuery, CountQueryExt, GroupKey, Identifier, Query, QueryExt, RangeVertexQuery,
+        SortDirection, SpecificVertexQuery, ValidationError, VertexWithPropertyValueQuery,
+    };
+    use uuid::Uuid;
+
+    fn id(s: &str) -> Identifier {
+        Identifier::new(s).unwrap()
+    }
+
+    fn parse(s: &str) -> Query {
+        s.parse().unwrap()
+    }
+
+    fn error_position(s: &str) -> usize {
+        match s.parse::<Query>() {
+            Err(ValidationError::Syntax { position, .. }) => position,
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn should_parse_sources() {
+        assert_eq!(parse("V()"), AllVertexQuery.into());
+        assert_eq!(parse(" E ( ) "), AllEdgeQuery.into());
+        assert_eq!(parse("V(type=user)"), RangeVertexQuery::new().t(id("user")).into());
+        assert_eq!(
+            parse("V(type=user, email=\"a@example.com\")"),
+            VertexWithPropertyValueQuery::new(id("email"), ijson!("a@example.com"))
+                .t(id("user"))
+                .into()
+        );
+
+        let first = Uuid::from_u128(1);
+        let second = Uuid::from_u128(2);
+        assert_eq!(
+            parse(&format!("V({first}, {second})")),
+            SpecificVertexQuery::new(vec![first, second]).into()
+        );
+    }
+
+    #[test]
+    fn should_parse_steps() {
+        let expected = RangeVertexQuery::new()
+            .t(id("user"))
+            .outbound()
+            .unwrap()
+            .t(id("follows"))
+            .inbound()
+            .unwrap()
+            .with_property_equal_to(id("active"), ijson!(true))
+            .unwrap()
+            .properties()
+            .unwrap()
+            .name(id("name"));
+        assert_eq!(
+            parse("V(type=user).out(follows).has(active=true).props(name)"),
+            expected.into()
+        );
+
+        let expected = AllEdgeQuery
+            .outbound()
+            .unwrap()
+            .without_property(id("deleted"))
+            .unwrap()
+            .order_by(id("score"), SortDirection::Descending)
+            .unwrap()
+            .limit(10)
+            .include()
+            .group_by(GroupKey::Type)
+            .unwrap();
+        assert_eq!(
+            parse("E().outV().hasNot(deleted).order(score, desc).limit(10).include().groupCount()"),
+            expected.into()
+        );
+
+        assert_eq!(
+            parse("V().inE(follows, likes)"),
+            AllVertexQuery
+                .inbound()
+                .unwrap()
+                .t(id("follows"))
+                .add_t(id("likes"))
+                .into()
+        );
+
+        assert_eq!(
+            parse("V().outE().inV()"),
+            AllVertexQuery.outbound().unwrap().inbound().unwrap().into()
+        );
+
+        assert_eq!(
+            parse("E().outV().outE()"),
+            AllEdgeQuery.outbound().unwrap().outbound().unwrap().into()
+        );
+
+        assert_eq!(
+            parse("V().limit(5).inE().has(weight=-1.5e2).count()"),
+            RangeVertexQuery::new()
+                .limit(5)
+                .inbound()
+                .unwrap()
+                .with_property_equal_to(id("weight"), ijson!(-150.0))
+                .unwrap()
+                .count()
+                .unwrap()
+                .into()
+        );
+
+        assert_eq!(
+            parse("V(type=user).degree(follows)"),
+            RangeVertexQuery::new()
+                .t(id("user"))
+                .degree()
+                .unwrap()
+                .t(id("follows"))
+                .into()
+        );
+    }
+
+    #[test]
+    fn should_parse_values() {
+        let has_x = |value| -> Query { AllVertexQuery.with_property_equal_to(id("x"), value).unwrap().into() };
+        assert_eq!(parse("V().has(x=active)"), has_x(ijson!("active")));
+        assert_eq!(parse("V().has(x=\"two words\")"), has_x(ijson!("two words")));
+        assert_eq!(parse("V().has(x=null)"), has_x(ijson!(null)));
+        assert_eq!(
+            parse("V().has(x=[1, {\"a\": false}])"),
+            has_x(ijson!([1, {"a": false}]))
+        );
+        assert_eq!(parse("V().has(x=3)"), has_x(ijson!(3)));
+    }
+
+    #[test]
+    fn should_not_parse_invalid_text() {
+        assert_eq!(error_position(""), 0);
+        assert_eq!(error_position("X()"), 0);
+        assert_eq!(error_position("V().out(follows"), 15);
+        assert_eq!(error_position("V().walk()"), 4);
+        assert_eq!(error_position("V(not-a-uuid)"), 2);
+        assert_eq!(error_position("E().outE()"), 4);
+        assert_eq!(error_position("V().count().limit(1)"), 12);
+        assert_eq!(error_position("V().order(score, sideways)"), 17);
+        assert_eq!(error_position("V().has(x=01)"), 10);
+        assert_eq!(error_position("V() V()"), 4);
+        assert!(matches!(
+            "V().count().props()".parse::<Query>(),
+            Err(ValidationError::InnerQuery)
+        ));
+    }
+}
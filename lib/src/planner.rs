@@ -0,0 +1,376 @@
+//! Plans queries before they're run. Chains of property filters are
+//! reordered so that the most selective kinds run first, a filter directly
+//! over a scan of every vertex or edge (of a type) is rewritten as a lookup
+//! in the property's index, and property filters choose between reading
+//! their index and checking each piped item based on cardinality estimates.
+
+use std::mem;
+
+use crate::errors::{Error, Result};
+use crate::{
+    EdgeWithPropertyPresenceQuery, EdgeWithPropertyRangeQuery, EdgeWithPropertyValueQuery, FilterStrategy, Identifier,
+    IndexScope, Json, Query, QueryOutputValue, Transaction, VertexWithPropertyPresenceQuery,
+    VertexWithPropertyRangeQuery, VertexWithPropertyValueQuery,
+};
+
+// Piped inputs up to this size are filtered by checking each item's
+// property when the size of the property's index can't be estimated, since
+// reading the index could cost far more.
+const SMALL_INPUT: u64 = 1000;
+
+/// Plans a query, returning the query to run in its place.
+pub(crate) fn plan<'a, T: Transaction<'a>>(txn: &T, mut q: Query) -> Result<Query> {
+    if !is_filter(&q) {
+        if let Some(inner) = inner_query_mut(&mut q) {
+            let planned = plan(txn, take_query(inner))?;
+            **inner = planned;
+        }
+        return Ok(q);
+    }
+
+    // Detach the chain of filters from the query they filter, innermost
+    // first
+    let mut filters = Vec::new();
+    let mut base = q;
+    while is_filter(&base) {
+        let inner = take_query(inner_query_mut(&mut base).unwrap());
+        filters.push(base);
+        base = inner;
+    }
+    filters.reverse();
+    filters.sort_by_key(filter_rank);
+
+    let mut q = plan(txn, base)?;
+    let mut filters = filters.into_iter().peekable();
+    if let Some(index_query) = filters
+        .peek()
+        .and_then(|first| index_lookup(txn, &q, first).transpose())
+    {
+        q = index_query?;
+        filters.next();
+    }
+    for mut filter in filters {
+        **inner_query_mut(&mut filter).unwrap() = q;
+        q = filter;
+    }
+    Ok(q)
+}
+
+/// Chooses how a property filter is run.
+///
+/// # Arguments
+/// * `inner`: The query the filter pipes from.
+/// * `name`: The property name.
+/// * `value`: The property value filtered on, or `None` if filtering on
+///   the property's presence.
+pub(crate) fn filter_strategy<'a, T: Transaction<'a>>(
+    txn: &T,
+    inner: &Query,
+    name: Identifier,
+    value: Option<&Json>,
+) -> FilterStrategy {
+    let index_rows = match (value, inner.output_type()) {
+        (Some(value), Ok(QueryOutputValue::Vertices(_))) => txn.estimated_vertex_property_value_count(name, value),
+        (Some(value), Ok(QueryOutputValue::Edges(_))) => txn.estimated_edge_property_value_count(name, value),
+        _ => None,
+    };
+    choose_filter_strategy(estimate_rows(txn, inner), index_rows)
+}
+
+fn choose_filter_strategy(input_rows: Option<u64>, index_rows: Option<u64>) -> FilterStrategy {
+    match (input_rows, index_rows) {
+        (Some(input_rows), Some(index_rows)) if input_rows < index_rows => FilterStrategy::PropertyChecks,
+        (Some(input_rows), None) if input_rows <= SMALL_INPUT => FilterStrategy::PropertyChecks,
+        _ => FilterStrategy::IndexLookup,
+    }
+}
+
+/// Gets the strategy of a query that's a property filter, or `None` for
+/// other queries.
+pub(crate) fn strategy<'a, T: Transaction<'a>>(txn: &T, q: &Query) -> Option<FilterStrategy> {
+    match q {
+        Query::PipeWithPropertyPresence(q) => Some(filter_strategy(txn, &q.inner, q.name, None)),
+        Query::PipeWithPropertyValue(q) => Some(filter_strategy(txn, &q.inner, q.name, Some(&q.value))),
+        _ => None,
+    }
+}
+
+/// Estimates the number of rows a query outputs, or `None` if it can't be
+/// estimated.
+pub(crate) fn estimate_rows<'a, T: Transaction<'a>>(txn: &T, q: &Query) -> Option<u64> {
+    match q {
+        Query::AllVertex => txn.estimated_vertex_count(),
+        Query::RangeVertex(q) => limited(txn.estimated_vertex_count(), q.limit),
+        Query::SpecificVertex(q) => Some(q.ids.len() as u64),
+        Query::VertexWithPropertyValue(q) => txn.estimated_vertex_property_value_count(q.name, &q.value),
+        Query::NearestVertices(q) => limited(txn.estimated_vertex_count(), q.k),
+        Query::AllEdge => txn.estimated_edge_count(),
+        Query::SpecificEdge(q) => Some(q.edges.len() as u64),
+        Query::EdgeWithPropertyValue(q) => txn.estimated_edge_property_value_count(q.name, &q.value),
+        Query::Pipe(q) => {
+            let input_rows = estimate_rows(txn, &q.inner);
+            let rows = match q.inner.output_type() {
+                // Assume the piped vertices have the average degree
+                Ok(QueryOutputValue::Vertices(_)) => {
+                    match (input_rows, txn.estimated_edge_count(), txn.estimated_vertex_count()) {
+                        (Some(input_rows), Some(edge_count), Some(vertex_count)) if vertex_count > 0 => {
+                            Some(input_rows.saturating_mul(edge_count).div_ceil(vertex_count))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => input_rows,
+            };
+            limited(rows, q.limit)
+        }
+        Query::PipeWithPropertyValue(q) => {
+            let input_rows = estimate_rows(txn, &q.inner);
+            let index_rows = match q.inner.output_type() {
+                Ok(QueryOutputValue::Vertices(_)) if q.equal => {
+                    txn.estimated_vertex_property_value_count(q.name, &q.value)
+                }
+                Ok(QueryOutputValue::Edges(_)) if q.equal => txn.estimated_edge_property_value_count(q.name, &q.value),
+                _ => None,
+            };
+            match (input_rows, index_rows) {
+                (Some(input_rows), Some(index_rows)) => Some(input_rows.min(index_rows)),
+                _ => input_rows,
+            }
+        }
+        Query::PipeProperty(q) => estimate_rows(txn, &q.inner),
+        Query::PipeWithPropertyPresence(q) => estimate_rows(txn, &q.inner),
+        Query::PipeWithPropertyRange(q) => estimate_rows(txn, &q.inner),
+        Query::PipeWithPropertyPattern(q) => estimate_rows(txn, &q.inner),
+        Query::PipeWithinBoundingBox(q) => estimate_rows(txn, &q.inner),
+        Query::PipeWithinRadius(q) => estimate_rows(txn, &q.inner),
+        Query::Traversal(q) => limited(None, q.limit),
+        Query::OrderBy(q) => limited(estimate_rows(txn, &q.inner), q.limit),
+        Query::Include(q) => estimate_rows(txn, &q.inner),
+        Query::GroupBy(q) => estimate_rows(txn, &q.inner),
+        Query::Degree(q) => estimate_rows(txn, &q.inner),
+        Query::ShortestPath(_) | Query::Count(_) | Query::Sum(_) | Query::Min(_) | Query::Max(_) | Query::Avg(_) => {
+            Some(1)
+        }
+        Query::VertexWithPropertyPresence(_)
+        | Query::VertexWithPropertyRange(_)
+        | Query::VertexWithPropertyValues(_)
+        | Query::VertexWithPropertyMatch(_)
+        | Query::WithinBoundingBox(_)
+        | Query::WithinRadius(_)
+        | Query::EdgeWithPropertyPresence(_)
+        | Query::EdgeWithPropertyRange(_) => None,
+    }
+}
+
+fn limited(rows: Option<u64>, limit: u32) -> Option<u64> {
+    if limit == u32::MAX {
+        rows
+    } else {
+        Some(rows.map_or(limit as u64, |rows| rows.min(limit as u64)))
+    }
+}
+
+/// Gets the name of the kind of a query, for explaining plans.
+pub(crate) fn query_name(q: &Query) -> &'static str {
+    match q {
+        Query::AllVertex => "AllVertex",
+        Query::RangeVertex(_) => "RangeVertex",
+        Query::SpecificVertex(_) => "SpecificVertex",
+        Query::VertexWithPropertyPresence(_) => "VertexWithPropertyPresence",
+        Query::VertexWithPropertyValue(_) => "VertexWithPropertyValue",
+        Query::VertexWithPropertyRange(_) => "VertexWithPropertyRange",
+        Query::VertexWithPropertyValues(_) => "VertexWithPropertyValues",
+        Query::VertexWithPropertyMatch(_) => "VertexWithPropertyMatch",
+        Query::NearestVertices(_) => "NearestVertices",
+        Query::WithinBoundingBox(_) => "WithinBoundingBox",
+        Query::WithinRadius(_) => "WithinRadius",
+        Query::AllEdge => "AllEdge",
+        Query::SpecificEdge(_) => "SpecificEdge",
+        Query::EdgeWithPropertyPresence(_) => "EdgeWithPropertyPresence",
+        Query::EdgeWithPropertyValue(_) => "EdgeWithPropertyValue",
+        Query::EdgeWithPropertyRange(_) => "EdgeWithPropertyRange",
+        Query::Pipe(_) => "Pipe",
+        Query::PipeProperty(_) => "PipeProperty",
+        Query::PipeWithPropertyPresence(_) => "PipeWithPropertyPresence",
+        Query::PipeWithPropertyValue(_) => "PipeWithPropertyValue",
+        Query::PipeWithPropertyRange(_) => "PipeWithPropertyRange",
+        Query::PipeWithPropertyPattern(_) => "PipeWithPropertyPattern",
+        Query::PipeWithinBoundingBox(_) => "PipeWithinBoundingBox",
+        Query::PipeWithinRadius(_) => "PipeWithinRadius",
+        Query::Traversal(_) => "Traversal",
+        Query::ShortestPath(_) => "ShortestPath",
+        Query::OrderBy(_) => "OrderBy",
+        Query::Include(_) => "Include",
+        Query::Count(_) => "Count",
+        Query::Sum(_) => "Sum",
+        Query::Min(_) => "Min",
+        Query::Max(_) => "Max",
+        Query::Avg(_) => "Avg",
+        Query::GroupBy(_) => "GroupBy",
+        Query::Degree(_) => "Degree",
+    }
+}
+
+/// Gets the query that a query pipes from, if any.
+pub(crate) fn inner_query(q: &Query) -> Option<&Query> {
+    match q {
+        Query::Pipe(q) => Some(&q.inner),
+        Query::PipeProperty(q) => Some(&q.inner),
+        Query::PipeWithPropertyPresence(q) => Some(&q.inner),
+        Query::PipeWithPropertyValue(q) => Some(&q.inner),
+        Query::PipeWithPropertyRange(q) => Some(&q.inner),
+        Query::PipeWithPropertyPattern(q) => Some(&q.inner),
+        Query::PipeWithinBoundingBox(q) => Some(&q.inner),
+        Query::PipeWithinRadius(q) => Some(&q.inner),
+        Query::Traversal(q) => Some(&q.inner),
+        Query::OrderBy(q) => Some(&q.inner),
+        Query::Include(q) => Some(&q.inner),
+        Query::Count(q) => Some(&q.inner),
+        Query::Sum(q) => Some(&q.inner),
+        Query::Min(q) => Some(&q.inner),
+        Query::Max(q) => Some(&q.inner),
+        Query::Avg(q) => Some(&q.inner),
+        Query::GroupBy(q) => Some(&q.inner),
+        Query::Degree(q) => Some(&q.inner),
+        _ => None,
+    }
+}
+
+fn inner_query_mut(q: &mut Query) -> Option<&mut Box<Query>> {
+    match q {
+        Query::Pipe(q) => Some(&mut q.inner),
+        Query::PipeProperty(q) => Some(&mut q.inner),
+        Query::PipeWithPropertyPresence(q) => Some(&mut q.inner),
+        Query::PipeWithPropertyValue(q) => Some(&mut q.inner),
+        Query::PipeWithPropertyRange(q) => Some(&mut q.inner),
+        Query::PipeWithPropertyPattern(q) => Some(&mut q.inner),
+        Query::PipeWithinBoundingBox(q) => Some(&mut q.inner),
+        Query::PipeWithinRadius(q) => Some(&mut q.inner),
+        Query::Traversal(q) => Some(&mut q.inner),
+        Query::OrderBy(q) => Some(&mut q.inner),
+        Query::Include(q) => Some(&mut q.inner),
+        Query::Count(q) => Some(&mut q.inner),
+        Query::Sum(q) => Some(&mut q.inner),
+        Query::Min(q) => Some(&mut q.inner),
+        Query::Max(q) => Some(&mut q.inner),
+        Query::Avg(q) => Some(&mut q.inner),
+        Query::GroupBy(q) => Some(&mut q.inner),
+        Query::Degree(q) => Some(&mut q.inner),
+        _ => None,
+    }
+}
+
+fn take_query(q: &mut Box<Query>) -> Query {
+    *mem::replace(q, Box::new(Query::AllVertex))
+}
+
+// Whether a query only filters the vertices or edges it pipes from, which
+// means it can be reordered with other filters.
+fn is_filter(q: &Query) -> bool {
+    matches!(
+        q,
+        Query::PipeWithPropertyPresence(_)
+            | Query::PipeWithPropertyValue(_)
+            | Query::PipeWithPropertyRange(_)
+            | Query::PipeWithPropertyPattern(_)
+            | Query::PipeWithinBoundingBox(_)
+            | Query::PipeWithinRadius(_)
+    )
+}
+
+// Ranks filters by how selective and cheap they typically are, lowest
+// first. Negated filters usually keep most items, so they run last.
+fn filter_rank(q: &Query) -> u8 {
+    match q {
+        Query::PipeWithPropertyValue(q) if q.equal => 0,
+        Query::PipeWithPropertyRange(_) => 1,
+        Query::PipeWithPropertyPresence(q) if q.exists => 2,
+        Query::PipeWithinBoundingBox(_) | Query::PipeWithinRadius(_) => 3,
+        Query::PipeWithPropertyPattern(_) => 4,
+        _ => 5,
+    }
+}
+
+// Rewrites a filter over every vertex or edge, or every vertex of a type,
+// as a lookup in the property's index, pushing the type down into the
+// lookup. Returns `None` if the filter can't be rewritten, including if
+// the index doesn't cover everything being filtered.
+fn index_lookup<'a, T: Transaction<'a>>(txn: &T, base: &Query, filter: &Query) -> Result<Option<Query>> {
+    let (vertices, t) = match base {
+        Query::AllVertex => (true, None),
+        Query::RangeVertex(q) if q.start_id.is_none() && q.limit == u32::MAX => (true, q.t),
+        Query::AllEdge => (false, None),
+        _ => return Ok(None),
+    };
+    let name = match filter {
+        Query::PipeWithPropertyPresence(q) if q.exists => q.name,
+        Query::PipeWithPropertyValue(q) if q.equal => q.name,
+        Query::PipeWithPropertyRange(q) => q.name,
+        _ => return Ok(None),
+    };
+
+    // Datastores that can't list their indexes are scanned instead
+    let scopes = match txn.indexed_properties() {
+        Ok(mut indexes) => indexes.remove(&name).unwrap_or_default(),
+        Err(Error::Unsupported) => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let covered = match t {
+        Some(t) => scopes.iter().any(|scope| scope.covers_vertex(t)),
+        None => scopes.contains(&IndexScope::All),
+    };
+    if !covered {
+        return Ok(None);
+    }
+
+    Ok(Some(match (filter, vertices) {
+        (Query::PipeWithPropertyPresence(_), true) => VertexWithPropertyPresenceQuery { name, t }.into(),
+        (Query::PipeWithPropertyPresence(_), false) => EdgeWithPropertyPresenceQuery { name, t }.into(),
+        (Query::PipeWithPropertyValue(q), true) => VertexWithPropertyValueQuery {
+            name,
+            value: q.value.clone(),
+            t,
+        }
+        .into(),
+        (Query::PipeWithPropertyValue(q), false) => EdgeWithPropertyValueQuery {
+            name,
+            value: q.value.clone(),
+            t,
+        }
+        .into(),
+        (Query::PipeWithPropertyRange(q), true) => VertexWithPropertyRangeQuery {
+            name,
+            lower: q.lower.clone(),
+            upper: q.upper.clone(),
+            t,
+        }
+        .into(),
+        (Query::PipeWithPropertyRange(q), false) => EdgeWithPropertyRangeQuery {
+            name,
+            lower: q.lower.clone(),
+            upper: q.upper.clone(),
+            t,
+        }
+        .into(),
+        _ => unreachable!(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::choose_filter_strategy;
+    use crate::FilterStrategy;
+
+    #[test]
+    fn should_choose_filter_strategy() {
+        assert_eq!(
+            choose_filter_strategy(Some(10), Some(100)),
+            FilterStrategy::PropertyChecks
+        );
+        assert_eq!(choose_filter_strategy(Some(100), Some(10)), FilterStrategy::IndexLookup);
+        assert_eq!(choose_filter_strategy(Some(1000), None), FilterStrategy::PropertyChecks);
+        assert_eq!(choose_filter_strategy(Some(1001), None), FilterStrategy::IndexLookup);
+        assert_eq!(choose_filter_strategy(None, Some(10)), FilterStrategy::IndexLookup);
+        assert_eq!(choose_filter_strategy(None, None), FilterStrategy::IndexLookup);
+    }
+}
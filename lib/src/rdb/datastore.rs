@@ -1,46 +1,198 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::iter;
+use std::mem;
+use std::ops::Bound;
 use std::path::Path;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use super::managers::*;
-use crate::errors::Result;
-use crate::{BulkInsertItem, Database, Datastore, DynIter, Edge, Identifier, Json, Transaction, Vertex};
-
-use rocksdb::{DBCompactionStyle, Options, WriteBatch, DB};
+use crate::errors::{Error, Result};
+use crate::util;
+use crate::{
+    BoundingBox, BulkInsertItem, Database, Datastore, DynIter, Edge, EdgeDirection, GeoPoint, Identifier, IndexScope,
+    Json, Stats, Transaction, UniqueConstraint, Vertex, VertexPoint, VertexVector,
+};
+
+use once_cell::unsync::OnceCell;
+use rocksdb::{DBCompactionStyle, OptimisticTransactionDB, Options, DB};
 use uuid::Uuid;
 
-const CF_NAMES: [&str; 8] = [
+const CF_NAMES: [&str; 14] = [
     "vertices:v2",
     "edge_ranges:v2",
     "reversed_edge_ranges:v2",
     "vertex_properties:v2",
     "edge_properties:v2",
-    "vertex_property_values:v2",
-    "edge_property_values:v2",
+    "vertex_property_values:v3",
+    "edge_property_values:v3",
+    "vertex_composite_values:v1",
+    "vertex_property_terms:v1",
+    "vertex_property_vectors:v1",
+    "vertex_property_geo:v1",
+    "vertex_unique_values:v1",
+    "stats:v1",
     "metadata:v2",
 ];
 
+// Column families from previous versions that have since been replaced.
+// Property value indexes used to be keyed by a hash of the value, which
+// wasn't stable across Rust releases; they're rebuilt into the current
+// column families when a database containing them is opened.
+const LEGACY_CF_NAMES: [&str; 2] = ["vertex_property_values:v2", "edge_property_values:v2"];
+
+// The number of entries committed to the stats counters after which they're
+// folded, which keeps reading the counters from slowing down as
+// transactions are committed.
+const STATS_FOLD_THRESHOLD: usize = 1000;
+
+fn open_db(path: &Path, opts: &Options) -> Result<OptimisticTransactionDB> {
+    // Listing column families fails if the database doesn't exist yet
+    let existing_cf_names = DB::list_cf(opts, path).unwrap_or_default();
+    let mut db = if existing_cf_names.is_empty() {
+        OptimisticTransactionDB::open(opts, path)?
+    } else {
+        OptimisticTransactionDB::open_cf(opts, path, &existing_cf_names)?
+    };
+
+    for cf_name in &CF_NAMES {
+        if !existing_cf_names.iter().any(|name| name == cf_name) {
+            db.create_cf(cf_name, opts)?;
+        }
+    }
+
+    // Databases from before stats were kept are counted up-front. This
+    // happens before any legacy indexes are rebuilt, which keeps the stats
+    // up to date as it goes.
+    build_stats(&db)?;
+
+    let legacy_cf_names: Vec<&str> = LEGACY_CF_NAMES
+        .into_iter()
+        .filter(|cf_name| existing_cf_names.iter().any(|name| name == cf_name))
+        .collect();
+    if !legacy_cf_names.is_empty() {
+        rebuild_property_value_indexes(&db)?;
+        // The legacy column families are only dropped once the rebuilt
+        // indexes are committed, so an interrupted migration is retried on
+        // the next open
+        for cf_name in legacy_cf_names {
+            db.drop_cf(cf_name)?;
+        }
+    }
+
+    fold_stats(&db)?;
+    Ok(db)
+}
+
+// Counts everything in the database, unless that's already been done. The
+// counts are marked as built in the same transaction that writes them, so
+// an interrupted build is retried on the next open.
+fn build_stats(db: &OptimisticTransactionDB) -> Result<()> {
+    let txn = SnapshotTxn::new(db);
+    let metadata_manager = MetadataManager::new(db, &txn);
+    if metadata_manager.get_stats_built()? {
+        return Ok(());
+    }
+    let indexed_properties = metadata_manager.get_indexed_properties()?;
+    let stat_manager = StatManager::new(db, &txn);
+    stat_manager.clear()?;
+
+    let vertex_manager = VertexManager::new(db, &txn);
+    for item in vertex_manager.iterate_for_range(Uuid::default()) {
+        stat_manager.add_vertex_type(item?.t, 1)?;
+    }
+
+    let edge_range_manager = EdgeRangeManager::new(db, &txn);
+    for item in edge_range_manager.iterate_for_all() {
+        stat_manager.add_edge_type(item?.t, 1)?;
+    }
+
+    // The values are read from the properties, since the index entries only
+    // hold their encodings
+    let vertex_property_value_manager = VertexPropertyValueManager::new(db, &txn);
+    let vertex_property_manager = VertexPropertyManager::new(db, &txn);
+    let edge_property_value_manager = EdgePropertyValueManager::new(db, &txn);
+    let edge_property_manager = EdgePropertyManager::new(db, &txn);
+    for name in indexed_properties.keys() {
+        for item in vertex_property_value_manager.iterate_for_name(*name) {
+            let (_, id) = item?;
+            if let Some(value) = vertex_property_manager.get(id, *name)? {
+                stat_manager.add_vertex_property_value(*name, &value, 1)?;
+            }
+        }
+        for item in edge_property_value_manager.iterate_for_name(*name) {
+            let (_, edge) = item?;
+            if let Some(value) = edge_property_manager.get(&edge, *name)? {
+                stat_manager.add_edge_property_value(*name, &value, 1)?;
+            }
+        }
+    }
+
+    metadata_manager.set_stats_built()?;
+    txn.commit()
+}
+
+fn fold_stats(db: &OptimisticTransactionDB) -> Result<()> {
+    let txn = SnapshotTxn::new(db);
+    StatManager::new(db, &txn).fold()?;
+    txn.commit()
+}
+
+// Counts can't fail, so an error reading one is logged, and counts as
+// nothing.
+fn count_or_log(result: Result<u64>, what: &str) -> u64 {
+    result.unwrap_or_else(|err| {
+        log::error!("failed to read the {what} count: {err}");
+        0
+    })
+}
+
+fn rebuild_property_value_indexes(db: &OptimisticTransactionDB) -> Result<()> {
+    let unfolded_stats = AtomicUsize::new(0);
+    let mut txn = RocksdbTransaction {
+        db,
+        txn: SnapshotTxn::new(db),
+        indexes: OnceCell::new(),
+        unfolded_stats: &unfolded_stats,
+    };
+    for (name, scopes) in txn.load_indexes()?.properties.clone() {
+        txn.index_existing_values(name, &scopes)?;
+    }
+    txn.commit()
+}
+
 pub struct RocksdbTransaction<'a> {
-    db: &'a DB,
-    indexed_properties: Arc<RwLock<HashSet<Identifier>>>,
-    vertex_manager: VertexManager<'a>,
-    edge_manager: EdgeManager<'a>,
-    edge_range_manager: EdgeRangeManager<'a>,
-    reversed_edge_range_manager: EdgeRangeManager<'a>,
-    vertex_property_manager: VertexPropertyManager<'a>,
-    edge_property_manager: EdgePropertyManager<'a>,
-    vertex_property_value_manager: VertexPropertyValueManager<'a>,
-    edge_property_value_manager: EdgePropertyValueManager<'a>,
-    metadata_manager: MetadataManager<'a>,
+    db: &'a OptimisticTransactionDB,
+    txn: SnapshotTxn<'a>,
+    // Lazily read from the metadata, since not every transaction needs it
+    indexes: OnceCell<Indexes>,
+    // The number of entries committed to the stats counters since they were
+    // last folded, shared by the datastore's transactions
+    unfolded_stats: &'a AtomicUsize,
 }
 
 impl<'a> RocksdbTransaction<'a> {
+    fn load_indexes(&self) -> Result<&Indexes> {
+        self.indexes.get_or_try_init(|| {
+            let metadata_manager = MetadataManager::new(self.db, &self.txn);
+            Ok(Indexes {
+                properties: metadata_manager.get_indexed_properties()?,
+                composites: metadata_manager.get_composite_indexes()?,
+                unique_constraints: metadata_manager.get_unique_constraints()?,
+                fulltext: metadata_manager.get_fulltext_indexes()?,
+                vectors: metadata_manager.get_vector_indexes()?,
+                geo: metadata_manager.get_geo_indexes()?,
+            })
+        })
+    }
+
     fn vertex_ids_from_property_value_iterator(
         &'a self,
         iter: impl Iterator<Item = Result<VertexPropertyValueKey>> + 'a,
     ) -> impl Iterator<Item = Result<Uuid>> + 'a {
-        iter.filter_map(|item| match item {
-            Ok((_, _, id)) => match self.vertex_manager.exists(id) {
+        let vertex_manager = VertexManager::new(self.db, &self.txn);
+        iter.filter_map(move |item| match item {
+            Ok((_, id)) => match vertex_manager.exists(id) {
                 Ok(true) => Some(Ok(id)),
                 Ok(false) => None,
                 Err(err) => Some(Err(err)),
@@ -48,26 +200,113 @@ impl<'a> RocksdbTransaction<'a> {
             Err(err) => Some(Err(err)),
         })
     }
+
+    // Indexes the existing vertices, calling `extract` with each vertex and
+    // then `write` with each value it returns. Values are collected before
+    // any are written, since writing to the transaction may invalidate its
+    // open iterators.
+    fn index_existing_vertices<T>(
+        &self,
+        mut extract: impl FnMut(&Vertex, &VertexPropertyManager) -> Result<Option<T>>,
+        mut write: impl FnMut(Uuid, T) -> Result<()>,
+    ) -> Result<()> {
+        let vertex_manager = VertexManager::new(self.db, &self.txn);
+        let vertex_property_manager = VertexPropertyManager::new(self.db, &self.txn);
+        let mut values = Vec::new();
+        for item in vertex_manager.iterate_for_range(Uuid::default()) {
+            let vertex = item?;
+            if let Some(value) = extract(&vertex, &vertex_property_manager)? {
+                values.push((vertex.id, value));
+            }
+        }
+
+        for (id, value) in values {
+            write(id, value)?;
+        }
+        Ok(())
+    }
+
+    // Removes an index from the loaded indexes with `remove`. If it was
+    // there, the remaining indexes are written to the metadata with `save`,
+    // and the index's entries are deleted with `delete`.
+    fn remove_index<T>(
+        &mut self,
+        remove: impl FnOnce(&mut Indexes) -> Option<T>,
+        save: impl FnOnce(&MetadataManager, &Indexes) -> Result<()>,
+        delete: impl FnOnce(&Self, T) -> Result<()>,
+    ) -> Result<()> {
+        let mut indexes = self.load_indexes()?.clone();
+        let removed = match remove(&mut indexes) {
+            Some(removed) => removed,
+            None => return Ok(()),
+        };
+
+        let metadata_manager = MetadataManager::new(self.db, &self.txn);
+        save(&metadata_manager, &indexes)?;
+        delete(self, removed)?;
+
+        self.indexes = OnceCell::from(indexes);
+        Ok(())
+    }
+
+    // Adds the existing values of a property to the property value indexes,
+    // for the vertices and edges covered by the given scopes.
+    fn index_existing_values(&self, name: Identifier, scopes: &HashSet<IndexScope>) -> Result<()> {
+        let vertex_property_value_manager = VertexPropertyValueManager::new(self.db, &self.txn);
+        self.index_existing_vertices(
+            |vertex, vertex_property_manager| {
+                if !scopes.iter().any(|scope| scope.covers_vertex(vertex.t)) {
+                    return Ok(None);
+                }
+                vertex_property_manager.get(vertex.id, name)
+            },
+            |id, property_value| vertex_property_value_manager.set(id, name, &property_value),
+        )?;
+
+        // Like the vertices, the edges' values are collected first
+        let edge_range_manager = EdgeRangeManager::new(self.db, &self.txn);
+        let edge_property_manager = EdgePropertyManager::new(self.db, &self.txn);
+        let mut edge_property_values = Vec::new();
+        for item in edge_range_manager.iterate_for_all() {
+            let edge = item?;
+            if !scopes.iter().any(|scope| scope.covers_edge(edge.t)) {
+                continue;
+            }
+            if let Some(property_value) = edge_property_manager.get(&edge, name)? {
+                edge_property_values.push((edge, property_value));
+            }
+        }
+
+        let edge_property_value_manager = EdgePropertyValueManager::new(self.db, &self.txn);
+        for (edge, property_value) in edge_property_values {
+            edge_property_value_manager.set(&edge, name, &property_value)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
     fn vertex_count(&self) -> u64 {
-        let iter = self.vertex_manager.iterate_for_range(Uuid::default());
-        iter.count() as u64
+        let stat_manager = StatManager::new(self.db, &self.txn);
+        count_or_log(stat_manager.vertex_count(), "vertex")
     }
 
     fn all_vertices(&'a self) -> Result<DynIter<'a, Vertex>> {
-        let iter = self.vertex_manager.iterate_for_range(Uuid::default());
+        let vertex_manager = VertexManager::new(self.db, &self.txn);
+        let iter = vertex_manager.iterate_for_range(Uuid::default());
         Ok(Box::new(iter))
     }
 
     fn range_vertices(&'a self, offset: Uuid) -> Result<DynIter<'a, Vertex>> {
-        let iter = self.vertex_manager.iterate_for_range(offset);
+        let vertex_manager = VertexManager::new(self.db, &self.txn);
+        let iter = vertex_manager.iterate_for_range(offset);
         Ok(Box::new(iter))
     }
 
     fn specific_vertices(&'a self, ids: Vec<Uuid>) -> Result<DynIter<'a, Vertex>> {
-        let iter = ids.into_iter().filter_map(move |id| match self.vertex_manager.get(id) {
+        let vertex_manager = VertexManager::new(self.db, &self.txn);
+        let iter = ids.into_iter().filter_map(move |id| match vertex_manager.get(id) {
             Ok(Some(t)) => Some(Ok(Vertex::with_id(id, t))),
             Ok(None) => None,
             Err(err) => Some(Err(err)),
@@ -77,8 +316,9 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
     }
 
     fn vertex_ids_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Uuid>>> {
-        if self.indexed_properties.read().unwrap().contains(&name) {
-            let iter = self.vertex_property_value_manager.iterate_for_name(name);
+        if self.load_indexes()?.properties.contains_key(&name) {
+            let vertex_property_value_manager = VertexPropertyValueManager::new(self.db, &self.txn);
+            let iter = vertex_property_value_manager.iterate_for_name(name);
             let iter = self.vertex_ids_from_property_value_iterator(iter);
             Ok(Some(Box::new(iter)))
         } else {
@@ -87,8 +327,29 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
     }
 
     fn vertex_ids_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Uuid>>> {
-        if self.indexed_properties.read().unwrap().contains(&name) {
-            let iter = self.vertex_property_value_manager.iterate_for_value(name, value);
+        if self.load_indexes()?.properties.contains_key(&name) {
+            let vertex_property_value_manager = VertexPropertyValueManager::new(self.db, &self.txn);
+            let iter = vertex_property_value_manager.iterate_for_value(name, value);
+            let iter = self.vertex_ids_from_property_value_iterator(iter);
+            Ok(Some(Box::new(iter)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn vertex_ids_with_property_range(
+        &'a self,
+        name: Identifier,
+        lower: Bound<&Json>,
+        upper: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        if self.load_indexes()?.properties.contains_key(&name) {
+            let range = match util::JsonRange::new(lower, upper) {
+                Some(range) => range,
+                None => return Ok(Some(Box::new(iter::empty()))),
+            };
+            let vertex_property_value_manager = VertexPropertyValueManager::new(self.db, &self.txn);
+            let iter = vertex_property_value_manager.iterate_for_range(name, &range);
             let iter = self.vertex_ids_from_property_value_iterator(iter);
             Ok(Some(Box::new(iter)))
         } else {
@@ -96,34 +357,108 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
         }
     }
 
+    fn vertex_ids_with_composite_values(
+        &'a self,
+        names: &[Identifier],
+        values: &[Json],
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        if let Some(index_id) = self.load_indexes()?.composites.get(names) {
+            let vertex_composite_value_manager = VertexCompositeValueManager::new(self.db, &self.txn);
+            let iter = vertex_composite_value_manager.iterate_for_values(*index_id, values);
+            Ok(Some(Box::new(iter)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn vertex_ids_with_property_terms(
+        &'a self,
+        name: Identifier,
+        terms: &[String],
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        if !self.load_indexes()?.fulltext.contains(&name) {
+            return Ok(None);
+        }
+
+        // The vertices with the first term are narrowed down by each of the
+        // rest in turn
+        let vertex_property_term_manager = VertexPropertyTermManager::new(self.db, &self.txn);
+        let mut vertex_ids: Option<HashSet<Uuid>> = None;
+        for term in terms {
+            let term_ids = vertex_property_term_manager
+                .iterate_for_term(name, term)
+                .filter(|item| match (item, &vertex_ids) {
+                    (Ok(id), Some(vertex_ids)) => vertex_ids.contains(id),
+                    _ => true,
+                })
+                .collect::<Result<HashSet<Uuid>>>()?;
+            if term_ids.is_empty() {
+                return Ok(Some(Box::new(iter::empty())));
+            }
+            vertex_ids = Some(term_ids);
+        }
+        Ok(Some(Box::new(vertex_ids.unwrap_or_default().into_iter().map(Ok))))
+    }
+
+    fn vertex_property_vectors(&'a self, name: Identifier) -> Result<Option<DynIter<'a, VertexVector>>> {
+        if self.load_indexes()?.vectors.contains(&name) {
+            let vertex_property_vector_manager = VertexPropertyVectorManager::new(self.db, &self.txn);
+            Ok(Some(Box::new(vertex_property_vector_manager.iterate_for_name(name))))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn vertex_points_within(
+        &'a self,
+        name: Identifier,
+        bbox: &BoundingBox,
+    ) -> Result<Option<DynIter<'a, VertexPoint>>> {
+        if self.load_indexes()?.geo.contains(&name) {
+            let vertex_property_geo_manager = VertexPropertyGeoManager::new(self.db, &self.txn);
+            let points = vertex_property_geo_manager.iterate_within(name, bbox)?;
+            Ok(Some(Box::new(points.into_iter().map(Ok))))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn edge_count(&self) -> u64 {
-        let iter = self.edge_range_manager.iterate_for_all();
-        iter.count() as u64
+        let stat_manager = StatManager::new(self.db, &self.txn);
+        count_or_log(stat_manager.edge_count(), "edge")
     }
 
     fn all_edges(&'a self) -> Result<DynIter<'a, Edge>> {
-        let iter = self.edge_range_manager.iterate_for_all();
+        let edge_range_manager = EdgeRangeManager::new(self.db, &self.txn);
+        let iter = edge_range_manager.iterate_for_all();
         Ok(Box::new(iter))
     }
 
     fn range_edges(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
-        let iter = self
-            .edge_range_manager
-            .iterate_for_range(offset.outbound_id, offset.t, offset.inbound_id)?;
+        let edge_range_manager = EdgeRangeManager::new(self.db, &self.txn);
+        let iter = edge_range_manager.iterate_for_range(offset.outbound_id, offset.t, offset.inbound_id)?;
         Ok(Box::new(iter))
     }
 
     fn range_reversed_edges(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
-        let iter =
-            self.reversed_edge_range_manager
-                .iterate_for_range(offset.outbound_id, offset.t, offset.inbound_id)?;
+        let reversed_edge_range_manager = EdgeRangeManager::new_reversed(self.db, &self.txn);
+        let iter = reversed_edge_range_manager.iterate_for_range(offset.outbound_id, offset.t, offset.inbound_id)?;
         Ok(Box::new(iter))
     }
 
+    fn vertex_degree(&'a self, id: Uuid, direction: EdgeDirection, t: Option<Identifier>) -> Result<u64> {
+        let edge_range_manager = match direction {
+            EdgeDirection::Outbound => EdgeRangeManager::new(self.db, &self.txn),
+            EdgeDirection::Inbound => EdgeRangeManager::new_reversed(self.db, &self.txn),
+        };
+        edge_range_manager.count_for_root(id, t)
+    }
+
     fn specific_edges(&'a self, edges: Vec<Edge>) -> Result<DynIter<'a, Edge>> {
+        let edge_range_manager = EdgeRangeManager::new(self.db, &self.txn);
         let iter = edges
             .into_iter()
-            .filter_map(move |e| match self.edge_range_manager.contains(&e) {
+            .filter_map(move |e| match edge_range_manager.contains(&e) {
                 Ok(true) => Some(Ok(e)),
                 Ok(false) => None,
                 Err(err) => Some(Err(err)),
@@ -133,12 +468,25 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
     }
 
     fn edges_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Edge>>> {
-        if self.indexed_properties.read().unwrap().contains(&name) {
-            let iter = self
-                .edge_property_value_manager
-                .iterate_for_name(name)
+        if self.load_indexes()?.properties.contains_key(&name) {
+            let edge_property_value_manager = EdgePropertyValueManager::new(self.db, &self.txn);
+            let iter = edge_property_value_manager.iterate_for_name(name).map(|r| match r {
+                Ok((_, e)) => Ok(e),
+                Err(err) => Err(err),
+            });
+            Ok(Some(Box::new(iter)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn edges_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Edge>>> {
+        if self.load_indexes()?.properties.contains_key(&name) {
+            let edge_property_value_manager = EdgePropertyValueManager::new(self.db, &self.txn);
+            let iter = edge_property_value_manager
+                .iterate_for_value(name, value)
                 .map(|r| match r {
-                    Ok((_, _, e)) => Ok(e),
+                    Ok((_, e)) => Ok(e),
                     Err(err) => Err(err),
                 });
             Ok(Some(Box::new(iter)))
@@ -147,13 +495,22 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
         }
     }
 
-    fn edges_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Edge>>> {
-        if self.indexed_properties.read().unwrap().contains(&name) {
-            let iter = self
-                .edge_property_value_manager
-                .iterate_for_value(name, value)
+    fn edges_with_property_range(
+        &'a self,
+        name: Identifier,
+        lower: Bound<&Json>,
+        upper: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Edge>>> {
+        if self.load_indexes()?.properties.contains_key(&name) {
+            let range = match util::JsonRange::new(lower, upper) {
+                Some(range) => range,
+                None => return Ok(Some(Box::new(iter::empty()))),
+            };
+            let edge_property_value_manager = EdgePropertyValueManager::new(self.db, &self.txn);
+            let iter = edge_property_value_manager
+                .iterate_for_range(name, &range)
                 .map(|r| match r {
-                    Ok((_, _, e)) => Ok(e),
+                    Ok((_, e)) => Ok(e),
                     Err(err) => Err(err),
                 });
             Ok(Some(Box::new(iter)))
@@ -163,198 +520,462 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
     }
 
     fn vertex_property(&self, vertex: &Vertex, name: Identifier) -> Result<Option<Json>> {
-        match self.vertex_property_manager.get(vertex.id, name)? {
+        let vertex_property_manager = VertexPropertyManager::new(self.db, &self.txn);
+        match vertex_property_manager.get(vertex.id, name)? {
             None => Ok(None),
             Some(value) => Ok(Some(value)),
         }
     }
 
     fn all_vertex_properties_for_vertex(&'a self, vertex: &Vertex) -> Result<DynIter<'a, (Identifier, Json)>> {
-        let iter = self.vertex_property_manager.iterate_for_owner(vertex.id)?;
+        let vertex_property_manager = VertexPropertyManager::new(self.db, &self.txn);
+        let iter = vertex_property_manager.iterate_for_owner(vertex.id)?;
         let props: Result<Vec<_>> = iter.collect();
         let iter = props?.into_iter().map(|(_, name, value)| Ok((name, value)));
         Ok(Box::new(iter))
     }
 
     fn edge_property(&self, edge: &Edge, name: Identifier) -> Result<Option<Json>> {
-        match self.edge_property_manager.get(edge, name)? {
+        let edge_property_manager = EdgePropertyManager::new(self.db, &self.txn);
+        match edge_property_manager.get(edge, name)? {
             None => Ok(None),
             Some(value) => Ok(Some(value)),
         }
     }
 
     fn all_edge_properties_for_edge(&'a self, edge: &Edge) -> Result<DynIter<'a, (Identifier, Json)>> {
-        let iter = self.edge_property_manager.iterate_for_owner(edge)?;
+        let edge_property_manager = EdgePropertyManager::new(self.db, &self.txn);
+        let iter = edge_property_manager.iterate_for_owner(edge)?;
         let props: Result<Vec<_>> = iter.collect();
         let iter = props?.into_iter().map(|(_, name, value)| Ok((name, value)));
         Ok(Box::new(iter))
     }
 
+    fn estimated_vertex_count(&self) -> Option<u64> {
+        StatManager::new(self.db, &self.txn).vertex_count().ok()
+    }
+
+    fn estimated_edge_count(&self) -> Option<u64> {
+        StatManager::new(self.db, &self.txn).edge_count().ok()
+    }
+
+    fn estimated_vertex_property_value_count(&self, name: Identifier, value: &Json) -> Option<u64> {
+        if !self.load_indexes().ok()?.properties.contains_key(&name) {
+            return None;
+        }
+        let stat_manager = StatManager::new(self.db, &self.txn);
+        stat_manager.vertex_property_value_count(name, value).ok()
+    }
+
+    fn estimated_edge_property_value_count(&self, name: Identifier, value: &Json) -> Option<u64> {
+        if !self.load_indexes().ok()?.properties.contains_key(&name) {
+            return None;
+        }
+        let stat_manager = StatManager::new(self.db, &self.txn);
+        stat_manager.edge_property_value_count(name, value).ok()
+    }
+
+    fn stats(&self) -> Result<Stats> {
+        StatManager::new(self.db, &self.txn).stats()
+    }
+
     fn delete_vertices(&mut self, vertices: Vec<Vertex>) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexes = self.load_indexes()?;
+        let vertex_manager = VertexManager::new(self.db, &self.txn);
 
         for vertex in vertices.into_iter() {
-            self.vertex_manager.delete(&mut batch, &indexed_properties, vertex.id)?;
+            vertex_manager.delete(indexes, vertex.id)?;
         }
 
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn delete_edges(&mut self, edges: Vec<Edge>) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexes = self.load_indexes()?;
+        let vertex_manager = VertexManager::new(self.db, &self.txn);
+        let edge_manager = EdgeManager::new(self.db, &self.txn);
 
         for edge in edges.into_iter() {
-            if self.vertex_manager.get(edge.outbound_id)?.is_some() {
-                self.edge_manager.delete(&mut batch, &indexed_properties, &edge)?;
+            if vertex_manager.get(edge.outbound_id)?.is_some() {
+                edge_manager.delete(indexes, &edge)?;
             };
         }
 
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn delete_vertex_properties(&mut self, props: Vec<(Uuid, Identifier)>) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexes = self.load_indexes()?;
+        let vertex_property_manager = VertexPropertyManager::new(self.db, &self.txn);
 
         for (id, name) in props.into_iter() {
-            self.vertex_property_manager
-                .delete(&mut batch, &indexed_properties, id, name)?;
+            vertex_property_manager.delete(indexes, id, name)?;
         }
 
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn delete_edge_properties(&mut self, props: Vec<(Edge, Identifier)>) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexes = self.load_indexes()?;
+        let edge_property_manager = EdgePropertyManager::new(self.db, &self.txn);
 
         for (edge, name) in props.into_iter() {
-            self.edge_property_manager
-                .delete(&mut batch, &indexed_properties, &edge, name)?;
+            edge_property_manager.delete(indexes, &edge, name)?;
         }
 
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn sync(&self) -> Result<()> {
-        self.vertex_manager.compact();
-        self.edge_range_manager.compact();
-        self.edge_range_manager.compact();
-        self.vertex_property_manager.compact();
-        self.edge_property_manager.compact();
-        self.vertex_property_value_manager.compact();
-        self.edge_property_value_manager.compact();
-        self.metadata_manager.compact();
+        fold_stats(self.db)?;
+        for cf_name in &CF_NAMES {
+            let cf = self.db.cf_handle(cf_name).unwrap();
+            self.db
+                .compact_range_cf(cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+        }
         self.db.flush()?;
         Ok(())
     }
 
+    fn commit(&mut self) -> Result<()> {
+        // Swap in a fresh rocksdb transaction, so this one remains usable
+        // after committing
+        let txn = mem::replace(&mut self.txn, SnapshotTxn::new(self.db));
+        self.indexes = OnceCell::new();
+        let counter_deltas = txn.counter_delta_count();
+        txn.commit()?;
+
+        // Whichever transaction takes the unfolded entries past the threshold
+        // folds them. The commit has already succeeded by then, so a failed
+        // fold is only logged, and left for a later commit to retry.
+        let unfolded = self.unfolded_stats.fetch_add(counter_deltas, Ordering::Relaxed) + counter_deltas;
+        if unfolded >= STATS_FOLD_THRESHOLD
+            && self
+                .unfolded_stats
+                .compare_exchange(unfolded, 0, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            if let Err(err) = fold_stats(self.db) {
+                log::warn!("failed to fold the stats counters: {err}");
+                self.unfolded_stats.fetch_add(unfolded, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+
+    fn rollback(&mut self) -> Result<()> {
+        self.txn.rollback()?;
+        self.indexes = OnceCell::new();
+        Ok(())
+    }
+
+    fn lock_vertex_property_value(&mut self, t: Identifier, name: Identifier, value: &Json) -> Result<()> {
+        let metadata_manager = MetadataManager::new(self.db, &self.txn);
+        metadata_manager.lock_vertex_property_value(t, name, value)
+    }
+
     fn create_vertex(&mut self, vertex: &Vertex) -> Result<bool> {
-        if self.vertex_manager.exists(vertex.id)? {
+        let vertex_manager = VertexManager::new(self.db, &self.txn);
+        if vertex_manager.exists(vertex.id)? {
             Ok(false)
         } else {
-            let mut batch = WriteBatch::default();
-            self.vertex_manager.create(&mut batch, vertex)?;
-            self.db.write(batch)?;
+            vertex_manager.create(vertex)?;
             Ok(true)
         }
     }
 
     fn create_edge(&mut self, edge: &Edge) -> Result<bool> {
-        if !self.vertex_manager.exists(edge.outbound_id)? || !self.vertex_manager.exists(edge.inbound_id)? {
+        let vertex_manager = VertexManager::new(self.db, &self.txn);
+        if !vertex_manager.exists(edge.outbound_id)? || !vertex_manager.exists(edge.inbound_id)? {
             Ok(false)
         } else {
-            let mut batch = WriteBatch::default();
-            self.edge_manager.set(&mut batch, edge)?;
-            self.db.write(batch)?;
+            let edge_manager = EdgeManager::new(self.db, &self.txn);
+            edge_manager.set(edge)?;
             Ok(true)
         }
     }
 
-    // We override the default `bulk_insert` implementation because further
-    // optimization can be done by using `WriteBatch`s.
+    // We override the default `bulk_insert` implementation because it skips
+    // the existence checks that the individual calls make.
     fn bulk_insert(&mut self, items: Vec<BulkInsertItem>) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexes = self.load_indexes()?;
+        let vertex_manager = VertexManager::new(self.db, &self.txn);
+        let edge_manager = EdgeManager::new(self.db, &self.txn);
+        let vertex_property_manager = VertexPropertyManager::new(self.db, &self.txn);
+        let edge_property_manager = EdgePropertyManager::new(self.db, &self.txn);
 
         for item in items {
             match item {
                 BulkInsertItem::Vertex(ref vertex) => {
-                    self.vertex_manager.create(&mut batch, vertex)?;
+                    vertex_manager.create(vertex)?;
                 }
                 BulkInsertItem::Edge(ref edge) => {
-                    self.edge_manager.set(&mut batch, edge)?;
+                    edge_manager.set(edge)?;
                 }
                 BulkInsertItem::VertexProperty(id, ref name, ref value) => {
-                    self.vertex_property_manager
-                        .set(&mut batch, &indexed_properties, id, *name, value)?;
+                    vertex_property_manager.set(indexes, id, *name, value)?;
                 }
                 BulkInsertItem::EdgeProperty(ref edge, ref name, ref value) => {
-                    self.edge_property_manager
-                        .set(&mut batch, &indexed_properties, edge, *name, value)?;
+                    edge_property_manager.set(indexes, edge, *name, value)?;
                 }
             }
         }
 
-        self.db.write(batch)?;
         Ok(())
     }
 
-    fn index_property(&mut self, name: Identifier) -> Result<()> {
-        let mut indexed_properties = self.indexed_properties.write().unwrap();
-        if !indexed_properties.insert(name) {
+    fn index_property(&mut self, name: Identifier, scope: IndexScope) -> Result<()> {
+        let mut indexes = self.load_indexes()?.clone();
+        if !indexes.properties.entry(name).or_default().insert(scope) {
             return Ok(());
         }
 
-        let mut batch = WriteBatch::default();
-        self.metadata_manager
-            .set_indexed_properties(&mut batch, &indexed_properties)?;
+        let metadata_manager = MetadataManager::new(self.db, &self.txn);
+        metadata_manager.set_indexed_properties(&indexes.properties)?;
 
-        for item in self.vertex_manager.iterate_for_range(Uuid::default()) {
-            let vertex = item?;
-            if let Some(property_value) = self.vertex_property_manager.get(vertex.id, name)? {
-                self.vertex_property_value_manager
-                    .set(&mut batch, vertex.id, name, &property_value);
+        self.index_existing_values(name, &HashSet::from([scope]))?;
+        self.indexes = OnceCell::from(indexes);
+        Ok(())
+    }
+
+    fn unindex_property(&mut self, name: Identifier, scope: IndexScope) -> Result<()> {
+        let mut indexes = self.load_indexes()?.clone();
+        let remaining_scopes = match indexes.properties.get_mut(&name) {
+            Some(scopes) if scopes.contains(&scope) => {
+                scopes.remove(&scope);
+                scopes.clone()
             }
+            _ => return Ok(()),
+        };
+        if remaining_scopes.is_empty() {
+            indexes.properties.remove(&name);
         }
 
-        for item in self.edge_range_manager.iterate_for_all() {
-            let edge = item?;
-            if let Some(property_value) = self.edge_property_manager.get(&edge, name)? {
-                self.edge_property_value_manager
-                    .set(&mut batch, &edge, name, &property_value);
-            }
+        let metadata_manager = MetadataManager::new(self.db, &self.txn);
+        metadata_manager.set_indexed_properties(&indexes.properties)?;
+
+        // Rather than working out which entries were only covered by the
+        // removed scope, the index is rebuilt from the remaining scopes
+        let vertex_property_value_manager = VertexPropertyValueManager::new(self.db, &self.txn);
+        vertex_property_value_manager.delete_for_name(name)?;
+        let edge_property_value_manager = EdgePropertyValueManager::new(self.db, &self.txn);
+        edge_property_value_manager.delete_for_name(name)?;
+        if !remaining_scopes.is_empty() {
+            self.index_existing_values(name, &remaining_scopes)?;
+        }
+
+        self.indexes = OnceCell::from(indexes);
+        Ok(())
+    }
+
+    fn indexed_properties(&self) -> Result<HashMap<Identifier, HashSet<IndexScope>>> {
+        Ok(self.load_indexes()?.properties.clone())
+    }
+
+    fn index_composite(&mut self, names: Vec<Identifier>) -> Result<()> {
+        let mut indexes = self.load_indexes()?.clone();
+        if indexes.composites.contains_key(&names) {
+            return Ok(());
+        }
+
+        let index_id = util::generate_uuid_v1();
+        let vertex_composite_value_manager = VertexCompositeValueManager::new(self.db, &self.txn);
+        self.index_existing_vertices(
+            |vertex, vertex_property_manager| vertex_property_manager.get_all(vertex.id, &names),
+            |id, property_values| vertex_composite_value_manager.set(index_id, &property_values, id),
+        )?;
+
+        indexes.composites.insert(names, index_id);
+        let metadata_manager = MetadataManager::new(self.db, &self.txn);
+        metadata_manager.set_composite_indexes(&indexes.composites)?;
+
+        self.indexes = OnceCell::from(indexes);
+        Ok(())
+    }
+
+    fn unindex_composite(&mut self, names: Vec<Identifier>) -> Result<()> {
+        self.remove_index(
+            |indexes| indexes.composites.remove(&names),
+            |metadata_manager, indexes| metadata_manager.set_composite_indexes(&indexes.composites),
+            |txn, index_id| VertexCompositeValueManager::new(txn.db, &txn.txn).delete_for_index(index_id),
+        )
+    }
+
+    fn composite_indexes(&self) -> Result<HashSet<Vec<Identifier>>> {
+        Ok(self.load_indexes()?.composites.keys().cloned().collect())
+    }
+
+    fn index_property_fulltext(&mut self, name: Identifier) -> Result<()> {
+        let mut indexes = self.load_indexes()?.clone();
+        if indexes.fulltext.contains(&name) {
+            return Ok(());
+        }
+
+        let vertex_property_term_manager = VertexPropertyTermManager::new(self.db, &self.txn);
+        self.index_existing_vertices(
+            |vertex, vertex_property_manager| {
+                let property_value = vertex_property_manager.get(vertex.id, name)?;
+                Ok(property_value.and_then(|value| value.as_str().map(util::tokenize)))
+            },
+            |id, terms| {
+                for term in terms {
+                    vertex_property_term_manager.set(name, &term, id)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        indexes.fulltext.insert(name);
+        let metadata_manager = MetadataManager::new(self.db, &self.txn);
+        metadata_manager.set_fulltext_indexes(&indexes.fulltext)?;
+
+        self.indexes = OnceCell::from(indexes);
+        Ok(())
+    }
+
+    fn unindex_property_fulltext(&mut self, name: Identifier) -> Result<()> {
+        self.remove_index(
+            |indexes| indexes.fulltext.remove(&name).then_some(()),
+            |metadata_manager, indexes| metadata_manager.set_fulltext_indexes(&indexes.fulltext),
+            |txn, ()| VertexPropertyTermManager::new(txn.db, &txn.txn).delete_for_name(name),
+        )
+    }
+
+    fn fulltext_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        Ok(self.load_indexes()?.fulltext.clone())
+    }
+
+    fn index_property_vector(&mut self, name: Identifier) -> Result<()> {
+        let mut indexes = self.load_indexes()?.clone();
+        if indexes.vectors.contains(&name) {
+            return Ok(());
+        }
+
+        let vertex_property_vector_manager = VertexPropertyVectorManager::new(self.db, &self.txn);
+        self.index_existing_vertices(
+            |vertex, vertex_property_manager| {
+                let property_value = vertex_property_manager.get(vertex.id, name)?;
+                Ok(property_value.and_then(|value| util::json_to_vector(&value)))
+            },
+            |id, vector| vertex_property_vector_manager.set(name, id, &vector),
+        )?;
+
+        indexes.vectors.insert(name);
+        let metadata_manager = MetadataManager::new(self.db, &self.txn);
+        metadata_manager.set_vector_indexes(&indexes.vectors)?;
+
+        self.indexes = OnceCell::from(indexes);
+        Ok(())
+    }
+
+    fn unindex_property_vector(&mut self, name: Identifier) -> Result<()> {
+        self.remove_index(
+            |indexes| indexes.vectors.remove(&name).then_some(()),
+            |metadata_manager, indexes| metadata_manager.set_vector_indexes(&indexes.vectors),
+            |txn, ()| VertexPropertyVectorManager::new(txn.db, &txn.txn).delete_for_name(name),
+        )
+    }
+
+    fn vector_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        Ok(self.load_indexes()?.vectors.clone())
+    }
+
+    fn index_property_geo(&mut self, name: Identifier) -> Result<()> {
+        let mut indexes = self.load_indexes()?.clone();
+        if indexes.geo.contains(&name) {
+            return Ok(());
+        }
+
+        let vertex_property_geo_manager = VertexPropertyGeoManager::new(self.db, &self.txn);
+        self.index_existing_vertices(
+            |vertex, vertex_property_manager| {
+                let property_value = vertex_property_manager.get(vertex.id, name)?;
+                Ok(property_value.and_then(|value| GeoPoint::from_json(&value)))
+            },
+            |id, point| vertex_property_geo_manager.set(name, id, &point),
+        )?;
+
+        indexes.geo.insert(name);
+        let metadata_manager = MetadataManager::new(self.db, &self.txn);
+        metadata_manager.set_geo_indexes(&indexes.geo)?;
+
+        self.indexes = OnceCell::from(indexes);
+        Ok(())
+    }
+
+    fn unindex_property_geo(&mut self, name: Identifier) -> Result<()> {
+        self.remove_index(
+            |indexes| indexes.geo.remove(&name).then_some(()),
+            |metadata_manager, indexes| metadata_manager.set_geo_indexes(&indexes.geo),
+            |txn, ()| VertexPropertyGeoManager::new(txn.db, &txn.txn).delete_for_name(name),
+        )
+    }
+
+    fn geo_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        Ok(self.load_indexes()?.geo.clone())
+    }
+
+    fn add_unique_constraint(&mut self, constraint: UniqueConstraint) -> Result<()> {
+        let mut indexes = self.load_indexes()?.clone();
+        if indexes.unique_constraints.contains(&constraint) {
+            return Ok(());
         }
 
-        self.db.write(batch)?;
+        // Values are checked as they're collected, so a violation is found
+        // before any are written
+        let mut seen_values = HashSet::new();
+        let vertex_unique_value_manager = VertexUniqueValueManager::new(self.db, &self.txn);
+        self.index_existing_vertices(
+            |vertex, vertex_property_manager| {
+                if vertex.t != constraint.t {
+                    return Ok(None);
+                }
+                let property_value = vertex_property_manager.get(vertex.id, constraint.name)?;
+                if let Some(ref property_value) = property_value {
+                    if !seen_values.insert(util::build(&[util::Component::Json(property_value)])) {
+                        return Err(Error::ConstraintViolation);
+                    }
+                }
+                Ok(property_value)
+            },
+            |id, property_value| vertex_unique_value_manager.set(constraint, &property_value, id),
+        )?;
+
+        indexes.unique_constraints.insert(constraint);
+        let metadata_manager = MetadataManager::new(self.db, &self.txn);
+        metadata_manager.set_unique_constraints(&indexes.unique_constraints)?;
+
+        self.indexes = OnceCell::from(indexes);
         Ok(())
     }
 
+    fn remove_unique_constraint(&mut self, constraint: UniqueConstraint) -> Result<()> {
+        self.remove_index(
+            |indexes| indexes.unique_constraints.remove(&constraint).then_some(()),
+            |metadata_manager, indexes| metadata_manager.set_unique_constraints(&indexes.unique_constraints),
+            |txn, ()| VertexUniqueValueManager::new(txn.db, &txn.txn).delete_for_constraint(constraint),
+        )
+    }
+
+    fn unique_constraints(&self) -> Result<HashSet<UniqueConstraint>> {
+        Ok(self.load_indexes()?.unique_constraints.clone())
+    }
+
     fn set_vertex_properties(&mut self, vertices: Vec<Uuid>, name: Identifier, value: &Json) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexes = self.load_indexes()?;
+        let vertex_property_manager = VertexPropertyManager::new(self.db, &self.txn);
         for id in vertices.into_iter() {
-            self.vertex_property_manager
-                .set(&mut batch, &indexed_properties, id, name, value)?;
+            vertex_property_manager.set(indexes, id, name, value)?;
         }
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn set_edge_properties(&mut self, edges: Vec<Edge>, name: Identifier, value: &Json) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexes = self.load_indexes()?;
+        let edge_property_manager = EdgePropertyManager::new(self.db, &self.txn);
         for edge in edges.into_iter() {
-            self.edge_property_manager
-                .set(&mut batch, &indexed_properties, &edge, name, value)?;
+            edge_property_manager.set(indexes, &edge, name, value)?;
         }
-        self.db.write(batch)?;
         Ok(())
     }
 }
@@ -362,8 +983,8 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
 /// A datastore that is backed by rocksdb.
 #[derive(Debug)]
 pub struct RocksdbDatastore {
-    db: Arc<DB>,
-    indexed_properties: Arc<RwLock<HashSet<Identifier>>>,
+    db: Arc<OptimisticTransactionDB>,
+    unfolded_stats: AtomicUsize,
 }
 
 impl RocksdbDatastore {
@@ -375,25 +996,11 @@ impl RocksdbDatastore {
         let opts = RocksdbDatastore::get_options(None);
         let path = path.as_ref();
 
-        let db = match DB::open_cf(&opts, path, CF_NAMES) {
-            Ok(db) => db,
-            Err(_) => {
-                let mut db = DB::open(&opts, path)?;
-
-                for cf_name in &CF_NAMES {
-                    db.create_cf(cf_name, &opts)?;
-                }
-
-                db
-            }
-        };
-
-        let metadata_manager = MetadataManager::new(&db);
-        let indexed_properties = metadata_manager.get_indexed_properties()?;
+        let db = open_db(path, &opts)?;
 
         Ok(Database::new(RocksdbDatastore {
             db: Arc::new(db),
-            indexed_properties: Arc::new(RwLock::new(indexed_properties)),
+            unfolded_stats: AtomicUsize::new(0),
         }))
     }
 
@@ -405,25 +1012,11 @@ impl RocksdbDatastore {
     pub fn new_db_with_options<P: AsRef<Path>>(path: P, opts: &Options) -> Result<Database<RocksdbDatastore>> {
         let path = path.as_ref();
 
-        let db = match DB::open_cf(opts, path, CF_NAMES) {
-            Ok(db) => db,
-            Err(_) => {
-                let mut db = DB::open(opts, path)?;
-
-                for cf_name in &CF_NAMES {
-                    db.create_cf(cf_name, opts)?;
-                }
-
-                db
-            }
-        };
-
-        let metadata_manager = MetadataManager::new(&db);
-        let indexed_properties = metadata_manager.get_indexed_properties()?;
+        let db = open_db(path, opts)?;
 
         Ok(Database::new(RocksdbDatastore {
             db: Arc::new(db),
-            indexed_properties: Arc::new(RwLock::new(indexed_properties)),
+            unfolded_stats: AtomicUsize::new(0),
         }))
     }
 
@@ -473,19 +1066,19 @@ impl Datastore for RocksdbDatastore {
         = RocksdbTransaction<'a>
     where
         Self: 'a;
+    type ReadTransaction<'a>
+        = RocksdbTransaction<'a>
+    where
+        Self: 'a;
     fn transaction(&'_ self) -> Self::Transaction<'_> {
         RocksdbTransaction {
             db: &self.db,
-            indexed_properties: self.indexed_properties.clone(),
-            vertex_manager: VertexManager::new(&self.db),
-            edge_manager: EdgeManager::new(&self.db),
-            edge_range_manager: EdgeRangeManager::new(&self.db),
-            reversed_edge_range_manager: EdgeRangeManager::new_reversed(&self.db),
-            vertex_property_manager: VertexPropertyManager::new(&self.db),
-            edge_property_manager: EdgePropertyManager::new(&self.db),
-            vertex_property_value_manager: VertexPropertyValueManager::new(&self.db),
-            edge_property_value_manager: EdgePropertyValueManager::new(&self.db),
-            metadata_manager: MetadataManager::new(&self.db),
+            txn: SnapshotTxn::new(&self.db),
+            indexes: OnceCell::new(),
+            unfolded_stats: &self.unfolded_stats,
         }
     }
+    fn read_transaction(&'_ self) -> Self::ReadTransaction<'_> {
+        self.transaction()
+    }
 }
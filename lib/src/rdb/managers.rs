@@ -1,22 +1,147 @@
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Cursor;
 use std::ops::Deref;
 use std::result::Result as StdResult;
 
-use crate::errors::Result;
+use crate::errors::{Error, Result};
 use crate::models;
 use crate::util;
+use crate::{VertexPoint, VertexVector};
 
-use rocksdb::{ColumnFamilyRef, DBIterator, Direction, IteratorMode, WriteBatch, DB};
+use rocksdb::{
+    AsColumnFamilyRef, ColumnFamilyRef, DBIteratorWithThreadMode, Direction, IteratorMode, OptimisticTransactionDB,
+    OptimisticTransactionOptions, ReadOptions, WriteOptions,
+};
 use uuid::Uuid;
 
 pub type OwnedPropertyItem = (Uuid, models::Identifier, models::Json);
 pub type EdgePropertyItem = (models::Edge, models::Identifier, models::Json);
-pub type VertexPropertyValueKey = (models::Identifier, u64, Uuid);
-pub type EdgePropertyValueKey = (models::Identifier, u64, models::Edge);
+pub type VertexPropertyValueKey = (models::Identifier, Uuid);
+pub type EdgePropertyValueKey = (models::Identifier, models::Edge);
+pub type IndexedProperties = HashMap<models::Identifier, HashSet<models::IndexScope>>;
+pub type CompositeIndexes = HashMap<Vec<models::Identifier>, Uuid>;
+pub type UniqueConstraints = HashSet<models::UniqueConstraint>;
+pub type FulltextIndexes = HashSet<models::Identifier>;
+pub type VectorIndexes = HashSet<models::Identifier>;
+pub type GeoIndexes = HashSet<models::Identifier>;
+type Txn<'a> = rocksdb::Transaction<'a, OptimisticTransactionDB>;
+type TxnIterator<'a> = DBIteratorWithThreadMode<'a, Txn<'a>>;
 type RocksReadResult = StdResult<(Box<[u8]>, Box<[u8]>), rocksdb::Error>;
+// Counter keys mapped to the amount to add to the counter, along with the
+// serialized property value for property value counters
+type CounterDeltas = BTreeMap<Vec<u8>, (i64, Vec<u8>)>;
+
+/// The indexes that are maintained as data changes, as stored in the
+/// metadata.
+#[derive(Clone, Default)]
+pub(crate) struct Indexes {
+    pub properties: IndexedProperties,
+    // Each composite index is assigned an ID, which prefixes its entries
+    pub composites: CompositeIndexes,
+    pub unique_constraints: UniqueConstraints,
+    pub fulltext: FulltextIndexes,
+    pub vectors: VectorIndexes,
+    pub geo: GeoIndexes,
+}
+
+impl Indexes {
+    fn has_composite(&self, name: models::Identifier) -> bool {
+        self.composites.keys().any(|names| names.contains(&name))
+    }
+
+    fn has_unique(&self, name: models::Identifier) -> bool {
+        self.unique_constraints.iter().any(|constraint| constraint.name == name)
+    }
+}
+
+/// A rocksdb transaction whose reads are all made against its own snapshot,
+/// so that they observe the same point-in-time view of the database that
+/// conflicts are checked against. Writes made within the transaction are
+/// still visible to its own reads.
+pub(crate) struct SnapshotTxn<'a> {
+    db: &'a OptimisticTransactionDB,
+    txn: Txn<'a>,
+    // Identifies the transaction's changes to the stats counters, which are
+    // written when it commits
+    id: Uuid,
+    counter_deltas: RefCell<CounterDeltas>,
+}
+
+impl<'a> SnapshotTxn<'a> {
+    pub fn new(db: &'a OptimisticTransactionDB) -> Self {
+        let mut txn_opts = OptimisticTransactionOptions::new();
+        // Check for conflicts from the start of the transaction, rather than
+        // from the first time a key is written
+        txn_opts.set_snapshot(true);
+        let txn = db.transaction_opt(&WriteOptions::default(), &txn_opts);
+        SnapshotTxn {
+            db,
+            txn,
+            id: util::generate_uuid_v1(),
+            counter_deltas: RefCell::new(CounterDeltas::new()),
+        }
+    }
+
+    fn read_opts(&self) -> ReadOptions {
+        // The read options only keep the underlying snapshot, which is owned
+        // by the transaction, so the handle to it can be dropped right away
+        let mut opts = ReadOptions::default();
+        opts.set_snapshot(&self.txn.snapshot());
+        opts
+    }
+
+    pub fn get_cf<K: AsRef<[u8]>>(&self, cf: &impl AsColumnFamilyRef, key: K) -> Result<Option<Vec<u8>>> {
+        Ok(self.txn.get_cf_opt(cf, key, &self.read_opts())?)
+    }
+
+    pub fn get_for_update_cf<K: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+        exclusive: bool,
+    ) -> Result<Option<Vec<u8>>> {
+        Ok(self.txn.get_for_update_cf_opt(cf, key, exclusive, &self.read_opts())?)
+    }
 
-fn take_with_prefix(iterator: DBIterator<'_>, prefix: Vec<u8>) -> impl Iterator<Item = RocksReadResult> + '_ {
+    pub fn iterator_cf(&self, cf: &impl AsColumnFamilyRef, mode: IteratorMode) -> TxnIterator<'_> {
+        self.txn.iterator_cf_opt(cf, self.read_opts(), mode)
+    }
+
+    pub fn put_cf<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, cf: &impl AsColumnFamilyRef, key: K, value: V) -> Result<()> {
+        self.txn.put_cf(cf, key, value)?;
+        Ok(())
+    }
+
+    pub fn delete_cf<K: AsRef<[u8]>>(&self, cf: &impl AsColumnFamilyRef, key: K) -> Result<()> {
+        self.txn.delete_cf(cf, key)?;
+        Ok(())
+    }
+
+    /// The number of stats counter entries that committing will write.
+    pub fn counter_delta_count(&self) -> usize {
+        self.counter_deltas
+            .borrow()
+            .values()
+            .filter(|(delta, _)| *delta != 0)
+            .count()
+    }
+
+    pub fn commit(self) -> Result<()> {
+        StatManager::new(self.db, &self).write_counter_deltas()?;
+        self.txn.commit()?;
+        Ok(())
+    }
+
+    pub fn rollback(&self) -> Result<()> {
+        self.txn.rollback()?;
+        self.counter_deltas.borrow_mut().clear();
+        Ok(())
+    }
+}
+
+fn take_with_prefix(iterator: TxnIterator<'_>, prefix: Vec<u8>) -> impl Iterator<Item = RocksReadResult> + '_ {
     iterator.take_while(move |item| -> bool {
         if let Ok((ref k, _)) = *item {
             k.starts_with(&prefix)
@@ -26,15 +151,52 @@ fn take_with_prefix(iterator: DBIterator<'_>, prefix: Vec<u8>) -> impl Iterator<
     })
 }
 
+fn take_within_range<'a, I>(iterator: I, end: Option<Vec<u8>>) -> impl Iterator<Item = RocksReadResult> + 'a
+where
+    I: Iterator<Item = RocksReadResult> + 'a,
+{
+    iterator.take_while(move |item| -> bool {
+        match (item, &end) {
+            (Ok((ref k, _)), Some(ref end)) => **k < **end,
+            _ => true,
+        }
+    })
+}
+
+// Deletes every key in a column family with the given prefix.
+fn delete_with_prefix(txn: &SnapshotTxn<'_>, cf: &impl AsColumnFamilyRef, prefix: Vec<u8>) -> Result<()> {
+    // Keys are collected before being deleted, since writing to the
+    // transaction may invalidate its open iterators
+    let iter = txn.iterator_cf(cf, IteratorMode::From(&prefix, Direction::Forward));
+    let keys = take_with_prefix(iter, prefix)
+        .map(|item| item.map(|(k, _)| k))
+        .collect::<StdResult<Vec<Box<[u8]>>, rocksdb::Error>>()?;
+    for key in keys {
+        txn.delete_cf(cf, key)?;
+    }
+    Ok(())
+}
+
+// Builds the key to start iterating a property value index from, and the
+// exclusive key to stop at, for a range of values under a property name
+// prefix.
+fn property_value_range_keys(prefix: &[u8], range: &util::JsonRange) -> (Vec<u8>, Option<Vec<u8>>) {
+    let start = [prefix, &range.start].concat();
+    let end = range.end.as_ref().map(|end| [prefix, end].concat());
+    (start, end)
+}
+
 pub(crate) struct VertexManager<'a> {
-    db: &'a DB,
+    db: &'a OptimisticTransactionDB,
+    txn: &'a SnapshotTxn<'a>,
     cf: ColumnFamilyRef<'a>,
 }
 
 impl<'a> VertexManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
         VertexManager {
             db,
+            txn,
             cf: db.cf_handle("vertices:v2").unwrap(),
         }
     }
@@ -44,11 +206,11 @@ impl<'a> VertexManager<'a> {
     }
 
     pub fn exists(&self, id: Uuid) -> Result<bool> {
-        Ok(self.db.get_cf(&self.cf, self.key(id))?.is_some())
+        Ok(self.txn.get_cf(&self.cf, self.key(id))?.is_some())
     }
 
     pub fn get(&self, id: Uuid) -> Result<Option<models::Identifier>> {
-        match self.db.get_cf(&self.cf, self.key(id))? {
+        match self.txn.get_cf(&self.cf, self.key(id))? {
             Some(value_bytes) => {
                 let mut cursor = Cursor::new(value_bytes.deref());
                 unsafe { Ok(Some(util::read_identifier(&mut cursor)?)) }
@@ -57,10 +219,10 @@ impl<'a> VertexManager<'a> {
         }
     }
 
-    pub fn iterate_for_range(&'a self, id: Uuid) -> impl Iterator<Item = Result<models::Vertex>> + 'a {
+    pub fn iterate_for_range(&self, id: Uuid) -> impl Iterator<Item = Result<models::Vertex>> + 'a {
         let low_key = util::build(&[util::Component::Uuid(id)]);
         let iter = self
-            .db
+            .txn
             .iterator_cf(&self.cf, IteratorMode::From(&low_key, Direction::Forward));
         iter.map(|item| -> Result<models::Vertex> {
             let (k, v) = item?;
@@ -77,100 +239,106 @@ impl<'a> VertexManager<'a> {
         })
     }
 
-    pub fn create(&self, batch: &mut WriteBatch, vertex: &models::Vertex) -> Result<()> {
+    pub fn create(&self, vertex: &models::Vertex) -> Result<()> {
+        // Bulk inserts may overwrite an existing vertex
+        let stat_manager = StatManager::new(self.db, self.txn);
+        if let Some(t) = self.get(vertex.id)? {
+            stat_manager.add_vertex_type(t, -1)?;
+        }
         let key = self.key(vertex.id);
-        batch.put_cf(&self.cf, &key, util::build(&[util::Component::Identifier(vertex.t)]));
+        self.txn
+            .put_cf(&self.cf, &key, util::build(&[util::Component::Identifier(vertex.t)]))?;
+        stat_manager.add_vertex_type(vertex.t, 1)?;
         Ok(())
     }
 
-    pub fn delete(
-        &self,
-        batch: &mut WriteBatch,
-        indexed_properties: &HashSet<models::Identifier>,
-        id: Uuid,
-    ) -> Result<()> {
-        batch.delete_cf(&self.cf, self.key(id));
-
-        let vertex_property_manager = VertexPropertyManager::new(self.db);
-        for item in vertex_property_manager.iterate_for_owner(id)? {
-            let (vertex_property_owner_id, vertex_property_name, _) = item?;
-            vertex_property_manager.delete(
-                batch,
-                indexed_properties,
-                vertex_property_owner_id,
-                vertex_property_name,
-            )?;
+    pub fn delete(&self, indexes: &Indexes, id: Uuid) -> Result<()> {
+        if let Some(t) = self.get(id)? {
+            StatManager::new(self.db, self.txn).add_vertex_type(t, -1)?;
+        }
+        self.txn.delete_cf(&self.cf, self.key(id))?;
+
+        // Items are collected before being deleted, since writing to the
+        // transaction may invalidate its open iterators
+        let vertex_property_manager = VertexPropertyManager::new(self.db, self.txn);
+        let vertex_properties = vertex_property_manager
+            .iterate_for_owner(id)?
+            .collect::<Result<Vec<OwnedPropertyItem>>>()?;
+        for (vertex_property_owner_id, vertex_property_name, _) in vertex_properties {
+            vertex_property_manager.delete(indexes, vertex_property_owner_id, vertex_property_name)?;
         }
 
-        let edge_manager = EdgeManager::new(self.db);
+        let edge_manager = EdgeManager::new(self.db, self.txn);
 
         {
-            let edge_range_manager = EdgeRangeManager::new(self.db);
-            for item in edge_range_manager.iterate_for_root(id, None)? {
-                let edge = item?;
+            let edge_range_manager = EdgeRangeManager::new(self.db, self.txn);
+            let edges = edge_range_manager
+                .iterate_for_root(id, None)?
+                .collect::<Result<Vec<models::Edge>>>()?;
+            for edge in edges {
                 debug_assert_eq!(edge.outbound_id, id);
-                edge_manager.delete(batch, indexed_properties, &edge)?;
+                edge_manager.delete(indexes, &edge)?;
             }
         }
 
         {
-            let reversed_edge_range_manager = EdgeRangeManager::new_reversed(self.db);
-            for item in reversed_edge_range_manager.iterate_for_root(id, None)? {
-                let edge = item?;
+            let reversed_edge_range_manager = EdgeRangeManager::new_reversed(self.db, self.txn);
+            let reversed_edges = reversed_edge_range_manager
+                .iterate_for_root(id, None)?
+                .collect::<Result<Vec<models::Edge>>>()?;
+            for edge in reversed_edges {
                 debug_assert_eq!(edge.outbound_id, id);
-                edge_manager.delete(batch, indexed_properties, &edge)?;
+                edge_manager.delete(indexes, &edge)?;
             }
         }
 
         Ok(())
     }
-
-    pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
-    }
 }
 
 pub(crate) struct EdgeManager<'a> {
-    db: &'a DB,
+    db: &'a OptimisticTransactionDB,
+    txn: &'a SnapshotTxn<'a>,
 }
 
 impl<'a> EdgeManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
-        EdgeManager { db }
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
+        EdgeManager { db, txn }
     }
 
-    pub fn set(&self, batch: &mut WriteBatch, edge: &models::Edge) -> Result<()> {
-        let edge_range_manager = EdgeRangeManager::new(self.db);
-        let reversed_edge_range_manager = EdgeRangeManager::new_reversed(self.db);
+    pub fn set(&self, edge: &models::Edge) -> Result<()> {
+        let edge_range_manager = EdgeRangeManager::new(self.db, self.txn);
+        let reversed_edge_range_manager = EdgeRangeManager::new_reversed(self.db, self.txn);
         let reversed_edge = edge.reversed();
 
         if edge_range_manager.contains(edge)? {
-            edge_range_manager.delete(batch, edge)?;
-            reversed_edge_range_manager.delete(batch, &reversed_edge)?;
+            edge_range_manager.delete(edge)?;
+            reversed_edge_range_manager.delete(&reversed_edge)?;
+        } else {
+            StatManager::new(self.db, self.txn).add_edge_type(edge.t, 1)?;
         }
 
-        edge_range_manager.set(batch, edge)?;
-        reversed_edge_range_manager.set(batch, &reversed_edge)?;
+        edge_range_manager.set(edge)?;
+        reversed_edge_range_manager.set(&reversed_edge)?;
         Ok(())
     }
 
-    pub fn delete(
-        &self,
-        batch: &mut WriteBatch,
-        indexed_properties: &HashSet<models::Identifier>,
-        edge: &models::Edge,
-    ) -> Result<()> {
-        let edge_range_manager = EdgeRangeManager::new(self.db);
-        edge_range_manager.delete(batch, edge)?;
+    pub fn delete(&self, indexes: &Indexes, edge: &models::Edge) -> Result<()> {
+        let edge_range_manager = EdgeRangeManager::new(self.db, self.txn);
+        if edge_range_manager.contains(edge)? {
+            StatManager::new(self.db, self.txn).add_edge_type(edge.t, -1)?;
+        }
+        edge_range_manager.delete(edge)?;
 
-        let reversed_edge_range_manager = EdgeRangeManager::new_reversed(self.db);
-        reversed_edge_range_manager.delete(batch, &edge.reversed())?;
+        let reversed_edge_range_manager = EdgeRangeManager::new_reversed(self.db, self.txn);
+        reversed_edge_range_manager.delete(&edge.reversed())?;
 
-        let edge_property_manager = EdgePropertyManager::new(self.db);
-        for item in edge_property_manager.iterate_for_owner(edge)? {
-            let (edge_property_edge, edge_property_name, _) = item?;
-            edge_property_manager.delete(batch, indexed_properties, &edge_property_edge, edge_property_name)?;
+        let edge_property_manager = EdgePropertyManager::new(self.db, self.txn);
+        let edge_properties = edge_property_manager
+            .iterate_for_owner(edge)?
+            .collect::<Result<Vec<EdgePropertyItem>>>()?;
+        for (edge_property_edge, edge_property_name, _) in edge_properties {
+            edge_property_manager.delete(indexes, &edge_property_edge, edge_property_name)?;
         }
 
         Ok(())
@@ -178,21 +346,21 @@ impl<'a> EdgeManager<'a> {
 }
 
 pub(crate) struct EdgeRangeManager<'a> {
-    db: &'a DB,
+    txn: &'a SnapshotTxn<'a>,
     cf: ColumnFamilyRef<'a>,
 }
 
 impl<'a> EdgeRangeManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
         EdgeRangeManager {
-            db,
+            txn,
             cf: db.cf_handle("edge_ranges:v2").unwrap(),
         }
     }
 
-    pub fn new_reversed(db: &'a DB) -> Self {
+    pub fn new_reversed(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
         EdgeRangeManager {
-            db,
+            txn,
             cf: db.cf_handle("reversed_edge_ranges:v2").unwrap(),
         }
     }
@@ -205,7 +373,7 @@ impl<'a> EdgeRangeManager<'a> {
         ])
     }
 
-    fn iterate<I>(&'a self, iterator: I) -> impl Iterator<Item = Result<models::Edge>> + 'a
+    fn iterate<I>(iterator: I) -> impl Iterator<Item = Result<models::Edge>> + 'a
     where
         I: Iterator<Item = RocksReadResult> + 'a,
     {
@@ -220,38 +388,44 @@ impl<'a> EdgeRangeManager<'a> {
     }
 
     pub fn contains(&self, edge: &models::Edge) -> Result<bool> {
-        Ok(self.db.get_cf(&self.cf, self.key(edge))?.is_some())
+        Ok(self.txn.get_cf(&self.cf, self.key(edge))?.is_some())
+    }
+
+    fn iterate_keys_for_root(
+        &self,
+        id: Uuid,
+        t: Option<models::Identifier>,
+    ) -> impl Iterator<Item = RocksReadResult> + 'a {
+        let prefix = match t {
+            Some(t) => util::build(&[util::Component::Uuid(id), util::Component::Identifier(t)]),
+            None => util::build(&[util::Component::Uuid(id)]),
+        };
+        let iter = self
+            .txn
+            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        take_with_prefix(iter, prefix)
     }
 
     pub fn iterate_for_root(
-        &'a self,
+        &self,
         id: Uuid,
         t: Option<models::Identifier>,
     ) -> Result<Box<dyn Iterator<Item = Result<models::Edge>> + 'a>> {
-        let (prefix, iter) = match t {
-            Some(t) => {
-                let prefix = util::build(&[util::Component::Uuid(id), util::Component::Identifier(t)]);
-                let low_key = util::build(&[util::Component::Uuid(id), util::Component::Identifier(t)]);
-                let iter = self
-                    .db
-                    .iterator_cf(&self.cf, IteratorMode::From(&low_key, Direction::Forward));
-                (prefix, iter)
-            }
-            None => {
-                let prefix = util::build(&[util::Component::Uuid(id)]);
-                let iter = self
-                    .db
-                    .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
-                (prefix, iter)
-            }
-        };
+        Ok(Box::new(Self::iterate(self.iterate_keys_for_root(id, t))))
+    }
 
-        let iter = take_with_prefix(iter, prefix);
-        Ok(Box::new(self.iterate(iter)))
+    // Counts the edges of a vertex by their keys, without decoding them.
+    pub fn count_for_root(&self, id: Uuid, t: Option<models::Identifier>) -> Result<u64> {
+        let mut count = 0;
+        for item in self.iterate_keys_for_root(id, t) {
+            item?;
+            count += 1;
+        }
+        Ok(count)
     }
 
     pub fn iterate_for_range(
-        &'a self,
+        &self,
         first_id: Uuid,
         t: models::Identifier,
         second_id: Uuid,
@@ -262,42 +436,39 @@ impl<'a> EdgeRangeManager<'a> {
             util::Component::Uuid(second_id),
         ]);
         let iter = self
-            .db
+            .txn
             .iterator_cf(&self.cf, IteratorMode::From(&low_key, Direction::Forward));
-        Ok(Box::new(self.iterate(iter)))
+        Ok(Box::new(Self::iterate(iter)))
     }
 
-    pub fn iterate_for_all(&'a self) -> impl Iterator<Item = Result<models::Edge>> + 'a {
-        let iterator = self.db.iterator_cf(&self.cf, IteratorMode::Start);
-        self.iterate(iterator)
+    pub fn iterate_for_all(&self) -> impl Iterator<Item = Result<models::Edge>> + 'a {
+        let iterator = self.txn.iterator_cf(&self.cf, IteratorMode::Start);
+        Self::iterate(iterator)
     }
 
-    pub fn set(&self, batch: &mut WriteBatch, edge: &models::Edge) -> Result<()> {
+    pub fn set(&self, edge: &models::Edge) -> Result<()> {
         let key = self.key(edge);
-        batch.put_cf(&self.cf, &key, []);
+        self.txn.put_cf(&self.cf, &key, [])?;
         Ok(())
     }
 
-    pub fn delete(&self, batch: &mut WriteBatch, edge: &models::Edge) -> Result<()> {
-        batch.delete_cf(&self.cf, self.key(edge));
+    pub fn delete(&self, edge: &models::Edge) -> Result<()> {
+        self.txn.delete_cf(&self.cf, self.key(edge))?;
         Ok(())
     }
-
-    pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
-    }
 }
 
 pub(crate) struct VertexPropertyManager<'a> {
-    db: &'a DB,
+    db: &'a OptimisticTransactionDB,
+    txn: &'a SnapshotTxn<'a>,
     cf: ColumnFamilyRef<'a>,
 }
 
 impl<'a> VertexPropertyManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
         VertexPropertyManager {
             db,
+            txn,
             cf: db.cf_handle("vertex_properties:v2").unwrap(),
         }
     }
@@ -309,14 +480,11 @@ impl<'a> VertexPropertyManager<'a> {
         ])
     }
 
-    pub fn iterate_for_owner(
-        &'a self,
-        vertex_id: Uuid,
-    ) -> Result<impl Iterator<Item = Result<OwnedPropertyItem>> + 'a> {
+    pub fn iterate_for_owner(&self, vertex_id: Uuid) -> Result<impl Iterator<Item = Result<OwnedPropertyItem>> + 'a> {
         let prefix = util::build(&[util::Component::Uuid(vertex_id)]);
 
         let iterator = self
-            .db
+            .txn
             .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
 
         let filtered = take_with_prefix(iterator, prefix);
@@ -333,8 +501,21 @@ impl<'a> VertexPropertyManager<'a> {
         }))
     }
 
+    // Whether a vertex's value for a property belongs in the property's
+    // index. Only looks up the vertex's type if the index is scoped.
+    fn is_indexed(&self, indexes: &Indexes, vertex_id: Uuid, name: models::Identifier) -> Result<bool> {
+        match indexes.properties.get(&name) {
+            Some(scopes) if scopes.contains(&models::IndexScope::All) => Ok(true),
+            Some(scopes) => match VertexManager::new(self.db, self.txn).get(vertex_id)? {
+                Some(t) => Ok(scopes.iter().any(|scope| scope.covers_vertex(t))),
+                None => Ok(false),
+            },
+            None => Ok(false),
+        }
+    }
+
     pub fn get(&self, vertex_id: Uuid, name: models::Identifier) -> Result<Option<models::Json>> {
-        match self.db.get_cf(&self.cf, self.key(vertex_id, name))? {
+        match self.txn.get_cf(&self.cf, self.key(vertex_id, name))? {
             Some(value_bytes) => Ok(Some(serde_json::from_slice(&value_bytes)?)),
             None => Ok(None),
         }
@@ -342,58 +523,180 @@ impl<'a> VertexPropertyManager<'a> {
 
     pub fn set(
         &self,
-        batch: &mut WriteBatch,
-        indexed_properties: &HashSet<models::Identifier>,
+        indexes: &Indexes,
         vertex_id: Uuid,
         name: models::Identifier,
         value: &models::Json,
     ) -> Result<()> {
-        let is_indexed = indexed_properties.contains(&name);
         let key = self.key(vertex_id, name);
-        if is_indexed {
-            self.delete(batch, indexed_properties, vertex_id, name)?;
+        let unique_constraint = self.unique_constraint(indexes, vertex_id, name)?;
+        if let Some(constraint) = unique_constraint {
+            let vertex_unique_value_manager = VertexUniqueValueManager::new(self.db, self.txn);
+            if vertex_unique_value_manager
+                .get(constraint, value)?
+                .is_some_and(|owner_id| owner_id != vertex_id)
+            {
+                return Err(Error::ConstraintViolation);
+            }
+        }
+        if indexes.properties.contains_key(&name)
+            || indexes.has_composite(name)
+            || indexes.has_unique(name)
+            || indexes.fulltext.contains(&name)
+            || indexes.vectors.contains(&name)
+            || indexes.geo.contains(&name)
+        {
+            self.delete(indexes, vertex_id, name)?;
         }
         let value_json = serde_json::to_vec(value)?;
-        batch.put_cf(&self.cf, &key, &value_json);
-        if is_indexed {
-            let vertex_property_value_manager = VertexPropertyValueManager::new(self.db);
-            vertex_property_value_manager.set(batch, vertex_id, name, value);
+        self.txn.put_cf(&self.cf, &key, &value_json)?;
+        if self.is_indexed(indexes, vertex_id, name)? {
+            let vertex_property_value_manager = VertexPropertyValueManager::new(self.db, self.txn);
+            vertex_property_value_manager.set(vertex_id, name, value)?;
+        }
+        let vertex_composite_value_manager = VertexCompositeValueManager::new(self.db, self.txn);
+        for (index_id, values) in self.composite_entries(indexes, vertex_id, name)? {
+            vertex_composite_value_manager.set(index_id, &values, vertex_id)?;
+        }
+        if let Some(constraint) = unique_constraint {
+            let vertex_unique_value_manager = VertexUniqueValueManager::new(self.db, self.txn);
+            vertex_unique_value_manager.set(constraint, value, vertex_id)?;
+        }
+        if indexes.fulltext.contains(&name) {
+            if let Some(text) = value.as_str() {
+                let vertex_property_term_manager = VertexPropertyTermManager::new(self.db, self.txn);
+                for term in util::tokenize(text) {
+                    vertex_property_term_manager.set(name, &term, vertex_id)?;
+                }
+            }
+        }
+        if indexes.vectors.contains(&name) {
+            if let Some(vector) = util::json_to_vector(value) {
+                let vertex_property_vector_manager = VertexPropertyVectorManager::new(self.db, self.txn);
+                vertex_property_vector_manager.set(name, vertex_id, &vector)?;
+            }
+        }
+        if indexes.geo.contains(&name) {
+            if let Some(point) = models::GeoPoint::from_json(value) {
+                let vertex_property_geo_manager = VertexPropertyGeoManager::new(self.db, self.txn);
+                vertex_property_geo_manager.set(name, vertex_id, &point)?;
+            }
         }
         Ok(())
     }
 
-    pub fn delete(
+    pub fn delete(&self, indexes: &Indexes, vertex_id: Uuid, name: models::Identifier) -> Result<()> {
+        // The value is removed from the index regardless of the owner's
+        // type, since the owner may already be deleted
+        if indexes.properties.contains_key(&name) {
+            if let Some(value) = self.get(vertex_id, name)? {
+                let vertex_property_value_manager = VertexPropertyValueManager::new(self.db, self.txn);
+                vertex_property_value_manager.delete(vertex_id, name, &value)?;
+            }
+        }
+        let vertex_composite_value_manager = VertexCompositeValueManager::new(self.db, self.txn);
+        for (index_id, values) in self.composite_entries(indexes, vertex_id, name)? {
+            vertex_composite_value_manager.delete(index_id, &values, vertex_id)?;
+        }
+        if indexes.has_unique(name) {
+            if let Some(value) = self.get(vertex_id, name)? {
+                let vertex_unique_value_manager = VertexUniqueValueManager::new(self.db, self.txn);
+                for constraint in indexes.unique_constraints.iter().filter(|c| c.name == name) {
+                    vertex_unique_value_manager.delete(*constraint, &value, vertex_id)?;
+                }
+            }
+        }
+        if indexes.fulltext.contains(&name) {
+            if let Some(text) = self.get(vertex_id, name)?.as_ref().and_then(|value| value.as_str()) {
+                let vertex_property_term_manager = VertexPropertyTermManager::new(self.db, self.txn);
+                for term in util::tokenize(text) {
+                    vertex_property_term_manager.delete(name, &term, vertex_id)?;
+                }
+            }
+        }
+        if indexes.vectors.contains(&name) {
+            let vertex_property_vector_manager = VertexPropertyVectorManager::new(self.db, self.txn);
+            vertex_property_vector_manager.delete(name, vertex_id)?;
+        }
+        if indexes.geo.contains(&name) {
+            if let Some(point) = self
+                .get(vertex_id, name)?
+                .as_ref()
+                .and_then(models::GeoPoint::from_json)
+            {
+                let vertex_property_geo_manager = VertexPropertyGeoManager::new(self.db, self.txn);
+                vertex_property_geo_manager.delete(name, vertex_id, &point)?;
+            }
+        }
+        self.txn.delete_cf(&self.cf, self.key(vertex_id, name))?;
+        Ok(())
+    }
+
+    // Gets the unique constraint that applies to a vertex's value for a
+    // property, if any. Only looks up the vertex's type if the property is
+    // constrained for some type.
+    fn unique_constraint(
         &self,
-        batch: &mut WriteBatch,
-        indexed_properties: &HashSet<models::Identifier>,
+        indexes: &Indexes,
         vertex_id: Uuid,
         name: models::Identifier,
-    ) -> Result<()> {
-        if indexed_properties.contains(&name) {
-            if let Some(value) = self.get(vertex_id, name)? {
-                let vertex_property_value_manager = VertexPropertyValueManager::new(self.db);
-                vertex_property_value_manager.delete(batch, vertex_id, name, &value);
+    ) -> Result<Option<models::UniqueConstraint>> {
+        if !indexes.has_unique(name) {
+            return Ok(None);
+        }
+        match VertexManager::new(self.db, self.txn).get(vertex_id)? {
+            Some(t) => {
+                let constraint = models::UniqueConstraint::new(t, name);
+                Ok(indexes.unique_constraints.get(&constraint).copied())
             }
+            None => Ok(None),
         }
-        batch.delete_cf(&self.cf, self.key(vertex_id, name));
-        Ok(())
     }
 
-    pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+    // Gets a vertex's values for a list of properties, or `None` if it
+    // doesn't have every one of them.
+    pub fn get_all(&self, vertex_id: Uuid, names: &[models::Identifier]) -> Result<Option<Vec<models::Json>>> {
+        let mut values = Vec::with_capacity(names.len());
+        for name in names {
+            match self.get(vertex_id, *name)? {
+                Some(value) => values.push(value),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(values))
+    }
+
+    // Gets the IDs of the composite indexes that include a property and
+    // that a vertex is in, along with the vertex's values in each.
+    fn composite_entries(
+        &self,
+        indexes: &Indexes,
+        vertex_id: Uuid,
+        name: models::Identifier,
+    ) -> Result<Vec<(Uuid, Vec<models::Json>)>> {
+        let mut entries = Vec::new();
+        for (names, index_id) in &indexes.composites {
+            if names.contains(&name) {
+                if let Some(values) = self.get_all(vertex_id, names)? {
+                    entries.push((*index_id, values));
+                }
+            }
+        }
+        Ok(entries)
     }
 }
 
 pub(crate) struct EdgePropertyManager<'a> {
-    db: &'a DB,
+    db: &'a OptimisticTransactionDB,
+    txn: &'a SnapshotTxn<'a>,
     cf: ColumnFamilyRef<'a>,
 }
 
 impl<'a> EdgePropertyManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
         EdgePropertyManager {
             db,
+            txn,
             cf: db.cf_handle("edge_properties:v2").unwrap(),
         }
     }
@@ -408,7 +711,7 @@ impl<'a> EdgePropertyManager<'a> {
     }
 
     pub fn iterate_for_owner(
-        &'a self,
+        &self,
         edge: &'a models::Edge,
     ) -> Result<Box<dyn Iterator<Item = Result<EdgePropertyItem>> + 'a>> {
         let prefix = util::build(&[
@@ -418,7 +721,7 @@ impl<'a> EdgePropertyManager<'a> {
         ]);
 
         let iterator = self
-            .db
+            .txn
             .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
 
         let filtered = take_with_prefix(iterator, prefix);
@@ -448,7 +751,7 @@ impl<'a> EdgePropertyManager<'a> {
     }
 
     pub fn get(&self, edge: &models::Edge, name: models::Identifier) -> Result<Option<models::Json>> {
-        match self.db.get_cf(&self.cf, self.key(edge, name))? {
+        match self.txn.get_cf(&self.cf, self.key(edge, name))? {
             Some(value_bytes) => Ok(Some(serde_json::from_slice(&value_bytes)?)),
             None => Ok(None),
         }
@@ -456,59 +759,52 @@ impl<'a> EdgePropertyManager<'a> {
 
     pub fn set(
         &self,
-        batch: &mut WriteBatch,
-        indexed_properties: &HashSet<models::Identifier>,
+        indexes: &Indexes,
         edge: &models::Edge,
         name: models::Identifier,
         value: &models::Json,
     ) -> Result<()> {
-        let is_indexed = indexed_properties.contains(&name);
         let key = self.key(edge, name);
-        if is_indexed {
-            self.delete(batch, indexed_properties, edge, name)?;
+        if indexes.properties.contains_key(&name) {
+            self.delete(indexes, edge, name)?;
         }
         let value_json = serde_json::to_vec(value)?;
-        batch.put_cf(&self.cf, &key, &value_json);
-        if is_indexed {
-            let edge_property_value_manager = EdgePropertyValueManager::new(self.db);
-            edge_property_value_manager.set(batch, edge, name, value);
+        self.txn.put_cf(&self.cf, &key, &value_json)?;
+        if indexes
+            .properties
+            .get(&name)
+            .is_some_and(|scopes| scopes.iter().any(|scope| scope.covers_edge(edge.t)))
+        {
+            let edge_property_value_manager = EdgePropertyValueManager::new(self.db, self.txn);
+            edge_property_value_manager.set(edge, name, value)?;
         }
         Ok(())
     }
 
-    pub fn delete(
-        &self,
-        batch: &mut WriteBatch,
-        indexed_properties: &HashSet<models::Identifier>,
-        edge: &models::Edge,
-        name: models::Identifier,
-    ) -> Result<()> {
-        if indexed_properties.contains(&name) {
+    pub fn delete(&self, indexes: &Indexes, edge: &models::Edge, name: models::Identifier) -> Result<()> {
+        if indexes.properties.contains_key(&name) {
             if let Some(value) = self.get(edge, name)? {
-                let edge_property_value_manager = EdgePropertyValueManager::new(self.db);
-                edge_property_value_manager.delete(batch, edge, name, &value);
+                let edge_property_value_manager = EdgePropertyValueManager::new(self.db, self.txn);
+                edge_property_value_manager.delete(edge, name, &value)?;
             }
         }
-        batch.delete_cf(&self.cf, self.key(edge, name));
+        self.txn.delete_cf(&self.cf, self.key(edge, name))?;
         Ok(())
     }
-
-    pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
-    }
 }
 
 pub(crate) struct VertexPropertyValueManager<'a> {
-    db: &'a DB,
+    txn: &'a SnapshotTxn<'a>,
     cf: ColumnFamilyRef<'a>,
+    stat_manager: StatManager<'a>,
 }
 
 impl<'a> VertexPropertyValueManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
         VertexPropertyValueManager {
-            db,
-            cf: db.cf_handle("vertex_property_values:v2").unwrap(),
+            txn,
+            cf: db.cf_handle("vertex_property_values:v3").unwrap(),
+            stat_manager: StatManager::new(db, txn),
         }
     }
 
@@ -520,36 +816,33 @@ impl<'a> VertexPropertyValueManager<'a> {
         ])
     }
 
-    fn iterate(
-        &'a self,
-        iterator: DBIterator<'a>,
-        prefix: Vec<u8>,
-    ) -> impl Iterator<Item = Result<VertexPropertyValueKey>> + 'a {
-        let filtered = take_with_prefix(iterator, prefix);
-
+    fn iterate<I>(filtered: I) -> impl Iterator<Item = Result<VertexPropertyValueKey>> + 'a
+    where
+        I: Iterator<Item = RocksReadResult> + 'a,
+    {
         filtered.map(move |item| -> Result<VertexPropertyValueKey> {
             let (k, _) = item?;
             let mut cursor = Cursor::new(k);
             let name = unsafe { util::read_identifier(&mut cursor)? };
-            let value_hash = util::read_u64(&mut cursor)?;
+            util::skip_json(&mut cursor)?;
             let vertex_id = util::read_uuid(&mut cursor)?;
-            Ok((name, value_hash, vertex_id))
+            Ok((name, vertex_id))
         })
     }
 
     pub fn iterate_for_name(
-        &'a self,
+        &self,
         property_name: models::Identifier,
     ) -> impl Iterator<Item = Result<VertexPropertyValueKey>> + 'a {
         let prefix = util::build(&[util::Component::Identifier(property_name)]);
         let iter = self
-            .db
+            .txn
             .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
-        self.iterate(iter, prefix)
+        Self::iterate(take_with_prefix(iter, prefix))
     }
 
     pub fn iterate_for_value(
-        &'a self,
+        &self,
         property_name: models::Identifier,
         property_value: &models::Json,
     ) -> impl Iterator<Item = Result<VertexPropertyValueKey>> + 'a {
@@ -558,49 +851,70 @@ impl<'a> VertexPropertyValueManager<'a> {
             util::Component::Json(property_value),
         ]);
         let iter = self
-            .db
+            .txn
             .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
-        self.iterate(iter, prefix)
+        Self::iterate(take_with_prefix(iter, prefix))
     }
 
-    pub fn set(
+    pub fn iterate_for_range(
         &self,
-        batch: &mut WriteBatch,
-        vertex_id: Uuid,
         property_name: models::Identifier,
-        property_value: &models::Json,
-    ) {
+        range: &util::JsonRange,
+    ) -> impl Iterator<Item = Result<VertexPropertyValueKey>> + 'a {
+        let prefix = util::build(&[util::Component::Identifier(property_name)]);
+        let (start, end) = property_value_range_keys(&prefix, range);
+        let iter = self
+            .txn
+            .iterator_cf(&self.cf, IteratorMode::From(&start, Direction::Forward));
+        Self::iterate(take_within_range(take_with_prefix(iter, prefix), end))
+    }
+
+    // Entries are checked for before being set or deleted, so that the
+    // counters of each value only change when the index does
+    pub fn set(&self, vertex_id: Uuid, property_name: models::Identifier, property_value: &models::Json) -> Result<()> {
         let key = self.key(property_name, property_value, vertex_id);
-        batch.put_cf(&self.cf, key, []);
+        if self.txn.get_cf(&self.cf, &key)?.is_none() {
+            self.txn.put_cf(&self.cf, key, [])?;
+            self.stat_manager
+                .add_vertex_property_value(property_name, property_value, 1)?;
+        }
+        Ok(())
     }
 
     pub fn delete(
         &self,
-        batch: &mut WriteBatch,
         vertex_id: Uuid,
         property_name: models::Identifier,
         property_value: &models::Json,
-    ) {
+    ) -> Result<()> {
         let key = self.key(property_name, property_value, vertex_id);
-        batch.delete_cf(&self.cf, key);
+        if self.txn.get_cf(&self.cf, &key)?.is_some() {
+            self.txn.delete_cf(&self.cf, key)?;
+            self.stat_manager
+                .add_vertex_property_value(property_name, property_value, -1)?;
+        }
+        Ok(())
     }
 
-    pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+    pub fn delete_for_name(&self, property_name: models::Identifier) -> Result<()> {
+        self.stat_manager.delete_for_vertex_property(property_name)?;
+        let prefix = util::build(&[util::Component::Identifier(property_name)]);
+        delete_with_prefix(self.txn, &self.cf, prefix)
     }
 }
 
 pub(crate) struct EdgePropertyValueManager<'a> {
-    db: &'a DB,
+    txn: &'a SnapshotTxn<'a>,
     cf: ColumnFamilyRef<'a>,
+    stat_manager: StatManager<'a>,
 }
 
 impl<'a> EdgePropertyValueManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
         EdgePropertyValueManager {
-            db,
-            cf: db.cf_handle("edge_property_values:v2").unwrap(),
+            txn,
+            cf: db.cf_handle("edge_property_values:v3").unwrap(),
+            stat_manager: StatManager::new(db, txn),
         }
     }
 
@@ -614,38 +928,35 @@ impl<'a> EdgePropertyValueManager<'a> {
         ])
     }
 
-    fn iterate(
-        &'a self,
-        iterator: DBIterator<'a>,
-        prefix: Vec<u8>,
-    ) -> impl Iterator<Item = Result<EdgePropertyValueKey>> + 'a {
-        let filtered = take_with_prefix(iterator, prefix);
-
+    fn iterate<I>(filtered: I) -> impl Iterator<Item = Result<EdgePropertyValueKey>> + 'a
+    where
+        I: Iterator<Item = RocksReadResult> + 'a,
+    {
         filtered.map(move |item| -> Result<EdgePropertyValueKey> {
             let (k, _) = item?;
             let mut cursor = Cursor::new(k);
             let name = unsafe { util::read_identifier(&mut cursor)? };
-            let value_hash = util::read_u64(&mut cursor)?;
+            util::skip_json(&mut cursor)?;
             let out_id = util::read_uuid(&mut cursor)?;
             let t = unsafe { util::read_identifier(&mut cursor)? };
             let in_id = util::read_uuid(&mut cursor)?;
-            Ok((name, value_hash, models::Edge::new(out_id, t, in_id)))
+            Ok((name, models::Edge::new(out_id, t, in_id)))
         })
     }
 
     pub fn iterate_for_name(
-        &'a self,
+        &self,
         property_name: models::Identifier,
     ) -> impl Iterator<Item = Result<EdgePropertyValueKey>> + 'a {
         let prefix = util::build(&[util::Component::Identifier(property_name)]);
         let iter = self
-            .db
+            .txn
             .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
-        self.iterate(iter, prefix)
+        Self::iterate(take_with_prefix(iter, prefix))
     }
 
     pub fn iterate_for_value(
-        &'a self,
+        &self,
         property_name: models::Identifier,
         property_value: &models::Json,
     ) -> impl Iterator<Item = Result<EdgePropertyValueKey>> + 'a {
@@ -654,67 +965,747 @@ impl<'a> EdgePropertyValueManager<'a> {
             util::Component::Json(property_value),
         ]);
         let iter = self
-            .db
+            .txn
             .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
-        self.iterate(iter, prefix)
+        Self::iterate(take_with_prefix(iter, prefix))
+    }
+
+    pub fn iterate_for_range(
+        &self,
+        property_name: models::Identifier,
+        range: &util::JsonRange,
+    ) -> impl Iterator<Item = Result<EdgePropertyValueKey>> + 'a {
+        let prefix = util::build(&[util::Component::Identifier(property_name)]);
+        let (start, end) = property_value_range_keys(&prefix, range);
+        let iter = self
+            .txn
+            .iterator_cf(&self.cf, IteratorMode::From(&start, Direction::Forward));
+        Self::iterate(take_within_range(take_with_prefix(iter, prefix), end))
     }
 
     pub fn set(
         &self,
-        batch: &mut WriteBatch,
         edge: &models::Edge,
         property_name: models::Identifier,
         property_value: &models::Json,
-    ) {
+    ) -> Result<()> {
         let key = self.key(property_name, property_value, edge);
-        batch.put_cf(&self.cf, key, []);
+        if self.txn.get_cf(&self.cf, &key)?.is_none() {
+            self.txn.put_cf(&self.cf, key, [])?;
+            self.stat_manager
+                .add_edge_property_value(property_name, property_value, 1)?;
+        }
+        Ok(())
     }
 
     pub fn delete(
         &self,
-        batch: &mut WriteBatch,
         edge: &models::Edge,
         property_name: models::Identifier,
         property_value: &models::Json,
-    ) {
+    ) -> Result<()> {
         let key = self.key(property_name, property_value, edge);
-        batch.delete_cf(&self.cf, key);
+        if self.txn.get_cf(&self.cf, &key)?.is_some() {
+            self.txn.delete_cf(&self.cf, key)?;
+            self.stat_manager
+                .add_edge_property_value(property_name, property_value, -1)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_for_name(&self, property_name: models::Identifier) -> Result<()> {
+        self.stat_manager.delete_for_edge_property(property_name)?;
+        let prefix = util::build(&[util::Component::Identifier(property_name)]);
+        delete_with_prefix(self.txn, &self.cf, prefix)
+    }
+}
+
+pub(crate) struct VertexCompositeValueManager<'a> {
+    txn: &'a SnapshotTxn<'a>,
+    cf: ColumnFamilyRef<'a>,
+}
+
+impl<'a> VertexCompositeValueManager<'a> {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
+        VertexCompositeValueManager {
+            txn,
+            cf: db.cf_handle("vertex_composite_values:v1").unwrap(),
+        }
+    }
+
+    fn prefix(&self, index_id: Uuid, property_values: &[models::Json]) -> Vec<u8> {
+        let mut components = vec![util::Component::Uuid(index_id)];
+        components.extend(property_values.iter().map(util::Component::Json));
+        util::build(&components)
+    }
+
+    fn key(&self, index_id: Uuid, property_values: &[models::Json], vertex_id: Uuid) -> Vec<u8> {
+        let mut key = self.prefix(index_id, property_values);
+        key.extend(util::build(&[util::Component::Uuid(vertex_id)]));
+        key
+    }
+
+    pub fn iterate_for_values(
+        &self,
+        index_id: Uuid,
+        property_values: &[models::Json],
+    ) -> impl Iterator<Item = Result<Uuid>> + 'a {
+        let prefix = self.prefix(index_id, property_values);
+        let iter = self
+            .txn
+            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        take_with_prefix(iter, prefix).map(move |item| -> Result<Uuid> {
+            let (k, _) = item?;
+            // The vertex ID is always the last component
+            let offset = k.len() - 16;
+            let mut cursor = Cursor::new(k);
+            cursor.set_position(offset as u64);
+            util::read_uuid(&mut cursor)
+        })
+    }
+
+    pub fn set(&self, index_id: Uuid, property_values: &[models::Json], vertex_id: Uuid) -> Result<()> {
+        let key = self.key(index_id, property_values, vertex_id);
+        self.txn.put_cf(&self.cf, key, [])?;
+        Ok(())
+    }
+
+    pub fn delete(&self, index_id: Uuid, property_values: &[models::Json], vertex_id: Uuid) -> Result<()> {
+        let key = self.key(index_id, property_values, vertex_id);
+        self.txn.delete_cf(&self.cf, key)?;
+        Ok(())
     }
 
-    pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+    pub fn delete_for_index(&self, index_id: Uuid) -> Result<()> {
+        let prefix = util::build(&[util::Component::Uuid(index_id)]);
+        delete_with_prefix(self.txn, &self.cf, prefix)
+    }
+}
+
+pub(crate) struct VertexPropertyTermManager<'a> {
+    txn: &'a SnapshotTxn<'a>,
+    cf: ColumnFamilyRef<'a>,
+}
+
+impl<'a> VertexPropertyTermManager<'a> {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
+        VertexPropertyTermManager {
+            txn,
+            cf: db.cf_handle("vertex_property_terms:v1").unwrap(),
+        }
+    }
+
+    fn prefix(&self, property_name: models::Identifier, term: &str) -> Vec<u8> {
+        // Terms are encoded as JSON strings, so that none is a prefix of
+        // another
+        let term = models::Json::new(serde_json::Value::String(term.to_string()));
+        util::build(&[util::Component::Identifier(property_name), util::Component::Json(&term)])
+    }
+
+    fn key(&self, property_name: models::Identifier, term: &str, vertex_id: Uuid) -> Vec<u8> {
+        let mut key = self.prefix(property_name, term);
+        key.extend(util::build(&[util::Component::Uuid(vertex_id)]));
+        key
+    }
+
+    pub fn iterate_for_term(
+        &self,
+        property_name: models::Identifier,
+        term: &str,
+    ) -> impl Iterator<Item = Result<Uuid>> + 'a {
+        let prefix = self.prefix(property_name, term);
+        let iter = self
+            .txn
+            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        take_with_prefix(iter, prefix.clone()).map(move |item| -> Result<Uuid> {
+            let (k, _) = item?;
+            let mut cursor = Cursor::new(k);
+            cursor.set_position(prefix.len() as u64);
+            util::read_uuid(&mut cursor)
+        })
+    }
+
+    pub fn set(&self, property_name: models::Identifier, term: &str, vertex_id: Uuid) -> Result<()> {
+        let key = self.key(property_name, term, vertex_id);
+        self.txn.put_cf(&self.cf, key, [])?;
+        Ok(())
+    }
+
+    pub fn delete(&self, property_name: models::Identifier, term: &str, vertex_id: Uuid) -> Result<()> {
+        let key = self.key(property_name, term, vertex_id);
+        self.txn.delete_cf(&self.cf, key)?;
+        Ok(())
+    }
+
+    pub fn delete_for_name(&self, property_name: models::Identifier) -> Result<()> {
+        let prefix = util::build(&[util::Component::Identifier(property_name)]);
+        delete_with_prefix(self.txn, &self.cf, prefix)
+    }
+}
+
+pub(crate) struct VertexPropertyVectorManager<'a> {
+    txn: &'a SnapshotTxn<'a>,
+    cf: ColumnFamilyRef<'a>,
+}
+
+impl<'a> VertexPropertyVectorManager<'a> {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
+        VertexPropertyVectorManager {
+            txn,
+            cf: db.cf_handle("vertex_property_vectors:v1").unwrap(),
+        }
+    }
+
+    fn key(&self, property_name: models::Identifier, vertex_id: Uuid) -> Vec<u8> {
+        util::build(&[
+            util::Component::Identifier(property_name),
+            util::Component::Uuid(vertex_id),
+        ])
+    }
+
+    pub fn iterate_for_name(
+        &self,
+        property_name: models::Identifier,
+    ) -> impl Iterator<Item = Result<VertexVector>> + 'a {
+        let prefix = util::build(&[util::Component::Identifier(property_name)]);
+        let iter = self
+            .txn
+            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        take_with_prefix(iter, prefix.clone()).map(move |item| -> Result<VertexVector> {
+            let (k, v) = item?;
+            let mut cursor = Cursor::new(k);
+            cursor.set_position(prefix.len() as u64);
+            let vertex_id = util::read_uuid(&mut cursor)?;
+            // Vectors are stored as a run of big-endian floats
+            let vector = v
+                .chunks_exact(8)
+                .map(|chunk| f64::from_be_bytes(chunk.try_into().unwrap()))
+                .collect();
+            Ok((vertex_id, vector))
+        })
+    }
+
+    pub fn set(&self, property_name: models::Identifier, vertex_id: Uuid, vector: &[f64]) -> Result<()> {
+        let key = self.key(property_name, vertex_id);
+        let value: Vec<u8> = vector.iter().flat_map(|c| c.to_be_bytes()).collect();
+        self.txn.put_cf(&self.cf, key, value)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, property_name: models::Identifier, vertex_id: Uuid) -> Result<()> {
+        let key = self.key(property_name, vertex_id);
+        self.txn.delete_cf(&self.cf, key)?;
+        Ok(())
+    }
+
+    pub fn delete_for_name(&self, property_name: models::Identifier) -> Result<()> {
+        let prefix = util::build(&[util::Component::Identifier(property_name)]);
+        delete_with_prefix(self.txn, &self.cf, prefix)
+    }
+}
+
+pub(crate) struct VertexPropertyGeoManager<'a> {
+    txn: &'a SnapshotTxn<'a>,
+    cf: ColumnFamilyRef<'a>,
+}
+
+impl<'a> VertexPropertyGeoManager<'a> {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
+        VertexPropertyGeoManager {
+            txn,
+            cf: db.cf_handle("vertex_property_geo:v1").unwrap(),
+        }
+    }
+
+    // Points are keyed by the cell they fall in, so that the points in an
+    // area can be found by scanning the ranges of cells that cover it
+    fn key(&self, property_name: models::Identifier, point: &models::GeoPoint, vertex_id: Uuid) -> Vec<u8> {
+        let mut key = util::build(&[util::Component::Identifier(property_name)]);
+        key.extend_from_slice(&util::geo_cell(point).to_be_bytes());
+        key.extend_from_slice(vertex_id.as_bytes());
+        key
+    }
+
+    pub fn iterate_within(
+        &self,
+        property_name: models::Identifier,
+        bbox: &models::BoundingBox,
+    ) -> Result<Vec<VertexPoint>> {
+        let prefix = util::build(&[util::Component::Identifier(property_name)]);
+        let mut points = Vec::new();
+        for (start, end) in util::geo_cell_ranges(bbox) {
+            let mut start_key = prefix.clone();
+            start_key.extend_from_slice(&start.to_be_bytes());
+            let iter = self
+                .txn
+                .iterator_cf(&self.cf, IteratorMode::From(&start_key, Direction::Forward));
+            for item in take_with_prefix(iter, prefix.clone()) {
+                let (k, v) = item?;
+                let mut cursor = Cursor::new(k);
+                cursor.set_position(prefix.len() as u64);
+                if util::read_u64(&mut cursor)? > end {
+                    break;
+                }
+                let vertex_id = util::read_uuid(&mut cursor)?;
+                let point = models::GeoPoint {
+                    lat: f64::from_be_bytes(v[0..8].try_into().unwrap()),
+                    lon: f64::from_be_bytes(v[8..16].try_into().unwrap()),
+                };
+                if bbox.contains(&point) {
+                    points.push((vertex_id, point));
+                }
+            }
+        }
+        Ok(points)
+    }
+
+    pub fn set(&self, property_name: models::Identifier, vertex_id: Uuid, point: &models::GeoPoint) -> Result<()> {
+        let key = self.key(property_name, point, vertex_id);
+        let mut value = point.lat.to_be_bytes().to_vec();
+        value.extend_from_slice(&point.lon.to_be_bytes());
+        self.txn.put_cf(&self.cf, key, value)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, property_name: models::Identifier, vertex_id: Uuid, point: &models::GeoPoint) -> Result<()> {
+        let key = self.key(property_name, point, vertex_id);
+        self.txn.delete_cf(&self.cf, key)?;
+        Ok(())
+    }
+
+    pub fn delete_for_name(&self, property_name: models::Identifier) -> Result<()> {
+        let prefix = util::build(&[util::Component::Identifier(property_name)]);
+        delete_with_prefix(self.txn, &self.cf, prefix)
+    }
+}
+
+pub(crate) struct VertexUniqueValueManager<'a> {
+    txn: &'a SnapshotTxn<'a>,
+    cf: ColumnFamilyRef<'a>,
+}
+
+impl<'a> VertexUniqueValueManager<'a> {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
+        VertexUniqueValueManager {
+            txn,
+            cf: db.cf_handle("vertex_unique_values:v1").unwrap(),
+        }
+    }
+
+    fn key(&self, constraint: models::UniqueConstraint, property_value: &models::Json) -> Vec<u8> {
+        util::build(&[
+            util::Component::Identifier(constraint.t),
+            util::Component::Identifier(constraint.name),
+            util::Component::Json(property_value),
+        ])
+    }
+
+    pub fn get(&self, constraint: models::UniqueConstraint, property_value: &models::Json) -> Result<Option<Uuid>> {
+        // Read for update, so that two transactions can't concurrently
+        // claim the same value
+        let key = self.key(constraint, property_value);
+        match self.txn.get_for_update_cf(&self.cf, key, true)? {
+            Some(value_bytes) => Ok(Some(util::read_uuid(&mut Cursor::new(value_bytes))?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set(
+        &self,
+        constraint: models::UniqueConstraint,
+        property_value: &models::Json,
+        vertex_id: Uuid,
+    ) -> Result<()> {
+        let key = self.key(constraint, property_value);
+        self.txn.put_cf(&self.cf, key, vertex_id.as_bytes())?;
+        Ok(())
+    }
+
+    // Deletes the value, but only if it belongs to the given vertex.
+    pub fn delete(
+        &self,
+        constraint: models::UniqueConstraint,
+        property_value: &models::Json,
+        vertex_id: Uuid,
+    ) -> Result<()> {
+        if self.get(constraint, property_value)? == Some(vertex_id) {
+            self.txn.delete_cf(&self.cf, self.key(constraint, property_value))?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_for_constraint(&self, constraint: models::UniqueConstraint) -> Result<()> {
+        let prefix = util::build(&[
+            util::Component::Identifier(constraint.t),
+            util::Component::Identifier(constraint.name),
+        ]);
+        delete_with_prefix(self.txn, &self.cf, prefix)
     }
 }
 
 pub(crate) struct MetadataManager<'a> {
-    db: &'a DB,
+    txn: &'a SnapshotTxn<'a>,
     cf: ColumnFamilyRef<'a>,
 }
 
 impl<'a> MetadataManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
         MetadataManager {
-            db,
+            txn,
             cf: db.cf_handle("metadata:v2").unwrap(),
         }
     }
 
-    pub fn get_indexed_properties(&self) -> Result<HashSet<models::Identifier>> {
-        match self.db.get_cf(&self.cf, "indexed_properties")? {
+    pub fn get_indexed_properties(&self) -> Result<IndexedProperties> {
+        // Read for update, so that the transaction conflicts with any other
+        // that concurrently changes which properties are indexed
+        if let Some(value_bytes) = self.txn.get_for_update_cf(&self.cf, "property_indexes", true)? {
+            return Ok(bincode::deserialize(&value_bytes)?);
+        }
+
+        // Databases from before index scopes were introduced only store the
+        // names of indexed properties, each of which covers everything
+        match self.txn.get_for_update_cf(&self.cf, "indexed_properties", true)? {
+            Some(value_bytes) => {
+                let names: HashSet<models::Identifier> = bincode::deserialize(&value_bytes)?;
+                Ok(names
+                    .into_iter()
+                    .map(|name| (name, HashSet::from([models::IndexScope::All])))
+                    .collect())
+            }
+            None => Ok(IndexedProperties::default()),
+        }
+    }
+
+    pub fn set_indexed_properties(&self, indices: &IndexedProperties) -> Result<()> {
+        let value_bytes = bincode::serialize(&indices)?;
+        self.txn.put_cf(&self.cf, "property_indexes", &value_bytes)?;
+        self.txn.delete_cf(&self.cf, "indexed_properties")?;
+        Ok(())
+    }
+
+    pub fn get_composite_indexes(&self) -> Result<CompositeIndexes> {
+        match self.txn.get_for_update_cf(&self.cf, "composite_indexes", true)? {
+            Some(value_bytes) => Ok(bincode::deserialize(&value_bytes)?),
+            None => Ok(CompositeIndexes::default()),
+        }
+    }
+
+    pub fn set_composite_indexes(&self, indices: &CompositeIndexes) -> Result<()> {
+        let value_bytes = bincode::serialize(&indices)?;
+        self.txn.put_cf(&self.cf, "composite_indexes", &value_bytes)?;
+        Ok(())
+    }
+
+    // Locks a vertex property value by reading it for update and writing a
+    // tombstone, so that any other transaction that locks it after this one
+    // started fails to commit. Nothing is left behind in the metadata.
+    pub fn lock_vertex_property_value(
+        &self,
+        t: models::Identifier,
+        name: models::Identifier,
+        value: &models::Json,
+    ) -> Result<()> {
+        let mut key = b"vertex_property_value_lock:".to_vec();
+        key.extend(util::build(&[
+            util::Component::Identifier(t),
+            util::Component::Identifier(name),
+            util::Component::Json(value),
+        ]));
+        self.txn.get_for_update_cf(&self.cf, &key, true)?;
+        self.txn.delete_cf(&self.cf, &key)?;
+        Ok(())
+    }
+
+    pub fn get_fulltext_indexes(&self) -> Result<FulltextIndexes> {
+        match self.txn.get_for_update_cf(&self.cf, "fulltext_indexes", true)? {
+            Some(value_bytes) => Ok(bincode::deserialize(&value_bytes)?),
+            None => Ok(FulltextIndexes::default()),
+        }
+    }
+
+    pub fn set_fulltext_indexes(&self, indices: &FulltextIndexes) -> Result<()> {
+        let value_bytes = bincode::serialize(&indices)?;
+        self.txn.put_cf(&self.cf, "fulltext_indexes", &value_bytes)?;
+        Ok(())
+    }
+
+    pub fn get_vector_indexes(&self) -> Result<VectorIndexes> {
+        match self.txn.get_for_update_cf(&self.cf, "vector_indexes", true)? {
             Some(value_bytes) => Ok(bincode::deserialize(&value_bytes)?),
-            None => Ok(HashSet::default()),
+            None => Ok(VectorIndexes::default()),
         }
     }
 
-    pub fn set_indexed_properties(&self, batch: &mut WriteBatch, indices: &HashSet<models::Identifier>) -> Result<()> {
+    pub fn set_vector_indexes(&self, indices: &VectorIndexes) -> Result<()> {
         let value_bytes = bincode::serialize(&indices)?;
-        batch.put_cf(&self.cf, "indexed_properties", &value_bytes);
+        self.txn.put_cf(&self.cf, "vector_indexes", &value_bytes)?;
         Ok(())
     }
 
-    pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+    pub fn get_geo_indexes(&self) -> Result<GeoIndexes> {
+        match self.txn.get_for_update_cf(&self.cf, "geo_indexes", true)? {
+            Some(value_bytes) => Ok(bincode::deserialize(&value_bytes)?),
+            None => Ok(GeoIndexes::default()),
+        }
     }
+
+    pub fn set_geo_indexes(&self, indices: &GeoIndexes) -> Result<()> {
+        let value_bytes = bincode::serialize(&indices)?;
+        self.txn.put_cf(&self.cf, "geo_indexes", &value_bytes)?;
+        Ok(())
+    }
+
+    pub fn get_stats_built(&self) -> Result<bool> {
+        Ok(self.txn.get_cf(&self.cf, "stats_built")?.is_some())
+    }
+
+    pub fn set_stats_built(&self) -> Result<()> {
+        self.txn.put_cf(&self.cf, "stats_built", [])?;
+        Ok(())
+    }
+
+    pub fn get_unique_constraints(&self) -> Result<UniqueConstraints> {
+        match self.txn.get_for_update_cf(&self.cf, "unique_constraints", true)? {
+            Some(value_bytes) => Ok(bincode::deserialize(&value_bytes)?),
+            None => Ok(UniqueConstraints::default()),
+        }
+    }
+
+    pub fn set_unique_constraints(&self, constraints: &UniqueConstraints) -> Result<()> {
+        let value_bytes = bincode::serialize(&constraints)?;
+        self.txn.put_cf(&self.cf, "unique_constraints", &value_bytes)?;
+        Ok(())
+    }
+}
+
+// Prefixes of the keys of each kind of counter in the stats, which are all
+// the same length so that none is a prefix of another
+const VERTEX_TYPE_COUNTER: &str = "vt";
+const EDGE_TYPE_COUNTER: &str = "et";
+const VERTEX_PROPERTY_VALUE_COUNTER: &str = "vp";
+const EDGE_PROPERTY_VALUE_COUNTER: &str = "ep";
+
+/// Maintains counters of the vertices and edges of each type, and of the
+/// vertices and edges with each indexed property value.
+///
+/// Rather than each transaction updating a counter in place, which would
+/// make every pair of concurrent transactions that touch the same type
+/// conflict, a transaction's changes to the counters are collected and
+/// written under a key suffixed by the transaction's ID when it commits. A
+/// counter is the sum of all of the entries under its key. The entries are
+/// folded into one, suffixed by the nil UUID, when the database is opened or
+/// synced, and by the commit that takes the number of entries written since
+/// the last fold past a threshold.
+pub(crate) struct StatManager<'a> {
+    txn: &'a SnapshotTxn<'a>,
+    cf: ColumnFamilyRef<'a>,
+}
+
+impl<'a> StatManager<'a> {
+    pub fn new(db: &'a OptimisticTransactionDB, txn: &'a SnapshotTxn<'a>) -> Self {
+        StatManager {
+            txn,
+            cf: db.cf_handle("stats:v1").unwrap(),
+        }
+    }
+
+    fn type_key(kind: &str, t: models::Identifier) -> Vec<u8> {
+        util::build(&[util::Component::FixedLengthString(kind), util::Component::Identifier(t)])
+    }
+
+    fn property_value_key(kind: &str, name: models::Identifier, value: &models::Json) -> Vec<u8> {
+        util::build(&[
+            util::Component::FixedLengthString(kind),
+            util::Component::Identifier(name),
+            util::Component::Json(value),
+        ])
+    }
+
+    fn add(&self, key: Vec<u8>, payload: impl FnOnce() -> Result<Vec<u8>>, delta: i64) -> Result<()> {
+        let mut counter_deltas = self.txn.counter_deltas.borrow_mut();
+        match counter_deltas.get_mut(&key) {
+            Some((total, _)) => *total += delta,
+            None => {
+                counter_deltas.insert(key, (delta, payload()?));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_vertex_type(&self, t: models::Identifier, delta: i64) -> Result<()> {
+        self.add(Self::type_key(VERTEX_TYPE_COUNTER, t), || Ok(Vec::new()), delta)
+    }
+
+    pub fn add_edge_type(&self, t: models::Identifier, delta: i64) -> Result<()> {
+        self.add(Self::type_key(EDGE_TYPE_COUNTER, t), || Ok(Vec::new()), delta)
+    }
+
+    pub fn add_vertex_property_value(&self, name: models::Identifier, value: &models::Json, delta: i64) -> Result<()> {
+        let key = Self::property_value_key(VERTEX_PROPERTY_VALUE_COUNTER, name, value);
+        self.add(key, || Ok(serde_json::to_vec(value)?), delta)
+    }
+
+    pub fn add_edge_property_value(&self, name: models::Identifier, value: &models::Json, delta: i64) -> Result<()> {
+        let key = Self::property_value_key(EDGE_PROPERTY_VALUE_COUNTER, name, value);
+        self.add(key, || Ok(serde_json::to_vec(value)?), delta)
+    }
+
+    // Sums the counters whose keys start with a prefix, including the
+    // transaction's own changes to them.
+    fn counters(&self, prefix: Vec<u8>) -> Result<CounterDeltas> {
+        let mut counters = CounterDeltas::new();
+        let iter = self
+            .txn
+            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        for item in take_with_prefix(iter, prefix.clone()) {
+            let (k, v) = item?;
+            let key = k[..k.len() - 16].to_vec();
+            let mut cursor = Cursor::new(&v[..8]);
+            let delta = util::read_u64(&mut cursor)? as i64;
+            let (total, _) = counters.entry(key).or_insert_with(|| (0, v[8..].to_vec()));
+            *total += delta;
+        }
+        let counter_deltas = self.txn.counter_deltas.borrow();
+        for (key, (delta, payload)) in counter_deltas.range(prefix.clone()..) {
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            let (total, _) = counters.entry(key.clone()).or_insert_with(|| (0, payload.clone()));
+            *total += delta;
+        }
+        Ok(counters)
+    }
+
+    fn total(&self, prefix: Vec<u8>) -> Result<u64> {
+        let counters = self.counters(prefix)?;
+        Ok(counters.values().map(|(total, _)| *total).sum::<i64>().max(0) as u64)
+    }
+
+    pub fn vertex_count(&self) -> Result<u64> {
+        self.total(util::build(&[util::Component::FixedLengthString(VERTEX_TYPE_COUNTER)]))
+    }
+
+    pub fn edge_count(&self) -> Result<u64> {
+        self.total(util::build(&[util::Component::FixedLengthString(EDGE_TYPE_COUNTER)]))
+    }
+
+    pub fn vertex_property_value_count(&self, name: models::Identifier, value: &models::Json) -> Result<u64> {
+        self.total(Self::property_value_key(VERTEX_PROPERTY_VALUE_COUNTER, name, value))
+    }
+
+    pub fn edge_property_value_count(&self, name: models::Identifier, value: &models::Json) -> Result<u64> {
+        self.total(Self::property_value_key(EDGE_PROPERTY_VALUE_COUNTER, name, value))
+    }
+
+    pub fn stats(&self) -> Result<models::Stats> {
+        let mut stats = models::Stats::default();
+        let mut property_value_counts: BTreeMap<(models::Identifier, Vec<u8>), models::PropertyValueCount> =
+            BTreeMap::new();
+        for (key, (total, payload)) in self.counters(Vec::new())? {
+            if total <= 0 {
+                continue;
+            }
+            let total = total as u64;
+            let mut cursor = Cursor::new(&key[2..]);
+            let name = unsafe { util::read_identifier(&mut cursor)? };
+            let kind = &key[..2];
+            if kind == VERTEX_TYPE_COUNTER.as_bytes() {
+                stats.vertex_count += total;
+                stats.vertex_type_counts.insert(name, total);
+            } else if kind == EDGE_TYPE_COUNTER.as_bytes() {
+                stats.edge_count += total;
+                stats.edge_type_counts.insert(name, total);
+            } else {
+                // Keys are ordered by the value's encoding after the name
+                let value_key = key[2 + cursor.position() as usize..].to_vec();
+                let count = match property_value_counts.entry((name, value_key)) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(models::PropertyValueCount {
+                        name,
+                        value: serde_json::from_slice(&payload)?,
+                        vertex_count: 0,
+                        edge_count: 0,
+                    }),
+                };
+                if kind == VERTEX_PROPERTY_VALUE_COUNTER.as_bytes() {
+                    count.vertex_count = total;
+                } else {
+                    count.edge_count = total;
+                }
+            }
+        }
+        stats.property_value_counts = property_value_counts.into_values().collect();
+        Ok(stats)
+    }
+
+    // Writes the transaction's changes to the counters.
+    fn write_counter_deltas(&self) -> Result<()> {
+        let counter_deltas = self.txn.counter_deltas.take();
+        for (mut key, (delta, payload)) in counter_deltas {
+            if delta != 0 {
+                key.extend(self.txn.id.as_bytes());
+                self.txn.put_cf(&self.cf, &key, counter_value(delta, &payload))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Folds the entries of each counter into one, so that reading them
+    /// stays cheap. Counters that sum to zero are removed.
+    pub fn fold(&self) -> Result<()> {
+        let keys = self
+            .txn
+            .iterator_cf(&self.cf, IteratorMode::Start)
+            .map(|item| Ok(item?.0))
+            .collect::<Result<Vec<Box<[u8]>>>>()?;
+        let counters = self.counters(Vec::new())?;
+        for key in keys {
+            self.txn.delete_cf(&self.cf, key)?;
+        }
+        for (mut key, (total, payload)) in counters {
+            if total != 0 {
+                key.extend(Uuid::nil().as_bytes());
+                self.txn.put_cf(&self.cf, &key, counter_value(total, &payload))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes every counter, for when they're rebuilt.
+    pub fn clear(&self) -> Result<()> {
+        self.txn.counter_deltas.borrow_mut().clear();
+        delete_with_prefix(self.txn, &self.cf, Vec::new())
+    }
+
+    fn delete_for_property(&self, kind: &str, name: models::Identifier) -> Result<()> {
+        let prefix = util::build(&[
+            util::Component::FixedLengthString(kind),
+            util::Component::Identifier(name),
+        ]);
+        self.txn
+            .counter_deltas
+            .borrow_mut()
+            .retain(|key, _| !key.starts_with(&prefix));
+        delete_with_prefix(self.txn, &self.cf, prefix)
+    }
+
+    /// Removes the counters of a property's values for vertices, for when
+    /// its index is removed or rebuilt.
+    pub fn delete_for_vertex_property(&self, name: models::Identifier) -> Result<()> {
+        self.delete_for_property(VERTEX_PROPERTY_VALUE_COUNTER, name)
+    }
+
+    /// Removes the counters of a property's values for edges, for when its
+    /// index is removed or rebuilt.
+    pub fn delete_for_edge_property(&self, name: models::Identifier) -> Result<()> {
+        self.delete_for_property(EDGE_PROPERTY_VALUE_COUNTER, name)
+    }
+}
+
+fn counter_value(total: i64, payload: &[u8]) -> Vec<u8> {
+    let mut value = Vec::with_capacity(8 + payload.len());
+    value.extend(total.to_be_bytes());
+    value.extend(payload);
+    value
 }
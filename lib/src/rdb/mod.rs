@@ -28,6 +28,15 @@ mod tests {
         RocksdbDatastore::new_db_with_options(path, &RocksdbDatastore::get_options(Some(1))).unwrap()
     });
 
+    #[cfg(feature = "test-suite")]
+    rollback_test_impl!({
+        use super::RocksdbDatastore;
+        use tempfile::tempdir;
+
+        let path = tempdir().unwrap().into_path();
+        RocksdbDatastore::new_db_with_options(path, &RocksdbDatastore::get_options(Some(1))).unwrap()
+    });
+
     #[test]
     fn should_repair() {
         use super::RocksdbDatastore;
@@ -73,4 +82,286 @@ mod tests {
             assert_eq!(edge.outbound_id, out_v.id);
         }
     }
+
+    #[test]
+    fn should_read_from_a_consistent_snapshot() {
+        use crate::{Datastore, Transaction};
+
+        let path = tempdir().unwrap().into_path();
+        let db: crate::Database<crate::RocksdbDatastore> = crate::RocksdbDatastore::new_db(&path).unwrap();
+        let t = crate::Identifier::new("person").unwrap();
+        db.create_vertex_from_type(t).unwrap();
+
+        // Writes that land after a transaction starts should not be visible
+        // to it
+        let txn = db.datastore.transaction();
+        db.create_vertex_from_type(t).unwrap();
+        assert_eq!(txn.vertex_count(), 1);
+        assert_eq!(txn.all_vertices().unwrap().count(), 1);
+        drop(txn);
+
+        let txn = db.datastore.transaction();
+        assert_eq!(txn.vertex_count(), 2);
+    }
+
+    #[test]
+    fn should_fail_to_commit_conflicting_transactions() {
+        use crate::{Datastore, Error, Transaction, Vertex};
+
+        let path = tempdir().unwrap().into_path();
+        let db: crate::Database<crate::RocksdbDatastore> = crate::RocksdbDatastore::new_db(&path).unwrap();
+        let vertex = Vertex::new(crate::Identifier::new("person").unwrap());
+
+        // Both transactions create the same vertex, so whichever commits
+        // second conflicts
+        let mut first = db.datastore.transaction();
+        let mut second = db.datastore.transaction();
+        assert!(first.create_vertex(&vertex).unwrap());
+        assert!(second.create_vertex(&vertex).unwrap());
+        first.commit().unwrap();
+        assert!(matches!(second.commit(), Err(Error::Conflict)));
+        drop(second);
+
+        // The database reports conflicts the same way, and keeps none of the
+        // conflicting transaction's changes
+        let other = Vertex::new(vertex.t);
+        let result = db.transaction(|txn| {
+            assert!(txn.create_vertex(&other)?);
+            let mut concurrent = db.datastore.transaction();
+            assert!(concurrent.create_vertex(&other)?);
+            concurrent.commit()
+        });
+        assert!(matches!(result, Err(Error::Conflict)));
+        assert_eq!(db.stats().unwrap().vertex_count, 2);
+    }
+
+    #[test]
+    fn should_get_or_create_vertex_concurrently() {
+        use crate::{ijson, Identifier, IndexScope};
+        use std::sync::Barrier;
+        use std::thread;
+
+        let path = tempdir().unwrap().into_path();
+        let db: crate::Database<crate::RocksdbDatastore> = crate::RocksdbDatastore::new_db(&path).unwrap();
+        let t = Identifier::new("user").unwrap();
+        let email = Identifier::new("email").unwrap();
+        db.index_property(email, IndexScope::All).unwrap();
+
+        // Both threads race to create each vertex, and the one that loses
+        // retries to get the vertex the other created
+        let barrier = Barrier::new(2);
+        let results: Vec<Vec<_>> = thread::scope(|s| {
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    s.spawn(|| {
+                        (0..20)
+                            .map(|i| {
+                                barrier.wait();
+                                db.get_or_create_vertex(t, email, &ijson!(i), Vec::new())
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        for (first, second) in results[0].iter().zip(&results[1]) {
+            // Unwrapped once both threads are done, so that one failing
+            // doesn't leave the other waiting on the barrier
+            let (first_id, first_created) = first.as_ref().unwrap();
+            let (second_id, second_created) = second.as_ref().unwrap();
+            assert_eq!(first_id, second_id);
+            assert!(first_created ^ second_created);
+        }
+        assert_eq!(db.stats().unwrap().vertex_count, 20);
+    }
+
+    #[test]
+    fn should_estimate_rows_from_stats() {
+        use crate::{
+            ijson, AllEdgeQuery, AllVertexQuery, Edge, EdgeWithPropertyValueQuery, Identifier, IndexScope,
+            SpecificEdgeQuery, SpecificVertexQuery, VertexWithPropertyValueQuery,
+        };
+
+        let path = tempdir().unwrap().into_path();
+        let db: crate::Database<crate::RocksdbDatastore> = crate::RocksdbDatastore::new_db(&path).unwrap();
+        let name = Identifier::new("age").unwrap();
+        db.index_property(name, IndexScope::All).unwrap();
+        let t = Identifier::new("person").unwrap();
+        let ids: Vec<_> = (0..3).map(|_| db.create_vertex_from_type(t).unwrap()).collect();
+        let edge_t = Identifier::new("knows").unwrap();
+        let edges = vec![Edge::new(ids[0], edge_t, ids[1]), Edge::new(ids[0], edge_t, ids[2])];
+        for edge in &edges {
+            db.create_edge(edge).unwrap();
+        }
+        db.set_properties(SpecificVertexQuery::single(ids[0]), name, &ijson!(30))
+            .unwrap();
+        db.set_properties(SpecificEdgeQuery::single(edges[0].clone()), name, &ijson!(30))
+            .unwrap();
+
+        let estimated_rows = |q: crate::Query| db.explain(q).unwrap().steps.last().unwrap().estimated_rows;
+        assert_eq!(estimated_rows(AllVertexQuery.into()), Some(3));
+        assert_eq!(estimated_rows(AllEdgeQuery.into()), Some(2));
+        assert_eq!(
+            estimated_rows(VertexWithPropertyValueQuery::new(name, ijson!(30)).into()),
+            Some(1)
+        );
+        assert_eq!(
+            estimated_rows(EdgeWithPropertyValueQuery::new(name, ijson!(30)).into()),
+            Some(1)
+        );
+
+        // Piped vertices are assumed to have the average degree
+        assert_eq!(estimated_rows(AllVertexQuery.outbound().unwrap().into()), Some(2));
+
+        // There are no counts for values of properties that aren't indexed
+        let unindexed = Identifier::new("unindexed").unwrap();
+        assert_eq!(
+            estimated_rows(VertexWithPropertyValueQuery::new(unindexed, ijson!(30)).into()),
+            None
+        );
+    }
+
+    #[test]
+    fn should_migrate_legacy_property_value_indexes() {
+        use super::RocksdbDatastore;
+        use crate::{ijson, Identifier, IndexScope, SpecificVertexQuery, VertexWithPropertyValueQuery};
+        use rocksdb::{OptimisticTransactionDB, DB};
+        use std::collections::{HashMap, HashSet};
+
+        let path = tempdir().unwrap().into_path();
+        let opts = RocksdbDatastore::get_options(Some(1));
+        let name = Identifier::new("age").unwrap();
+
+        let id = {
+            let db = RocksdbDatastore::new_db_with_options(&path, &opts).unwrap();
+            db.index_property(name, IndexScope::All).unwrap();
+            let id = db.create_vertex_from_type(Identifier::new("person").unwrap()).unwrap();
+            db.set_properties(SpecificVertexQuery::single(id), name, &ijson!(30))
+                .unwrap();
+            id
+        };
+
+        // Swap the value indexes for the column families used before the
+        // index encoding changed, and the index metadata for the format used
+        // before index scopes
+        {
+            let cf_names = DB::list_cf(&opts, &path).unwrap();
+            let mut db = OptimisticTransactionDB::open_cf(&opts, &path, &cf_names).unwrap();
+            for cf_name in ["vertex_property_values", "edge_property_values"] {
+                db.drop_cf(&format!("{cf_name}:v3")).unwrap();
+                db.create_cf(format!("{cf_name}:v2"), &opts).unwrap();
+            }
+
+            let txn = db.transaction();
+            let cf = db.cf_handle("metadata:v2").unwrap();
+            txn.delete_cf(cf, "property_indexes").unwrap();
+            let names = HashSet::from([name]);
+            txn.put_cf(cf, "indexed_properties", bincode::serialize(&names).unwrap())
+                .unwrap();
+            txn.commit().unwrap();
+        }
+
+        let db = RocksdbDatastore::new_db_with_options(&path, &opts).unwrap();
+        assert_eq!(
+            db.indexed_properties().unwrap(),
+            HashMap::from([(name, HashSet::from([IndexScope::All]))])
+        );
+        let output = db.get(VertexWithPropertyValueQuery::new(name, ijson!(30))).unwrap();
+        let vertices = crate::util::extract_vertices(output).unwrap();
+        assert_eq!(vertices.into_iter().map(|v| v.id).collect::<Vec<_>>(), vec![id]);
+        drop(db);
+
+        let cf_names = DB::list_cf(&opts, &path).unwrap();
+        assert!(!cf_names.iter().any(|cf_name| cf_name.ends_with("property_values:v2")));
+    }
+
+    #[test]
+    fn should_rebuild_stats() {
+        use super::RocksdbDatastore;
+        use crate::{ijson, Edge, Identifier, IndexScope, SpecificVertexQuery};
+        use rocksdb::{OptimisticTransactionDB, DB};
+
+        let path = tempdir().unwrap().into_path();
+        let opts = RocksdbDatastore::get_options(Some(1));
+        let name = Identifier::new("age").unwrap();
+
+        let stats = {
+            let db = RocksdbDatastore::new_db_with_options(&path, &opts).unwrap();
+            db.index_property(name, IndexScope::All).unwrap();
+            let t = Identifier::new("person").unwrap();
+            let id = db.create_vertex_from_type(t).unwrap();
+            let other_id = db.create_vertex_from_type(t).unwrap();
+            db.create_edge(&Edge::new(id, Identifier::new("knows").unwrap(), other_id))
+                .unwrap();
+            db.set_properties(SpecificVertexQuery::single(id), name, &ijson!(30))
+                .unwrap();
+            db.stats().unwrap()
+        };
+
+        // The counts are kept when the database is reopened
+        {
+            let db = RocksdbDatastore::new_db_with_options(&path, &opts).unwrap();
+            assert_eq!(db.stats().unwrap(), stats);
+        }
+
+        // And rebuilt for a database created before they were kept
+        {
+            let cf_names = DB::list_cf(&opts, &path).unwrap();
+            let mut db = OptimisticTransactionDB::open_cf(&opts, &path, &cf_names).unwrap();
+            db.drop_cf("stats:v1").unwrap();
+            let txn = db.transaction();
+            txn.delete_cf(db.cf_handle("metadata:v2").unwrap(), "stats_built")
+                .unwrap();
+            txn.commit().unwrap();
+        }
+
+        {
+            let db = RocksdbDatastore::new_db_with_options(&path, &opts).unwrap();
+            assert_eq!(stats.vertex_count, 2);
+            assert_eq!(stats.property_value_counts.len(), 1);
+            assert_eq!(db.stats().unwrap(), stats);
+        }
+
+        // Or where building them didn't finish, in which case the counts that
+        // were written are replaced
+        {
+            let cf_names = DB::list_cf(&opts, &path).unwrap();
+            let db = OptimisticTransactionDB::open_cf(&opts, &path, &cf_names).unwrap();
+            let txn = db.transaction();
+            txn.delete_cf(db.cf_handle("metadata:v2").unwrap(), "stats_built")
+                .unwrap();
+            txn.commit().unwrap();
+        }
+
+        let db = RocksdbDatastore::new_db_with_options(&path, &opts).unwrap();
+        assert_eq!(db.stats().unwrap(), stats);
+    }
+
+    #[test]
+    fn should_fold_stats_as_transactions_commit() {
+        use super::RocksdbDatastore;
+        use crate::Identifier;
+        use rocksdb::{IteratorMode, OptimisticTransactionDB, DB};
+
+        let path = tempdir().unwrap().into_path();
+        let opts = RocksdbDatastore::get_options(Some(1));
+
+        {
+            let db = RocksdbDatastore::new_db_with_options(&path, &opts).unwrap();
+            let t = Identifier::new("person").unwrap();
+            for _ in 0..1500 {
+                db.create_vertex_from_type(t).unwrap();
+            }
+            assert_eq!(db.stats().unwrap().vertex_count, 1500);
+        }
+
+        // Each commit writes an entry to the counter, and the first thousand
+        // were folded into one
+        let cf_names = DB::list_cf(&opts, &path).unwrap();
+        let db = OptimisticTransactionDB::open_cf(&opts, &path, &cf_names).unwrap();
+        let cf = db.cf_handle("stats:v1").unwrap();
+        assert_eq!(db.iterator_cf(cf, IteratorMode::Start).count(), 501);
+    }
 }
@@ -0,0 +1,107 @@
+use super::util;
+use crate::util::{extract_aggregate, extract_groups};
+use crate::{
+    ijson, AllEdgeQuery, AllVertexQuery, Database, Datastore, Error, Group, GroupKey, Identifier, Json, Query,
+    QueryExt, SpecificEdgeQuery, SpecificVertexQuery,
+};
+
+fn get_aggregate<D: Datastore, Q: Into<Query>>(db: &Database<D>, q: Q) -> Result<Option<Json>, Error> {
+    Ok(extract_aggregate(db.get(q)?).unwrap())
+}
+
+pub fn should_aggregate_properties<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let score = Identifier::new("score")?;
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!(1))])?;
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!(2.5))])?;
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!(4))])?;
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!("n/a"))])?;
+    util::create_vertex_with_properties(db, "user", &[])?;
+
+    // Values that aren't numbers are skipped when summing or averaging
+    assert_eq!(get_aggregate(db, AllVertexQuery.sum(score)?)?, Some(ijson!(7.5)));
+    assert_eq!(get_aggregate(db, AllVertexQuery.avg(score)?)?, Some(ijson!(2.5)));
+
+    // But not when getting the smallest or largest value, where strings
+    // are larger than numbers
+    assert_eq!(get_aggregate(db, AllVertexQuery.min(score)?)?, Some(ijson!(1)));
+    assert_eq!(get_aggregate(db, AllVertexQuery.max(score)?)?, Some(ijson!("n/a")));
+    Ok(())
+}
+
+pub fn should_aggregate_nothing<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let score = Identifier::new("score")?;
+    util::create_vertex_with_properties(db, "user", &[])?;
+    assert_eq!(get_aggregate(db, AllVertexQuery.sum(score)?)?, None);
+    assert_eq!(get_aggregate(db, AllVertexQuery.avg(score)?)?, None);
+    assert_eq!(get_aggregate(db, AllVertexQuery.min(score)?)?, None);
+    assert_eq!(get_aggregate(db, AllVertexQuery.max(score)?)?, None);
+    Ok(())
+}
+
+pub fn should_sum_integers_as_integers<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let score = Identifier::new("score")?;
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!(1))])?;
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!(-4))])?;
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!(10))])?;
+    assert_eq!(get_aggregate(db, AllVertexQuery.sum(score)?)?, Some(ijson!(7)));
+
+    // A single float makes the sum a float
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!(0.5))])?;
+    assert_eq!(get_aggregate(db, AllVertexQuery.sum(score)?)?, Some(ijson!(7.5)));
+    Ok(())
+}
+
+pub fn should_aggregate_edge_properties<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let weight = Identifier::new("weight")?;
+    let edges = util::get_edges(db, SpecificVertexQuery::single(outbound_id).outbound()?)?;
+    for (i, edge) in edges.iter().enumerate() {
+        db.set_properties(SpecificEdgeQuery::single(edge.clone()), weight, &ijson!(i))?;
+    }
+    let q = SpecificVertexQuery::single(outbound_id).outbound()?;
+    assert_eq!(get_aggregate(db, q.clone().sum(weight)?)?, Some(ijson!(10)));
+    assert_eq!(get_aggregate(db, q.max(weight)?)?, Some(ijson!(4)));
+    Ok(())
+}
+
+pub fn should_group_by_type<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    util::create_vertex_with_properties(db, "user", &[])?;
+    util::create_vertex_with_properties(db, "post", &[])?;
+    util::create_vertex_with_properties(db, "user", &[])?;
+    util::create_edges(db)?;
+
+    let groups = extract_groups(db.get(AllVertexQuery.group_by(GroupKey::Type)?)?).unwrap();
+    assert_eq!(
+        groups,
+        vec![
+            Group::new(ijson!("post"), 1),
+            Group::new(ijson!("test_inbound_vertex_type"), 5),
+            Group::new(ijson!("test_outbound_vertex_type"), 1),
+            Group::new(ijson!("user"), 2),
+        ]
+    );
+
+    let groups = extract_groups(db.get(AllEdgeQuery.group_by(GroupKey::Type)?)?).unwrap();
+    assert_eq!(groups, vec![Group::new(ijson!("test_edge_type"), 5)]);
+    Ok(())
+}
+
+pub fn should_group_by_property<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!(2))])?;
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!("high"))])?;
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!(2))])?;
+    util::create_vertex_with_properties(db, "user", &[("score", ijson!(1))])?;
+    util::create_vertex_with_properties(db, "user", &[])?;
+
+    let q = AllVertexQuery.group_by(GroupKey::Property(Identifier::new("score")?))?;
+    let groups = extract_groups(db.get(q)?).unwrap();
+    assert_eq!(
+        groups,
+        vec![
+            Group::new(ijson!(1), 1),
+            Group::new(ijson!(2), 2),
+            Group::new(ijson!("high"), 1),
+        ]
+    );
+    Ok(())
+}
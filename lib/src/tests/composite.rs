@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use super::util;
+use crate::{
+    expect_err, ijson, Database, Datastore, Error, Identifier, QueryExt, SpecificVertexQuery, ValidationError,
+    VertexWithPropertyValuesQuery,
+};
+
+fn state_city() -> Result<Vec<Identifier>, Error> {
+    Ok(vec![Identifier::new("state")?, Identifier::new("city")?])
+}
+
+pub fn should_query_composite_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let state = Identifier::new("state")?;
+    let city = Identifier::new("city")?;
+    let or_portland =
+        util::create_vertex_with_properties(db, "place", &[("state", ijson!("OR")), ("city", ijson!("Portland"))])?;
+    let or_eugene =
+        util::create_vertex_with_properties(db, "place", &[("state", ijson!("OR")), ("city", ijson!("Eugene"))])?;
+    let me_portland =
+        util::create_vertex_with_properties(db, "place", &[("state", ijson!("ME")), ("city", ijson!("Portland"))])?;
+    util::create_vertex_with_properties(db, "place", &[("state", ijson!("OR"))])?;
+
+    // Existing values are indexed
+    db.index_composite(state_city()?)?;
+    assert_eq!(db.composite_indexes()?, HashSet::from([state_city()?]));
+
+    // Vertices without every property of the index aren't in it
+    assert_eq!(
+        util::get_vertex_ids(db, VertexWithPropertyValuesQuery::new(state, ijson!("OR")))?,
+        HashSet::from([or_portland, or_eugene])
+    );
+    assert_eq!(
+        util::get_vertex_ids(
+            db,
+            VertexWithPropertyValuesQuery::new(state, ijson!("OR")).and(city, ijson!("Portland"))
+        )?,
+        HashSet::from([or_portland])
+    );
+
+    // The queried properties can be in any order
+    assert_eq!(
+        util::get_vertex_ids(
+            db,
+            VertexWithPropertyValuesQuery::new(city, ijson!("Portland")).and(state, ijson!("ME"))
+        )?,
+        HashSet::from([me_portland])
+    );
+    assert!(util::get_vertex_ids(db, VertexWithPropertyValuesQuery::new(state, ijson!("WA")))?.is_empty());
+
+    // Only prefixes of the index's properties can be queried
+    let result = util::get_vertices(db, VertexWithPropertyValuesQuery::new(city, ijson!("Portland")));
+    expect_err!(result, Error::NotIndexed);
+
+    // The query can be piped from
+    let q = VertexWithPropertyValuesQuery::new(state, ijson!("ME")).outbound()?;
+    assert!(util::get_edges(db, q)?.is_empty());
+    Ok(())
+}
+
+pub fn should_update_composite_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let state = Identifier::new("state")?;
+    let city = Identifier::new("city")?;
+    db.index_composite(state_city()?)?;
+    let id =
+        util::create_vertex_with_properties(db, "place", &[("state", ijson!("OR")), ("city", ijson!("Portland"))])?;
+    let other_id =
+        util::create_vertex_with_properties(db, "place", &[("state", ijson!("OR")), ("city", ijson!("Salem"))])?;
+    let q = VertexWithPropertyValuesQuery::new(state, ijson!("OR"));
+    assert_eq!(util::get_vertex_ids(db, q.clone())?, HashSet::from([id, other_id]));
+
+    // Updating a property moves the vertex in the index
+    db.set_properties(SpecificVertexQuery::single(id), city, &ijson!("Bend"))?;
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().and(city, ijson!("Bend")))?,
+        HashSet::from([id])
+    );
+    assert!(util::get_vertex_ids(db, q.clone().and(city, ijson!("Portland")))?.is_empty());
+
+    // Deleting a property removes the vertex from the index
+    db.delete(SpecificVertexQuery::single(id).properties()?.name(city))?;
+    assert_eq!(util::get_vertex_ids(db, q.clone())?, HashSet::from([other_id]));
+
+    // Deleting a vertex removes it from the index
+    db.delete(SpecificVertexQuery::single(other_id))?;
+    assert!(util::get_vertex_ids(db, q)?.is_empty());
+    Ok(())
+}
+
+pub fn should_unindex_composite<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let state = Identifier::new("state")?;
+    util::create_vertex_with_properties(db, "place", &[("state", ijson!("OR")), ("city", ijson!("Portland"))])?;
+    db.index_composite(state_city()?)?;
+    db.unindex_composite(state_city()?)?;
+    assert!(db.composite_indexes()?.is_empty());
+    let result = util::get_vertices(db, VertexWithPropertyValuesQuery::new(state, ijson!("OR")));
+    expect_err!(result, Error::NotIndexed);
+
+    // Reindexing picks the values back up
+    db.index_composite(state_city()?)?;
+    assert_eq!(
+        util::get_vertex_ids(db, VertexWithPropertyValuesQuery::new(state, ijson!("OR")))?.len(),
+        1
+    );
+    Ok(())
+}
+
+pub fn should_rollback_composite_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let state = Identifier::new("state")?;
+    let city = Identifier::new("city")?;
+    let id =
+        util::create_vertex_with_properties(db, "place", &[("state", ijson!("OR")), ("city", ijson!("Portland"))])?;
+    db.index_composite(vec![state])?;
+
+    let result: Result<(), Error> = db.transaction(|txn| {
+        txn.index_composite(state_city()?)?;
+        txn.set_properties(SpecificVertexQuery::single(id), state, &ijson!("ME"))?;
+        txn.set_properties(SpecificVertexQuery::single(id), city, &ijson!("Bangor"))?;
+        Err(Error::OperationOnQuery)
+    });
+    expect_err!(result, Error::OperationOnQuery);
+
+    let q = VertexWithPropertyValuesQuery::new(state, ijson!("OR"));
+    assert_eq!(util::get_vertex_ids(db, q)?, HashSet::from([id]));
+    let q = VertexWithPropertyValuesQuery::new(state, ijson!("ME"));
+    assert!(util::get_vertex_ids(db, q)?.is_empty());
+    let result = util::get_vertices(
+        db,
+        VertexWithPropertyValuesQuery::new(state, ijson!("OR")).and(city, ijson!("Portland")),
+    );
+    expect_err!(result, Error::NotIndexed);
+    Ok(())
+}
+
+pub fn should_not_index_invalid_composite<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let state = Identifier::new("state")?;
+    let result = db.index_composite(Vec::new());
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    let result = db.index_composite(vec![state, state]);
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    assert!(db.composite_indexes()?.is_empty());
+
+    db.index_composite(state_city()?)?;
+    let q = VertexWithPropertyValuesQuery::new(state, ijson!("OR")).and(state, ijson!("WA"));
+    let result = util::get_vertices(db, q);
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use super::util;
+use crate::{
+    expect_err, ijson, BulkInsertItem, Database, Datastore, Error, Identifier, QueryExt, SpecificVertexQuery,
+    UniqueConstraint, Vertex,
+};
+
+fn user_email() -> Result<UniqueConstraint, Error> {
+    Ok(UniqueConstraint::new(
+        Identifier::new("user")?,
+        Identifier::new("email")?,
+    ))
+}
+
+pub fn should_enforce_unique_constraint<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let email = Identifier::new("email")?;
+    db.add_unique_constraint(user_email()?)?;
+    assert_eq!(db.unique_constraints()?, HashSet::from([user_email()?]));
+    let id = util::create_vertex_with_properties(db, "user", &[("email", ijson!("a@example.com"))])?;
+
+    // Another vertex of the type can't have the same value
+    let other_id = db.create_vertex_from_type(Identifier::new("user")?)?;
+    let result = db.set_properties(SpecificVertexQuery::single(other_id), email, &ijson!("a@example.com"));
+    expect_err!(result, Error::ConstraintViolation);
+    let q = SpecificVertexQuery::single(other_id).properties()?.name(email);
+    assert!(util::get_vertex_properties(db, q)?.is_empty());
+
+    // Re-setting a vertex's own value is fine
+    db.set_properties(SpecificVertexQuery::single(id), email, &ijson!("a@example.com"))?;
+
+    // Vertices of other types aren't constrained
+    util::create_vertex_with_properties(db, "admin", &[("email", ijson!("a@example.com"))])?;
+
+    // Setting the same value on several vertices at once is a violation
+    let another_id = db.create_vertex_from_type(Identifier::new("user")?)?;
+    let q = SpecificVertexQuery::new(vec![other_id, another_id]);
+    let result = db.set_properties(q, email, &ijson!("b@example.com"));
+    expect_err!(result, Error::ConstraintViolation);
+    Ok(())
+}
+
+pub fn should_release_unique_values<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let email = Identifier::new("email")?;
+    db.add_unique_constraint(user_email()?)?;
+    let id = util::create_vertex_with_properties(db, "user", &[("email", ijson!("a@example.com"))])?;
+    let other_id = util::create_vertex_with_properties(db, "user", &[("email", ijson!("b@example.com"))])?;
+
+    // Updating a value frees up the old one
+    db.set_properties(SpecificVertexQuery::single(id), email, &ijson!("c@example.com"))?;
+    db.set_properties(SpecificVertexQuery::single(other_id), email, &ijson!("a@example.com"))?;
+
+    // As does deleting the property or the vertex
+    db.delete(SpecificVertexQuery::single(id).properties()?.name(email))?;
+    util::create_vertex_with_properties(db, "user", &[("email", ijson!("c@example.com"))])?;
+    db.delete(SpecificVertexQuery::single(other_id))?;
+    util::create_vertex_with_properties(db, "user", &[("email", ijson!("a@example.com"))])?;
+    Ok(())
+}
+
+pub fn should_enforce_unique_constraint_on_bulk_insert<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let email = Identifier::new("email")?;
+    db.add_unique_constraint(user_email()?)?;
+    let existing_id = util::create_vertex_with_properties(db, "user", &[("email", ijson!("a@example.com"))])?;
+
+    let vertex = Vertex::new(Identifier::new("user")?);
+    let result = db.bulk_insert(vec![
+        BulkInsertItem::Vertex(vertex.clone()),
+        BulkInsertItem::VertexProperty(vertex.id, email, ijson!("a@example.com")),
+    ]);
+    expect_err!(result, Error::ConstraintViolation);
+
+    let first = Vertex::new(Identifier::new("user")?);
+    let second = Vertex::new(Identifier::new("user")?);
+    let result = db.bulk_insert(vec![
+        BulkInsertItem::Vertex(first.clone()),
+        BulkInsertItem::Vertex(second.clone()),
+        BulkInsertItem::VertexProperty(first.id, email, ijson!("b@example.com")),
+        BulkInsertItem::VertexProperty(second.id, email, ijson!("b@example.com")),
+    ]);
+    expect_err!(result, Error::ConstraintViolation);
+
+    // The existing value is still owned by its vertex
+    db.set_properties(
+        SpecificVertexQuery::single(existing_id),
+        email,
+        &ijson!("a@example.com"),
+    )?;
+    Ok(())
+}
+
+pub fn should_not_add_violated_unique_constraint<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    util::create_vertex_with_properties(db, "user", &[("email", ijson!("a@example.com"))])?;
+    util::create_vertex_with_properties(db, "user", &[("email", ijson!("a@example.com"))])?;
+    let result = db.add_unique_constraint(user_email()?);
+    expect_err!(result, Error::ConstraintViolation);
+    assert!(db.unique_constraints()?.is_empty());
+    Ok(())
+}
+
+pub fn should_remove_unique_constraint<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    db.add_unique_constraint(user_email()?)?;
+    util::create_vertex_with_properties(db, "user", &[("email", ijson!("a@example.com"))])?;
+    db.remove_unique_constraint(user_email()?)?;
+    assert!(db.unique_constraints()?.is_empty());
+    let id = util::create_vertex_with_properties(db, "user", &[("email", ijson!("a@example.com"))])?;
+
+    // Values are checked again when the constraint is re-added
+    let result = db.add_unique_constraint(user_email()?);
+    expect_err!(result, Error::ConstraintViolation);
+    db.delete(SpecificVertexQuery::single(id))?;
+    db.add_unique_constraint(user_email()?)?;
+    Ok(())
+}
+
+pub fn should_rollback_unique_constraint<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let email = Identifier::new("email")?;
+    db.add_unique_constraint(user_email()?)?;
+    let id = util::create_vertex_with_properties(db, "user", &[("email", ijson!("a@example.com"))])?;
+
+    let result: Result<(), Error> = db.transaction(|txn| {
+        txn.delete(SpecificVertexQuery::single(id))?;
+        txn.remove_unique_constraint(user_email()?)?;
+        Err(Error::OperationOnQuery)
+    });
+    expect_err!(result, Error::OperationOnQuery);
+
+    // The restored vertex still owns its value
+    let other_id = db.create_vertex_from_type(Identifier::new("user")?)?;
+    let result = db.set_properties(SpecificVertexQuery::single(other_id), email, &ijson!("a@example.com"));
+    expect_err!(result, Error::ConstraintViolation);
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use crate::util::extract_degrees;
+use crate::{
+    Database, Datastore, Degree, Edge, Error, Identifier, Query, QueryExt, RangeVertexQuery, SpecificVertexQuery,
+};
+
+use uuid::Uuid;
+
+fn get_degrees<D: Datastore, Q: Into<Query>>(db: &Database<D>, q: Q) -> Result<Vec<Degree>, Error> {
+    Ok(extract_degrees(db.get(q)?).unwrap())
+}
+
+pub fn should_get_degrees<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let user = Identifier::new("user")?;
+    let follows = Identifier::new("follows")?;
+    let blocks = Identifier::new("blocks")?;
+    let alice_id = db.create_vertex_from_type(user)?;
+    let bob_id = db.create_vertex_from_type(user)?;
+    let carol_id = db.create_vertex_from_type(user)?;
+    let dave_id = db.create_vertex_from_type(user)?;
+    db.create_edge(&Edge::new(alice_id, follows, bob_id))?;
+    db.create_edge(&Edge::new(alice_id, follows, carol_id))?;
+    db.create_edge(&Edge::new(alice_id, blocks, dave_id))?;
+    db.create_edge(&Edge::new(bob_id, follows, carol_id))?;
+    db.create_edge(&Edge::new(carol_id, follows, alice_id))?;
+
+    // Degrees are output in the same order as the vertices
+    let q = SpecificVertexQuery::new(vec![dave_id, carol_id, bob_id, alice_id]).degree()?;
+    assert_eq!(
+        get_degrees(db, q.clone())?,
+        vec![
+            Degree::new(dave_id, 0, 1),
+            Degree::new(carol_id, 1, 2),
+            Degree::new(bob_id, 1, 1),
+            Degree::new(alice_id, 3, 1),
+        ]
+    );
+
+    assert_eq!(
+        get_degrees(db, q.clone().t(follows))?,
+        vec![
+            Degree::new(dave_id, 0, 0),
+            Degree::new(carol_id, 1, 2),
+            Degree::new(bob_id, 1, 1),
+            Degree::new(alice_id, 2, 1),
+        ]
+    );
+
+    assert_eq!(
+        get_degrees(db, q.t(Identifier::new("likes")?))?,
+        vec![
+            Degree::new(dave_id, 0, 0),
+            Degree::new(carol_id, 0, 0),
+            Degree::new(bob_id, 0, 0),
+            Degree::new(alice_id, 0, 0),
+        ]
+    );
+    Ok(())
+}
+
+pub fn should_not_count_edges_of_adjacent_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    // Vertices with consecutive IDs, whose edges are next to each other in
+    // the edge indexes
+    let t = Identifier::new("test_vertex_type")?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let ids: Vec<Uuid> = (1..=3).map(Uuid::from_u128).collect();
+    for id in &ids {
+        db.create_vertex(&crate::Vertex::with_id(*id, t))?;
+    }
+    db.create_edge(&Edge::new(ids[0], edge_t, ids[1]))?;
+    db.create_edge(&Edge::new(ids[2], edge_t, ids[1]))?;
+
+    let degrees = get_degrees(db, RangeVertexQuery::new().t(t).degree()?.t(edge_t))?;
+    assert_eq!(
+        degrees,
+        vec![
+            Degree::new(ids[0], 1, 0),
+            Degree::new(ids[1], 0, 2),
+            Degree::new(ids[2], 1, 0),
+        ]
+    );
+    Ok(())
+}
+
+pub fn should_include_degree_query_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let id = db.create_vertex_from_type(Identifier::new("user")?)?;
+    let output = db.get(SpecificVertexQuery::single(id).include().degree()?)?;
+    assert_eq!(output.len(), 2);
+    assert_eq!(extract_degrees(output), Some(vec![Degree::new(id, 0, 0)]));
+    Ok(())
+}
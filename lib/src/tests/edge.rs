@@ -2,8 +2,9 @@ use std::collections::HashSet;
 
 use super::util;
 use crate::{
-    ijson, models, AllEdgeQuery, Database, Datastore, Edge, EdgeDirection, EdgeWithPropertyValueQuery, Error,
-    Identifier, QueryExt, SpecificEdgeQuery, SpecificVertexQuery,
+    expect_err, ijson, models, AllEdgeQuery, Database, Datastore, Edge, EdgeDirection, EdgeWithPropertyValueQuery,
+    Error, Identifier, IndexScope, PipeQuery, QueryExt, RangeVertexQuery, SpecificEdgeQuery, SpecificVertexQuery,
+    ValidationError,
 };
 
 use uuid::Uuid;
@@ -221,10 +222,142 @@ pub fn should_get_edges_piped<D: Datastore>(db: &Database<D>) -> Result<(), Erro
     Ok(())
 }
 
+fn get_edge_pages<D: Datastore>(db: &Database<D>, q: PipeQuery) -> Result<Vec<Vec<Edge>>, Error> {
+    let mut pages: Vec<Vec<Edge>> = Vec::new();
+    loop {
+        let page_q = match pages.last().and_then(|page| page.last()) {
+            Some(last) => q.clone().after(last.clone()),
+            None => q.clone(),
+        };
+        let page = util::get_edges(db, page_q)?;
+        if page.is_empty() {
+            return Ok(pages);
+        }
+        pages.push(page);
+    }
+}
+
+pub fn should_resume_piped_edges<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (first_id, first_inbound_ids) = util::create_edges(db)?;
+    let (second_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::new(vec![first_id, second_id]).outbound()?;
+    let all_edges = util::get_edges(db, q.clone())?;
+    assert_eq!(all_edges.len(), 10);
+
+    // Pages continue across the piped vertices
+    let pages = get_edge_pages(db, q.clone().limit(3))?;
+    assert_eq!(
+        pages.iter().map(|page| page.len()).collect::<Vec<_>>(),
+        vec![3, 3, 3, 1]
+    );
+    assert_eq!(pages.concat(), all_edges);
+
+    // As do pages with a type filter
+    let pages = get_edge_pages(db, q.limit(4).t(Identifier::new("test_edge_type")?))?;
+    assert_eq!(pages.concat(), all_edges);
+
+    // Inbound edges are resumed in the same way
+    let q = SpecificVertexQuery::new(first_inbound_ids.to_vec()).inbound()?;
+    let pages = get_edge_pages(db, q.clone().limit(2))?;
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages.concat(), util::get_edges(db, q)?);
+    Ok(())
+}
+
+pub fn should_resume_piped_edges_of_deleted_vertex<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (first_id, _) = util::create_edges(db)?;
+    let (second_id, _) = util::create_edges(db)?;
+    let (last_id, first_id) = (first_id.max(second_id), first_id.min(second_id));
+    let outbound_t = Identifier::new("test_outbound_vertex_type")?;
+    let q = RangeVertexQuery::new().t(outbound_t).outbound()?;
+    let specific_q = SpecificVertexQuery::new(vec![first_id, last_id]).outbound()?;
+    let page = util::get_edges(db, q.clone().limit(3))?;
+    assert_eq!(page.len(), 3);
+    let after = page[2].clone();
+    assert_eq!(after.outbound_id, first_id);
+    db.delete(SpecificVertexQuery::single(first_id))?;
+
+    // Vertices piped in order of their IDs resume from the next vertex
+    let edges = util::get_edges(db, q.after(after.clone()))?;
+    assert_eq!(edges.len(), 5);
+    assert!(edges.iter().all(|edge| edge.outbound_id == last_id));
+
+    // Otherwise, there's no telling where to resume from
+    let result = db.get(specific_q.after(after));
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    Ok(())
+}
+
+pub fn should_get_edges_piped_with_types<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let vertex_t = Identifier::new("test_vertex_type")?;
+    let a = Identifier::new("a")?;
+    let b = Identifier::new("b")?;
+    let c = Identifier::new("c")?;
+    let id = db.create_vertex_from_type(vertex_t)?;
+    let other_id = db.create_vertex_from_type(vertex_t)?;
+    let mut outbound_edges = Vec::new();
+    let mut inbound_edges = Vec::new();
+    for t in [a, b, c] {
+        outbound_edges.push(Edge::new(id, t, other_id));
+        inbound_edges.push(Edge::new(other_id, t, id));
+    }
+    for edge in outbound_edges.iter().chain(inbound_edges.iter()) {
+        db.create_edge(edge)?;
+    }
+
+    // Edges are output in order regardless of the order of the types, and
+    // repeated types don't repeat edges
+    let q = SpecificVertexQuery::single(id).outbound()?.t(c).add_t(a).add_t(c);
+    assert_eq!(
+        util::get_edges(db, q.clone())?,
+        vec![outbound_edges[0].clone(), outbound_edges[2].clone()]
+    );
+    let pages = get_edge_pages(db, q.limit(1))?;
+    assert_eq!(
+        pages.concat(),
+        vec![outbound_edges[0].clone(), outbound_edges[2].clone()]
+    );
+
+    let q = SpecificVertexQuery::single(id).inbound()?.t(b).add_t(c);
+    assert_eq!(
+        util::get_edges(db, q.clone())?,
+        vec![inbound_edges[1].clone(), inbound_edges[2].clone()]
+    );
+    let pages = get_edge_pages(db, q.limit(1))?;
+    assert_eq!(pages.concat(), vec![inbound_edges[1].clone(), inbound_edges[2].clone()]);
+
+    let q = SpecificVertexQuery::single(id).outbound()?.t(Identifier::new("d")?);
+    assert!(util::get_edges(db, q)?.is_empty());
+
+    // Setting the type replaces the types that were added before
+    let q = SpecificVertexQuery::single(id).outbound()?.t(a).add_t(b).t(c);
+    assert_eq!(util::get_edges(db, q)?, vec![outbound_edges[2].clone()]);
+
+    // Vertices piped from edges are filtered by type in the same way
+    let q = SpecificVertexQuery::single(id)
+        .outbound()?
+        .inbound()?
+        .t(Identifier::new("other_vertex_type")?)
+        .add_t(vertex_t);
+    let vertices = util::get_vertices(db, q)?;
+    assert_eq!(vertices.len(), 3);
+    assert!(vertices.iter().all(|v| v.id == other_id));
+    Ok(())
+}
+
+pub fn should_not_resume_piped_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let edge = Edge::new(outbound_id, Identifier::new("test_edge_type")?, inbound_ids[0]);
+    let q = SpecificEdgeQuery::single(edge.clone()).inbound()?.after(edge);
+    let result = db.get(q);
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    Ok(())
+}
+
 /// Test for a regression, see
 /// https://github.com/indradb/indradb/issues/278#issuecomment-1515797381
 pub fn should_delete_indexed_edge_with_property_value<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
-    db.index_property(Identifier::new("k")?)?;
+    db.index_property(Identifier::new("k")?, IndexScope::All)?;
     db.delete(EdgeWithPropertyValueQuery::new(Identifier::new("k")?, ijson!(null)))?;
     Ok(())
 }
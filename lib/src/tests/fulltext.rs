@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+
+use super::util;
+use crate::{
+    expect_err, ijson, Database, Datastore, Error, Identifier, QueryExt, SpecificVertexQuery, ValidationError,
+    VertexWithPropertyMatchQuery,
+};
+
+use uuid::Uuid;
+
+fn ids_matching<D: Datastore>(db: &Database<D>, text: &str) -> Result<HashSet<Uuid>, Error> {
+    util::get_vertex_ids(db, VertexWithPropertyMatchQuery::new(Identifier::new("title")?, text))
+}
+
+pub fn should_query_fulltext_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let title = Identifier::new("title")?;
+    let moby_dick = util::create_vertex_with_properties(db, "book", &[("title", ijson!("Moby-Dick; or, The Whale"))])?;
+    let whale_rider = util::create_vertex_with_properties(db, "book", &[("title", ijson!("The Whale Rider"))])?;
+    util::create_vertex_with_properties(db, "book", &[("title", ijson!(42))])?;
+
+    // Existing values are indexed
+    db.index_property_fulltext(title)?;
+    assert_eq!(db.fulltext_indexed_properties()?, HashSet::from([title]));
+    let dune = util::create_vertex_with_properties(db, "book", &[("title", ijson!("Dune"))])?;
+
+    // Every term has to match, regardless of case and punctuation
+    assert_eq!(ids_matching(db, "whale")?, HashSet::from([moby_dick, whale_rider]));
+    assert_eq!(ids_matching(db, "THE whale, moby")?, HashSet::from([moby_dick]));
+    assert_eq!(ids_matching(db, "dune")?, HashSet::from([dune]));
+    assert!(ids_matching(db, "whale dune")?.is_empty());
+    assert!(ids_matching(db, "wha")?.is_empty());
+    assert!(ids_matching(db, "42")?.is_empty());
+
+    // The query can be piped from
+    let q = VertexWithPropertyMatchQuery::new(title, "whale").outbound()?;
+    assert!(util::get_edges(db, q)?.is_empty());
+    Ok(())
+}
+
+pub fn should_update_fulltext_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let title = Identifier::new("title")?;
+    db.index_property_fulltext(title)?;
+    let id = util::create_vertex_with_properties(db, "book", &[("title", ijson!("Moby-Dick"))])?;
+    let other_id = util::create_vertex_with_properties(db, "book", &[("title", ijson!("Moby Grape"))])?;
+
+    // Updating a value replaces its terms
+    db.set_properties(SpecificVertexQuery::single(id), title, &ijson!("The Whale"))?;
+    assert_eq!(ids_matching(db, "whale")?, HashSet::from([id]));
+    assert_eq!(ids_matching(db, "moby")?, HashSet::from([other_id]));
+
+    // Deleting a property or vertex removes its terms
+    db.delete(SpecificVertexQuery::single(id).properties()?.name(title))?;
+    assert!(ids_matching(db, "whale")?.is_empty());
+    db.delete(SpecificVertexQuery::single(other_id))?;
+    assert!(ids_matching(db, "moby")?.is_empty());
+    Ok(())
+}
+
+pub fn should_unindex_fulltext<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let title = Identifier::new("title")?;
+    util::create_vertex_with_properties(db, "book", &[("title", ijson!("Dune"))])?;
+    db.index_property_fulltext(title)?;
+    db.unindex_property_fulltext(title)?;
+    assert!(db.fulltext_indexed_properties()?.is_empty());
+    let result = ids_matching(db, "dune");
+    expect_err!(result, Error::NotIndexed);
+
+    // Reindexing picks the values back up
+    db.index_property_fulltext(title)?;
+    assert_eq!(ids_matching(db, "dune")?.len(), 1);
+    Ok(())
+}
+
+pub fn should_rollback_fulltext_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let title = Identifier::new("title")?;
+    db.index_property_fulltext(title)?;
+    let id = util::create_vertex_with_properties(db, "book", &[("title", ijson!("Dune"))])?;
+
+    let result: Result<(), Error> = db.transaction(|txn| {
+        txn.set_properties(SpecificVertexQuery::single(id), title, &ijson!("Dune Messiah"))?;
+        txn.unindex_property_fulltext(title)?;
+        Err(Error::OperationOnQuery)
+    });
+    expect_err!(result, Error::OperationOnQuery);
+
+    assert_eq!(ids_matching(db, "dune")?, HashSet::from([id]));
+    assert!(ids_matching(db, "messiah")?.is_empty());
+    Ok(())
+}
+
+pub fn should_not_query_fulltext_without_terms<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    db.index_property_fulltext(Identifier::new("title")?)?;
+    let result = ids_matching(db, " -- ");
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    Ok(())
+}
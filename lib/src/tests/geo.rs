@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+
+use super::util;
+use crate::{
+    expect_err, ijson, AllEdgeQuery, BoundingBox, Database, Datastore, Edge, Error, GeoPoint, Identifier, QueryExt,
+    SpecificEdgeQuery, SpecificVertexQuery, ValidationError, WithinBoundingBoxQuery, WithinRadiusQuery,
+};
+
+use uuid::Uuid;
+
+fn bbox(min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> BoundingBox {
+    BoundingBox {
+        min_lat,
+        min_lon,
+        max_lat,
+        max_lon,
+    }
+}
+
+fn ids_within_bounding_box<D: Datastore>(db: &Database<D>, bbox: BoundingBox) -> Result<HashSet<Uuid>, Error> {
+    util::get_vertex_ids(db, WithinBoundingBoxQuery::new(Identifier::new("location")?, bbox))
+}
+
+fn ids_within_radius<D: Datastore>(db: &Database<D>, lat: f64, lon: f64, radius: f64) -> Result<HashSet<Uuid>, Error> {
+    let q = WithinRadiusQuery::new(Identifier::new("location")?, GeoPoint { lat, lon }, radius);
+    util::get_vertex_ids(db, q)
+}
+
+pub fn should_query_within_bounding_box<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let location = Identifier::new("location")?;
+    let portland =
+        util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 45.5152, "lon": -122.6784}))])?;
+    let seattle =
+        util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 47.6062, "lon": -122.3321}))])?;
+    let fiji =
+        util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": -17.7134, "lon": 178.065}))])?;
+    let samoa =
+        util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": -13.759, "lon": -172.1046}))])?;
+    util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 45.5, "lon": "-122.6"}))])?;
+    util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 95, "lon": -122.6}))])?;
+    util::create_vertex_with_properties(db, "venue", &[("location", ijson!([45.5, -122.6]))])?;
+
+    // Existing values are indexed
+    db.index_property_geo(location)?;
+    assert_eq!(db.geo_indexed_properties()?, HashSet::from([location]));
+
+    assert_eq!(
+        ids_within_bounding_box(db, bbox(45.0, -123.0, 46.0, -122.0))?,
+        HashSet::from([portland])
+    );
+    assert_eq!(
+        ids_within_bounding_box(db, bbox(40.0, -125.0, 50.0, -120.0))?,
+        HashSet::from([portland, seattle])
+    );
+    assert!(ids_within_bounding_box(db, bbox(-10.0, -10.0, 10.0, 10.0))?.is_empty());
+
+    // Points on the edges of the box are included
+    assert_eq!(
+        ids_within_bounding_box(db, bbox(45.5152, -122.6784, 45.5152, -122.6784))?,
+        HashSet::from([portland])
+    );
+
+    // Boxes can cross the antimeridian
+    assert_eq!(
+        ids_within_bounding_box(db, bbox(-20.0, 170.0, -10.0, -170.0))?,
+        HashSet::from([fiji, samoa])
+    );
+    assert_eq!(
+        ids_within_bounding_box(db, bbox(-20.0, 179.0, -10.0, -170.0))?,
+        HashSet::from([samoa])
+    );
+    assert_eq!(
+        ids_within_bounding_box(db, bbox(-90.0, -180.0, 90.0, 180.0))?,
+        HashSet::from([portland, seattle, fiji, samoa])
+    );
+
+    // The query can be piped from
+    let other_id = db.create_vertex_from_type(Identifier::new("user")?)?;
+    let edge = Edge::new(other_id, Identifier::new("follows")?, portland);
+    db.create_edge(&edge)?;
+    let q = WithinBoundingBoxQuery::new(location, bbox(45.0, -123.0, 46.0, -122.0)).inbound()?;
+    assert_eq!(util::get_edges(db, q)?, vec![edge]);
+    Ok(())
+}
+
+pub fn should_query_within_radius<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    db.index_property_geo(Identifier::new("location")?)?;
+    let center = util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 0, "lon": 0}))])?;
+    // About 1.1km north
+    let north = util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 0.01, "lon": 0}))])?;
+    // About 1.6km away, in the corner of the box around a 1.5km circle
+    let corner = util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 0.01, "lon": 0.01}))])?;
+    // About 1.1km away, across the antimeridian from the next one
+    let west = util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 0, "lon": 179.995}))])?;
+    let east = util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 0, "lon": -179.995}))])?;
+
+    assert_eq!(ids_within_radius(db, 0.0, 0.0, 1500.0)?, HashSet::from([center, north]));
+    assert_eq!(
+        ids_within_radius(db, 0.0, 0.0, 2000.0)?,
+        HashSet::from([center, north, corner])
+    );
+    assert_eq!(ids_within_radius(db, 0.0, 0.0, 0.0)?, HashSet::from([center]));
+    assert_eq!(ids_within_radius(db, 0.0, 180.0, 1000.0)?, HashSet::from([west, east]));
+    assert_eq!(ids_within_radius(db, 0.0, -179.99, 1000.0)?, HashSet::from([east]));
+
+    // A circle covering a pole covers every longitude
+    let polar = util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 89.99, "lon": 90}))])?;
+    assert_eq!(ids_within_radius(db, 90.0, -90.0, 2000.0)?, HashSet::from([polar]));
+    Ok(())
+}
+
+pub fn should_pipe_within_radius<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let location = Identifier::new("location")?;
+    let follows = Identifier::new("follows")?;
+    let user_id = db.create_vertex_from_type(Identifier::new("user")?)?;
+    let near =
+        util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 45.52, "lon": -122.68}))])?;
+    let far =
+        util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 47.61, "lon": -122.33}))])?;
+    util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 45.521, "lon": -122.681}))])?;
+    for id in [near, far] {
+        db.create_edge(&Edge::new(user_id, follows, id))?;
+    }
+
+    // The venues within 5km that the user follows, which doesn't need an
+    // index
+    let center = GeoPoint::new(45.5152, -122.6784)?;
+    let q = SpecificVertexQuery::single(user_id)
+        .outbound()?
+        .inbound()?
+        .within_radius(location, center, 5000.0)?;
+    assert_eq!(util::get_vertex_ids(db, q)?, HashSet::from([near]));
+    let q = SpecificVertexQuery::single(user_id)
+        .outbound()?
+        .inbound()?
+        .within_bounding_box(location, bbox(40.0, -125.0, 50.0, -120.0))?;
+    assert_eq!(util::get_vertex_ids(db, q)?, HashSet::from([near, far]));
+
+    // Edge properties can be filtered too
+    let edge = Edge::new(user_id, follows, near);
+    db.set_properties(
+        SpecificEdgeQuery::single(edge.clone()),
+        location,
+        &ijson!({"lat": 0, "lon": 0}),
+    )?;
+    let q = AllEdgeQuery.within_radius(location, GeoPoint::new(0.0, 0.0)?, 1.0)?;
+    assert_eq!(util::get_edges(db, q)?, vec![edge]);
+    Ok(())
+}
+
+pub fn should_update_geo_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let location = Identifier::new("location")?;
+    db.index_property_geo(location)?;
+    let id = util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 10, "lon": 10}))])?;
+    let other_id =
+        util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 10.5, "lon": 10.5}))])?;
+    let area = bbox(9.0, 9.0, 11.0, 11.0);
+
+    // Updating a value moves its point
+    db.set_properties(
+        SpecificVertexQuery::single(id),
+        location,
+        &ijson!({"lat": -10, "lon": -10}),
+    )?;
+    assert_eq!(ids_within_bounding_box(db, area)?, HashSet::from([other_id]));
+    assert_eq!(
+        ids_within_bounding_box(db, bbox(-11.0, -11.0, -9.0, -9.0))?,
+        HashSet::from([id])
+    );
+
+    // Setting it to something that isn't a point removes it
+    db.set_properties(SpecificVertexQuery::single(id), location, &ijson!("somewhere"))?;
+    assert!(ids_within_bounding_box(db, bbox(-11.0, -11.0, -9.0, -9.0))?.is_empty());
+
+    // As does deleting a property or vertex
+    db.delete(SpecificVertexQuery::single(other_id).properties()?.name(location))?;
+    assert!(ids_within_bounding_box(db, area)?.is_empty());
+    db.set_properties(
+        SpecificVertexQuery::single(id),
+        location,
+        &ijson!({"lat": 10, "lon": 10}),
+    )?;
+    db.delete(SpecificVertexQuery::single(id))?;
+    assert!(ids_within_bounding_box(db, area)?.is_empty());
+    Ok(())
+}
+
+pub fn should_query_many_geo_points<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    db.index_property_geo(Identifier::new("location")?)?;
+    let mut ids = Vec::new();
+    for lat in 0..10 {
+        for lon in 0..10 {
+            let id = util::create_vertex_with_properties(
+                db,
+                "venue",
+                &[("location", ijson!({"lat": lat as f64 / 10.0, "lon": lon as f64 / 10.0}))],
+            )?;
+            ids.push(id);
+        }
+    }
+
+    // Points are in row-major order, so this is a 3x4 block of them
+    let expected: HashSet<Uuid> = (2..5)
+        .flat_map(|lat| (5..9).map(move |lon| lat * 10 + lon))
+        .map(|i| ids[i])
+        .collect();
+    assert_eq!(ids_within_bounding_box(db, bbox(0.15, 0.45, 0.45, 0.85))?, expected);
+    assert_eq!(ids_within_bounding_box(db, bbox(0.0, 0.0, 1.0, 1.0))?.len(), 100);
+
+    // Remove a row, and check that the rest are still found
+    for id in &ids[30..40] {
+        db.delete(SpecificVertexQuery::single(*id))?;
+    }
+    assert_eq!(ids_within_bounding_box(db, bbox(0.15, 0.45, 0.45, 0.85))?.len(), 8);
+    assert_eq!(ids_within_bounding_box(db, bbox(0.0, 0.0, 1.0, 1.0))?.len(), 90);
+    Ok(())
+}
+
+pub fn should_unindex_geo<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let location = Identifier::new("location")?;
+    let id = util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 1, "lon": 2}))])?;
+    db.index_property_geo(location)?;
+    db.unindex_property_geo(location)?;
+    assert!(db.geo_indexed_properties()?.is_empty());
+    let result = ids_within_radius(db, 1.0, 2.0, 10.0);
+    expect_err!(result, Error::NotIndexed);
+
+    // Reindexing picks the values back up
+    db.index_property_geo(location)?;
+    assert_eq!(ids_within_radius(db, 1.0, 2.0, 10.0)?, HashSet::from([id]));
+    Ok(())
+}
+
+pub fn should_rollback_geo_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let location = Identifier::new("location")?;
+    db.index_property_geo(location)?;
+    let id = util::create_vertex_with_properties(db, "venue", &[("location", ijson!({"lat": 1, "lon": 2}))])?;
+
+    let result: Result<(), Error> = db.transaction(|txn| {
+        txn.set_properties(SpecificVertexQuery::single(id), location, &ijson!({"lat": 3, "lon": 4}))?;
+        txn.unindex_property_geo(location)?;
+        Err(Error::OperationOnQuery)
+    });
+    expect_err!(result, Error::OperationOnQuery);
+
+    assert_eq!(ids_within_radius(db, 1.0, 2.0, 10.0)?, HashSet::from([id]));
+    assert!(ids_within_radius(db, 3.0, 4.0, 10.0)?.is_empty());
+    Ok(())
+}
+
+pub fn should_not_query_invalid_area<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    db.index_property_geo(Identifier::new("location")?)?;
+    let result = ids_within_bounding_box(db, bbox(10.0, 0.0, -10.0, 1.0));
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    let result = ids_within_bounding_box(db, bbox(0.0, 0.0, 1.0, 181.0));
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    let result = ids_within_radius(db, 0.0, 0.0, -1.0);
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    let result = ids_within_radius(db, 0.0, 0.0, f64::NAN);
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    let result = ids_within_radius(db, 91.0, 0.0, 1.0);
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use super::util;
 use crate::{
-    ijson, CountQueryExt, Database, Datastore, Edge, Error, Identifier, NamedProperty, QueryExt, QueryOutputValue,
-    SpecificVertexQuery, Vertex, VertexProperties,
+    ijson, CountQueryExt, Database, Datastore, Edge, Error, Identifier, IndexScope, NamedProperty, QueryExt,
+    QueryOutputValue, QueryOutputValueIter, SpecificVertexQuery, Vertex, VertexProperties,
 };
 
 pub fn should_get_nested_include_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
@@ -31,6 +31,40 @@ pub fn should_get_nested_include_query<D: Datastore>(db: &Database<D>) -> Result
     Ok(())
 }
 
+pub fn should_get_nested_include_query_lazily<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::single(outbound_id)
+        .include()
+        .outbound()?
+        .include()
+        .count()?;
+    let output = db.get_iter(q, |output| {
+        output
+            .into_iter()
+            .map(QueryOutputValueIter::into_value)
+            .collect::<Result<Vec<_>, Error>>()
+    })?;
+    assert_eq!(output.len(), 3);
+    assert_eq!(
+        output[0],
+        QueryOutputValue::Vertices(vec![Vertex::with_id(
+            outbound_id,
+            Identifier::new("test_outbound_vertex_type")?
+        )])
+    );
+    assert_eq!(
+        output[1],
+        QueryOutputValue::Edges(
+            inbound_ids
+                .into_iter()
+                .map(|id| Edge::new(outbound_id, Identifier::new("test_edge_type").unwrap(), id))
+                .collect()
+        )
+    );
+    assert_eq!(output[2], QueryOutputValue::Count(5));
+    Ok(())
+}
+
 pub fn should_get_unnested_include_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
     let id = db.create_vertex_from_type(Identifier::new("foo")?)?;
     let q = SpecificVertexQuery::single(id);
@@ -52,7 +86,7 @@ pub fn should_get_unnested_include_query<D: Datastore>(db: &Database<D>) -> Resu
 pub fn should_include_with_property_presence<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
     let id = db.create_vertex_from_type(Identifier::new("foo")?)?;
     let q = SpecificVertexQuery::single(id);
-    db.index_property(Identifier::new("bar")?)?;
+    db.index_property(Identifier::new("bar")?, IndexScope::All)?;
     db.set_properties(q.clone(), Identifier::new("bar")?, &ijson!(true))?;
     let output = db.get(q.clone().include().with_property(Identifier::new("bar")?)?)?;
     assert_eq!(
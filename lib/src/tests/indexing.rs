@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+
 use super::util;
 use crate::{expect_err, ijson, models, Database, Datastore, Error, QueryExt};
 use uuid::Uuid;
@@ -6,7 +8,7 @@ fn setup_vertex_with_indexed_property<D: Datastore>(
     db: &Database<D>,
     property_name: models::Identifier,
 ) -> Result<Uuid, Error> {
-    db.index_property(property_name)?;
+    db.index_property(property_name, models::IndexScope::All)?;
     let id = db.create_vertex_from_type(models::Identifier::new("test_vertex_type")?)?;
     let q = models::SpecificVertexQuery::single(id);
     db.set_properties(q, property_name, &ijson!(true))?;
@@ -17,7 +19,7 @@ fn setup_edge_with_indexed_property<D: Datastore>(
     db: &Database<D>,
     property_name: models::Identifier,
 ) -> Result<models::Edge, Error> {
-    db.index_property(property_name)?;
+    db.index_property(property_name, models::IndexScope::All)?;
     let vertex_t = models::Identifier::new("test_vertex_type")?;
     let outbound_id = db.create_vertex_from_type(vertex_t)?;
     let inbound_id = db.create_vertex_from_type(vertex_t)?;
@@ -56,7 +58,7 @@ pub fn should_index_existing_vertex_property<D: Datastore>(db: &Database<D>) ->
     db.set_properties(q.clone(), property_name, &ijson!(true))?;
 
     // Index property
-    db.index_property(property_name)?;
+    db.index_property(property_name, models::IndexScope::All)?;
 
     // Get the vertex
     let result = util::get_vertices(db, models::VertexWithPropertyPresenceQuery::new(property_name))?;
@@ -73,7 +75,7 @@ pub fn should_index_existing_vertex_property<D: Datastore>(db: &Database<D>) ->
     // Check against another property
     let result = util::get_vertices(db, q.clone().without_property(other_property_name)?);
     expect_err!(result, Error::NotIndexed);
-    db.index_property(other_property_name.clone())?;
+    db.index_property(other_property_name.clone(), models::IndexScope::All)?;
     let result = util::get_vertices(db, q.without_property(other_property_name)?)?;
     assert_eq!(result.len(), 1);
     assert_eq!(result[0].id, id);
@@ -95,7 +97,7 @@ pub fn should_index_existing_edge_property<D: Datastore>(db: &Database<D>) -> Re
     db.set_properties(q.clone(), property_name, &ijson!(true))?;
 
     // Index property
-    db.index_property(property_name)?;
+    db.index_property(property_name, models::IndexScope::All)?;
 
     // Get the edge
     let result = util::get_edges(db, models::EdgeWithPropertyPresenceQuery::new(property_name))?;
@@ -112,7 +114,7 @@ pub fn should_index_existing_edge_property<D: Datastore>(db: &Database<D>) -> Re
     // Check against another property
     let result = util::get_edges(db, q.clone().without_property(other_property_name)?);
     expect_err!(result, Error::NotIndexed);
-    db.index_property(other_property_name)?;
+    db.index_property(other_property_name, models::IndexScope::All)?;
     let result = util::get_edges(db, q.without_property(other_property_name)?)?;
     assert_eq!(result.len(), 1);
     assert_eq!(result[0], edge);
@@ -229,9 +231,96 @@ pub fn should_update_indexed_edge_property<D: Datastore>(db: &Database<D>) -> Re
     Ok(())
 }
 
+pub fn should_unindex_property<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let property_name = models::Identifier::new("unindexable-property")?;
+    let other_property_name = models::Identifier::new("some-other-property")?;
+    let id = setup_vertex_with_indexed_property(db, property_name)?;
+    let edge = setup_edge_with_indexed_property(db, property_name)?;
+    db.index_property(other_property_name, models::IndexScope::All)?;
+    assert_eq!(
+        db.indexed_properties()?,
+        HashMap::from([
+            (property_name, HashSet::from([models::IndexScope::All])),
+            (other_property_name, HashSet::from([models::IndexScope::All]))
+        ])
+    );
+
+    db.unindex_property(property_name, models::IndexScope::All)?;
+    assert_eq!(
+        db.indexed_properties()?,
+        HashMap::from([(other_property_name, HashSet::from([models::IndexScope::All]))])
+    );
+    let result = util::get_vertices(db, models::VertexWithPropertyPresenceQuery::new(property_name));
+    expect_err!(result, Error::NotIndexed);
+    let result = util::get_edges(db, models::EdgeWithPropertyPresenceQuery::new(property_name));
+    expect_err!(result, Error::NotIndexed);
+
+    // Unindexing shouldn't touch the properties themselves
+    let q = models::SpecificVertexQuery::single(id)
+        .properties()?
+        .name(property_name);
+    assert_eq!(util::get_vertex_properties(db, q)?.len(), 1);
+    let q = models::SpecificEdgeQuery::single(edge)
+        .properties()?
+        .name(property_name);
+    assert_eq!(util::get_edge_properties(db, q)?.len(), 1);
+
+    // Unindexing a property that isn't indexed is a no-op
+    db.unindex_property(property_name, models::IndexScope::All)?;
+    assert_eq!(
+        db.indexed_properties()?,
+        HashMap::from([(other_property_name, HashSet::from([models::IndexScope::All]))])
+    );
+    Ok(())
+}
+
+pub fn should_reindex_property<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let property_name = models::Identifier::new("reindexable-property")?;
+    let id = setup_vertex_with_indexed_property(db, property_name)?;
+    db.unindex_property(property_name, models::IndexScope::All)?;
+
+    // Changes made while the property is unindexed should be picked up, with
+    // nothing left over from the old index
+    let q = models::SpecificVertexQuery::single(id);
+    db.set_properties(q, property_name, &ijson!(false))?;
+    db.index_property(property_name, models::IndexScope::All)?;
+    let result = util::get_vertices(
+        db,
+        models::VertexWithPropertyValueQuery::new(property_name, ijson!(true)),
+    )?;
+    assert!(result.is_empty());
+    let result = util::get_vertices(
+        db,
+        models::VertexWithPropertyValueQuery::new(property_name, ijson!(false)),
+    )?;
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].id, id);
+    Ok(())
+}
+
+pub fn should_rollback_unindexing_property<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let property_name = models::Identifier::new("unindexable-property")?;
+    let id = setup_vertex_with_indexed_property(db, property_name)?;
+
+    let result: Result<(), Error> = db.transaction(|txn| {
+        txn.unindex_property(property_name, models::IndexScope::All)?;
+        assert!(txn.indexed_properties()?.is_empty());
+        Err(Error::OperationOnQuery)
+    });
+    expect_err!(result, Error::OperationOnQuery);
+
+    let result = util::get_vertices(
+        db,
+        models::VertexWithPropertyValueQuery::new(property_name, ijson!(true)),
+    )?;
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].id, id);
+    Ok(())
+}
+
 pub fn should_query_indexed_vertex_property_empty<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
     let property_name = models::Identifier::new("queryable-vertex-property")?;
-    db.index_property(property_name)?;
+    db.index_property(property_name, models::IndexScope::All)?;
     let result = util::get_vertices(db, models::VertexWithPropertyPresenceQuery::new(property_name))?;
     assert_eq!(result.len(), 0);
     Ok(())
@@ -239,7 +328,7 @@ pub fn should_query_indexed_vertex_property_empty<D: Datastore>(db: &Database<D>
 
 pub fn should_query_indexed_edge_property_empty<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
     let property_name = models::Identifier::new("queryable-edge-property")?;
-    db.index_property(property_name)?;
+    db.index_property(property_name, models::IndexScope::All)?;
     let result = util::get_edges(db, models::EdgeWithPropertyPresenceQuery::new(property_name))?;
     assert_eq!(result.len(), 0);
     Ok(())
@@ -249,7 +338,7 @@ pub fn should_query_indexed_edge_property_empty<D: Datastore>(db: &Database<D>)
 /// https://github.com/indradb/indradb/issues/278
 pub fn should_get_vertex_with_property_value_empty<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
     let property_name = models::Identifier::new("II")?;
-    db.index_property(property_name)?;
+    db.index_property(property_name, models::IndexScope::All)?;
     let results = util::get_vertices(
         db,
         models::VertexWithPropertyValueQuery::new(property_name, ijson!(null)),
@@ -274,3 +363,132 @@ pub fn should_pipe_not_indexed_errors<D: Datastore>(db: &Database<D>) -> Result<
 
     Ok(())
 }
+
+// Creates a vertex of each of two types, both with the property set.
+fn setup_vertices_with_scoped_property<D: Datastore>(
+    db: &Database<D>,
+    property_name: models::Identifier,
+) -> Result<(Uuid, Uuid), Error> {
+    let user_id = db.create_vertex_from_type(models::Identifier::new("user")?)?;
+    let org_id = db.create_vertex_from_type(models::Identifier::new("org")?)?;
+    let q = models::SpecificVertexQuery::new(vec![user_id, org_id]);
+    db.set_properties(q, property_name, &ijson!("bob"))?;
+    Ok((user_id, org_id))
+}
+
+pub fn should_query_vertex_type_scoped_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let property_name = models::Identifier::new("scoped-vertex-property")?;
+    let user_t = models::Identifier::new("user")?;
+    let org_t = models::Identifier::new("org")?;
+    let (existing_user_id, _) = setup_vertices_with_scoped_property(db, property_name)?;
+    db.index_property(property_name, models::IndexScope::VertexType(user_t))?;
+    let (user_id, _) = setup_vertices_with_scoped_property(db, property_name)?;
+
+    let q = models::VertexWithPropertyValueQuery::new(property_name, ijson!("bob"));
+    let result = util::get_vertices(db, q.clone().t(user_t))?;
+    let ids: HashSet<Uuid> = result.into_iter().map(|v| v.id).collect();
+    assert_eq!(ids, HashSet::from([existing_user_id, user_id]));
+    let result = util::get_vertices(
+        db,
+        models::VertexWithPropertyPresenceQuery::new(property_name).t(user_t),
+    )?;
+    assert_eq!(result.len(), 2);
+
+    // The index doesn't cover other types, or all vertices
+    let result = util::get_vertices(db, q.clone().t(org_t));
+    expect_err!(result, Error::NotIndexed);
+    let result = util::get_vertices(db, q.clone());
+    expect_err!(result, Error::NotIndexed);
+    let result = util::get_edges(db, models::EdgeWithPropertyPresenceQuery::new(property_name));
+    expect_err!(result, Error::NotIndexed);
+
+    // Piped queries can use it as long as every vertex is covered
+    let result = util::get_vertices(
+        db,
+        models::RangeVertexQuery::new()
+            .t(user_t)
+            .with_property_equal_to(property_name, ijson!("bob"))?,
+    )?;
+    assert_eq!(result.len(), 2);
+    let result = util::get_vertices(db, models::AllVertexQuery.with_property(property_name)?);
+    expect_err!(result, Error::NotIndexed);
+
+    // Updates and deletes are reflected in the index
+    db.set_properties(
+        models::SpecificVertexQuery::single(user_id),
+        property_name,
+        &ijson!("alice"),
+    )?;
+    db.delete(models::SpecificVertexQuery::single(existing_user_id))?;
+    let result = util::get_vertices(db, q.t(user_t))?;
+    assert!(result.is_empty());
+    let q = models::VertexWithPropertyValueQuery::new(property_name, ijson!("alice")).t(user_t);
+    let result = util::get_vertices(db, q)?;
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].id, user_id);
+    Ok(())
+}
+
+pub fn should_query_edge_type_scoped_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let property_name = models::Identifier::new("scoped-edge-property")?;
+    let follows_t = models::Identifier::new("follows")?;
+    let blocks_t = models::Identifier::new("blocks")?;
+    let (user_id, org_id) = setup_vertices_with_scoped_property(db, property_name)?;
+    let follows_edge = models::Edge::new(user_id, follows_t, org_id);
+    let blocks_edge = models::Edge::new(user_id, blocks_t, org_id);
+    db.create_edge(&follows_edge)?;
+    db.create_edge(&blocks_edge)?;
+    db.index_property(property_name, models::IndexScope::EdgeType(follows_t))?;
+    let q = models::SpecificEdgeQuery::new(vec![follows_edge.clone(), blocks_edge]);
+    db.set_properties(q, property_name, &ijson!(1))?;
+
+    let q = models::EdgeWithPropertyRangeQuery::new(property_name).gte(ijson!(0));
+    let result = util::get_edges(db, q.clone().t(follows_t))?;
+    assert_eq!(result, vec![follows_edge]);
+    let result = util::get_edges(db, q.clone().t(blocks_t));
+    expect_err!(result, Error::NotIndexed);
+    let result = util::get_edges(db, q);
+    expect_err!(result, Error::NotIndexed);
+
+    // A vertex type scope isn't used for edges of the same name
+    let result = util::get_vertices(
+        db,
+        models::VertexWithPropertyPresenceQuery::new(property_name).t(models::Identifier::new("user")?),
+    );
+    expect_err!(result, Error::NotIndexed);
+    Ok(())
+}
+
+pub fn should_unindex_property_scope<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let property_name = models::Identifier::new("multi-scoped-property")?;
+    let user_t = models::Identifier::new("user")?;
+    let (user_id, _) = setup_vertices_with_scoped_property(db, property_name)?;
+    db.index_property(property_name, models::IndexScope::All)?;
+    db.index_property(property_name, models::IndexScope::VertexType(user_t))?;
+    assert_eq!(
+        db.indexed_properties()?,
+        HashMap::from([(
+            property_name,
+            HashSet::from([models::IndexScope::All, models::IndexScope::VertexType(user_t)])
+        )])
+    );
+
+    let q = models::VertexWithPropertyPresenceQuery::new(property_name);
+    assert_eq!(util::get_vertices(db, q.clone())?.len(), 2);
+
+    // Removing the broader scope leaves the narrower one in place
+    db.unindex_property(property_name, models::IndexScope::All)?;
+    assert_eq!(
+        db.indexed_properties()?,
+        HashMap::from([(property_name, HashSet::from([models::IndexScope::VertexType(user_t)]))])
+    );
+    let result = util::get_vertices(db, q.clone());
+    expect_err!(result, Error::NotIndexed);
+    let result = util::get_vertices(db, q.t(user_t))?;
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].id, user_id);
+
+    db.unindex_property(property_name, models::IndexScope::VertexType(user_t))?;
+    assert!(db.indexed_properties()?.is_empty());
+    Ok(())
+}
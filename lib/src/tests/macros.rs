@@ -35,7 +35,11 @@ macro_rules! full_test_impl {
 
         // Vertices
         define_test!(should_create_vertex_from_type, $code);
+        define_test!(should_get_or_create_vertex, $code);
+        define_test!(should_not_get_or_create_vertex_unindexed, $code);
         define_test!(should_get_all_vertices, $code);
+        define_test!(should_get_all_vertices_lazily, $code);
+        define_test!(should_get_many_vertices, $code);
         define_test!(should_get_range_vertices, $code);
         define_test!(should_get_no_vertices_with_zero_limit, $code);
         define_test!(should_get_range_vertices_out_of_range, $code);
@@ -67,13 +71,27 @@ macro_rules! full_test_impl {
         define_test!(should_get_edge_range, $code);
         define_test!(should_get_edges, $code);
         define_test!(should_get_edges_piped, $code);
+        define_test!(should_resume_piped_edges, $code);
+        define_test!(should_resume_piped_edges_of_deleted_vertex, $code);
+        define_test!(should_get_edges_piped_with_types, $code);
+        define_test!(should_not_resume_piped_vertices, $code);
         define_test!(should_delete_indexed_edge_with_property_value, $code);
 
         // Include queries
         define_test!(should_get_nested_include_query, $code);
+        define_test!(should_get_nested_include_query_lazily, $code);
         define_test!(should_get_unnested_include_query, $code);
         define_test!(should_include_with_property_presence, $code);
 
+        // Traversals
+        define_test!(should_traverse_outbound, $code);
+        define_test!(should_traverse_inbound_with_type_filter, $code);
+        define_test!(should_traverse_from_multiple_vertices, $code);
+        define_test!(should_get_shortest_path, $code);
+        define_test!(should_get_inbound_shortest_path, $code);
+        define_test!(should_get_shortest_path_to_self, $code);
+        define_test!(should_not_get_shortest_path_between_unconnected_vertices, $code);
+
         // Indexing
         define_test!(should_not_query_unindexed_vertex_property, $code);
         define_test!(should_not_query_unindexed_edge_property, $code);
@@ -83,10 +101,97 @@ macro_rules! full_test_impl {
         define_test!(should_delete_indexed_edge_property, $code);
         define_test!(should_update_indexed_vertex_property, $code);
         define_test!(should_update_indexed_edge_property, $code);
+        define_test!(should_unindex_property, $code);
+        define_test!(should_reindex_property, $code);
         define_test!(should_query_indexed_vertex_property_empty, $code);
         define_test!(should_query_indexed_edge_property_empty, $code);
         define_test!(should_get_vertex_with_property_value_empty, $code);
         define_test!(should_pipe_not_indexed_errors, $code);
+        define_test!(should_query_vertex_type_scoped_index, $code);
+        define_test!(should_query_edge_type_scoped_index, $code);
+        define_test!(should_unindex_property_scope, $code);
+
+        // Property ranges
+        define_test!(should_get_vertices_with_property_range, $code);
+        define_test!(should_get_empty_property_range, $code);
+        define_test!(should_get_edges_with_property_range, $code);
+        define_test!(should_pipe_with_property_range, $code);
+        define_test!(should_not_query_unindexed_property_range, $code);
+
+        // String patterns
+        define_test!(should_pipe_with_property_prefix, $code);
+        define_test!(should_pipe_with_property_prefix_unindexed, $code);
+        define_test!(should_pipe_with_property_prefix_scoped_index, $code);
+        define_test!(should_pipe_with_property_glob, $code);
+        define_test!(should_pipe_with_property_regex, $code);
+
+        // Composite indexes
+        define_test!(should_query_composite_index, $code);
+        define_test!(should_update_composite_index, $code);
+        define_test!(should_unindex_composite, $code);
+        define_test!(should_not_index_invalid_composite, $code);
+
+        // Full-text indexes
+        define_test!(should_query_fulltext_index, $code);
+        define_test!(should_update_fulltext_index, $code);
+        define_test!(should_unindex_fulltext, $code);
+        define_test!(should_not_query_fulltext_without_terms, $code);
+
+        // Vector indexes
+        define_test!(should_query_nearest_vertices, $code);
+        define_test!(should_update_vector_index, $code);
+        define_test!(should_unindex_vector, $code);
+        define_test!(should_not_query_nearest_vertices_with_invalid_vector, $code);
+
+        // Geospatial indexes
+        define_test!(should_query_within_bounding_box, $code);
+        define_test!(should_query_within_radius, $code);
+        define_test!(should_pipe_within_radius, $code);
+        define_test!(should_update_geo_index, $code);
+        define_test!(should_query_many_geo_points, $code);
+        define_test!(should_unindex_geo, $code);
+        define_test!(should_not_query_invalid_area, $code);
+
+        // Ordered queries
+        define_test!(should_page_through_ordered_vertices, $code);
+        define_test!(should_order_values_of_different_types, $code);
+        define_test!(should_order_edges, $code);
+        define_test!(should_pipe_from_ordered_query, $code);
+        define_test!(should_not_order_with_invalid_cursor, $code);
+
+        // Aggregates
+        define_test!(should_aggregate_properties, $code);
+        define_test!(should_aggregate_nothing, $code);
+        define_test!(should_sum_integers_as_integers, $code);
+        define_test!(should_aggregate_edge_properties, $code);
+        define_test!(should_group_by_type, $code);
+        define_test!(should_group_by_property, $code);
+
+        // Text queries
+        define_test!(should_query_str, $code);
+        define_test!(should_not_query_invalid_str, $code);
+
+        // Degrees
+        define_test!(should_get_degrees, $code);
+        define_test!(should_not_count_edges_of_adjacent_vertices, $code);
+        define_test!(should_include_degree_query_vertices, $code);
+
+        // Query plans
+        define_test!(should_push_filter_into_index_lookup, $code);
+        define_test!(should_reorder_filters, $code);
+        define_test!(should_check_properties_of_few_vertices, $code);
+
+        // Stats
+        define_test!(should_get_empty_stats, $code);
+        define_test!(should_count_types, $code);
+        define_test!(should_count_property_values, $code);
+
+        // Unique constraints
+        define_test!(should_enforce_unique_constraint, $code);
+        define_test!(should_release_unique_values, $code);
+        define_test!(should_enforce_unique_constraint_on_bulk_insert, $code);
+        define_test!(should_not_add_violated_unique_constraint, $code);
+        define_test!(should_remove_unique_constraint, $code);
 
         // Properties
         define_test!(should_handle_vertex_properties, $code);
@@ -102,5 +207,25 @@ macro_rules! full_test_impl {
         define_test!(should_not_set_properties_on_count, $code);
         define_test!(should_not_pipe_properties_on_vertex_count, $code);
         define_test!(should_not_pipe_property_presence_on_vertex_count, $code);
+
+        // Transactions
+        define_test!(should_commit_transaction, $code);
+    };
+}
+
+/// Use this macro to enable the tests of rolling back transactions, for
+/// datastores that support it.
+#[macro_export]
+macro_rules! rollback_test_impl {
+    ($code:expr) => {
+        define_test!(should_rollback_transaction, $code);
+        define_test!(should_rollback_dropped_transaction, $code);
+        define_test!(should_rollback_unindexing_property, $code);
+        define_test!(should_rollback_composite_index, $code);
+        define_test!(should_rollback_fulltext_index, $code);
+        define_test!(should_rollback_vector_index, $code);
+        define_test!(should_rollback_geo_index, $code);
+        define_test!(should_rollback_unique_constraint, $code);
+        define_test!(should_not_count_rolled_back_writes, $code);
     };
 }
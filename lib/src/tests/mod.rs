@@ -2,23 +2,54 @@
 //!
 //! These are exported so that datastore implementations outside of the
 //! `indradb` crate can reuse them. Generally you can use the convenience macro
-//! `full_test_impl`.
+//! `full_test_impl`, along with `rollback_test_impl` for datastores that
+//! support rolling back transactions.
 
+mod aggregate;
 mod bulk_insert;
+mod composite;
+mod constraint;
+mod degree;
 mod edge;
+mod fulltext;
+mod geo;
 mod include_query;
 mod indexing;
 #[macro_use]
 mod macros;
+mod order;
+mod pattern;
+mod plan;
 mod properties;
+mod range;
+mod stats;
 mod sync;
+mod text;
+mod transaction;
+mod traversal;
 mod util;
+mod vector;
 mod vertex;
 
+pub use self::aggregate::*;
 pub use self::bulk_insert::*;
+pub use self::composite::*;
+pub use self::constraint::*;
+pub use self::degree::*;
 pub use self::edge::*;
+pub use self::fulltext::*;
+pub use self::geo::*;
 pub use self::include_query::*;
 pub use self::indexing::*;
+pub use self::order::*;
+pub use self::pattern::*;
+pub use self::plan::*;
 pub use self::properties::*;
+pub use self::range::*;
+pub use self::stats::*;
 pub use self::sync::*;
+pub use self::text::*;
+pub use self::transaction::*;
+pub use self::traversal::*;
+pub use self::vector::*;
 pub use self::vertex::*;
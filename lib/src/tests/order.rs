@@ -0,0 +1,152 @@
+use super::util;
+use crate::util::{extract_cursor, extract_edges, extract_vertices};
+use crate::{
+    ijson, Cursor, Database, Datastore, Edge, Error, Identifier, OrderByQuery, QueryExt, SortDirection,
+    SpecificEdgeQuery, SpecificVertexQuery, ValidationError,
+};
+
+use uuid::Uuid;
+
+fn get_vertex_page<D: Datastore>(db: &Database<D>, q: OrderByQuery) -> Result<(Vec<Uuid>, Option<Cursor>), Error> {
+    let output = db.get(q)?;
+    let cursor = extract_cursor(&output);
+    let ids = extract_vertices(output).unwrap().into_iter().map(|v| v.id).collect();
+    Ok((ids, cursor))
+}
+
+fn get_all_pages<D: Datastore>(
+    db: &Database<D>,
+    ids: &[Uuid],
+    direction: SortDirection,
+    limit: u32,
+) -> Result<Vec<Vec<Uuid>>, Error> {
+    let q = SpecificVertexQuery::new(ids.to_vec())
+        .order_by(Identifier::new("score")?, direction)?
+        .limit(limit);
+    let mut pages = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page_q = match cursor {
+            Some(cursor) => q.clone().after(cursor),
+            None => q.clone(),
+        };
+        let (page, next_cursor) = get_vertex_page(db, page_q)?;
+        pages.push(page);
+        if next_cursor.is_none() {
+            return Ok(pages);
+        }
+        cursor = next_cursor;
+    }
+}
+
+pub fn should_page_through_ordered_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let high = util::create_vertex_with_properties(db, "user", &[("score", ijson!(3))])?;
+    let low = util::create_vertex_with_properties(db, "user", &[("score", ijson!(1))])?;
+    let middle = util::create_vertex_with_properties(db, "user", &[("score", ijson!(2.5))])?;
+    let other_low = util::create_vertex_with_properties(db, "user", &[("score", ijson!(1))])?;
+    let missing = util::create_vertex_with_properties(db, "user", &[])?;
+    let ids = [high, low, middle, other_low, missing];
+    let (first_low, second_low) = if low < other_low {
+        (low, other_low)
+    } else {
+        (other_low, low)
+    };
+
+    // Ties are broken by ID, and items without the property are last
+    assert_eq!(
+        get_all_pages(db, &ids, SortDirection::Ascending, 2)?,
+        vec![vec![first_low, second_low], vec![middle, high], vec![missing]]
+    );
+    assert_eq!(
+        get_all_pages(db, &ids, SortDirection::Descending, 2)?,
+        vec![vec![high, middle], vec![first_low, second_low], vec![missing]]
+    );
+    assert_eq!(
+        get_all_pages(db, &ids, SortDirection::Descending, 10)?,
+        vec![vec![high, middle, first_low, second_low, missing]]
+    );
+
+    // A page that ends exactly at the last item has no cursor
+    assert_eq!(
+        get_all_pages(db, &ids, SortDirection::Ascending, 5)?,
+        vec![vec![first_low, second_low, middle, high, missing]]
+    );
+    Ok(())
+}
+
+pub fn should_order_values_of_different_types<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let string = util::create_vertex_with_properties(db, "user", &[("score", ijson!("10"))])?;
+    let number = util::create_vertex_with_properties(db, "user", &[("score", ijson!(10))])?;
+    let null = util::create_vertex_with_properties(db, "user", &[("score", ijson!(null))])?;
+    let boolean = util::create_vertex_with_properties(db, "user", &[("score", ijson!(true))])?;
+    assert_eq!(
+        get_all_pages(db, &[string, number, null, boolean], SortDirection::Ascending, 1)?,
+        vec![vec![null], vec![boolean], vec![number], vec![string]]
+    );
+    Ok(())
+}
+
+pub fn should_order_edges<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let t = Identifier::new("test_edge_type")?;
+    let weight = Identifier::new("weight")?;
+    let edges: Vec<Edge> = inbound_ids.iter().map(|id| Edge::new(outbound_id, t, *id)).collect();
+    for (i, edge) in edges.iter().enumerate() {
+        db.set_properties(SpecificEdgeQuery::single(edge.clone()), weight, &ijson!(i))?;
+    }
+
+    let q = SpecificEdgeQuery::new(edges.clone())
+        .order_by(weight, SortDirection::Descending)?
+        .limit(3);
+    let output = db.get(q.clone())?;
+    let cursor = extract_cursor(&output).unwrap();
+    assert_eq!(
+        extract_edges(output).unwrap(),
+        vec![edges[4].clone(), edges[3].clone(), edges[2].clone()]
+    );
+
+    let output = db.get(q.after(cursor))?;
+    assert_eq!(extract_cursor(&output), None);
+    assert_eq!(extract_edges(output).unwrap(), vec![edges[1].clone(), edges[0].clone()]);
+    Ok(())
+}
+
+pub fn should_pipe_from_ordered_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let low = util::create_vertex_with_properties(db, "user", &[("score", ijson!(1))])?;
+    let high = util::create_vertex_with_properties(db, "user", &[("score", ijson!(2))])?;
+    util::create_edge_from(db, low)?;
+    util::create_edge_from(db, high)?;
+
+    let q = SpecificVertexQuery::new(vec![low, high])
+        .order_by(Identifier::new("score")?, SortDirection::Descending)?
+        .limit(1)
+        .outbound()?;
+    let output = db.get(q)?;
+    assert!(extract_cursor(&output).is_some());
+    let edges = extract_edges(output).unwrap();
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0].outbound_id, high);
+    Ok(())
+}
+
+pub fn should_not_order_with_invalid_cursor<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let id = util::create_vertex_with_properties(db, "user", &[("score", ijson!(1))])?;
+    let other_id = util::create_vertex_with_properties(db, "user", &[("score", ijson!(2))])?;
+    let score = Identifier::new("score")?;
+
+    let q = SpecificVertexQuery::new(vec![id]).order_by(score, SortDirection::Ascending)?;
+    let result = db.get(q.clone().after(Cursor::from("not a cursor".to_string())));
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+
+    // Cursors from an edge query can't be used for a vertex query
+    util::create_edge_from(db, id)?;
+    util::create_edge_from(db, other_id)?;
+    let edge_q = SpecificVertexQuery::new(vec![id, other_id])
+        .outbound()?
+        .order_by(score, SortDirection::Ascending)?
+        .limit(1);
+    let cursor = extract_cursor(&db.get(edge_q)?).unwrap();
+    let result = db.get(q.after(cursor));
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    Ok(())
+}
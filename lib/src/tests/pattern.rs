@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use super::util;
+use crate::{
+    ijson, AllEdgeQuery, AllVertexQuery, Database, Datastore, Edge, Error, Identifier, IndexScope, QueryExt,
+    SpecificEdgeQuery,
+};
+
+// Checks prefix lookups, which are answered differently depending on how
+// the property is indexed.
+fn check_prefix_lookups<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("name")?;
+    let ada_lovelace = util::create_vertex_with_properties(db, "user", &[("name", ijson!("Ada Lovelace"))])?;
+    let adam = util::create_vertex_with_properties(db, "user", &[("name", ijson!("Adam"))])?;
+    let adelaide = util::create_vertex_with_properties(db, "city", &[("name", ijson!("Adelaide"))])?;
+    let last = util::create_vertex_with_properties(db, "user", &[("name", ijson!("Ad\u{10ffff}"))])?;
+    util::create_vertex_with_properties(db, "user", &[("name", ijson!("ada"))])?;
+    util::create_vertex_with_properties(db, "user", &[("name", ijson!("Ac"))])?;
+    util::create_vertex_with_properties(db, "user", &[("name", ijson!("Ae"))])?;
+    util::create_vertex_with_properties(db, "user", &[("name", ijson!(["Ada"]))])?;
+    db.create_vertex_from_type(Identifier::new("user")?)?;
+
+    let q = AllVertexQuery.with_property_starting_with(name, "Ada")?;
+    assert_eq!(util::get_vertex_ids(db, q)?, HashSet::from([ada_lovelace, adam]));
+    let q = AllVertexQuery.with_property_starting_with(name, "Ad")?;
+    assert_eq!(
+        util::get_vertex_ids(db, q)?,
+        HashSet::from([ada_lovelace, adam, adelaide, last])
+    );
+    let q = AllVertexQuery.with_property_starting_with(name, "Ad\u{10ffff}")?;
+    assert_eq!(util::get_vertex_ids(db, q)?, HashSet::from([last]));
+    let q = AllVertexQuery.with_property_starting_with(name, "Adx")?;
+    assert!(util::get_vertex_ids(db, q)?.is_empty());
+    Ok(())
+}
+
+pub fn should_pipe_with_property_prefix<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    db.index_property(Identifier::new("name")?, IndexScope::All)?;
+    check_prefix_lookups(db)
+}
+
+pub fn should_pipe_with_property_prefix_unindexed<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    check_prefix_lookups(db)
+}
+
+pub fn should_pipe_with_property_prefix_scoped_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    // Cities aren't covered by the index, so their names are checked instead
+    db.index_property(
+        Identifier::new("name")?,
+        IndexScope::VertexType(Identifier::new("user")?),
+    )?;
+    check_prefix_lookups(db)
+}
+
+pub fn should_pipe_with_property_glob<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("name")?;
+    let ada_lovelace = util::create_vertex_with_properties(db, "user", &[("name", ijson!("Ada Lovelace"))])?;
+    let alan_turing = util::create_vertex_with_properties(db, "user", &[("name", ijson!("Alan Turing"))])?;
+    let a_b = util::create_vertex_with_properties(db, "user", &[("name", ijson!("A.B"))])?;
+    util::create_vertex_with_properties(db, "user", &[("name", ijson!("AxB"))])?;
+    util::create_vertex_with_properties(db, "user", &[("name", ijson!("ada lovelace"))])?;
+
+    let q = AllVertexQuery.with_property_matching_glob(name, "A*a*")?;
+    assert_eq!(util::get_vertex_ids(db, q)?, HashSet::from([ada_lovelace, alan_turing]));
+    let q = AllVertexQuery.with_property_matching_glob(name, "A?a? *")?;
+    assert_eq!(util::get_vertex_ids(db, q)?, HashSet::from([alan_turing]));
+
+    // Anything other than a wildcard matches literally
+    let q = AllVertexQuery.with_property_matching_glob(name, "A.B")?;
+    assert_eq!(util::get_vertex_ids(db, q)?, HashSet::from([a_b]));
+    Ok(())
+}
+
+pub fn should_pipe_with_property_regex<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let outbound_id = db.create_vertex_from_type(Identifier::new("user")?)?;
+    let inbound_id = db.create_vertex_from_type(Identifier::new("user")?)?;
+    let name = Identifier::new("name")?;
+    let mut edges = Vec::new();
+    for (t, value) in [
+        ("follows", ijson!("since 2019")),
+        ("likes", ijson!("since 1999")),
+        ("blocks", ijson!(2019)),
+    ] {
+        let edge = Edge::new(outbound_id, Identifier::new(t)?, inbound_id);
+        db.create_edge(&edge)?;
+        db.set_properties(SpecificEdgeQuery::single(edge.clone()), name, &value)?;
+        edges.push(edge);
+    }
+
+    let q = AllEdgeQuery.with_property_matching_regex(name, "20[0-9]{2}")?;
+    assert_eq!(util::get_edges(db, q)?, vec![edges[0].clone()]);
+    let q = AllEdgeQuery.with_property_matching_regex(name, "^since")?;
+    assert_eq!(util::get_edges(db, q)?.len(), 2);
+
+    // The filter can be piped from
+    let q = AllEdgeQuery.with_property_matching_regex(name, "1999")?.inbound()?;
+    assert_eq!(util::get_vertices(db, q)?.len(), 1);
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use crate::util::extract_vertices;
+use crate::{
+    ijson, Database, Datastore, Error, FilterStrategy, Identifier, IndexScope, PlanStep, Query, QueryExt,
+    RangeVertexQuery, SpecificVertexQuery, VertexWithPropertyValueQuery,
+};
+
+fn step_names(steps: &[PlanStep]) -> Vec<&str> {
+    steps.iter().map(|step| step.name.as_str()).collect()
+}
+
+pub fn should_push_filter_into_index_lookup<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let user = Identifier::new("user")?;
+    let name = Identifier::new("name")?;
+    db.index_property(name, IndexScope::All)?;
+    let alice_id = db.create_vertex_from_type(user)?;
+    let post_id = db.create_vertex_from_type(Identifier::new("post")?)?;
+    db.set_properties(SpecificVertexQuery::single(alice_id), name, &ijson!("alice"))?;
+    db.set_properties(SpecificVertexQuery::single(post_id), name, &ijson!("alice"))?;
+
+    let q = RangeVertexQuery::new()
+        .t(user)
+        .with_property_equal_to(name, ijson!("alice"))?;
+    let plan = db.explain(q.clone())?;
+    let expected: Query = VertexWithPropertyValueQuery::new(name, ijson!("alice")).t(user).into();
+    assert_eq!(plan.query, expected);
+    assert_eq!(step_names(&plan.steps), vec!["VertexWithPropertyValue"]);
+
+    // The rewritten query gets the same vertices
+    let vertices = extract_vertices(db.get(q)?).unwrap();
+    assert_eq!(vertices.len(), 1);
+    assert_eq!(vertices[0].id, alice_id);
+    Ok(())
+}
+
+pub fn should_reorder_filters<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let active = Identifier::new("active")?;
+    let role = Identifier::new("role")?;
+    db.index_property(active, IndexScope::All)?;
+    db.index_property(role, IndexScope::All)?;
+    let id = db.create_vertex_from_type(Identifier::new("user")?)?;
+    db.set_properties(SpecificVertexQuery::single(id), active, &ijson!(true))?;
+    db.set_properties(SpecificVertexQuery::single(id), role, &ijson!("admin"))?;
+
+    let q = SpecificVertexQuery::single(id)
+        .with_property(active)?
+        .with_property_equal_to(role, ijson!("admin"))?;
+    let plan = db.explain(q)?;
+    assert_eq!(
+        step_names(&plan.steps),
+        vec!["SpecificVertex", "PipeWithPropertyValue", "PipeWithPropertyPresence"]
+    );
+    assert_eq!(plan.steps[0].estimated_rows, Some(1));
+    Ok(())
+}
+
+pub fn should_check_properties_of_few_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let user = Identifier::new("user")?;
+    let active = Identifier::new("active")?;
+    db.index_property(active, IndexScope::All)?;
+    let mut ids = Vec::new();
+    for _ in 0..5 {
+        let id = db.create_vertex_from_type(user)?;
+        db.set_properties(SpecificVertexQuery::single(id), active, &ijson!(true))?;
+        ids.push(id);
+    }
+    db.set_properties(SpecificVertexQuery::single(ids[0]), active, &ijson!(false))?;
+
+    // Two piped vertices are fewer than the four in the index with the
+    // value, so each vertex's property is read instead
+    let q = SpecificVertexQuery::new(ids[..2].to_vec()).with_property_equal_to(active, ijson!(true))?;
+    let plan = db.explain(q.clone())?;
+    assert_eq!(plan.steps.len(), 2);
+    assert_eq!(plan.steps[0].estimated_rows, Some(2));
+    assert_eq!(plan.steps[1].strategy, Some(FilterStrategy::PropertyChecks));
+    assert_eq!(plan.steps[1].estimated_rows, Some(2));
+
+    let vertices = extract_vertices(db.get(q)?).unwrap();
+    assert_eq!(vertices.len(), 1);
+    assert_eq!(vertices[0].id, ids[1]);
+
+    // Uncovered vertices still error, whichever way they're filtered
+    let result = db.get(SpecificVertexQuery::single(ids[0]).with_property(Identifier::new("unindexed")?)?);
+    expect_err!(result, Error::NotIndexed);
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use super::util;
+use crate::{
+    ijson, AllEdgeQuery, AllVertexQuery, Database, Datastore, Edge, EdgeWithPropertyRangeQuery, Error, Identifier,
+    IndexScope, Json, QueryExt, SpecificEdgeQuery, SpecificVertexQuery, VertexWithPropertyRangeQuery,
+};
+
+use uuid::Uuid;
+
+// Creates a vertex for each value, with the value set on an indexed `age`
+// property, along with a vertex that doesn't have the property at all.
+fn create_vertices_with_ages<D: Datastore>(db: &Database<D>, ages: &[Json]) -> Result<Vec<Uuid>, Error> {
+    db.index_property(Identifier::new("age")?, IndexScope::All)?;
+    db.create_vertex_from_type(Identifier::new("test_vertex_type")?)?;
+    ages.iter()
+        .map(|age| util::create_vertex_with_properties(db, "test_vertex_type", &[("age", age.clone())]))
+        .collect()
+}
+
+fn ages() -> Vec<Json> {
+    vec![
+        ijson!(-3),
+        ijson!(5),
+        ijson!(10),
+        ijson!(10.5),
+        ijson!(20),
+        ijson!("30"),
+        ijson!(null),
+    ]
+}
+
+fn select(ids: &[Uuid], indices: &[usize]) -> HashSet<Uuid> {
+    indices.iter().map(|i| ids[*i]).collect()
+}
+
+pub fn should_get_vertices_with_property_range<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_vertices_with_ages(db, &ages())?;
+    let q = VertexWithPropertyRangeQuery::new(Identifier::new("age")?);
+
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().gt(ijson!(10)))?,
+        select(&ids, &[3, 4])
+    );
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().gte(ijson!(10)))?,
+        select(&ids, &[2, 3, 4])
+    );
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().lt(ijson!(10)))?,
+        select(&ids, &[0, 1])
+    );
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().lte(ijson!(10)))?,
+        select(&ids, &[0, 1, 2])
+    );
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().between(ijson!(5), ijson!(20)))?,
+        select(&ids, &[1, 2, 3, 4])
+    );
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().gt(ijson!(-3.5)).lt(ijson!(5)))?,
+        select(&ids, &[0])
+    );
+
+    // Integers and floats of the same value compare equal
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().between(ijson!(10.0), ijson!(10.0)))?,
+        select(&ids, &[2])
+    );
+
+    // Values of other types are excluded
+    assert_eq!(util::get_vertex_ids(db, q.clone().lt(ijson!("a")))?, select(&ids, &[5]));
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().gte(ijson!(null)))?,
+        select(&ids, &[6])
+    );
+
+    // An unbounded range includes everything with the property
+    assert_eq!(util::get_vertex_ids(db, q)?, select(&ids, &[0, 1, 2, 3, 4, 5, 6]));
+    Ok(())
+}
+
+pub fn should_get_empty_property_range<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    create_vertices_with_ages(db, &ages())?;
+    let q = VertexWithPropertyRangeQuery::new(Identifier::new("age")?);
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().gt(ijson!(20)).lt(ijson!(10)))?,
+        HashSet::new()
+    );
+    assert_eq!(
+        util::get_vertex_ids(db, q.clone().gt(ijson!(10)).lt(ijson!(10)))?,
+        HashSet::new()
+    );
+    assert_eq!(
+        util::get_vertex_ids(db, q.between(ijson!(5), ijson!("30")))?,
+        HashSet::new()
+    );
+    Ok(())
+}
+
+pub fn should_get_edges_with_property_range<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("weight")?;
+    db.index_property(name, IndexScope::All)?;
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let t = Identifier::new("test_edge_type")?;
+    let edges: Vec<Edge> = inbound_ids.iter().map(|id| Edge::new(outbound_id, t, *id)).collect();
+    for (i, edge) in edges.iter().enumerate() {
+        db.set_properties(SpecificEdgeQuery::single(edge.clone()), name, &ijson!(i as f64 / 2.0))?;
+    }
+
+    let q = EdgeWithPropertyRangeQuery::new(name).gte(ijson!(0.5)).lt(ijson!(2));
+    let range: HashSet<Edge> = util::get_edges(db, q)?.into_iter().collect();
+    assert_eq!(
+        range,
+        HashSet::from([edges[1].clone(), edges[2].clone(), edges[3].clone()])
+    );
+
+    let q = EdgeWithPropertyRangeQuery::new(name).gt(ijson!(1));
+    let range: HashSet<Edge> = util::get_edges(db, q)?.into_iter().collect();
+    assert_eq!(range, HashSet::from([edges[3].clone(), edges[4].clone()]));
+
+    let q = AllEdgeQuery.with_property_range(name)?.lte(ijson!(0.5));
+    let range: HashSet<Edge> = util::get_edges(db, q)?.into_iter().collect();
+    assert_eq!(range, HashSet::from([edges[0].clone(), edges[1].clone()]));
+    Ok(())
+}
+
+pub fn should_pipe_with_property_range<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_vertices_with_ages(db, &ages())?;
+    let q = SpecificVertexQuery::new(vec![ids[0], ids[2], ids[4]])
+        .with_property_range(Identifier::new("age")?)?
+        .gte(ijson!(0));
+    let vertices = util::get_vertices(db, q)?;
+    let range: HashSet<Uuid> = vertices.into_iter().map(|v| v.id).collect();
+    assert_eq!(range, select(&ids, &[2, 4]));
+    Ok(())
+}
+
+pub fn should_not_query_unindexed_property_range<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("foo")?;
+    let result = util::get_vertices(db, VertexWithPropertyRangeQuery::new(name).gt(ijson!(1)));
+    expect_err!(result, Error::NotIndexed);
+    let result = util::get_edges(db, EdgeWithPropertyRangeQuery::new(name).gt(ijson!(1)));
+    expect_err!(result, Error::NotIndexed);
+    let result = util::get_vertices(db, AllVertexQuery.with_property_range(name)?.gt(ijson!(1)));
+    expect_err!(result, Error::NotIndexed);
+    Ok(())
+}
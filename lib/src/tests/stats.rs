@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use crate::util::extract_count;
+use crate::{
+    ijson, AllVertexQuery, CountQueryExt, Database, Datastore, Edge, Error, Identifier, IndexScope, PropertyValueCount,
+    SpecificEdgeQuery, SpecificVertexQuery, Stats,
+};
+
+pub fn should_get_empty_stats<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    assert_eq!(db.stats()?, Stats::default());
+    Ok(())
+}
+
+pub fn should_count_types<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let user = Identifier::new("user")?;
+    let post = Identifier::new("post")?;
+    let wrote = Identifier::new("wrote")?;
+    let alice_id = db.create_vertex_from_type(user)?;
+    let bob_id = db.create_vertex_from_type(user)?;
+    let post_id = db.create_vertex_from_type(post)?;
+    db.create_edge(&Edge::new(alice_id, wrote, post_id))?;
+    db.create_edge(&Edge::new(bob_id, wrote, post_id))?;
+    // Creating an edge that already exists shouldn't count it twice
+    db.create_edge(&Edge::new(bob_id, wrote, post_id))?;
+
+    let stats = db.stats()?;
+    assert_eq!(stats.vertex_count, 3);
+    assert_eq!(stats.edge_count, 2);
+    assert_eq!(stats.vertex_type_counts, BTreeMap::from([(post, 1), (user, 2)]));
+    assert_eq!(stats.edge_type_counts, BTreeMap::from([(wrote, 2)]));
+    assert_eq!(extract_count(db.get(AllVertexQuery.count()?)?), Some(3));
+
+    // Deleting a vertex deletes its edges too
+    db.delete(SpecificVertexQuery::single(alice_id))?;
+    let stats = db.stats()?;
+    assert_eq!(stats.vertex_count, 2);
+    assert_eq!(stats.edge_count, 1);
+    assert_eq!(stats.vertex_type_counts, BTreeMap::from([(post, 1), (user, 1)]));
+    assert_eq!(stats.edge_type_counts, BTreeMap::from([(wrote, 1)]));
+
+    db.delete(SpecificEdgeQuery::single(Edge::new(bob_id, wrote, post_id)))?;
+    let stats = db.stats()?;
+    assert_eq!(stats.edge_count, 0);
+    assert!(stats.edge_type_counts.is_empty());
+    Ok(())
+}
+
+pub fn should_count_property_values<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let user = Identifier::new("user")?;
+    let follows = Identifier::new("follows")?;
+    let role = Identifier::new("role")?;
+    let unindexed = Identifier::new("unindexed")?;
+    db.index_property(role, IndexScope::All)?;
+    let alice_id = db.create_vertex_from_type(user)?;
+    let bob_id = db.create_vertex_from_type(user)?;
+    let edge = Edge::new(alice_id, follows, bob_id);
+    db.create_edge(&edge)?;
+    db.set_properties(SpecificVertexQuery::new(vec![alice_id, bob_id]), role, &ijson!("admin"))?;
+    // Setting the same value again shouldn't count it twice
+    db.set_properties(SpecificVertexQuery::single(bob_id), role, &ijson!("admin"))?;
+    db.set_properties(SpecificEdgeQuery::single(edge.clone()), role, &ijson!("admin"))?;
+    db.set_properties(SpecificEdgeQuery::single(edge.clone()), unindexed, &ijson!(1))?;
+
+    assert_eq!(
+        db.stats()?.property_value_counts,
+        vec![PropertyValueCount {
+            name: role,
+            value: ijson!("admin"),
+            vertex_count: 2,
+            edge_count: 1,
+        }]
+    );
+
+    // Changing a value moves it to the new value's count
+    db.set_properties(SpecificVertexQuery::single(bob_id), role, &ijson!("member"))?;
+    assert_eq!(
+        db.stats()?.property_value_counts,
+        vec![
+            PropertyValueCount {
+                name: role,
+                value: ijson!("admin"),
+                vertex_count: 1,
+                edge_count: 1,
+            },
+            PropertyValueCount {
+                name: role,
+                value: ijson!("member"),
+                vertex_count: 1,
+                edge_count: 0,
+            }
+        ]
+    );
+
+    // Deleting the vertex deletes its edge and both of their values
+    db.delete(SpecificVertexQuery::single(alice_id))?;
+    assert_eq!(
+        db.stats()?.property_value_counts,
+        vec![PropertyValueCount {
+            name: role,
+            value: ijson!("member"),
+            vertex_count: 1,
+            edge_count: 0,
+        }]
+    );
+
+    db.unindex_property(role, IndexScope::All)?;
+    assert!(db.stats()?.property_value_counts.is_empty());
+    Ok(())
+}
+
+pub fn should_not_count_rolled_back_writes<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let user = Identifier::new("user")?;
+    db.create_vertex_from_type(user)?;
+    let result: Result<(), Error> = db.transaction(|txn| {
+        txn.create_vertex_from_type(user)?;
+        Err(Error::OperationOnQuery)
+    });
+    expect_err!(result, Error::OperationOnQuery);
+
+    let stats = db.stats()?;
+    assert_eq!(stats.vertex_count, 1);
+    assert_eq!(stats.vertex_type_counts, BTreeMap::from([(user, 1)]));
+    Ok(())
+}
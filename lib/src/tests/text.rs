@@ -0,0 +1,46 @@
+use super::util;
+use crate::util::{extract_count, extract_vertex_properties, extract_vertices};
+use crate::{ijson, Database, Datastore, Error, Identifier, IndexScope, SpecificVertexQuery, ValidationError};
+
+pub fn should_query_str<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let active = Identifier::new("active")?;
+    db.index_property(active, IndexScope::All)?;
+    db.set_properties(
+        SpecificVertexQuery::new(inbound_ids[..2].to_vec()),
+        active,
+        &ijson!(true),
+    )?;
+    db.set_properties(SpecificVertexQuery::single(inbound_ids[2]), active, &ijson!(false))?;
+
+    let output = db.query_str("V(type=test_outbound_vertex_type).out(test_edge_type).count()")?;
+    assert_eq!(extract_count(output), Some(5));
+
+    let output = db.query_str(&format!("V({}).in()", inbound_ids[2]))?;
+    let vertices = extract_vertices(output).unwrap();
+    assert_eq!(vertices.len(), 1);
+    assert_eq!(vertices[0].id, outbound_id);
+
+    let output = db.query_str(&format!("V({outbound_id}).out().has(active=false)"))?;
+    let vertices = extract_vertices(output).unwrap();
+    assert_eq!(vertices.len(), 1);
+    assert_eq!(vertices[0].id, inbound_ids[2]);
+
+    let output = db.query_str("V(type=test_outbound_vertex_type).out().has(active=true).props(active)")?;
+    let mut ids: Vec<_> = extract_vertex_properties(output)
+        .unwrap()
+        .into_iter()
+        .map(|vps| vps.vertex.id)
+        .collect();
+    ids.sort();
+    let mut expected_ids = inbound_ids[..2].to_vec();
+    expected_ids.sort();
+    assert_eq!(ids, expected_ids);
+    Ok(())
+}
+
+pub fn should_not_query_invalid_str<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let result = db.query_str("V().out(");
+    expect_err!(result, Error::Invalid(ValidationError::Syntax { position: 8, .. }));
+    Ok(())
+}
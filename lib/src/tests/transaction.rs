@@ -0,0 +1,71 @@
+use super::util;
+use crate::{ijson, models, AllVertexQuery, Database, Datastore, Error, QueryExt, SpecificVertexQuery, Transaction};
+
+pub fn should_commit_transaction<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let vertex_t = models::Identifier::new("test_vertex_type")?;
+    let edge_t = models::Identifier::new("test_edge_type")?;
+    let property_name = models::Identifier::new("foo")?;
+
+    let edge = db.transaction(|txn| {
+        let outbound_id = txn.create_vertex_from_type(vertex_t)?;
+        let inbound_id = txn.create_vertex_from_type(vertex_t)?;
+        let edge = models::Edge::new(outbound_id, edge_t, inbound_id);
+        assert!(txn.create_edge(&edge)?);
+        txn.set_properties(SpecificVertexQuery::single(outbound_id), property_name, &ijson!(true))?;
+        Ok(edge)
+    })?;
+
+    let vertices = util::get_vertices(db, AllVertexQuery)?;
+    assert_eq!(vertices.len(), 2);
+    let edges = util::get_edges(db, models::SpecificEdgeQuery::single(edge.clone()))?;
+    assert_eq!(edges, vec![edge.clone()]);
+    let props = util::get_vertex_properties(
+        db,
+        SpecificVertexQuery::single(edge.outbound_id)
+            .properties()?
+            .name(property_name),
+    )?;
+    assert_eq!(props.len(), 1);
+    assert_eq!(props[0].value, ijson!(true));
+    Ok(())
+}
+
+pub fn should_rollback_transaction<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let vertex_t = models::Identifier::new("test_vertex_type")?;
+    let id = db.create_vertex_from_type(vertex_t)?;
+    let property_name = models::Identifier::new("foo")?;
+    db.set_properties(SpecificVertexQuery::single(id), property_name, &ijson!(1))?;
+
+    let inbound_id = db.create_vertex_from_type(vertex_t)?;
+    let edge = models::Edge::new(id, models::Identifier::new("test_edge_type")?, inbound_id);
+    db.create_edge(&edge)?;
+    let created_vertex = models::Vertex::new(vertex_t);
+
+    let result: Result<(), Error> = db.transaction(|txn| {
+        txn.create_vertex(&created_vertex)?;
+        txn.set_properties(SpecificVertexQuery::single(id), property_name, &ijson!(2))?;
+        txn.delete(SpecificVertexQuery::single(inbound_id))?;
+        Err(Error::OperationOnQuery)
+    });
+
+    expect_err!(result, Error::OperationOnQuery);
+
+    let vertices = util::get_vertices(db, AllVertexQuery)?;
+    assert_eq!(vertices.len(), 2);
+    let edges = util::get_edges(db, models::SpecificEdgeQuery::single(edge.clone()))?;
+    assert_eq!(edges, vec![edge]);
+    let props = util::get_vertex_properties(db, SpecificVertexQuery::single(id).properties()?.name(property_name))?;
+    assert_eq!(props.len(), 1);
+    assert_eq!(props[0].value, ijson!(1));
+    Ok(())
+}
+
+pub fn should_rollback_dropped_transaction<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let vertex = models::Vertex::new(models::Identifier::new("test_vertex_type")?);
+    {
+        let mut txn = db.datastore.transaction();
+        assert!(txn.create_vertex(&vertex)?);
+    }
+    assert!(util::get_vertices(db, SpecificVertexQuery::single(vertex.id))?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+
+use super::util;
+use crate::{
+    models, Database, Datastore, Edge, EdgeDirection, Error, Identifier, Path, QueryExt, QueryOutputValue,
+    ShortestPathQuery, SpecificVertexQuery,
+};
+
+use uuid::Uuid;
+
+// Creates the chain `ids[0] -> ids[1] -> ids[2] -> ids[3] -> ids[0]`, with
+// an additional shortcut `ids[0] -> ids[2]` of a different edge type.
+fn create_cycle<D: Datastore>(db: &Database<D>) -> Result<[Uuid; 4], Error> {
+    let vertex_t = Identifier::new("test_vertex_type")?;
+    let ids = [
+        db.create_vertex_from_type(vertex_t)?,
+        db.create_vertex_from_type(vertex_t)?,
+        db.create_vertex_from_type(vertex_t)?,
+        db.create_vertex_from_type(vertex_t)?,
+    ];
+    let edge_t = Identifier::new("test_edge_type")?;
+    for i in 0..ids.len() {
+        db.create_edge(&Edge::new(ids[i], edge_t, ids[(i + 1) % ids.len()]))?;
+    }
+    db.create_edge(&Edge::new(ids[0], Identifier::new("shortcut_edge_type")?, ids[2]))?;
+    Ok(ids)
+}
+
+fn get_path<D: Datastore>(db: &Database<D>, q: ShortestPathQuery) -> Result<Option<Path>, Error> {
+    match db.get(q)?.pop() {
+        Some(QueryOutputValue::Path(path)) => Ok(path),
+        _ => Err(Error::Unsupported),
+    }
+}
+
+fn path_ids(path: Option<Path>) -> Option<Vec<Uuid>> {
+    let path = path?;
+    assert_eq!(path.edges.len() + 1, path.vertices.len());
+    Some(path.vertices.into_iter().map(|v| v.id).collect())
+}
+
+fn vertex_ids(vertices: Vec<models::Vertex>) -> HashSet<Uuid> {
+    let len = vertices.len();
+    let ids: HashSet<Uuid> = vertices.into_iter().map(|v| v.id).collect();
+    // each vertex should only be returned once
+    assert_eq!(ids.len(), len);
+    ids
+}
+
+pub fn should_traverse_outbound<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    let q = SpecificVertexQuery::single(ids[0]).traverse(EdgeDirection::Outbound)?;
+
+    // The shortcut puts `ids[2]` one hop away
+    let vertices = util::get_vertices(db, q.clone())?;
+    assert_eq!(vertex_ids(vertices), HashSet::from([ids[1], ids[2]]));
+
+    let vertices = util::get_vertices(db, q.clone().min_depth(2).max_depth(2))?;
+    assert_eq!(vertex_ids(vertices), HashSet::from([ids[3]]));
+
+    // The cycle back to the start shouldn't be revisited
+    let vertices = util::get_vertices(db, q.clone().min_depth(0).max_depth(u32::MAX))?;
+    assert_eq!(vertex_ids(vertices), HashSet::from(ids));
+
+    let vertices = util::get_vertices(db, q.min_depth(0).max_depth(0))?;
+    assert_eq!(vertex_ids(vertices), HashSet::from([ids[0]]));
+    Ok(())
+}
+
+pub fn should_traverse_inbound_with_type_filter<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    let q = SpecificVertexQuery::single(ids[2])
+        .traverse(EdgeDirection::Inbound)?
+        .t(Identifier::new("test_edge_type")?);
+
+    let vertices = util::get_vertices(db, q.clone().max_depth(2))?;
+    assert_eq!(vertex_ids(vertices), HashSet::from([ids[1], ids[0]]));
+
+    let vertices = util::get_vertices(db, q.clone().min_depth(3).max_depth(10))?;
+    assert_eq!(vertex_ids(vertices), HashSet::from([ids[3]]));
+
+    let vertices = util::get_vertices(db, q.max_depth(10).limit(2))?;
+    assert_eq!(vertices.len(), 2);
+    Ok(())
+}
+
+pub fn should_traverse_from_multiple_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    let q = SpecificVertexQuery::new(vec![ids[0], ids[3]])
+        .traverse(EdgeDirection::Outbound)?
+        .t(Identifier::new("test_edge_type")?)
+        .max_depth(10);
+
+    // `ids[0]` is reachable from `ids[3]`, but it's already been visited as
+    // a starting vertex
+    let vertices = util::get_vertices(db, q)?;
+    assert_eq!(vertex_ids(vertices), HashSet::from([ids[1], ids[2]]));
+    Ok(())
+}
+
+pub fn should_get_shortest_path<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let shortcut_edge_t = Identifier::new("shortcut_edge_type")?;
+
+    for bidirectional in [false, true] {
+        let q = ShortestPathQuery::new(ids[0], ids[3]).bidirectional(bidirectional);
+
+        let path = get_path(db, q.clone())?.unwrap();
+        assert_eq!(
+            path,
+            Path::new(
+                vec![
+                    models::Vertex::with_id(ids[0], Identifier::new("test_vertex_type")?),
+                    models::Vertex::with_id(ids[2], Identifier::new("test_vertex_type")?),
+                    models::Vertex::with_id(ids[3], Identifier::new("test_vertex_type")?),
+                ],
+                vec![
+                    Edge::new(ids[0], shortcut_edge_t, ids[2]),
+                    Edge::new(ids[2], edge_t, ids[3])
+                ],
+            )
+        );
+
+        let path = get_path(db, q.clone().t(vec![edge_t]))?;
+        assert_eq!(path_ids(path), Some(vec![ids[0], ids[1], ids[2], ids[3]]));
+
+        let path = get_path(db, q.clone().t(vec![edge_t]).max_depth(2))?;
+        assert_eq!(path_ids(path), None);
+
+        let path = get_path(db, q.t(vec![edge_t, shortcut_edge_t]).max_depth(2))?;
+        assert_eq!(path_ids(path), Some(vec![ids[0], ids[2], ids[3]]));
+    }
+
+    Ok(())
+}
+
+pub fn should_get_inbound_shortest_path<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+
+    for bidirectional in [false, true] {
+        let q = ShortestPathQuery::new(ids[3], ids[0])
+            .direction(EdgeDirection::Inbound)
+            .bidirectional(bidirectional);
+        let path = get_path(db, q)?.unwrap();
+        assert_eq!(
+            path.edges,
+            vec![
+                Edge::new(ids[2], Identifier::new("test_edge_type")?, ids[3]),
+                Edge::new(ids[0], Identifier::new("shortcut_edge_type")?, ids[2]),
+            ]
+        );
+        assert_eq!(path_ids(Some(path)), Some(vec![ids[3], ids[2], ids[0]]));
+    }
+
+    Ok(())
+}
+
+pub fn should_get_shortest_path_to_self<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    let path = get_path(db, ShortestPathQuery::new(ids[0], ids[0]).max_depth(0))?;
+    assert_eq!(path_ids(path), Some(vec![ids[0]]));
+    Ok(())
+}
+
+pub fn should_not_get_shortest_path_between_unconnected_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    let other_id = db.create_vertex_from_type(Identifier::new("test_vertex_type")?)?;
+
+    for bidirectional in [false, true] {
+        let q = ShortestPathQuery::new(ids[0], other_id).bidirectional(bidirectional);
+        assert_eq!(get_path(db, q)?, None);
+        let q = ShortestPathQuery::new(other_id, ids[0]).bidirectional(bidirectional);
+        assert_eq!(get_path(db, q)?, None);
+    }
+
+    let path = get_path(db, ShortestPathQuery::new(Uuid::default(), Uuid::default()))?;
+    assert_eq!(path, None);
+    Ok(())
+}
@@ -1,9 +1,29 @@
+use std::collections::HashSet;
+
 use crate::errors::{Error, Result};
 use crate::util::{extract_count, extract_edge_properties, extract_edges, extract_vertex_properties, extract_vertices};
 use crate::{models, CountQueryExt, Database, Datastore, QueryExt};
 
 use uuid::Uuid;
 
+// Creates a vertex of the given type, with each of the given properties set
+// on it.
+pub(crate) fn create_vertex_with_properties<D: Datastore>(
+    db: &Database<D>,
+    t: &str,
+    properties: &[(&str, models::Json)],
+) -> Result<Uuid> {
+    let id = db.create_vertex_from_type(models::Identifier::new(t)?)?;
+    for (name, value) in properties {
+        db.set_properties(
+            models::SpecificVertexQuery::single(id),
+            models::Identifier::new(*name)?,
+            value,
+        )?;
+    }
+    Ok(id)
+}
+
 pub(crate) fn create_edge_from<D: Datastore>(db: &Database<D>, outbound_id: Uuid) -> Result<Uuid> {
     let inbound_vertex_t = models::Identifier::new("test_inbound_vertex_type")?;
     let inbound_v = models::Vertex::new(inbound_vertex_t);
@@ -36,6 +56,16 @@ pub(crate) fn get_vertices<D: Datastore, Q: Into<models::Query>>(
     Ok(extract_vertices(db.get(q)?).unwrap())
 }
 
+// Gets the IDs of the vertices a query returns, checking that none are
+// returned more than once.
+pub(crate) fn get_vertex_ids<D: Datastore, Q: Into<models::Query>>(db: &Database<D>, q: Q) -> Result<HashSet<Uuid>> {
+    let vertices = get_vertices(db, q)?;
+    let len = vertices.len();
+    let ids: HashSet<Uuid> = vertices.into_iter().map(|v| v.id).collect();
+    assert_eq!(ids.len(), len);
+    Ok(ids)
+}
+
 pub(crate) fn get_vertex_count<D: Datastore>(db: &Database<D>) -> Result<u64> {
     Ok(extract_count(db.get(models::AllVertexQuery.count().unwrap())?).unwrap())
 }
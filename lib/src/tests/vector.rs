@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+
+use super::util;
+use crate::{
+    ijson, Database, Datastore, DistanceMetric, Edge, Error, Identifier, NearestVerticesQuery, QueryExt,
+    SpecificVertexQuery, ValidationError,
+};
+
+use uuid::Uuid;
+
+fn nearest_ids<D: Datastore>(
+    db: &Database<D>,
+    vector: Vec<f64>,
+    k: u32,
+    metric: DistanceMetric,
+) -> Result<Vec<Uuid>, Error> {
+    let q = NearestVerticesQuery::new(Identifier::new("embedding")?, vector, k, metric);
+    Ok(util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect())
+}
+
+pub fn should_query_nearest_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let embedding = Identifier::new("embedding")?;
+    let origin = util::create_vertex_with_properties(db, "doc", &[("embedding", ijson!([0, 0]))])?;
+    let near = util::create_vertex_with_properties(db, "doc", &[("embedding", ijson!([1, 0]))])?;
+    let far = util::create_vertex_with_properties(db, "doc", &[("embedding", ijson!([3, 0.5]))])?;
+    let up = util::create_vertex_with_properties(db, "doc", &[("embedding", ijson!([0, 5]))])?;
+    util::create_vertex_with_properties(db, "doc", &[("embedding", ijson!([1, 0, 0]))])?;
+    util::create_vertex_with_properties(db, "doc", &[("embedding", ijson!("[1, 0]"))])?;
+
+    // Existing values are indexed
+    db.index_property_vector(embedding)?;
+    assert_eq!(db.vector_indexed_properties()?, HashSet::from([embedding]));
+
+    // Results are ordered by distance, skipping values of other lengths
+    assert_eq!(
+        nearest_ids(db, vec![0.9, 0.0], 3, DistanceMetric::Euclidean)?,
+        vec![near, origin, far]
+    );
+    assert_eq!(
+        nearest_ids(db, vec![0.9, 0.0], 10, DistanceMetric::Euclidean)?,
+        vec![near, origin, far, up]
+    );
+    assert!(nearest_ids(db, vec![0.9, 0.0], 0, DistanceMetric::Euclidean)?.is_empty());
+
+    // Cosine distance ignores magnitude, and skips the zero vector
+    assert_eq!(
+        nearest_ids(db, vec![10.0, 0.0], 10, DistanceMetric::Cosine)?,
+        vec![near, far, up]
+    );
+    assert_eq!(
+        nearest_ids(db, vec![0.0, 1.0], 1, DistanceMetric::DotProduct)?,
+        vec![up]
+    );
+
+    // The query can be piped from
+    let other_id = db.create_vertex_from_type(Identifier::new("doc")?)?;
+    let edge = Edge::new(near, Identifier::new("cites")?, other_id);
+    db.create_edge(&edge)?;
+    let q = NearestVerticesQuery::new(embedding, vec![0.9, 0.0], 2, DistanceMetric::Euclidean).outbound()?;
+    assert_eq!(util::get_edges(db, q)?, vec![edge]);
+    Ok(())
+}
+
+pub fn should_update_vector_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let embedding = Identifier::new("embedding")?;
+    db.index_property_vector(embedding)?;
+    let id = util::create_vertex_with_properties(db, "doc", &[("embedding", ijson!([0, 0]))])?;
+    let other_id = util::create_vertex_with_properties(db, "doc", &[("embedding", ijson!([5, 5]))])?;
+
+    // Updating a value replaces its vector
+    db.set_properties(SpecificVertexQuery::single(id), embedding, &ijson!([10, 10]))?;
+    assert_eq!(
+        nearest_ids(db, vec![0.0, 0.0], 1, DistanceMetric::Euclidean)?,
+        vec![other_id]
+    );
+
+    // As does setting it to something that isn't a vector
+    db.set_properties(SpecificVertexQuery::single(id), embedding, &ijson!(null))?;
+    assert_eq!(
+        nearest_ids(db, vec![0.0, 0.0], 10, DistanceMetric::Euclidean)?,
+        vec![other_id]
+    );
+
+    // Deleting a property or vertex removes its vector
+    db.delete(SpecificVertexQuery::single(other_id).properties()?.name(embedding))?;
+    assert!(nearest_ids(db, vec![0.0, 0.0], 10, DistanceMetric::Euclidean)?.is_empty());
+    db.set_properties(SpecificVertexQuery::single(id), embedding, &ijson!([1, 1]))?;
+    db.delete(SpecificVertexQuery::single(id))?;
+    assert!(nearest_ids(db, vec![0.0, 0.0], 10, DistanceMetric::Euclidean)?.is_empty());
+    Ok(())
+}
+
+pub fn should_unindex_vector<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let embedding = Identifier::new("embedding")?;
+    let id = util::create_vertex_with_properties(db, "doc", &[("embedding", ijson!([1, 2]))])?;
+    db.index_property_vector(embedding)?;
+    db.unindex_property_vector(embedding)?;
+    assert!(db.vector_indexed_properties()?.is_empty());
+    let result = nearest_ids(db, vec![1.0, 2.0], 1, DistanceMetric::Euclidean);
+    expect_err!(result, Error::NotIndexed);
+
+    // Reindexing picks the values back up
+    db.index_property_vector(embedding)?;
+    assert_eq!(nearest_ids(db, vec![1.0, 2.0], 1, DistanceMetric::Euclidean)?, vec![id]);
+    Ok(())
+}
+
+pub fn should_rollback_vector_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let embedding = Identifier::new("embedding")?;
+    db.index_property_vector(embedding)?;
+    let id = util::create_vertex_with_properties(db, "doc", &[("embedding", ijson!([1, 2]))])?;
+
+    let result: Result<(), Error> = db.transaction(|txn| {
+        txn.set_properties(SpecificVertexQuery::single(id), embedding, &ijson!([1, 2, 3]))?;
+        txn.unindex_property_vector(embedding)?;
+        Err(Error::OperationOnQuery)
+    });
+    expect_err!(result, Error::OperationOnQuery);
+
+    assert_eq!(nearest_ids(db, vec![0.0, 0.0], 1, DistanceMetric::Euclidean)?, vec![id]);
+    assert!(nearest_ids(db, vec![0.0, 0.0, 0.0], 1, DistanceMetric::Euclidean)?.is_empty());
+    Ok(())
+}
+
+pub fn should_not_query_nearest_vertices_with_invalid_vector<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    db.index_property_vector(Identifier::new("embedding")?)?;
+    let result = nearest_ids(db, Vec::new(), 1, DistanceMetric::Euclidean);
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    let result = nearest_ids(db, vec![f64::NAN], 1, DistanceMetric::Euclidean);
+    expect_err!(result, Error::Invalid(ValidationError::InvalidValue));
+    Ok(())
+}
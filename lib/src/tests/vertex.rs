@@ -4,8 +4,8 @@ use std::error::Error as StdError;
 use super::util;
 use crate::util::extract_count;
 use crate::{
-    errors, expect_err, ijson, models, AllVertexQuery, CountQueryExt, Database, Datastore, Error, QueryExt,
-    RangeVertexQuery, SpecificVertexQuery,
+    errors, expect_err, ijson, models, AllVertexQuery, CountQueryExt, Database, Datastore, Error, IndexScope, QueryExt,
+    QueryOutputValueIter, RangeVertexQuery, SpecificVertexQuery,
 };
 
 use uuid::Uuid;
@@ -16,6 +16,45 @@ pub fn should_create_vertex_from_type<D: Datastore>(db: &Database<D>) -> Result<
     Ok(())
 }
 
+pub fn should_get_or_create_vertex<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let user_t = models::Identifier::new("user")?;
+    let email = models::Identifier::new("email")?;
+    let name = models::Identifier::new("name")?;
+    db.index_property(email, IndexScope::All)?;
+
+    let properties = vec![models::NamedProperty::new(name, ijson!("Alice"))];
+    let (id, created) = db.get_or_create_vertex(user_t, email, &ijson!("a@example.com"), properties)?;
+    assert!(created);
+    let props = util::get_all_vertex_properties(db, SpecificVertexQuery::single(id))?;
+    assert_eq!(props.len(), 1);
+    assert_eq!(props[0].vertex.t, user_t);
+    assert_eq!(props[0].props.len(), 2);
+
+    // The existing vertex is returned as-is
+    let properties = vec![models::NamedProperty::new(name, ijson!("Bob"))];
+    let (existing_id, created) = db.get_or_create_vertex(user_t, email, &ijson!("a@example.com"), properties)?;
+    assert!(!created);
+    assert_eq!(existing_id, id);
+    let q = SpecificVertexQuery::single(id).properties()?.name(name);
+    assert_eq!(util::get_vertex_properties(db, q)?[0].value, ijson!("Alice"));
+
+    // Vertices of other types aren't matched
+    let admin_t = models::Identifier::new("admin")?;
+    let (admin_id, created) = db.get_or_create_vertex(admin_t, email, &ijson!("a@example.com"), Vec::new())?;
+    assert!(created);
+    assert_ne!(admin_id, id);
+    Ok(())
+}
+
+pub fn should_not_get_or_create_vertex_unindexed<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let t = models::Identifier::new("user")?;
+    let email = models::Identifier::new("email")?;
+    let result = db.get_or_create_vertex(t, email, &ijson!("a@example.com"), Vec::new());
+    expect_err!(result, Error::NotIndexed);
+    assert_eq!(util::get_vertex_count(db)?, 0);
+    Ok(())
+}
+
 pub fn should_get_all_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
     let inserted_ids = create_vertices(db)?;
     let range = util::get_vertices(db, AllVertexQuery)?;
@@ -23,6 +62,38 @@ pub fn should_get_all_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Err
     Ok(())
 }
 
+pub fn should_get_all_vertices_lazily<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let inserted_ids = create_vertices(db)?;
+    let range = db.get_iter(AllVertexQuery, |mut output| {
+        assert_eq!(output.len(), 1);
+        match output.pop().unwrap() {
+            QueryOutputValueIter::Vertices(iter) => iter.collect::<Result<Vec<models::Vertex>, Error>>(),
+            _ => panic!("unexpected output"),
+        }
+    })?;
+    check_has_all_vertices(range, inserted_ids);
+
+    // Only take part of the output
+    let taken = db.get_iter(AllVertexQuery, |mut output| match output.pop().unwrap() {
+        QueryOutputValueIter::Vertices(iter) => Ok(iter.take(2).count()),
+        _ => panic!("unexpected output"),
+    })?;
+    assert_eq!(taken, 2);
+    Ok(())
+}
+
+pub fn should_get_many_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    // Enough vertices to be split up when streamed over gRPC
+    let t = models::Identifier::new("test_vertex_type")?;
+    let mut vertices: Vec<models::Vertex> = (0..2500).map(|_| models::Vertex::new(t)).collect();
+    db.bulk_insert(vertices.iter().cloned().map(models::BulkInsertItem::Vertex).collect())?;
+    let mut range = util::get_vertices(db, AllVertexQuery)?;
+    range.sort_by_key(|v| v.id);
+    vertices.sort_by_key(|v| v.id);
+    assert_eq!(range, vertices);
+    Ok(())
+}
+
 pub fn should_get_range_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
     let inserted_ids = create_vertices(db)?;
     let range = util::get_vertices(db, RangeVertexQuery::new())?;
@@ -200,7 +271,8 @@ pub fn should_not_pipe_on_vertex_count<D: Datastore>(db: &Database<D>) -> Result
         inner: Box::new(AllVertexQuery.count()?.into()),
         direction: models::EdgeDirection::Outbound,
         limit: 1,
-        t: None,
+        t: Vec::default(),
+        after: None,
     };
     let result = db.get(q);
     expect_err!(result, errors::Error::OperationOnQuery);
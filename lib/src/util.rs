@@ -1,9 +1,9 @@
 //! Utility functions. These are public because they may be useful for crates
 //! that implement Datastore.
 
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::io::{Cursor, Read, Result as IoResult, Write};
+use std::collections::BTreeSet;
+use std::io::{Cursor, Error as IoError, ErrorKind, Read, Result as IoResult, Write};
+use std::ops::Bound;
 use std::str;
 
 use crate::errors::{Result, ValidationError, ValidationResult};
@@ -18,6 +18,16 @@ const NODE_ID: [u8; 6] = [0, 0, 0, 0, 0, 0];
 
 static CONTEXT: Lazy<Context> = Lazy::new(|| Context::new(0));
 
+// Tags written before encoded JSON values, which determine how values of
+// different types are ordered relative to one another. Zero is reserved for
+// terminating arrays and objects.
+const JSON_NULL_TAG: u8 = 1;
+const JSON_BOOL_TAG: u8 = 2;
+const JSON_NUMBER_TAG: u8 = 3;
+const JSON_STRING_TAG: u8 = 4;
+const JSON_ARRAY_TAG: u8 = 5;
+const JSON_OBJECT_TAG: u8 = 6;
+
 /// A byte-serializable value, frequently employed in the keys of key/value
 /// store.
 pub enum Component<'a> {
@@ -27,7 +37,11 @@ pub enum Component<'a> {
     FixedLengthString(&'a str),
     /// An identifier.
     Identifier(models::Identifier),
-    /// A JSON value.
+    /// A JSON value. This is encoded such that the bytes of values of the
+    /// same type sort in the same order as the values themselves, and such
+    /// that no encoded value is a prefix of another. Since the value itself
+    /// is encoded rather than a hash of it, distinct values never collide,
+    /// and the encoding doesn't depend on the Rust release it's built with.
     Json(&'a models::Json),
 }
 
@@ -39,7 +53,7 @@ impl Component<'_> {
             Component::Uuid(_) => 16,
             Component::FixedLengthString(s) => s.len(),
             Component::Identifier(t) => t.0.len() + 1,
-            Component::Json(_) => 8,
+            Component::Json(json) => json_byte_len(json),
         }
     }
 
@@ -52,14 +66,221 @@ impl Component<'_> {
                 cursor.write_all(&[i.0.len() as u8])?;
                 cursor.write_all(i.0.as_bytes())
             }
-            Component::Json(json) => {
-                let mut hasher = DefaultHasher::new();
-                json.hash(&mut hasher);
-                let hash = hasher.finish();
-                cursor.write_u64::<BigEndian>(hash)
+            Component::Json(json) => write_json(cursor, json),
+        }
+    }
+}
+
+fn escaped_byte_len(s: &str) -> usize {
+    s.len() + s.bytes().filter(|b| *b == 0).count() + 2
+}
+
+// Writes a string such that it's terminated by `[0, 1]`, with any zero bytes
+// in it escaped as `[0, 255]`. This preserves the ordering of strings, while
+// making it possible to tell where they end.
+fn write_escaped(cursor: &mut Cursor<Vec<u8>>, s: &str) -> IoResult<()> {
+    for b in s.bytes() {
+        if b == 0 {
+            cursor.write_all(&[0, 255])?;
+        } else {
+            cursor.write_u8(b)?;
+        }
+    }
+    cursor.write_all(&[0, 1])
+}
+
+fn skip_escaped<T: AsRef<[u8]>>(cursor: &mut Cursor<T>) -> Result<()> {
+    loop {
+        if cursor.read_u8()? == 0 && cursor.read_u8()? == 1 {
+            return Ok(());
+        }
+    }
+}
+
+fn json_byte_len(value: &serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::Null => 1,
+        serde_json::Value::Bool(_) => 2,
+        serde_json::Value::Number(_) => 17,
+        serde_json::Value::String(v) => 1 + escaped_byte_len(v),
+        serde_json::Value::Array(v) => 2 + v.iter().map(json_byte_len).sum::<usize>(),
+        serde_json::Value::Object(v) => {
+            2 + v
+                .iter()
+                .map(|(sk, sv)| 1 + escaped_byte_len(sk) + json_byte_len(sv))
+                .sum::<usize>()
+        }
+    }
+}
+
+// Numbers are written as the nearest `f64`, followed by the difference
+// between that and the actual value for integers that can't be exactly
+// represented as an `f64`. Integers and floats that are equal are written
+// identically.
+fn write_json_number(cursor: &mut Cursor<Vec<u8>>, value: &serde_json::Number) -> IoResult<()> {
+    let (f, diff) = if let Some(i) = value.as_i64() {
+        let f = i as f64;
+        (f, (i as i128 - f as i128) as i64)
+    } else if let Some(u) = value.as_u64() {
+        let f = u as f64;
+        (f, (u as i128 - f as i128) as i64)
+    } else {
+        (value.as_f64().unwrap(), 0)
+    };
+
+    // Normalize negative zero, which is equal to positive zero
+    let bits = if f == 0.0 { 0 } else { f.to_bits() };
+    // Flip the sign bit for positive numbers, and every bit for negative
+    // numbers, so that the bytes sort in numeric order
+    let bits = if bits >> 63 == 1 { !bits } else { bits | (1 << 63) };
+    cursor.write_u64::<BigEndian>(bits)?;
+    cursor.write_u64::<BigEndian>((diff as u64) ^ (1 << 63))
+}
+
+fn write_json(cursor: &mut Cursor<Vec<u8>>, value: &serde_json::Value) -> IoResult<()> {
+    match value {
+        serde_json::Value::Null => cursor.write_u8(JSON_NULL_TAG),
+        serde_json::Value::Bool(v) => cursor.write_all(&[JSON_BOOL_TAG, *v as u8]),
+        serde_json::Value::Number(v) => {
+            cursor.write_u8(JSON_NUMBER_TAG)?;
+            write_json_number(cursor, v)
+        }
+        serde_json::Value::String(v) => {
+            cursor.write_u8(JSON_STRING_TAG)?;
+            write_escaped(cursor, v)
+        }
+        serde_json::Value::Array(v) => {
+            cursor.write_u8(JSON_ARRAY_TAG)?;
+            for sv in v {
+                write_json(cursor, sv)?;
+            }
+            cursor.write_u8(0)
+        }
+        serde_json::Value::Object(v) => {
+            cursor.write_u8(JSON_OBJECT_TAG)?;
+            for (sk, sv) in v {
+                cursor.write_u8(1)?;
+                write_escaped(cursor, sk)?;
+                write_json(cursor, sv)?;
+            }
+            cursor.write_u8(0)
+        }
+    }
+}
+
+fn skip_json_value<T: AsRef<[u8]>>(cursor: &mut Cursor<T>, tag: u8) -> Result<()> {
+    match tag {
+        JSON_NULL_TAG => {}
+        JSON_BOOL_TAG => {
+            cursor.read_u8()?;
+        }
+        JSON_NUMBER_TAG => {
+            cursor.read_u64::<BigEndian>()?;
+            cursor.read_u64::<BigEndian>()?;
+        }
+        JSON_STRING_TAG => skip_escaped(cursor)?,
+        JSON_ARRAY_TAG => loop {
+            match cursor.read_u8()? {
+                0 => break,
+                tag => skip_json_value(cursor, tag)?,
+            }
+        },
+        JSON_OBJECT_TAG => {
+            while cursor.read_u8()? != 0 {
+                skip_escaped(cursor)?;
+                let tag = cursor.read_u8()?;
+                skip_json_value(cursor, tag)?;
+            }
+        }
+        _ => return Err(IoError::new(ErrorKind::InvalidData, "invalid JSON type tag").into()),
+    }
+    Ok(())
+}
+
+/// A range of JSON values, expressed in terms of their byte encodings as
+/// written by `Component::Json`.
+///
+/// A range with a bound only includes values of the same type as the bound,
+/// since values of different types aren't comparable.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonRange {
+    /// The inclusive lower bound of bytes.
+    pub start: Vec<u8>,
+    /// The exclusive upper bound of bytes, or `None` if unbounded.
+    pub end: Option<Vec<u8>>,
+}
+
+impl JsonRange {
+    /// Creates a new JSON range. Returns `None` if the range is empty.
+    ///
+    /// # Arguments
+    /// * `lower`: The lower bound of values.
+    /// * `upper`: The upper bound of values.
+    pub fn new(lower: Bound<&models::Json>, upper: Bound<&models::Json>) -> Option<Self> {
+        let (mut start, lower_tag) = match lower {
+            Bound::Included(value) => {
+                let start = build(&[Component::Json(value)]);
+                let tag = start[0];
+                (start, Some(tag))
+            }
+            Bound::Excluded(value) => {
+                let start = build(&[Component::Json(value)]);
+                let tag = start[0];
+                (prefix_successor(start)?, Some(tag))
+            }
+            Bound::Unbounded => (Vec::new(), None),
+        };
+
+        let (mut end, upper_tag) = match upper {
+            Bound::Included(value) => {
+                let end = build(&[Component::Json(value)]);
+                let tag = end[0];
+                (prefix_successor(end), Some(tag))
             }
+            Bound::Excluded(value) => {
+                let end = build(&[Component::Json(value)]);
+                let tag = end[0];
+                (Some(end), Some(tag))
+            }
+            Bound::Unbounded => (None, None),
+        };
+
+        match (lower_tag, upper_tag) {
+            (Some(lower_tag), Some(upper_tag)) if lower_tag != upper_tag => return None,
+            (Some(lower_tag), None) => end = Some(vec![lower_tag + 1]),
+            (None, Some(upper_tag)) => start = vec![upper_tag],
+            _ => {}
+        }
+
+        if let Some(ref end) = end {
+            if start >= *end {
+                return None;
+            }
+        }
+
+        Some(Self { start, end })
+    }
+
+    /// Returns whether a value is in the range.
+    ///
+    /// # Arguments
+    /// * `value`: The value to check.
+    pub fn contains(&self, value: &models::Json) -> bool {
+        let bytes = build(&[Component::Json(value)]);
+        bytes >= self.start && self.end.as_ref().is_none_or(|end| bytes < *end)
+    }
+}
+
+// Gets the smallest byte string that is greater than every byte string
+// prefixed by the given one, or `None` if there isn't one.
+fn prefix_successor(mut bytes: Vec<u8>) -> Option<Vec<u8>> {
+    while let Some(last) = bytes.pop() {
+        if last < 255 {
+            bytes.push(last + 1);
+            return Some(bytes);
         }
     }
+    None
 }
 
 // Serializes component(s) into bytes.
@@ -111,6 +332,15 @@ pub unsafe fn read_identifier<T: AsRef<[u8]>>(cursor: &mut Cursor<T>) -> Result<
     Ok(models::Identifier::new_unchecked(s))
 }
 
+/// Skips past a JSON value written by `Component::Json`.
+///
+/// # Arguments
+/// * `cursor`: The bytes to read from.
+pub fn skip_json<T: AsRef<[u8]>>(cursor: &mut Cursor<T>) -> Result<()> {
+    let tag = cursor.read_u8()?;
+    skip_json_value(cursor, tag)
+}
+
 /// Reads a fixed-length string from bytes.
 ///
 /// # Arguments
@@ -159,6 +389,137 @@ pub fn next_uuid(uuid: Uuid) -> ValidationResult<Uuid> {
     Err(ValidationError::CannotIncrementUuid)
 }
 
+/// Splits text into the terms that full-text indexes are made up of: runs
+/// of alphanumeric characters, lowercased.
+///
+/// # Arguments
+/// * `text`: The text to split.
+pub fn tokenize(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+/// Reads a vector out of a JSON value, which is indexable by a vector index
+/// if it's a non-empty array of numbers.
+///
+/// # Arguments
+/// * `value`: The JSON value.
+pub fn json_to_vector(value: &models::Json) -> Option<Vec<f64>> {
+    let values = value.as_array()?;
+    if values.is_empty() {
+        return None;
+    }
+    values.iter().map(|value| value.as_f64()).collect()
+}
+
+/// Gets the smallest string that's greater than every string starting with
+/// a prefix, or `None` if there isn't one. Together with the prefix itself,
+/// this bounds the range of strings an ordered index has to scan to find
+/// those starting with the prefix.
+///
+/// # Arguments
+/// * `prefix`: The prefix.
+pub fn prefix_upper_bound(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(c) = chars.pop() {
+        // Skip over the surrogate range, which isn't made up of valid chars
+        let next = (c as u32 + 1..=char::MAX as u32).find_map(char::from_u32);
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+/// Translates a glob into an equivalent anchored regex. `*` matches any run
+/// of characters, `?` matches any single character, and everything else
+/// matches itself.
+///
+/// # Arguments
+/// * `glob`: The glob.
+pub fn glob_to_regex(glob: &str) -> String {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    pattern.push('$');
+    pattern
+}
+
+// Quantizes a coordinate to 32 bits, given the lowest value it can have and
+// the size of its range.
+fn quantize(value: f64, min: f64, range: f64) -> u32 {
+    (((value - min) / range) * 4_294_967_296.0).clamp(0.0, u32::MAX as f64) as u32
+}
+
+// Interleaves the bits of a longitude and latitude, starting with the
+// longitude's most significant bit.
+fn interleave(lon: u32, lat: u32) -> u64 {
+    (0..32).fold(0, |cell, i| {
+        cell | ((lon as u64 >> i & 1) << (2 * i + 1)) | ((lat as u64 >> i & 1) << (2 * i))
+    })
+}
+
+/// Gets the cell that a point falls in on a z-order curve. Points that are
+/// near each other tend to have nearby cells, which lets geospatial indexes
+/// be stored in an ordered key/value store.
+///
+/// # Arguments
+/// * `point`: The point.
+pub fn geo_cell(point: &models::GeoPoint) -> u64 {
+    interleave(quantize(point.lon, -180.0, 360.0), quantize(point.lat, -90.0, 180.0))
+}
+
+/// Gets inclusive ranges of cells, as produced by `geo_cell`, that together
+/// cover a bounding box. The box must not cross the antimeridian. Cells in
+/// the ranges may be outside of the box, so points read from them still
+/// have to be checked.
+///
+/// # Arguments
+/// * `bbox`: The bounding box.
+pub fn geo_cell_ranges(bbox: &models::BoundingBox) -> Vec<(u64, u64)> {
+    let (min_lon, max_lon) = (
+        quantize(bbox.min_lon, -180.0, 360.0),
+        quantize(bbox.max_lon, -180.0, 360.0),
+    );
+    let (min_lat, max_lat) = (
+        quantize(bbox.min_lat, -90.0, 180.0),
+        quantize(bbox.max_lat, -90.0, 180.0),
+    );
+
+    // Find the smallest cells, made up of the `2 * (32 - shift)` leading
+    // bits, of which the box spans no more than 4 along either axis
+    let shift = (0..32)
+        .find(|shift| (max_lon >> shift) - (min_lon >> shift) < 4 && (max_lat >> shift) - (min_lat >> shift) < 4)
+        .unwrap_or(31);
+
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    for lon in (min_lon >> shift)..=(max_lon >> shift) {
+        for lat in (min_lat >> shift)..=(max_lat >> shift) {
+            let start = interleave(lon, lat) << (2 * shift);
+            ranges.push((start, start | ((1 << (2 * shift)) - 1)));
+        }
+    }
+
+    // Merge adjacent ranges
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if last.1.checked_add(1) == Some(start) => last.1 = end,
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
 /// Extracts vertices from the last query output value, or `None`.
 ///
 /// # Arguments
@@ -219,13 +580,71 @@ pub fn extract_edge_properties(mut output: Vec<models::QueryOutputValue>) -> Opt
     }
 }
 
+/// Extracts the result of a sum, min, max or average from the last query
+/// output value, or `None`. The inner `Option` is `None` if there were no
+/// values to aggregate.
+///
+/// # Arguments
+/// * `output`: The query output.
+pub fn extract_aggregate(mut output: Vec<models::QueryOutputValue>) -> Option<Option<models::Json>> {
+    if let Some(models::QueryOutputValue::Aggregate(value)) = output.pop() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Extracts groups from the last query output value, or `None`.
+///
+/// # Arguments
+/// * `output`: The query output.
+pub fn extract_groups(mut output: Vec<models::QueryOutputValue>) -> Option<Vec<models::Group>> {
+    if let Some(models::QueryOutputValue::Groups(groups)) = output.pop() {
+        Some(groups)
+    } else {
+        None
+    }
+}
+
+/// Extracts degrees from the last query output value, or `None`.
+///
+/// # Arguments
+/// * `output`: The query output.
+pub fn extract_degrees(mut output: Vec<models::QueryOutputValue>) -> Option<Vec<models::Degree>> {
+    if let Some(models::QueryOutputValue::Degrees(degrees)) = output.pop() {
+        Some(degrees)
+    } else {
+        None
+    }
+}
+
+/// Extracts the cursor for the next page of an ordered query, or `None` if
+/// the output was the last page. Unlike the other extract functions, this
+/// borrows the output, since the cursor precedes the page of results.
+///
+/// # Arguments
+/// * `output`: The query output.
+pub fn extract_cursor(output: &[models::QueryOutputValue]) -> Option<models::Cursor> {
+    output
+        .iter()
+        .rev()
+        .find_map(|value| match value {
+            models::QueryOutputValue::Cursor(cursor) => Some(cursor.clone()),
+            _ => None,
+        })
+        .flatten()
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        extract_count, extract_edge_properties, extract_edges, extract_vertex_properties, extract_vertices,
-        generate_uuid_v1, next_uuid,
+        build, extract_aggregate, extract_count, extract_cursor, extract_degrees, extract_edge_properties,
+        extract_edges, extract_groups, extract_vertex_properties, extract_vertices, generate_uuid_v1, geo_cell,
+        geo_cell_ranges, glob_to_regex, json_to_vector, next_uuid, prefix_upper_bound, skip_json, tokenize, Component,
     };
+    use crate::{ijson, BoundingBox, GeoPoint};
     use core::str::FromStr;
+    use std::io::Cursor;
     use uuid::Uuid;
 
     #[test]
@@ -235,6 +654,77 @@ mod tests {
         assert_ne!(first, second);
     }
 
+    #[test]
+    fn should_encode_json_in_order() {
+        let values = [
+            ijson!(null),
+            ijson!(false),
+            ijson!(true),
+            ijson!(f64::MIN),
+            ijson!(i64::MIN),
+            ijson!(-1.5),
+            ijson!(-1),
+            ijson!(0),
+            ijson!(0.5),
+            ijson!(9007199254740992u64),
+            ijson!(9007199254740993u64),
+            ijson!(u64::MAX),
+            ijson!(f64::MAX),
+            ijson!(""),
+            ijson!("\u{0}"),
+            ijson!("a"),
+            ijson!("a\u{0}"),
+            ijson!("ab"),
+            ijson!([]),
+            ijson!([null]),
+            ijson!([1, 2]),
+            ijson!([2]),
+            ijson!({}),
+            ijson!({"a": 1}),
+            ijson!({"a": 1, "b": 1}),
+            ijson!({"b": 0}),
+        ];
+
+        let encoded: Vec<Vec<u8>> = values.iter().map(|v| build(&[Component::Json(v)])).collect();
+        for (i, bytes) in encoded.iter().enumerate() {
+            assert_eq!(bytes.len(), Component::Json(&values[i]).byte_len());
+            let mut cursor = Cursor::new(bytes);
+            skip_json(&mut cursor).unwrap();
+            assert_eq!(cursor.position() as usize, bytes.len());
+            if i > 0 {
+                assert!(
+                    encoded[i - 1] < *bytes,
+                    "{} should sort before {}",
+                    values[i - 1].0,
+                    values[i].0
+                );
+            }
+        }
+
+        assert_eq!(
+            build(&[Component::Json(&ijson!(1))]),
+            build(&[Component::Json(&ijson!(1.0))])
+        );
+        assert_eq!(
+            build(&[Component::Json(&ijson!(0))]),
+            build(&[Component::Json(&ijson!(-0.0))])
+        );
+    }
+
+    #[test]
+    fn should_encode_json_stably() {
+        // Keys written by earlier builds must keep matching, so the encoding
+        // should never change
+        let value = ijson!({"a": [null, true, -2.5, "x\u{0}"], "b": 3});
+        assert_eq!(
+            build(&[Component::Json(&value)]),
+            vec![
+                6, 1, 97, 0, 1, 5, 1, 2, 1, 3, 63, 251, 255, 255, 255, 255, 255, 255, 128, 0, 0, 0, 0, 0, 0, 0, 4, 120,
+                0, 255, 0, 1, 0, 1, 98, 0, 1, 3, 192, 8, 0, 0, 0, 0, 0, 0, 128, 0, 0, 0, 0, 0, 0, 0, 0
+            ]
+        );
+    }
+
     #[test]
     fn should_generate_next_uuid() {
         let result = next_uuid(Uuid::from_str("16151dea-a538-4bf1-9559-851e256cf139").unwrap());
@@ -248,6 +738,62 @@ mod tests {
         assert!(next_uuid(from_uuid).is_err());
     }
 
+    #[test]
+    fn should_tokenize() {
+        let terms: Vec<String> = tokenize("Ada Lovelace, née Byron; ADA-1815").into_iter().collect();
+        assert_eq!(terms, vec!["1815", "ada", "byron", "lovelace", "née"]);
+        assert!(tokenize(" -- ").is_empty());
+    }
+
+    #[test]
+    fn should_convert_json_to_vector() {
+        assert_eq!(json_to_vector(&ijson!([1, -2.5, 3])), Some(vec![1.0, -2.5, 3.0]));
+        assert_eq!(json_to_vector(&ijson!([1, "2"])), None);
+        assert_eq!(json_to_vector(&ijson!([])), None);
+        assert_eq!(json_to_vector(&ijson!(1)), None);
+    }
+
+    #[test]
+    fn should_get_prefix_upper_bound() {
+        assert_eq!(prefix_upper_bound("ada"), Some("adb".to_string()));
+        assert_eq!(prefix_upper_bound("a\u{d7ff}"), Some("a\u{e000}".to_string()));
+        assert_eq!(prefix_upper_bound("a\u{10ffff}"), Some("b".to_string()));
+        assert_eq!(prefix_upper_bound("\u{10ffff}"), None);
+        assert_eq!(prefix_upper_bound(""), None);
+    }
+
+    #[test]
+    fn should_convert_glob_to_regex() {
+        assert_eq!(glob_to_regex("ada*"), "^ada.*$");
+        assert_eq!(glob_to_regex("a?a.b"), "^a.a\\.b$");
+    }
+
+    #[test]
+    fn should_cover_bounding_box_with_geo_cells() {
+        let bbox = BoundingBox::new(
+            GeoPoint::new(45.4, -122.8).unwrap(),
+            GeoPoint::new(45.6, -122.5).unwrap(),
+        )
+        .unwrap();
+        let ranges = geo_cell_ranges(&bbox);
+        assert!(!ranges.is_empty() && ranges.len() <= 16);
+        let covered = |point: &GeoPoint| {
+            let cell = geo_cell(point);
+            ranges.iter().any(|(start, end)| cell >= *start && cell <= *end)
+        };
+        for lat in [45.4, 45.5, 45.6] {
+            for lon in [-122.8, -122.6, -122.5] {
+                assert!(covered(&GeoPoint::new(lat, lon).unwrap()));
+            }
+        }
+        assert!(!covered(&GeoPoint::new(-45.5, 57.4).unwrap()));
+
+        // Nearby points share their leading bits
+        let first = geo_cell(&GeoPoint::new(45.5, -122.6).unwrap());
+        let second = geo_cell(&GeoPoint::new(45.5001, -122.6001).unwrap());
+        assert_eq!(first >> 32, second >> 32);
+    }
+
     #[test]
     fn should_not_extract_vertices_on_empty() {
         assert_eq!(extract_vertices(vec![]), None);
@@ -272,4 +818,24 @@ mod tests {
     fn should_not_extract_edge_properties_on_empty() {
         assert_eq!(extract_edge_properties(vec![]), None);
     }
+
+    #[test]
+    fn should_not_extract_aggregate_on_empty() {
+        assert_eq!(extract_aggregate(vec![]), None);
+    }
+
+    #[test]
+    fn should_not_extract_groups_on_empty() {
+        assert_eq!(extract_groups(vec![]), None);
+    }
+
+    #[test]
+    fn should_not_extract_degrees_on_empty() {
+        assert_eq!(extract_degrees(vec![]), None);
+    }
+
+    #[test]
+    fn should_not_extract_cursor_on_empty() {
+        assert_eq!(extract_cursor(&[]), None);
+    }
 }
@@ -28,7 +28,7 @@ impl Default for VersionInfo {
         Self {
             rustc: env!("RUSTC_VERSION").to_string(),
             // If the interface is changed, this value should be incremented.
-            plugin_interface: 1,
+            plugin_interface: 2,
         }
     }
 }
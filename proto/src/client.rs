@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::error::Error as StdError;
 use std::fmt;
@@ -9,7 +10,7 @@ use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
 use tonic::transport::{Channel, Endpoint, Error as TonicTransportError};
-use tonic::{Request, Status};
+use tonic::{Request, Status, Streaming};
 use uuid::Uuid;
 
 const CHANNEL_CAPACITY: usize = 100;
@@ -73,6 +74,70 @@ impl<T> From<mpsc::error::SendError<T>> for ClientError {
     }
 }
 
+// Merges a chunk of a streamed query output value into the preceding chunks.
+fn merge_output_values(
+    value: indradb::QueryOutputValue,
+    chunk: indradb::QueryOutputValue,
+) -> Result<indradb::QueryOutputValue, ConversionError> {
+    match (value, chunk) {
+        (indradb::QueryOutputValue::Vertices(mut vertices), indradb::QueryOutputValue::Vertices(chunk)) => {
+            vertices.extend(chunk);
+            Ok(indradb::QueryOutputValue::Vertices(vertices))
+        }
+        (indradb::QueryOutputValue::Edges(mut edges), indradb::QueryOutputValue::Edges(chunk)) => {
+            edges.extend(chunk);
+            Ok(indradb::QueryOutputValue::Edges(edges))
+        }
+        (
+            indradb::QueryOutputValue::VertexProperties(mut vertex_properties),
+            indradb::QueryOutputValue::VertexProperties(chunk),
+        ) => {
+            vertex_properties.extend(chunk);
+            Ok(indradb::QueryOutputValue::VertexProperties(vertex_properties))
+        }
+        (
+            indradb::QueryOutputValue::EdgeProperties(mut edge_properties),
+            indradb::QueryOutputValue::EdgeProperties(chunk),
+        ) => {
+            edge_properties.extend(chunk);
+            Ok(indradb::QueryOutputValue::EdgeProperties(edge_properties))
+        }
+        (indradb::QueryOutputValue::Degrees(mut degrees), indradb::QueryOutputValue::Degrees(chunk)) => {
+            degrees.extend(chunk);
+            Ok(indradb::QueryOutputValue::Degrees(degrees))
+        }
+        _ => Err(ConversionError::UnexpectedResponseType),
+    }
+}
+
+// Collects streamed query output values, merging values that were streamed
+// in chunks back together.
+async fn collect_output_values(
+    mut res: Streaming<crate::QueryOutputValue>,
+) -> Result<Vec<indradb::QueryOutputValue>, ClientError> {
+    let mut output = Vec::<indradb::QueryOutputValue>::new();
+    let mut pending: Option<indradb::QueryOutputValue> = None;
+    while let Some(res) = res.next().await {
+        let res = res?;
+        let partial = res.partial;
+        let value: indradb::QueryOutputValue = res.try_into()?;
+        let value = match pending.take() {
+            Some(pending_value) => merge_output_values(pending_value, value)?,
+            None => value,
+        };
+        if partial {
+            pending = Some(value);
+        } else {
+            output.push(value);
+        }
+    }
+    if pending.is_some() {
+        // The stream ended partway through a value
+        return Err(ConversionError::UnexpectedResponseType.into());
+    }
+    Ok(output)
+}
+
 /// A higher-level client implementation.
 ///
 /// This should be better suited than the low-level client auto-generated by
@@ -142,18 +207,46 @@ impl Client {
         Ok(res.into_inner().created)
     }
 
+    /// Atomically gets the vertex of a type with a property value, or
+    /// creates one with the value and some other properties if there isn't
+    /// any. Returns the vertex's UUID, and whether it was created.
+    ///
+    /// # Arguments
+    /// * `t`: The vertex type.
+    /// * `name`: The property name to look the vertex up by.
+    /// * `value`: The property value to look the vertex up by.
+    /// * `properties`: The other properties to set if the vertex is created.
+    pub async fn get_or_create_vertex(
+        &mut self,
+        t: indradb::Identifier,
+        name: indradb::Identifier,
+        value: &indradb::Json,
+        properties: Vec<indradb::NamedProperty>,
+    ) -> Result<(Uuid, bool), ClientError> {
+        let req = crate::GetOrCreateVertexRequest::from((t, name, value.clone(), properties));
+        let res = self.0.get_or_create_vertex(req).await?;
+        Ok(res.into_inner().try_into()?)
+    }
+
     /// Gets values specified by a query.
     ///
     /// # Arguments
     /// * `q`: The query to run.
     pub async fn get<Q: Into<indradb::Query>>(&mut self, q: Q) -> Result<Vec<indradb::QueryOutputValue>, ClientError> {
         let q: crate::Query = q.into().into();
-        let mut output = Vec::<indradb::QueryOutputValue>::new();
-        let mut res = self.0.get(q).await?.into_inner();
-        while let Some(res) = res.next().await {
-            output.push(res?.try_into()?);
-        }
-        Ok(output)
+        let res = self.0.get(q).await?.into_inner();
+        collect_output_values(res).await
+    }
+
+    /// Gets values specified by a query in its text syntax, e.g.
+    /// `V(type=user).out(follows).count()`.
+    ///
+    /// # Arguments
+    /// * `text`: The text of the query to run.
+    pub async fn get_by_text(&mut self, text: &str) -> Result<Vec<indradb::QueryOutputValue>, ClientError> {
+        let req = crate::QueryText { text: text.to_string() };
+        let res = self.0.get_by_text(req).await?.into_inner();
+        collect_output_values(res).await
     }
 
     /// Deletes values specified by a query.
@@ -228,14 +321,209 @@ impl Client {
         }
     }
 
-    pub async fn index_property(&mut self, name: indradb::Identifier) -> Result<(), ClientError> {
+    pub async fn index_property(
+        &mut self,
+        name: indradb::Identifier,
+        scope: indradb::IndexScope,
+    ) -> Result<(), ClientError> {
         let request = Request::new(crate::IndexPropertyRequest {
             name: Some(name.into()),
+            scope: Some(scope.into()),
         });
         self.0.index_property(request).await?;
         Ok(())
     }
 
+    /// Disables indexing on a specified property under a given scope.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    /// * `scope`: The scope to unindex.
+    pub async fn unindex_property(
+        &mut self,
+        name: indradb::Identifier,
+        scope: indradb::IndexScope,
+    ) -> Result<(), ClientError> {
+        let request = Request::new(crate::UnindexPropertyRequest {
+            name: Some(name.into()),
+            scope: Some(scope.into()),
+        });
+        self.0.unindex_property(request).await?;
+        Ok(())
+    }
+
+    /// Gets the names of all indexed properties, along with the scopes each
+    /// is indexed under.
+    pub async fn indexed_properties(
+        &mut self,
+    ) -> Result<HashMap<indradb::Identifier, HashSet<indradb::IndexScope>>, ClientError> {
+        let res = self.0.indexed_properties(()).await?;
+        let mut indexed_properties: HashMap<indradb::Identifier, HashSet<indradb::IndexScope>> = HashMap::new();
+        for property in res.into_inner().properties {
+            let (name, scope): (indradb::Identifier, indradb::IndexScope) = property.try_into()?;
+            indexed_properties.entry(name).or_default().insert(scope);
+        }
+        Ok(indexed_properties)
+    }
+
+    /// Enables a composite index over an ordered list of vertex property
+    /// names.
+    ///
+    /// # Arguments
+    /// * `names`: The property names, in index order.
+    pub async fn index_composite(&mut self, names: Vec<indradb::Identifier>) -> Result<(), ClientError> {
+        let request = Request::new(crate::CompositeIndex::from(names));
+        self.0.index_composite(request).await?;
+        Ok(())
+    }
+
+    /// Disables a composite index.
+    ///
+    /// # Arguments
+    /// * `names`: The property names of the index, in index order.
+    pub async fn unindex_composite(&mut self, names: Vec<indradb::Identifier>) -> Result<(), ClientError> {
+        let request = Request::new(crate::CompositeIndex::from(names));
+        self.0.unindex_composite(request).await?;
+        Ok(())
+    }
+
+    /// Gets the property names of all composite indexes.
+    pub async fn composite_indexes(&mut self) -> Result<HashSet<Vec<indradb::Identifier>>, ClientError> {
+        let res = self.0.composite_indexes(()).await?;
+        let mut composite_indexes = HashSet::new();
+        for index in res.into_inner().indexes {
+            composite_indexes.insert(index.try_into()?);
+        }
+        Ok(composite_indexes)
+    }
+
+    /// Enables a full-text index on a vertex property.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    pub async fn index_property_fulltext(&mut self, name: indradb::Identifier) -> Result<(), ClientError> {
+        let request = Request::new(crate::Identifier::from(name));
+        self.0.index_property_fulltext(request).await?;
+        Ok(())
+    }
+
+    /// Disables a full-text index.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    pub async fn unindex_property_fulltext(&mut self, name: indradb::Identifier) -> Result<(), ClientError> {
+        let request = Request::new(crate::Identifier::from(name));
+        self.0.unindex_property_fulltext(request).await?;
+        Ok(())
+    }
+
+    /// Gets the names of all properties with a full-text index.
+    pub async fn fulltext_indexed_properties(&mut self) -> Result<HashSet<indradb::Identifier>, ClientError> {
+        let res = self.0.fulltext_indexed_properties(()).await?;
+        let mut names = HashSet::new();
+        for name in res.into_inner().names {
+            names.insert(name.try_into()?);
+        }
+        Ok(names)
+    }
+
+    /// Enables a vector index on a vertex property.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    pub async fn index_property_vector(&mut self, name: indradb::Identifier) -> Result<(), ClientError> {
+        let request = Request::new(crate::Identifier::from(name));
+        self.0.index_property_vector(request).await?;
+        Ok(())
+    }
+
+    /// Disables a vector index.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    pub async fn unindex_property_vector(&mut self, name: indradb::Identifier) -> Result<(), ClientError> {
+        let request = Request::new(crate::Identifier::from(name));
+        self.0.unindex_property_vector(request).await?;
+        Ok(())
+    }
+
+    /// Gets the names of all properties with a vector index.
+    pub async fn vector_indexed_properties(&mut self) -> Result<HashSet<indradb::Identifier>, ClientError> {
+        let res = self.0.vector_indexed_properties(()).await?;
+        let mut names = HashSet::new();
+        for name in res.into_inner().names {
+            names.insert(name.try_into()?);
+        }
+        Ok(names)
+    }
+
+    /// Enables a geospatial index on a vertex property.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    pub async fn index_property_geo(&mut self, name: indradb::Identifier) -> Result<(), ClientError> {
+        let request = Request::new(crate::Identifier::from(name));
+        self.0.index_property_geo(request).await?;
+        Ok(())
+    }
+
+    /// Disables a geospatial index.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to unindex.
+    pub async fn unindex_property_geo(&mut self, name: indradb::Identifier) -> Result<(), ClientError> {
+        let request = Request::new(crate::Identifier::from(name));
+        self.0.unindex_property_geo(request).await?;
+        Ok(())
+    }
+
+    /// Gets the names of all properties with a geospatial index.
+    pub async fn geo_indexed_properties(&mut self) -> Result<HashSet<indradb::Identifier>, ClientError> {
+        let res = self.0.geo_indexed_properties(()).await?;
+        let mut names = HashSet::new();
+        for name in res.into_inner().names {
+            names.insert(name.try_into()?);
+        }
+        Ok(names)
+    }
+
+    /// Adds a unique constraint on a vertex property.
+    ///
+    /// # Arguments
+    /// * `constraint`: The constraint to add.
+    pub async fn add_unique_constraint(&mut self, constraint: indradb::UniqueConstraint) -> Result<(), ClientError> {
+        let request = Request::new(crate::UniqueConstraint::from(constraint));
+        self.0.add_unique_constraint(request).await?;
+        Ok(())
+    }
+
+    /// Removes a unique constraint.
+    ///
+    /// # Arguments
+    /// * `constraint`: The constraint to remove.
+    pub async fn remove_unique_constraint(&mut self, constraint: indradb::UniqueConstraint) -> Result<(), ClientError> {
+        let request = Request::new(crate::UniqueConstraint::from(constraint));
+        self.0.remove_unique_constraint(request).await?;
+        Ok(())
+    }
+
+    /// Gets all unique constraints.
+    pub async fn unique_constraints(&mut self) -> Result<HashSet<indradb::UniqueConstraint>, ClientError> {
+        let res = self.0.unique_constraints(()).await?;
+        let mut unique_constraints = HashSet::new();
+        for constraint in res.into_inner().constraints {
+            unique_constraints.insert(constraint.try_into()?);
+        }
+        Ok(unique_constraints)
+    }
+
+    /// Gets counts of the vertices and edges of each type, and of the
+    /// vertices and edges with each indexed property value.
+    pub async fn stats(&mut self) -> Result<indradb::Stats, ClientError> {
+        let res = self.0.stats(()).await?;
+        Ok(res.into_inner().try_into()?)
+    }
+
     pub async fn execute_plugin(&mut self, name: &str, arg: indradb::Json) -> Result<indradb::Json, ClientError> {
         let req = Request::new(crate::ExecutePluginRequest {
             name: name.to_string(),
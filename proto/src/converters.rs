@@ -1,9 +1,11 @@
 //! Trait implementations for conveniently converting between protobuf and
 //! native IndraDB models.
 
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::error::Error as StdError;
 use std::fmt;
+use std::ops::Bound;
 
 use indradb::ValidationError;
 use serde_json::Error as SerdeJsonError;
@@ -67,6 +69,34 @@ fn required_field<T>(field_name: &str, value: Option<T>) -> Result<T, Conversion
     })
 }
 
+fn from_json_bound(bound: Bound<indradb::Json>) -> Option<crate::JsonBound> {
+    match bound {
+        Bound::Included(value) => Some(crate::JsonBound {
+            value: Some(value.into()),
+            inclusive: true,
+        }),
+        Bound::Excluded(value) => Some(crate::JsonBound {
+            value: Some(value.into()),
+            inclusive: false,
+        }),
+        Bound::Unbounded => None,
+    }
+}
+
+fn try_into_json_bound(bound: Option<crate::JsonBound>) -> Result<Bound<indradb::Json>, ConversionError> {
+    match bound {
+        Some(bound) => {
+            let value = required_field("value", bound.value)?.try_into()?;
+            if bound.inclusive {
+                Ok(Bound::Included(value))
+            } else {
+                Ok(Bound::Excluded(value))
+            }
+        }
+        None => Ok(Bound::Unbounded),
+    }
+}
+
 impl From<Uuid> for crate::Uuid {
     fn from(uuid: Uuid) -> Self {
         crate::Uuid {
@@ -171,14 +201,60 @@ impl From<indradb::Query> for crate::Query {
                 indradb::Query::VertexWithPropertyPresence(q) => {
                     crate::QueryVariant::VertexWithPropertyPresence(crate::VertexWithPropertyPresenceQuery {
                         name: Some(q.name.into()),
+                        t: q.t.map(|t| t.into()),
                     })
                 }
                 indradb::Query::VertexWithPropertyValue(q) => {
                     crate::QueryVariant::VertexWithPropertyValue(crate::VertexWithPropertyValueQuery {
                         name: Some(q.name.into()),
                         value: Some(q.value.into()),
+                        t: q.t.map(|t| t.into()),
+                    })
+                }
+                indradb::Query::VertexWithPropertyRange(q) => {
+                    crate::QueryVariant::VertexWithPropertyRange(crate::VertexWithPropertyRangeQuery {
+                        name: Some(q.name.into()),
+                        lower: from_json_bound(q.lower),
+                        upper: from_json_bound(q.upper),
+                        t: q.t.map(|t| t.into()),
+                    })
+                }
+                indradb::Query::VertexWithPropertyValues(q) => {
+                    crate::QueryVariant::VertexWithPropertyValues(crate::VertexWithPropertyValuesQuery {
+                        values: q
+                            .values
+                            .into_iter()
+                            .map(|(name, value)| indradb::NamedProperty::new(name, value).into())
+                            .collect(),
+                    })
+                }
+                indradb::Query::VertexWithPropertyMatch(q) => {
+                    crate::QueryVariant::VertexWithPropertyMatch(crate::VertexWithPropertyMatchQuery {
+                        name: Some(q.name.into()),
+                        text: q.text,
+                    })
+                }
+                indradb::Query::NearestVertices(q) => {
+                    let mut proto_q = crate::NearestVerticesQuery {
+                        name: Some(q.name.into()),
+                        vector: q.vector,
+                        k: q.k,
+                        metric: 0,
+                    };
+                    proto_q.set_metric(q.metric.into());
+                    crate::QueryVariant::NearestVertices(proto_q)
+                }
+                indradb::Query::WithinBoundingBox(q) => {
+                    crate::QueryVariant::WithinBoundingBox(crate::WithinBoundingBoxQuery {
+                        name: Some(q.name.into()),
+                        bbox: Some(q.bbox.into()),
                     })
                 }
+                indradb::Query::WithinRadius(q) => crate::QueryVariant::WithinRadius(crate::WithinRadiusQuery {
+                    name: Some(q.name.into()),
+                    center: Some(q.center.into()),
+                    radius: q.radius,
+                }),
 
                 indradb::Query::AllEdge => crate::QueryVariant::AllEdge(()),
                 indradb::Query::SpecificEdge(q) => crate::QueryVariant::SpecificEdge(crate::SpecificEdgeQuery {
@@ -187,12 +263,22 @@ impl From<indradb::Query> for crate::Query {
                 indradb::Query::EdgeWithPropertyPresence(q) => {
                     crate::QueryVariant::EdgeWithPropertyPresence(crate::EdgeWithPropertyPresenceQuery {
                         name: Some(q.name.into()),
+                        t: q.t.map(|t| t.into()),
                     })
                 }
                 indradb::Query::EdgeWithPropertyValue(q) => {
                     crate::QueryVariant::EdgeWithPropertyValue(crate::EdgeWithPropertyValueQuery {
                         name: Some(q.name.into()),
                         value: Some(q.value.into()),
+                        t: q.t.map(|t| t.into()),
+                    })
+                }
+                indradb::Query::EdgeWithPropertyRange(q) => {
+                    crate::QueryVariant::EdgeWithPropertyRange(crate::EdgeWithPropertyRangeQuery {
+                        name: Some(q.name.into()),
+                        lower: from_json_bound(q.lower),
+                        upper: from_json_bound(q.upper),
+                        t: q.t.map(|t| t.into()),
                     })
                 }
 
@@ -201,7 +287,8 @@ impl From<indradb::Query> for crate::Query {
                         inner: Some(Box::new((*q.inner).into())),
                         direction: 0,
                         limit: q.limit,
-                        t: q.t.map(|t| t.into()),
+                        t: q.t.into_iter().map(|t| t.into()).collect(),
+                        after: q.after.map(|e| e.into()),
                     };
                     proto_q.set_direction(q.direction.into());
                     crate::QueryVariant::Pipe(Box::new(proto_q))
@@ -230,6 +317,75 @@ impl From<indradb::Query> for crate::Query {
                     };
                     crate::QueryVariant::PipeWithPropertyValue(Box::new(proto_q))
                 }
+                indradb::Query::PipeWithPropertyRange(q) => {
+                    let proto_q = crate::PipeWithPropertyRangeQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                        lower: from_json_bound(q.lower),
+                        upper: from_json_bound(q.upper),
+                    };
+                    crate::QueryVariant::PipeWithPropertyRange(Box::new(proto_q))
+                }
+                indradb::Query::PipeWithPropertyPattern(q) => {
+                    let proto_q = crate::PipeWithPropertyPatternQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                        pattern: Some(q.pattern.into()),
+                    };
+                    crate::QueryVariant::PipeWithPropertyPattern(Box::new(proto_q))
+                }
+                indradb::Query::PipeWithinBoundingBox(q) => {
+                    let proto_q = crate::PipeWithinBoundingBoxQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                        bbox: Some(q.bbox.into()),
+                    };
+                    crate::QueryVariant::PipeWithinBoundingBox(Box::new(proto_q))
+                }
+                indradb::Query::PipeWithinRadius(q) => {
+                    let proto_q = crate::PipeWithinRadiusQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                        center: Some(q.center.into()),
+                        radius: q.radius,
+                    };
+                    crate::QueryVariant::PipeWithinRadius(Box::new(proto_q))
+                }
+                indradb::Query::Traversal(q) => {
+                    let mut proto_q = crate::TraversalQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        direction: 0,
+                        min_depth: q.min_depth,
+                        max_depth: q.max_depth,
+                        t: q.t.map(|t| t.into()),
+                        limit: q.limit,
+                    };
+                    proto_q.set_direction(q.direction.into());
+                    crate::QueryVariant::Traversal(Box::new(proto_q))
+                }
+                indradb::Query::ShortestPath(q) => {
+                    let mut proto_q = crate::ShortestPathQuery {
+                        source: Some(q.source.into()),
+                        target: Some(q.target.into()),
+                        direction: 0,
+                        t: q.t.into_iter().map(|t| t.into()).collect(),
+                        max_depth: q.max_depth,
+                        bidirectional: q.bidirectional,
+                    };
+                    proto_q.set_direction(q.direction.into());
+                    crate::QueryVariant::ShortestPath(proto_q)
+                }
+                indradb::Query::OrderBy(q) => {
+                    let mut proto_q = crate::OrderByQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                        direction: 0,
+                        limit: q.limit,
+                        cursor: q.cursor.map(String::from).unwrap_or_default(),
+                    };
+                    proto_q.set_direction(q.direction.into());
+                    crate::QueryVariant::OrderBy(Box::new(proto_q))
+                }
 
                 indradb::Query::Include(q) => {
                     let proto_q = crate::IncludeQuery {
@@ -243,6 +399,48 @@ impl From<indradb::Query> for crate::Query {
                     };
                     crate::QueryVariant::Count(Box::new(proto_q))
                 }
+                indradb::Query::Sum(q) => {
+                    let proto_q = crate::SumQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                    };
+                    crate::QueryVariant::Sum(Box::new(proto_q))
+                }
+                indradb::Query::Min(q) => {
+                    let proto_q = crate::MinQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                    };
+                    crate::QueryVariant::Min(Box::new(proto_q))
+                }
+                indradb::Query::Max(q) => {
+                    let proto_q = crate::MaxQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                    };
+                    crate::QueryVariant::Max(Box::new(proto_q))
+                }
+                indradb::Query::Avg(q) => {
+                    let proto_q = crate::AvgQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                    };
+                    crate::QueryVariant::Avg(Box::new(proto_q))
+                }
+                indradb::Query::GroupBy(q) => {
+                    let proto_q = crate::GroupByQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        key: Some(q.key.into()),
+                    };
+                    crate::QueryVariant::GroupBy(Box::new(proto_q))
+                }
+                indradb::Query::Degree(q) => {
+                    let proto_q = crate::DegreeQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        t: q.t.map(|t| t.into()),
+                    };
+                    crate::QueryVariant::Degree(Box::new(proto_q))
+                }
             }),
         }
     }
@@ -267,6 +465,7 @@ impl TryInto<indradb::Query> for crate::Query {
                 let name = required_field("name", q.name)?;
                 indradb::Query::VertexWithPropertyPresence(indradb::VertexWithPropertyPresenceQuery {
                     name: name.try_into()?,
+                    t: q.t.map(|t| t.try_into()).transpose()?,
                 })
             }
             crate::QueryVariant::VertexWithPropertyValue(q) => {
@@ -275,8 +474,51 @@ impl TryInto<indradb::Query> for crate::Query {
                 indradb::Query::VertexWithPropertyValue(indradb::VertexWithPropertyValueQuery {
                     name: name.try_into()?,
                     value: value.try_into()?,
+                    t: q.t.map(|t| t.try_into()).transpose()?,
+                })
+            }
+            crate::QueryVariant::VertexWithPropertyRange(q) => {
+                let name = required_field("name", q.name)?;
+                indradb::Query::VertexWithPropertyRange(indradb::VertexWithPropertyRangeQuery {
+                    name: name.try_into()?,
+                    lower: try_into_json_bound(q.lower)?,
+                    upper: try_into_json_bound(q.upper)?,
+                    t: q.t.map(|t| t.try_into()).transpose()?,
+                })
+            }
+            crate::QueryVariant::VertexWithPropertyValues(q) => {
+                let props: Result<Vec<indradb::NamedProperty>, ConversionError> =
+                    q.values.into_iter().map(|prop| prop.try_into()).collect();
+                indradb::Query::VertexWithPropertyValues(indradb::VertexWithPropertyValuesQuery {
+                    values: props?.into_iter().map(|prop| (prop.name, prop.value)).collect(),
+                })
+            }
+            crate::QueryVariant::VertexWithPropertyMatch(q) => {
+                indradb::Query::VertexWithPropertyMatch(indradb::VertexWithPropertyMatchQuery {
+                    name: required_field("name", q.name)?.try_into()?,
+                    text: q.text,
                 })
             }
+            crate::QueryVariant::NearestVertices(q) => {
+                let metric = q.metric().into();
+                indradb::Query::NearestVertices(indradb::NearestVerticesQuery {
+                    name: required_field("name", q.name)?.try_into()?,
+                    vector: q.vector,
+                    k: q.k,
+                    metric,
+                })
+            }
+            crate::QueryVariant::WithinBoundingBox(q) => {
+                indradb::Query::WithinBoundingBox(indradb::WithinBoundingBoxQuery {
+                    name: required_field("name", q.name)?.try_into()?,
+                    bbox: required_field("bbox", q.bbox)?.into(),
+                })
+            }
+            crate::QueryVariant::WithinRadius(q) => indradb::Query::WithinRadius(indradb::WithinRadiusQuery {
+                name: required_field("name", q.name)?.try_into()?,
+                center: required_field("center", q.center)?.into(),
+                radius: q.radius,
+            }),
 
             crate::QueryVariant::AllEdge(_q) => indradb::Query::AllEdge,
             crate::QueryVariant::SpecificEdge(q) => {
@@ -288,6 +530,7 @@ impl TryInto<indradb::Query> for crate::Query {
                 let name = required_field("name", q.name)?;
                 indradb::Query::EdgeWithPropertyPresence(indradb::EdgeWithPropertyPresenceQuery {
                     name: name.try_into()?,
+                    t: q.t.map(|t| t.try_into()).transpose()?,
                 })
             }
             crate::QueryVariant::EdgeWithPropertyValue(q) => {
@@ -296,18 +539,31 @@ impl TryInto<indradb::Query> for crate::Query {
                 indradb::Query::EdgeWithPropertyValue(indradb::EdgeWithPropertyValueQuery {
                     name: name.try_into()?,
                     value: value.try_into()?,
+                    t: q.t.map(|t| t.try_into()).transpose()?,
+                })
+            }
+            crate::QueryVariant::EdgeWithPropertyRange(q) => {
+                let name = required_field("name", q.name)?;
+                indradb::Query::EdgeWithPropertyRange(indradb::EdgeWithPropertyRangeQuery {
+                    name: name.try_into()?,
+                    lower: try_into_json_bound(q.lower)?,
+                    upper: try_into_json_bound(q.upper)?,
+                    t: q.t.map(|t| t.try_into()).transpose()?,
                 })
             }
 
             crate::QueryVariant::Pipe(q) => {
                 let direction = q.direction().into();
                 let limit = q.limit;
-                let t = q.t.map(|t| t.try_into()).transpose()?;
+                let t: Result<Vec<indradb::Identifier>, ConversionError> =
+                    q.t.into_iter().map(|t| t.try_into()).collect();
+                let after = q.after.map(|e| e.try_into()).transpose()?;
                 let inner = required_field("inner", q.inner)?;
                 indradb::Query::Pipe(indradb::PipeQuery {
                     direction,
                     limit,
-                    t,
+                    t: t?,
+                    after,
                     inner: Box::new((*inner).try_into()?),
                 })
             }
@@ -339,6 +595,89 @@ impl TryInto<indradb::Query> for crate::Query {
                     equal: q.equal,
                 })
             }
+            crate::QueryVariant::PipeWithPropertyRange(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let name = required_field("name", q.name)?;
+                indradb::Query::PipeWithPropertyRange(indradb::PipeWithPropertyRangeQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    name: name.try_into()?,
+                    lower: try_into_json_bound(q.lower)?,
+                    upper: try_into_json_bound(q.upper)?,
+                })
+            }
+            crate::QueryVariant::PipeWithPropertyPattern(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let name = required_field("name", q.name)?;
+                let pattern = required_field("pattern", q.pattern)?;
+                indradb::Query::PipeWithPropertyPattern(indradb::PipeWithPropertyPatternQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    name: name.try_into()?,
+                    pattern: pattern.try_into()?,
+                })
+            }
+            crate::QueryVariant::PipeWithinBoundingBox(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let name = required_field("name", q.name)?;
+                indradb::Query::PipeWithinBoundingBox(indradb::PipeWithinBoundingBoxQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    name: name.try_into()?,
+                    bbox: required_field("bbox", q.bbox)?.into(),
+                })
+            }
+            crate::QueryVariant::PipeWithinRadius(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let name = required_field("name", q.name)?;
+                indradb::Query::PipeWithinRadius(indradb::PipeWithinRadiusQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    name: name.try_into()?,
+                    center: required_field("center", q.center)?.into(),
+                    radius: q.radius,
+                })
+            }
+            crate::QueryVariant::Traversal(q) => {
+                let direction = q.direction().into();
+                let t = q.t.map(|t| t.try_into()).transpose()?;
+                let inner = required_field("inner", q.inner)?;
+                indradb::Query::Traversal(indradb::TraversalQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    direction,
+                    min_depth: q.min_depth,
+                    max_depth: q.max_depth,
+                    t,
+                    limit: q.limit,
+                })
+            }
+            crate::QueryVariant::ShortestPath(q) => {
+                let direction = q.direction().into();
+                let source = required_field("source", q.source)?;
+                let target = required_field("target", q.target)?;
+                let t: Result<Vec<indradb::Identifier>, ConversionError> =
+                    q.t.into_iter().map(|t| t.try_into()).collect();
+                indradb::Query::ShortestPath(indradb::ShortestPathQuery {
+                    source: source.try_into()?,
+                    target: target.try_into()?,
+                    direction,
+                    t: t?,
+                    max_depth: q.max_depth,
+                    bidirectional: q.bidirectional,
+                })
+            }
+            crate::QueryVariant::OrderBy(q) => {
+                let direction = q.direction().into();
+                let inner = required_field("inner", q.inner)?;
+                let name = required_field("name", q.name)?;
+                indradb::Query::OrderBy(indradb::OrderByQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    name: name.try_into()?,
+                    direction,
+                    limit: q.limit,
+                    cursor: if q.cursor.is_empty() {
+                        None
+                    } else {
+                        Some(q.cursor.into())
+                    },
+                })
+            }
 
             crate::QueryVariant::Include(q) => {
                 let inner = required_field("inner", q.inner)?;
@@ -352,6 +691,53 @@ impl TryInto<indradb::Query> for crate::Query {
                     inner: Box::new((*inner).try_into()?),
                 })
             }
+            crate::QueryVariant::Sum(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let name = required_field("name", q.name)?;
+                indradb::Query::Sum(indradb::SumQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    name: name.try_into()?,
+                })
+            }
+            crate::QueryVariant::Min(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let name = required_field("name", q.name)?;
+                indradb::Query::Min(indradb::MinQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    name: name.try_into()?,
+                })
+            }
+            crate::QueryVariant::Max(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let name = required_field("name", q.name)?;
+                indradb::Query::Max(indradb::MaxQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    name: name.try_into()?,
+                })
+            }
+            crate::QueryVariant::Avg(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let name = required_field("name", q.name)?;
+                indradb::Query::Avg(indradb::AvgQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    name: name.try_into()?,
+                })
+            }
+            crate::QueryVariant::GroupBy(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let key = required_field("key", q.key)?;
+                indradb::Query::GroupBy(indradb::GroupByQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    key: key.try_into()?,
+                })
+            }
+            crate::QueryVariant::Degree(q) => {
+                let inner = required_field("inner", q.inner)?;
+                indradb::Query::Degree(indradb::DegreeQuery {
+                    inner: Box::new((*inner).try_into()?),
+                    t: q.t.map(|t| t.try_into()).transpose()?,
+                })
+            }
         })
     }
 }
@@ -378,9 +764,50 @@ impl From<indradb::QueryOutputValue> for crate::QueryOutputValue {
                     edge_properties: edge_properties.into_iter().map(|ep| ep.into()).collect(),
                 })
             }
+            indradb::QueryOutputValue::Path(path) => {
+                let path = path.unwrap_or_else(|| indradb::Path::new(Vec::default(), Vec::default()));
+                crate::QueryOutputValueVariant::Path(crate::QueryOutputPath {
+                    vertices: path.vertices.into_iter().map(|v| v.into()).collect(),
+                    edges: path.edges.into_iter().map(|e| e.into()).collect(),
+                })
+            }
+            indradb::QueryOutputValue::Cursor(cursor) => {
+                crate::QueryOutputValueVariant::Cursor(cursor.map(String::from).unwrap_or_default())
+            }
+            indradb::QueryOutputValue::Aggregate(value) => {
+                crate::QueryOutputValueVariant::Aggregate(crate::QueryOutputAggregate {
+                    value: value.map(|value| value.into()),
+                })
+            }
+            indradb::QueryOutputValue::Groups(groups) => {
+                crate::QueryOutputValueVariant::Groups(crate::QueryOutputGroups {
+                    groups: groups
+                        .into_iter()
+                        .map(|group| crate::QueryOutputGroup {
+                            key: Some(group.key.into()),
+                            count: group.count,
+                        })
+                        .collect(),
+                })
+            }
+            indradb::QueryOutputValue::Degrees(degrees) => {
+                crate::QueryOutputValueVariant::Degrees(crate::QueryOutputDegrees {
+                    degrees: degrees
+                        .into_iter()
+                        .map(|degree| crate::QueryOutputDegree {
+                            id: Some(degree.id.into()),
+                            outbound: degree.outbound,
+                            inbound: degree.inbound,
+                        })
+                        .collect(),
+                })
+            }
         };
 
-        crate::QueryOutputValue { value: Some(variant) }
+        crate::QueryOutputValue {
+            value: Some(variant),
+            partial: false,
+        }
     }
 }
 
@@ -416,6 +843,49 @@ impl TryInto<indradb::QueryOutputValue> for crate::QueryOutputValue {
                     .collect();
                 indradb::QueryOutputValue::EdgeProperties(edge_properties?)
             }
+            crate::QueryOutputValueVariant::Path(path) => {
+                if path.vertices.is_empty() {
+                    indradb::QueryOutputValue::Path(None)
+                } else {
+                    let vertices: Result<Vec<indradb::Vertex>, ConversionError> =
+                        path.vertices.into_iter().map(|v| v.try_into()).collect();
+                    let edges: Result<Vec<indradb::Edge>, ConversionError> =
+                        path.edges.into_iter().map(|e| e.try_into()).collect();
+                    indradb::QueryOutputValue::Path(Some(indradb::Path::new(vertices?, edges?)))
+                }
+            }
+            crate::QueryOutputValueVariant::Cursor(cursor) => {
+                if cursor.is_empty() {
+                    indradb::QueryOutputValue::Cursor(None)
+                } else {
+                    indradb::QueryOutputValue::Cursor(Some(cursor.into()))
+                }
+            }
+            crate::QueryOutputValueVariant::Aggregate(aggregate) => {
+                indradb::QueryOutputValue::Aggregate(aggregate.value.map(|value| value.try_into()).transpose()?)
+            }
+            crate::QueryOutputValueVariant::Groups(groups) => {
+                let groups: Result<Vec<indradb::Group>, ConversionError> = groups
+                    .groups
+                    .into_iter()
+                    .map(|group| {
+                        let key = required_field("key", group.key)?;
+                        Ok(indradb::Group::new(key.try_into()?, group.count))
+                    })
+                    .collect();
+                indradb::QueryOutputValue::Groups(groups?)
+            }
+            crate::QueryOutputValueVariant::Degrees(degrees) => {
+                let degrees: Result<Vec<indradb::Degree>, ConversionError> = degrees
+                    .degrees
+                    .into_iter()
+                    .map(|degree| {
+                        let id = required_field("id", degree.id)?;
+                        Ok(indradb::Degree::new(id.try_into()?, degree.outbound, degree.inbound))
+                    })
+                    .collect();
+                indradb::QueryOutputValue::Degrees(degrees?)
+            }
         })
     }
 }
@@ -438,6 +908,44 @@ impl From<crate::EdgeDirection> for indradb::EdgeDirection {
     }
 }
 
+impl From<indradb::SortDirection> for crate::SortDirection {
+    fn from(direction: indradb::SortDirection) -> Self {
+        match direction {
+            indradb::SortDirection::Ascending => crate::SortDirection::Ascending,
+            indradb::SortDirection::Descending => crate::SortDirection::Descending,
+        }
+    }
+}
+
+impl From<crate::SortDirection> for indradb::SortDirection {
+    fn from(direction: crate::SortDirection) -> Self {
+        match direction {
+            crate::SortDirection::Ascending => indradb::SortDirection::Ascending,
+            crate::SortDirection::Descending => indradb::SortDirection::Descending,
+        }
+    }
+}
+
+impl From<indradb::DistanceMetric> for crate::DistanceMetric {
+    fn from(metric: indradb::DistanceMetric) -> Self {
+        match metric {
+            indradb::DistanceMetric::Euclidean => crate::DistanceMetric::Euclidean,
+            indradb::DistanceMetric::Cosine => crate::DistanceMetric::Cosine,
+            indradb::DistanceMetric::DotProduct => crate::DistanceMetric::DotProduct,
+        }
+    }
+}
+
+impl From<crate::DistanceMetric> for indradb::DistanceMetric {
+    fn from(metric: crate::DistanceMetric) -> Self {
+        match metric {
+            crate::DistanceMetric::Euclidean => indradb::DistanceMetric::Euclidean,
+            crate::DistanceMetric::Cosine => indradb::DistanceMetric::Cosine,
+            crate::DistanceMetric::DotProduct => indradb::DistanceMetric::DotProduct,
+        }
+    }
+}
+
 impl From<indradb::NamedProperty> for crate::NamedProperty {
     fn from(prop: indradb::NamedProperty) -> Self {
         crate::NamedProperty {
@@ -588,12 +1096,188 @@ impl TryInto<indradb::BulkInsertItem> for crate::BulkInsertItem {
     }
 }
 
-impl TryInto<indradb::Identifier> for crate::IndexPropertyRequest {
+impl From<indradb::GeoPoint> for crate::GeoPoint {
+    fn from(point: indradb::GeoPoint) -> Self {
+        crate::GeoPoint {
+            lat: point.lat,
+            lon: point.lon,
+        }
+    }
+}
+
+impl From<crate::GeoPoint> for indradb::GeoPoint {
+    fn from(point: crate::GeoPoint) -> Self {
+        indradb::GeoPoint {
+            lat: point.lat,
+            lon: point.lon,
+        }
+    }
+}
+
+impl From<indradb::BoundingBox> for crate::BoundingBox {
+    fn from(bbox: indradb::BoundingBox) -> Self {
+        crate::BoundingBox {
+            min_lat: bbox.min_lat,
+            min_lon: bbox.min_lon,
+            max_lat: bbox.max_lat,
+            max_lon: bbox.max_lon,
+        }
+    }
+}
+
+impl From<crate::BoundingBox> for indradb::BoundingBox {
+    fn from(bbox: crate::BoundingBox) -> Self {
+        indradb::BoundingBox {
+            min_lat: bbox.min_lat,
+            min_lon: bbox.min_lon,
+            max_lat: bbox.max_lat,
+            max_lon: bbox.max_lon,
+        }
+    }
+}
+
+impl From<indradb::StringPattern> for crate::StringPattern {
+    fn from(pattern: indradb::StringPattern) -> Self {
+        crate::StringPattern {
+            pattern: Some(match pattern {
+                indradb::StringPattern::StartsWith(prefix) => crate::StringPatternVariant::StartsWith(prefix),
+                indradb::StringPattern::Glob(glob) => crate::StringPatternVariant::Glob(glob),
+                indradb::StringPattern::Regex(regex) => crate::StringPatternVariant::Regex(regex),
+            }),
+        }
+    }
+}
+
+impl TryInto<indradb::StringPattern> for crate::StringPattern {
     type Error = ConversionError;
 
-    fn try_into(self) -> Result<indradb::Identifier, Self::Error> {
+    fn try_into(self) -> Result<indradb::StringPattern, Self::Error> {
+        Ok(match required_field("pattern", self.pattern)? {
+            crate::StringPatternVariant::StartsWith(prefix) => indradb::StringPattern::StartsWith(prefix),
+            crate::StringPatternVariant::Glob(glob) => indradb::StringPattern::Glob(glob),
+            crate::StringPatternVariant::Regex(regex) => indradb::StringPattern::Regex(regex),
+        })
+    }
+}
+
+impl From<indradb::GroupKey> for crate::GroupKey {
+    fn from(key: indradb::GroupKey) -> Self {
+        crate::GroupKey {
+            key: Some(match key {
+                indradb::GroupKey::Type => crate::GroupKeyVariant::Type(()),
+                indradb::GroupKey::Property(name) => crate::GroupKeyVariant::Property(name.into()),
+            }),
+        }
+    }
+}
+
+impl TryInto<indradb::GroupKey> for crate::GroupKey {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::GroupKey, Self::Error> {
+        Ok(match required_field("key", self.key)? {
+            crate::GroupKeyVariant::Type(()) => indradb::GroupKey::Type,
+            crate::GroupKeyVariant::Property(name) => indradb::GroupKey::Property(name.try_into()?),
+        })
+    }
+}
+
+impl From<indradb::IndexScope> for crate::IndexScope {
+    fn from(scope: indradb::IndexScope) -> Self {
+        crate::IndexScope {
+            scope: Some(match scope {
+                indradb::IndexScope::All => crate::IndexScopeVariant::All(()),
+                indradb::IndexScope::VertexType(t) => crate::IndexScopeVariant::VertexType(t.into()),
+                indradb::IndexScope::EdgeType(t) => crate::IndexScopeVariant::EdgeType(t.into()),
+            }),
+        }
+    }
+}
+
+impl TryInto<indradb::IndexScope> for crate::IndexScope {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::IndexScope, Self::Error> {
+        Ok(match required_field("scope", self.scope)? {
+            crate::IndexScopeVariant::All(()) => indradb::IndexScope::All,
+            crate::IndexScopeVariant::VertexType(t) => indradb::IndexScope::VertexType(t.try_into()?),
+            crate::IndexScopeVariant::EdgeType(t) => indradb::IndexScope::EdgeType(t.try_into()?),
+        })
+    }
+}
+
+// Requests from clients that predate index scopes don't set one, in which
+// case the index covers everything.
+fn try_into_index_scope(scope: Option<crate::IndexScope>) -> Result<indradb::IndexScope, ConversionError> {
+    match scope {
+        Some(scope) => scope.try_into(),
+        None => Ok(indradb::IndexScope::All),
+    }
+}
+
+impl TryInto<(indradb::Identifier, indradb::IndexScope)> for crate::IndexPropertyRequest {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<(indradb::Identifier, indradb::IndexScope), Self::Error> {
+        let name = required_field("name", self.name)?.try_into()?;
+        let scope = try_into_index_scope(self.scope)?;
+        Ok((name, scope))
+    }
+}
+
+impl TryInto<(indradb::Identifier, indradb::IndexScope)> for crate::UnindexPropertyRequest {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<(indradb::Identifier, indradb::IndexScope), Self::Error> {
         let name = required_field("name", self.name)?.try_into()?;
-        Ok(name)
+        let scope = try_into_index_scope(self.scope)?;
+        Ok((name, scope))
+    }
+}
+
+impl TryInto<(indradb::Identifier, indradb::IndexScope)> for crate::IndexedProperty {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<(indradb::Identifier, indradb::IndexScope), Self::Error> {
+        let name = required_field("name", self.name)?.try_into()?;
+        let scope = required_field("scope", self.scope)?.try_into()?;
+        Ok((name, scope))
+    }
+}
+
+impl From<Vec<indradb::Identifier>> for crate::CompositeIndex {
+    fn from(names: Vec<indradb::Identifier>) -> Self {
+        crate::CompositeIndex {
+            names: names.into_iter().map(|name| name.into()).collect(),
+        }
+    }
+}
+
+impl TryInto<Vec<indradb::Identifier>> for crate::CompositeIndex {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<Vec<indradb::Identifier>, Self::Error> {
+        self.names.into_iter().map(|name| name.try_into()).collect()
+    }
+}
+
+impl From<indradb::UniqueConstraint> for crate::UniqueConstraint {
+    fn from(constraint: indradb::UniqueConstraint) -> Self {
+        crate::UniqueConstraint {
+            t: Some(constraint.t.into()),
+            name: Some(constraint.name.into()),
+        }
+    }
+}
+
+impl TryInto<indradb::UniqueConstraint> for crate::UniqueConstraint {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::UniqueConstraint, Self::Error> {
+        Ok(indradb::UniqueConstraint::new(
+            required_field("t", self.t)?.try_into()?,
+            required_field("name", self.name)?.try_into()?,
+        ))
     }
 }
 
@@ -617,3 +1301,128 @@ impl From<(indradb::Query, indradb::Identifier, indradb::Json)> for crate::SetPr
         }
     }
 }
+
+impl
+    TryInto<(
+        indradb::Identifier,
+        indradb::Identifier,
+        indradb::Json,
+        Vec<indradb::NamedProperty>,
+    )> for crate::GetOrCreateVertexRequest
+{
+    type Error = ConversionError;
+
+    fn try_into(
+        self,
+    ) -> Result<
+        (
+            indradb::Identifier,
+            indradb::Identifier,
+            indradb::Json,
+            Vec<indradb::NamedProperty>,
+        ),
+        Self::Error,
+    > {
+        let t = required_field("t", self.t)?.try_into()?;
+        let name = required_field("name", self.name)?.try_into()?;
+        let value = required_field("value", self.value)?.try_into()?;
+        let properties: Result<Vec<indradb::NamedProperty>, ConversionError> =
+            self.properties.into_iter().map(|prop| prop.try_into()).collect();
+        Ok((t, name, value, properties?))
+    }
+}
+
+impl
+    From<(
+        indradb::Identifier,
+        indradb::Identifier,
+        indradb::Json,
+        Vec<indradb::NamedProperty>,
+    )> for crate::GetOrCreateVertexRequest
+{
+    fn from(
+        value: (
+            indradb::Identifier,
+            indradb::Identifier,
+            indradb::Json,
+            Vec<indradb::NamedProperty>,
+        ),
+    ) -> Self {
+        crate::GetOrCreateVertexRequest {
+            t: Some(value.0.into()),
+            name: Some(value.1.into()),
+            value: Some(value.2.into()),
+            properties: value.3.into_iter().map(|prop| prop.into()).collect(),
+        }
+    }
+}
+
+impl TryInto<(Uuid, bool)> for crate::GetOrCreateVertexResponse {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<(Uuid, bool), Self::Error> {
+        let id = required_field("id", self.id)?.try_into()?;
+        Ok((id, self.created))
+    }
+}
+
+impl From<indradb::Stats> for crate::StatsResponse {
+    fn from(stats: indradb::Stats) -> Self {
+        let type_counts = |counts: BTreeMap<indradb::Identifier, u64>| {
+            counts
+                .into_iter()
+                .map(|(t, count)| crate::TypeCount {
+                    t: Some(t.into()),
+                    count,
+                })
+                .collect()
+        };
+        crate::StatsResponse {
+            vertex_count: stats.vertex_count,
+            edge_count: stats.edge_count,
+            vertex_type_counts: type_counts(stats.vertex_type_counts),
+            edge_type_counts: type_counts(stats.edge_type_counts),
+            property_value_counts: stats
+                .property_value_counts
+                .into_iter()
+                .map(|count| crate::PropertyValueCount {
+                    name: Some(count.name.into()),
+                    value: Some(count.value.into()),
+                    vertex_count: count.vertex_count,
+                    edge_count: count.edge_count,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryInto<indradb::Stats> for crate::StatsResponse {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::Stats, Self::Error> {
+        let type_counts = |counts: Vec<crate::TypeCount>| {
+            counts
+                .into_iter()
+                .map(|count| Ok((required_field("t", count.t)?.try_into()?, count.count)))
+                .collect::<Result<BTreeMap<indradb::Identifier, u64>, ConversionError>>()
+        };
+        Ok(indradb::Stats {
+            vertex_count: self.vertex_count,
+            edge_count: self.edge_count,
+            vertex_type_counts: type_counts(self.vertex_type_counts)?,
+            edge_type_counts: type_counts(self.edge_type_counts)?,
+            property_value_counts: self
+                .property_value_counts
+                .into_iter()
+                .map(|count| {
+                    Ok(indradb::PropertyValueCount {
+                        name: required_field("name", count.name)?.try_into()?,
+                        value: required_field("value", count.value)?.try_into()?,
+                        vertex_count: count.vertex_count,
+                        edge_count: count.edge_count,
+                    })
+                })
+                .collect::<Result<Vec<indradb::PropertyValueCount>, ConversionError>>()?,
+        })
+    }
+}
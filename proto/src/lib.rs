@@ -5,9 +5,12 @@ extern crate indradb;
 tonic::include_proto!("indradb");
 
 pub use bulk_insert_item::Item as BulkInsertItemVariant;
+pub use group_key::Key as GroupKeyVariant;
+pub use index_scope::Scope as IndexScopeVariant;
 pub use indra_db_client::IndraDbClient as ProtoClient;
 pub use query::Query as QueryVariant;
 pub use query_output_value::Value as QueryOutputValueVariant;
+pub use string_pattern::Pattern as StringPatternVariant;
 
 mod converters;
 pub use converters::*;
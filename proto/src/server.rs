@@ -2,28 +2,76 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::error::Error as StdError;
 use std::fmt;
+use std::mem;
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use libloading::Library;
 use tokio::net::TcpListener;
+use tokio::runtime::Handle;
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::SendTimeoutError;
 use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
 use tokio_stream::{Stream, StreamExt};
 use tonic::transport::{Error as TonicTransportError, Server as TonicServer};
 use tonic::{Request, Response, Status, Streaming};
 
 const CHANNEL_CAPACITY: usize = 100;
+// The maximum number of items sent in each chunk of a streamed query output
+// value.
+const CHUNK_SIZE: usize = 1000;
+// How long a streamed query waits for the client to make room for another
+// message before giving up. Output is read from the datastore as it's sent,
+// so this keeps a slow or stalled client from holding the query's
+// transaction open, which in the in-memory datastore blocks writers.
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+type OutputStream = Pin<Box<dyn Stream<Item = Result<crate::QueryOutputValue, Status>> + Send + Sync + 'static>>;
+
+// Sends a message to the client from a blocking task. Fails if the client
+// has gone away, or doesn't make room for the message in time.
+fn send(
+    tx: &mpsc::Sender<Result<crate::QueryOutputValue, Status>>,
+    result: Result<crate::QueryOutputValue, Status>,
+) -> Result<(), Status> {
+    match Handle::current().block_on(tx.send_timeout(result, SEND_TIMEOUT)) {
+        Ok(()) => Ok(()),
+        Err(SendTimeoutError::Timeout(_)) => Err(Status::deadline_exceeded(
+            "timed out waiting for the client to read the query output",
+        )),
+        Err(SendTimeoutError::Closed(_)) => Err(Status::cancelled("the client went away")),
+    }
+}
 
-fn send(tx: &mpsc::Sender<Result<crate::QueryOutputValue, Status>>, result: Result<crate::QueryOutputValue, Status>) {
-    if let Err(err) = tx.blocking_send(result) {
-        eprintln!("could not send message to client: {err}");
+// Sends a query output value in chunks, as its items are read. Stops early
+// if a chunk can't be sent.
+fn send_chunked<T>(
+    tx: &mpsc::Sender<Result<crate::QueryOutputValue, Status>>,
+    iter: indradb::DynIter<'_, T>,
+    to_value: fn(Vec<T>) -> indradb::QueryOutputValue,
+) -> Result<(), Status> {
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+    for item in iter {
+        chunk.push(map_indradb_result(item)?);
+        if chunk.len() == CHUNK_SIZE {
+            let mut value: crate::QueryOutputValue =
+                to_value(mem::replace(&mut chunk, Vec::with_capacity(CHUNK_SIZE))).into();
+            value.partial = true;
+            send(tx, Ok(value))?;
+        }
     }
+    send(tx, Ok(to_value(chunk).into()))
 }
 
 fn map_indradb_result<T>(res: Result<T, indradb::Error>) -> Result<T, Status> {
-    res.map_err(|err| Status::internal(format!("{err}")))
+    res.map_err(|err| match err {
+        // Lets clients tell conflicts, which can be retried, apart from
+        // other errors
+        indradb::Error::Conflict => Status::aborted(format!("{err}")),
+        _ => Status::internal(format!("{err}")),
+    })
 }
 
 fn map_conversion_result<T>(res: Result<T, crate::ConversionError>) -> Result<T, Status> {
@@ -189,6 +237,63 @@ impl<D: indradb::Datastore + Send + Sync + 'static> Server<D> {
             }),
         })
     }
+
+    // Runs a query on a blocking thread, streaming its output values.
+    fn stream_query(&self, q: indradb::Query) -> OutputStream {
+        let db = self.db.clone();
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::task::spawn_blocking(move || {
+            let mut sent = Ok(());
+            let result = db.get_iter(q, |output| {
+                for value in output {
+                    sent = match value {
+                        indradb::QueryOutputValueIter::Vertices(iter) => {
+                            send_chunked(&tx, iter, indradb::QueryOutputValue::Vertices)
+                        }
+                        indradb::QueryOutputValueIter::Edges(iter) => {
+                            send_chunked(&tx, iter, indradb::QueryOutputValue::Edges)
+                        }
+                        indradb::QueryOutputValueIter::Count(count) => {
+                            send(&tx, Ok(indradb::QueryOutputValue::Count(count).into()))
+                        }
+                        indradb::QueryOutputValueIter::Path(path) => {
+                            send(&tx, Ok(indradb::QueryOutputValue::Path(path).into()))
+                        }
+                        indradb::QueryOutputValueIter::Cursor(cursor) => {
+                            send(&tx, Ok(indradb::QueryOutputValue::Cursor(cursor).into()))
+                        }
+                        indradb::QueryOutputValueIter::Aggregate(value) => {
+                            send(&tx, Ok(indradb::QueryOutputValue::Aggregate(value).into()))
+                        }
+                        indradb::QueryOutputValueIter::Groups(groups) => {
+                            send(&tx, Ok(indradb::QueryOutputValue::Groups(groups).into()))
+                        }
+                        indradb::QueryOutputValueIter::Degrees(iter) => {
+                            send_chunked(&tx, iter, indradb::QueryOutputValue::Degrees)
+                        }
+                        indradb::QueryOutputValueIter::VertexProperties(iter) => {
+                            send_chunked(&tx, iter, indradb::QueryOutputValue::VertexProperties)
+                        }
+                        indradb::QueryOutputValueIter::EdgeProperties(iter) => {
+                            send_chunked(&tx, iter, indradb::QueryOutputValue::EdgeProperties)
+                        }
+                    };
+                    if sent.is_err() {
+                        break;
+                    }
+                }
+                Ok(())
+            });
+            // Errors are sent once the query's transaction has been released
+            if let Err(err) = map_indradb_result(result).and(sent) {
+                if let Err(send_err) = send(&tx, Err(err)) {
+                    eprintln!("could not send message to client: {send_err}");
+                }
+            }
+        });
+
+        Box::pin(ReceiverStream::new(rx))
+    }
 }
 
 #[tonic::async_trait]
@@ -227,21 +332,35 @@ impl<D: indradb::Datastore + Send + Sync + 'static> crate::indra_db_server::Indr
         Ok(Response::new(crate::CreateResponse { created: res }))
     }
 
-    type GetStream = Pin<Box<dyn Stream<Item = Result<crate::QueryOutputValue, Status>> + Send + Sync + 'static>>;
-    async fn get(&self, request: Request<crate::Query>) -> Result<Response<Self::GetStream>, Status> {
+    async fn get_or_create_vertex(
+        &self,
+        request: Request<crate::GetOrCreateVertexRequest>,
+    ) -> Result<Response<crate::GetOrCreateVertexResponse>, Status> {
         let db = self.db.clone();
+        let (t, name, value, properties) = map_conversion_result(request.into_inner().try_into())?;
+        let (id, created) = map_jh_indra_result(
+            tokio::task::spawn_blocking(move || db.get_or_create_vertex(t, name, &value, properties)).await,
+        )?;
+        Ok(Response::new(crate::GetOrCreateVertexResponse {
+            id: Some(id.into()),
+            created,
+        }))
+    }
+
+    type GetStream = OutputStream;
+    async fn get(&self, request: Request<crate::Query>) -> Result<Response<Self::GetStream>, Status> {
         let q: indradb::Query = map_conversion_result(request.into_inner().try_into())?;
-        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
-        tokio::task::spawn_blocking(move || match map_indradb_result(db.get(q)) {
-            Ok(output) => {
-                for output in output.into_iter() {
-                    send(&tx, Ok(output.into()));
-                }
-            }
-            Err(err) => send(&tx, Err(err)),
-        });
+        Ok(Response::new(self.stream_query(q)))
+    }
 
-        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    type GetByTextStream = OutputStream;
+    async fn get_by_text(&self, request: Request<crate::QueryText>) -> Result<Response<Self::GetByTextStream>, Status> {
+        let q: indradb::Query = request
+            .into_inner()
+            .text
+            .parse()
+            .map_err(|err| Status::invalid_argument(format!("{err}")))?;
+        Ok(Response::new(self.stream_query(q)))
     }
 
     async fn delete(&self, request: Request<crate::Query>) -> Result<Response<()>, Status> {
@@ -279,11 +398,167 @@ impl<D: indradb::Datastore + Send + Sync + 'static> crate::indra_db_server::Indr
     async fn index_property(&self, request: Request<crate::IndexPropertyRequest>) -> Result<Response<()>, Status> {
         let db = self.db.clone();
 
-        let name: indradb::Identifier = map_conversion_result(request.into_inner().try_into())?;
-        map_jh_indra_result(tokio::task::spawn_blocking(move || db.index_property(name)).await)?;
+        let (name, scope): (indradb::Identifier, indradb::IndexScope) =
+            map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.index_property(name, scope)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn unindex_property(&self, request: Request<crate::UnindexPropertyRequest>) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+
+        let (name, scope): (indradb::Identifier, indradb::IndexScope) =
+            map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.unindex_property(name, scope)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn indexed_properties(&self, _: Request<()>) -> Result<Response<crate::IndexedPropertiesResponse>, Status> {
+        let db = self.db.clone();
+        let indexed_properties =
+            map_jh_indra_result(tokio::task::spawn_blocking(move || db.indexed_properties()).await)?;
+        let properties = indexed_properties
+            .into_iter()
+            .flat_map(|(name, scopes)| {
+                scopes.into_iter().map(move |scope| crate::IndexedProperty {
+                    name: Some(name.into()),
+                    scope: Some(scope.into()),
+                })
+            })
+            .collect();
+        Ok(Response::new(crate::IndexedPropertiesResponse { properties }))
+    }
+
+    async fn index_composite(&self, request: Request<crate::CompositeIndex>) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+        let names: Vec<indradb::Identifier> = map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.index_composite(names)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn unindex_composite(&self, request: Request<crate::CompositeIndex>) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+        let names: Vec<indradb::Identifier> = map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.unindex_composite(names)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn composite_indexes(&self, _: Request<()>) -> Result<Response<crate::CompositeIndexesResponse>, Status> {
+        let db = self.db.clone();
+        let composite_indexes = map_jh_indra_result(tokio::task::spawn_blocking(move || db.composite_indexes()).await)?;
+        let indexes = composite_indexes.into_iter().map(|names| names.into()).collect();
+        Ok(Response::new(crate::CompositeIndexesResponse { indexes }))
+    }
+
+    async fn index_property_fulltext(&self, request: Request<crate::Identifier>) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+        let name = map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.index_property_fulltext(name)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn unindex_property_fulltext(&self, request: Request<crate::Identifier>) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+        let name = map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.unindex_property_fulltext(name)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn fulltext_indexed_properties(
+        &self,
+        _: Request<()>,
+    ) -> Result<Response<crate::FulltextIndexedPropertiesResponse>, Status> {
+        let db = self.db.clone();
+        let fulltext_indexed_properties =
+            map_jh_indra_result(tokio::task::spawn_blocking(move || db.fulltext_indexed_properties()).await)?;
+        let names = fulltext_indexed_properties
+            .into_iter()
+            .map(|name| name.into())
+            .collect();
+        Ok(Response::new(crate::FulltextIndexedPropertiesResponse { names }))
+    }
+
+    async fn index_property_vector(&self, request: Request<crate::Identifier>) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+        let name = map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.index_property_vector(name)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn unindex_property_vector(&self, request: Request<crate::Identifier>) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+        let name = map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.unindex_property_vector(name)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn vector_indexed_properties(
+        &self,
+        _: Request<()>,
+    ) -> Result<Response<crate::VectorIndexedPropertiesResponse>, Status> {
+        let db = self.db.clone();
+        let vector_indexed_properties =
+            map_jh_indra_result(tokio::task::spawn_blocking(move || db.vector_indexed_properties()).await)?;
+        let names = vector_indexed_properties.into_iter().map(|name| name.into()).collect();
+        Ok(Response::new(crate::VectorIndexedPropertiesResponse { names }))
+    }
+
+    async fn index_property_geo(&self, request: Request<crate::Identifier>) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+        let name = map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.index_property_geo(name)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn unindex_property_geo(&self, request: Request<crate::Identifier>) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+        let name = map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.unindex_property_geo(name)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn geo_indexed_properties(
+        &self,
+        _: Request<()>,
+    ) -> Result<Response<crate::GeoIndexedPropertiesResponse>, Status> {
+        let db = self.db.clone();
+        let geo_indexed_properties =
+            map_jh_indra_result(tokio::task::spawn_blocking(move || db.geo_indexed_properties()).await)?;
+        let names = geo_indexed_properties.into_iter().map(|name| name.into()).collect();
+        Ok(Response::new(crate::GeoIndexedPropertiesResponse { names }))
+    }
+
+    async fn add_unique_constraint(&self, request: Request<crate::UniqueConstraint>) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+        let constraint: indradb::UniqueConstraint = map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.add_unique_constraint(constraint)).await)?;
         Ok(Response::new(()))
     }
 
+    async fn remove_unique_constraint(
+        &self,
+        request: Request<crate::UniqueConstraint>,
+    ) -> Result<Response<()>, Status> {
+        let db = self.db.clone();
+        let constraint: indradb::UniqueConstraint = map_conversion_result(request.into_inner().try_into())?;
+        map_jh_indra_result(tokio::task::spawn_blocking(move || db.remove_unique_constraint(constraint)).await)?;
+        Ok(Response::new(()))
+    }
+
+    async fn unique_constraints(&self, _: Request<()>) -> Result<Response<crate::UniqueConstraintsResponse>, Status> {
+        let db = self.db.clone();
+        let unique_constraints =
+            map_jh_indra_result(tokio::task::spawn_blocking(move || db.unique_constraints()).await)?;
+        let constraints = unique_constraints.into_iter().map(|c| c.into()).collect();
+        Ok(Response::new(crate::UniqueConstraintsResponse { constraints }))
+    }
+
+    async fn stats(&self, _: Request<()>) -> Result<Response<crate::StatsResponse>, Status> {
+        let db = self.db.clone();
+        let stats = map_jh_indra_result(tokio::task::spawn_blocking(move || db.stats()).await)?;
+        Ok(Response::new(stats.into()))
+    }
+
     async fn execute_plugin(
         &self,
         request: Request<crate::ExecutePluginRequest>,
@@ -303,6 +578,7 @@ impl<D: indradb::Datastore + Send + Sync + 'static> crate::indra_db_server::Indr
                     .call(&mut txn, arg)
                     .map_err(|err| Status::internal(format!("{err}")))?
             };
+            map_indradb_result(indradb::Transaction::commit(&mut txn))?;
             Ok(Response::new(crate::ExecutePluginResponse {
                 value: Some(response.into()),
             }))
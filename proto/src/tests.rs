@@ -7,16 +7,20 @@
 //! the database.)
 
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
+use std::ops::Bound;
 use std::rc::Rc;
 use std::result::Result as StdResult;
 use std::time::Duration;
 
 use indradb::{
-    util, AllEdgeQuery, AllVertexQuery, BulkInsertItem, CountQueryExt, Datastore, DynIter, Edge,
-    EdgeWithPropertyPresenceQuery, EdgeWithPropertyValueQuery, Error, Identifier, Json, Query, QueryExt,
-    QueryOutputValue, RangeVertexQuery, Result, SpecificEdgeQuery, SpecificVertexQuery, Transaction, Vertex,
-    VertexWithPropertyPresenceQuery, VertexWithPropertyValueQuery,
+    util, AllEdgeQuery, AllVertexQuery, BoundingBox, BulkInsertItem, CountQueryExt, Datastore, DynIter, Edge,
+    EdgeWithPropertyPresenceQuery, EdgeWithPropertyRangeQuery, EdgeWithPropertyValueQuery, Error, GeoPoint, Identifier,
+    IndexScope, Json, Query, QueryExt, QueryOutputValue, RangeVertexQuery, Result, SpecificEdgeQuery,
+    SpecificVertexQuery, Stats, Transaction, UniqueConstraint, Vertex, VertexPoint, VertexVector,
+    VertexWithPropertyMatchQuery, VertexWithPropertyPresenceQuery, VertexWithPropertyRangeQuery,
+    VertexWithPropertyValueQuery, VertexWithPropertyValuesQuery, WithinBoundingBoxQuery,
 };
 
 use tokio::runtime::Runtime;
@@ -27,13 +31,19 @@ use uuid::Uuid;
 fn map_client_result<T>(result: StdResult<T, crate::ClientError>) -> Result<T> {
     result.map_err(|err| {
         match err {
-            // this is the only error variant we need to handle for testing
+            // these are the only error variants we need to handle for testing
             crate::ClientError::Grpc { inner }
                 if inner.code() == tonic::Code::Internal
                     && inner.message() == "query attempted on a property that isn't indexed" =>
             {
                 Error::NotIndexed
             }
+            crate::ClientError::Grpc { inner }
+                if inner.code() == tonic::Code::Internal
+                    && inner.message() == "property value violates a unique constraint" =>
+            {
+                Error::ConstraintViolation
+            }
             // unexpected error variant
             _ => panic!("{}", err),
         }
@@ -75,6 +85,47 @@ impl<'a> ClientTransaction {
         let edges = util::extract_edges(self.get(q)?).ok_or(Error::Unsupported)?;
         Ok(Box::new(edges.into_iter().map(Ok)))
     }
+
+    // The server only answers property queries that an index covers, so a
+    // property that's only indexed for specific types is queried once per
+    // type.
+    fn index_types(&self, name: Identifier, vertex: bool) -> Result<Vec<Option<Identifier>>> {
+        let scopes = self.indexed_properties()?.remove(&name).unwrap_or_default();
+        if scopes.is_empty() || scopes.contains(&IndexScope::All) {
+            return Ok(vec![None]);
+        }
+        Ok(scopes
+            .into_iter()
+            .filter_map(|scope| match scope {
+                IndexScope::VertexType(t) if vertex => Some(Some(t)),
+                IndexScope::EdgeType(t) if !vertex => Some(Some(t)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn get_vertex_ids_for_index_types<F>(&'a self, name: Identifier, f: F) -> Result<Option<DynIter<'a, Uuid>>>
+    where
+        F: Fn(Option<Identifier>) -> Query,
+    {
+        let mut ids = Vec::new();
+        for t in self.index_types(name, true)? {
+            let vertices = util::extract_vertices(self.get(f(t))?).unwrap();
+            ids.extend(vertices.into_iter().map(|v| Ok(v.id)));
+        }
+        Ok(Some(Box::new(ids.into_iter())))
+    }
+
+    fn get_edges_for_index_types<F>(&'a self, name: Identifier, f: F) -> Result<Option<DynIter<'a, Edge>>>
+    where
+        F: Fn(Option<Identifier>) -> Query,
+    {
+        let mut edges = Vec::new();
+        for t in self.index_types(name, false)? {
+            edges.extend(util::extract_edges(self.get(f(t))?).unwrap().into_iter().map(Ok));
+        }
+        Ok(Some(Box::new(edges.into_iter())))
+    }
 }
 
 impl<'a> Transaction<'a> for ClientTransaction {
@@ -95,17 +146,95 @@ impl<'a> Transaction<'a> for ClientTransaction {
     }
 
     fn vertex_ids_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Uuid>>> {
-        let q = VertexWithPropertyPresenceQuery::new(name);
+        self.get_vertex_ids_for_index_types(name, |t| VertexWithPropertyPresenceQuery { name, t }.into())
+    }
+
+    fn vertex_ids_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Uuid>>> {
+        self.get_vertex_ids_for_index_types(name, |t| {
+            VertexWithPropertyValueQuery {
+                name,
+                value: value.clone(),
+                t,
+            }
+            .into()
+        })
+    }
+
+    fn vertex_ids_with_property_range(
+        &'a self,
+        name: Identifier,
+        lower: Bound<&Json>,
+        upper: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        self.get_vertex_ids_for_index_types(name, |t| {
+            VertexWithPropertyRangeQuery {
+                name,
+                lower: lower.cloned(),
+                upper: upper.cloned(),
+                t,
+            }
+            .into()
+        })
+    }
+
+    fn vertex_ids_with_composite_values(
+        &'a self,
+        names: &[Identifier],
+        values: &[Json],
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        let q = VertexWithPropertyValuesQuery {
+            values: names.iter().copied().zip(values.iter().cloned()).collect(),
+        };
         let vertices = util::extract_vertices(self.get(q)?).unwrap();
         Ok(Some(Box::new(vertices.into_iter().map(|v| Ok(v.id)))))
     }
 
-    fn vertex_ids_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Uuid>>> {
-        let q = VertexWithPropertyValueQuery::new(name, value.clone());
+    fn vertex_ids_with_property_terms(
+        &'a self,
+        name: Identifier,
+        terms: &[String],
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        // Terms are made up of characters that tokenizing leaves intact
+        let q = VertexWithPropertyMatchQuery::new(name, terms.join(" "));
         let vertices = util::extract_vertices(self.get(q)?).unwrap();
         Ok(Some(Box::new(vertices.into_iter().map(|v| Ok(v.id)))))
     }
 
+    fn vertex_property_vectors(&'a self, name: Identifier) -> Result<Option<DynIter<'a, VertexVector>>> {
+        if !self.vector_indexed_properties()?.contains(&name) {
+            return Ok(None);
+        }
+        // The vectors are read from the property values, the same way the
+        // index does
+        let q = AllVertexQuery.properties().unwrap().name(name);
+        let vertex_properties = util::extract_vertex_properties(self.get(q)?).unwrap();
+        let iter = vertex_properties.into_iter().filter_map(|vps| {
+            let vector = util::json_to_vector(&vps.props.first()?.value)?;
+            Some(Ok((vps.vertex.id, vector)))
+        });
+        Ok(Some(Box::new(iter)))
+    }
+
+    fn vertex_points_within(
+        &'a self,
+        name: Identifier,
+        bbox: &BoundingBox,
+    ) -> Result<Option<DynIter<'a, VertexPoint>>> {
+        if !self.geo_indexed_properties()?.contains(&name) {
+            return Ok(None);
+        }
+        let q = WithinBoundingBoxQuery::new(name, *bbox)
+            .properties()
+            .unwrap()
+            .name(name);
+        let vertex_properties = util::extract_vertex_properties(self.get(q)?).unwrap();
+        let iter = vertex_properties.into_iter().filter_map(|vps| {
+            let point = GeoPoint::from_json(&vps.props.first()?.value)?;
+            Some(Ok((vps.vertex.id, point)))
+        });
+        Ok(Some(Box::new(iter)))
+    }
+
     fn edge_count(&self) -> u64 {
         self.get_count(AllEdgeQuery.count().unwrap())
     }
@@ -121,12 +250,13 @@ impl<'a> Transaction<'a> for ClientTransaction {
     }
 
     fn range_reversed_edges(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
-        let edges = util::extract_edges(self.get(AllEdgeQuery)?).unwrap();
-        let iter = edges
+        let mut edges: Vec<Edge> = util::extract_edges(self.get(AllEdgeQuery)?)
+            .unwrap()
             .into_iter()
             .map(|e| e.reversed())
-            .filter(move |e| e >= &offset)
-            .map(Ok);
+            .collect();
+        edges.sort();
+        let iter = edges.into_iter().filter(move |e| e >= &offset).map(Ok);
         Ok(Box::new(iter))
     }
 
@@ -135,15 +265,35 @@ impl<'a> Transaction<'a> for ClientTransaction {
     }
 
     fn edges_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Edge>>> {
-        let q = EdgeWithPropertyPresenceQuery::new(name);
-        let edges = util::extract_edges(self.get(q)?).unwrap();
-        Ok(Some(Box::new(edges.into_iter().map(Ok))))
+        self.get_edges_for_index_types(name, |t| EdgeWithPropertyPresenceQuery { name, t }.into())
     }
 
     fn edges_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Edge>>> {
-        let q = EdgeWithPropertyValueQuery::new(name, value.clone());
-        let edges = util::extract_edges(self.get(q)?).unwrap();
-        Ok(Some(Box::new(edges.into_iter().map(Ok))))
+        self.get_edges_for_index_types(name, |t| {
+            EdgeWithPropertyValueQuery {
+                name,
+                value: value.clone(),
+                t,
+            }
+            .into()
+        })
+    }
+
+    fn edges_with_property_range(
+        &'a self,
+        name: Identifier,
+        lower: Bound<&Json>,
+        upper: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Edge>>> {
+        self.get_edges_for_index_types(name, |t| {
+            EdgeWithPropertyRangeQuery {
+                name,
+                lower: lower.cloned(),
+                upper: upper.cloned(),
+                t,
+            }
+            .into()
+        })
     }
 
     fn vertex_property(&self, vertex: &Vertex, name: Identifier) -> Result<Option<Json>> {
@@ -252,14 +402,154 @@ impl<'a> Transaction<'a> for ClientTransaction {
         )
     }
 
-    fn index_property(&mut self, name: Identifier) -> Result<()> {
+    fn index_property(&mut self, name: Identifier, scope: IndexScope) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().index_property(name, scope)),
+        )
+    }
+
+    fn unindex_property(&mut self, name: Identifier, scope: IndexScope) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().unindex_property(name, scope)),
+        )
+    }
+
+    fn indexed_properties(&self) -> Result<HashMap<Identifier, HashSet<IndexScope>>> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().indexed_properties()),
+        )
+    }
+
+    fn index_composite(&mut self, names: Vec<Identifier>) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().index_composite(names)),
+        )
+    }
+
+    fn unindex_composite(&mut self, names: Vec<Identifier>) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().unindex_composite(names)),
+        )
+    }
+
+    fn composite_indexes(&self) -> Result<HashSet<Vec<Identifier>>> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().composite_indexes()),
+        )
+    }
+
+    fn index_property_fulltext(&mut self, name: Identifier) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().index_property_fulltext(name)),
+        )
+    }
+
+    fn unindex_property_fulltext(&mut self, name: Identifier) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().unindex_property_fulltext(name)),
+        )
+    }
+
+    fn fulltext_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().fulltext_indexed_properties()),
+        )
+    }
+
+    fn index_property_vector(&mut self, name: Identifier) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().index_property_vector(name)),
+        )
+    }
+
+    fn unindex_property_vector(&mut self, name: Identifier) -> Result<()> {
         map_client_result(
             self.exec
                 .borrow_mut()
-                .block_on(self.client.borrow_mut().index_property(name)),
+                .block_on(self.client.borrow_mut().unindex_property_vector(name)),
         )
     }
 
+    fn vector_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().vector_indexed_properties()),
+        )
+    }
+
+    fn index_property_geo(&mut self, name: Identifier) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().index_property_geo(name)),
+        )
+    }
+
+    fn unindex_property_geo(&mut self, name: Identifier) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().unindex_property_geo(name)),
+        )
+    }
+
+    fn geo_indexed_properties(&self) -> Result<HashSet<Identifier>> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().geo_indexed_properties()),
+        )
+    }
+
+    fn add_unique_constraint(&mut self, constraint: UniqueConstraint) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().add_unique_constraint(constraint)),
+        )
+    }
+
+    fn remove_unique_constraint(&mut self, constraint: UniqueConstraint) -> Result<()> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().remove_unique_constraint(constraint)),
+        )
+    }
+
+    fn unique_constraints(&self) -> Result<HashSet<UniqueConstraint>> {
+        map_client_result(
+            self.exec
+                .borrow_mut()
+                .block_on(self.client.borrow_mut().unique_constraints()),
+        )
+    }
+
+    fn stats(&self) -> Result<Stats> {
+        map_client_result(self.exec.borrow_mut().block_on(self.client.borrow_mut().stats()))
+    }
+
     fn set_vertex_properties(&mut self, vertex_ids: Vec<Uuid>, name: Identifier, value: &Json) -> Result<()> {
         self.set_properties(SpecificVertexQuery::new(vertex_ids), name, value)
     }
@@ -297,15 +587,21 @@ impl ClientDatastore {
 
 impl Datastore for ClientDatastore {
     type Transaction<'a> = ClientTransaction;
+    type ReadTransaction<'a> = ClientTransaction;
     fn transaction(&'_ self) -> Self::Transaction<'_> {
         ClientTransaction {
             client: self.client.clone(),
             exec: self.exec.clone(),
         }
     }
+    fn read_transaction(&'_ self) -> Self::ReadTransaction<'_> {
+        self.transaction()
+    }
 }
 
-full_test_impl!({
+// Runs a server over an in-memory datastore, and connects to it.
+#[cfg(test)]
+fn new_client_db() -> indradb::Database<ClientDatastore> {
     use indradb::Database;
     use std::net::ToSocketAddrs;
     use std::sync::Arc;
@@ -322,4 +618,26 @@ full_test_impl!({
     ));
 
     Database::new(ClientDatastore::new(port as u16, rt))
-});
+}
+
+full_test_impl!(new_client_db());
+
+#[test]
+fn should_reassemble_chunked_output() {
+    let db = new_client_db();
+    let t = Identifier::new("test_vertex_type").unwrap();
+    let mut vertices: Vec<Vertex> = (0..2500).map(|_| Vertex::new(t)).collect();
+    db.bulk_insert(vertices.iter().cloned().map(BulkInsertItem::Vertex).collect())
+        .unwrap();
+
+    // The vertices are streamed in several chunks, which are put back
+    // together before the count that follows them
+    let txn = db.datastore.transaction();
+    let output = txn.get(AllVertexQuery.include().count().unwrap()).unwrap();
+    assert_eq!(output.len(), 2);
+    let mut output_vertices = util::extract_vertices(output[..1].to_vec()).unwrap();
+    output_vertices.sort_by_key(|vertex| vertex.id);
+    vertices.sort_by_key(|vertex| vertex.id);
+    assert_eq!(output_vertices, vertices);
+    assert_eq!(output[1], QueryOutputValue::Count(2500));
+}